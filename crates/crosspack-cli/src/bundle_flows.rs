@@ -149,7 +149,7 @@ fn run_bundle_apply_command(
         &provider_overrides,
         options.build_from_source,
     )?;
-    let receipts = read_install_receipts(layout)?;
+    let receipts = read_checked_install_receipts(layout)?;
     for plan in &group_plans {
         for package in &plan.resolved {
             validate_install_preflight_for_resolved(layout, package, &receipts)?;
@@ -251,8 +251,10 @@ fn run_bundle_apply_command(
                     InstallResolvedOptions {
                         snapshot_id: snapshot_id.as_deref(),
                         force_redownload: options.force_redownload,
+                        verify_files: false,
                         interaction_policy,
                         install_progress_mode,
+                        level_receipts: None,
                     },
                     Some(&mut source_build_journal),
                 )?;
@@ -285,7 +287,7 @@ fn run_bundle_apply_command(
 }
 
 fn build_export_bundle_document(layout: &PrefixLayout) -> Result<BundleDocument> {
-    let receipts = read_install_receipts(layout)?;
+    let receipts = read_checked_install_receipts(layout)?;
     let pins = read_all_pins(layout)?;
 
     let mut roots = receipts
@@ -352,7 +354,7 @@ fn load_bundle_snapshot_context_best_effort(
 
 fn bundle_snapshot_token(snapshot: &RegistrySourceSnapshotState) -> String {
     match snapshot {
-        RegistrySourceSnapshotState::Ready { snapshot_id } => format!("ready:{snapshot_id}"),
+        RegistrySourceSnapshotState::Ready { snapshot_id, .. } => format!("ready:{snapshot_id}"),
         RegistrySourceSnapshotState::None => "none".to_string(),
         RegistrySourceSnapshotState::Error { reason_code, .. } => format!("error:{reason_code}"),
     }
@@ -451,6 +453,7 @@ fn build_bundle_apply_group_plans(
                         root.name, requirement
                     )
                 })?,
+                features: Vec::new(),
             });
     }
 
@@ -3,38 +3,90 @@ fn ensure_upgrade_command_ready(layout: &PrefixLayout) -> Result<()> {
     ensure_no_active_transaction_for(layout, "upgrade")
 }
 
-fn run_outdated_command(layout: &PrefixLayout, registry_root: Option<&Path>) -> Result<()> {
-    let backend = select_metadata_backend(registry_root, layout)?;
-    let receipts = read_install_receipts(layout)?;
-    if receipts.is_empty() {
-        println!("No installed packages");
-        return Ok(());
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct OutdatedEntry {
+    name: String,
+    installed: Version,
+    latest_compatible: Option<Version>,
+    latest_available: Version,
+    source: String,
+}
+
+fn read_parsed_pins(layout: &PrefixLayout) -> Result<BTreeMap<String, VersionReq>> {
+    let mut pins = BTreeMap::new();
+    for (name, raw_req) in read_all_pins(layout)? {
+        let parsed = VersionReq::parse(&raw_req)
+            .with_context(|| format!("invalid pin requirement for '{name}' in state: {raw_req}"))?;
+        pins.insert(name, parsed);
     }
+    Ok(pins)
+}
 
-    let mut rows = Vec::new();
+fn list_outdated(
+    receipts: &[InstallReceipt],
+    backend: &MetadataBackend,
+    pins: &BTreeMap<String, VersionReq>,
+) -> Result<Vec<OutdatedEntry>> {
+    let mut entries = Vec::new();
     for receipt in receipts {
-        let installed_version = match Version::parse(&receipt.version) {
-            Ok(version) => version,
-            Err(_) => {
-                rows.push(format!(
-                    "{}\t{}\tunknown\tinvalid-installed-version",
-                    receipt.name, receipt.version
-                ));
-                continue;
-            }
+        let Ok(installed) = Version::parse(&receipt.version) else {
+            continue;
         };
-
         let Some((source, manifests)) = backend.package_versions_with_source(&receipt.name)? else {
             continue;
         };
         let Some(latest) = manifests.first() else {
             continue;
         };
+        if latest.version <= installed {
+            continue;
+        }
 
-        if latest.version > installed_version {
-            rows.push(format!(
+        let latest_compatible = match pins.get(&receipt.name) {
+            Some(pin) => manifests
+                .iter()
+                .map(|manifest| &manifest.version)
+                .filter(|version| pin.matches(version))
+                .max()
+                .cloned(),
+            None => Some(latest.version.clone()),
+        };
+        entries.push(OutdatedEntry {
+            name: receipt.name.clone(),
+            installed,
+            latest_compatible,
+            latest_available: latest.version.clone(),
+            source,
+        });
+    }
+
+    entries.sort_by(|left, right| left.name.cmp(&right.name));
+    Ok(entries)
+}
+
+fn run_outdated_command(layout: &PrefixLayout, registry_root: Option<&Path>) -> Result<()> {
+    let backend = select_metadata_backend(registry_root, layout)?;
+    let receipts = read_checked_install_receipts(layout)?;
+    if receipts.is_empty() {
+        println!("No installed packages");
+        return Ok(());
+    }
+
+    let pins = read_parsed_pins(layout)?;
+    let mut rows = list_outdated(&receipts, &backend, &pins)?
+        .into_iter()
+        .map(|entry| {
+            format!(
                 "{}\t{}\t{}\t{}",
-                receipt.name, receipt.version, latest.version, source
+                entry.name, entry.installed, entry.latest_available, entry.source
+            )
+        })
+        .collect::<Vec<_>>();
+    for receipt in &receipts {
+        if Version::parse(&receipt.version).is_err() {
+            rows.push(format!(
+                "{}\t{}\tunknown\tinvalid-installed-version",
+                receipt.name, receipt.version
             ));
         }
     }
@@ -52,12 +104,28 @@ fn run_outdated_command(layout: &PrefixLayout, registry_root: Option<&Path>) ->
     Ok(())
 }
 
-fn parse_receipt_dependency_name(entry: &str) -> Option<&str> {
-    entry.split_once('@').map(|(name, _)| name)
+// Sizes come from the receipt as recorded at install time; legacy receipts
+// without them report `unknown` rather than walking the package tree.
+fn format_list_line(receipt: &InstallReceipt, sizes: bool) -> String {
+    if !sizes {
+        return format!("{} {}", receipt.name, receipt.version);
+    }
+    let format_size = |value: Option<u64>| {
+        value
+            .map(|value| value.to_string())
+            .unwrap_or_else(|| "unknown".to_string())
+    };
+    format!(
+        "{} {} files={} bytes={}",
+        receipt.name,
+        receipt.version,
+        format_size(receipt.file_count),
+        format_size(receipt.installed_bytes)
+    )
 }
 
 fn run_depends_command(layout: &PrefixLayout, name: &str) -> Result<()> {
-    let receipts = read_install_receipts(layout)?;
+    let receipts = read_checked_install_receipts(layout)?;
     let Some(target) = receipts.iter().find(|receipt| receipt.name == name) else {
         println!("No installed package found: {name}");
         return Ok(());
@@ -66,7 +134,7 @@ fn run_depends_command(layout: &PrefixLayout, name: &str) -> Result<()> {
     let mut deps = target
         .dependencies
         .iter()
-        .filter_map(|entry| parse_receipt_dependency_name(entry))
+        .map(|dependency| dependency.name.as_str())
         .map(ToString::to_string)
         .collect::<Vec<_>>();
     deps.sort();
@@ -85,13 +153,13 @@ fn run_depends_command(layout: &PrefixLayout, name: &str) -> Result<()> {
 }
 
 fn run_uses_command(layout: &PrefixLayout, name: &str) -> Result<()> {
-    let receipts = read_install_receipts(layout)?;
+    let receipts = read_checked_install_receipts(layout)?;
     let mut users = Vec::new();
     for receipt in receipts {
         if receipt
             .dependencies
             .iter()
-            .filter_map(|entry| parse_receipt_dependency_name(entry))
+            .map(|dependency| dependency.name.as_str())
             .any(|dependency_name| dependency_name == name)
         {
             users.push(receipt.name);
@@ -114,7 +182,7 @@ fn run_uses_command(layout: &PrefixLayout, name: &str) -> Result<()> {
 }
 
 fn run_why_command(layout: &PrefixLayout, name: &str) -> Result<()> {
-    let receipts = read_install_receipts(layout)?;
+    let receipts = read_checked_install_receipts(layout)?;
     let receipt_map = receipts
         .iter()
         .map(|receipt| (receipt.name.clone(), receipt))
@@ -171,7 +239,7 @@ fn find_dependency_path_from_roots(
         let mut dependencies = receipt
             .dependencies
             .iter()
-            .filter_map(|entry| parse_receipt_dependency_name(entry))
+            .map(|dependency| dependency.name.as_str())
             .map(ToString::to_string)
             .collect::<Vec<_>>();
         dependencies.sort();
@@ -253,7 +321,7 @@ fn declared_service_for_name(layout: &PrefixLayout, name: &str) -> Result<Declar
 fn collect_declared_services(
     layout: &PrefixLayout,
 ) -> Result<HashMap<String, DeclaredServiceRecord>> {
-    let receipts = read_install_receipts(layout)?;
+    let receipts = read_checked_install_receipts(layout)?;
     let declared_by_package = read_all_declared_services_states(layout)?;
 
     let mut services = HashMap::new();
@@ -549,7 +617,7 @@ fn run_cache_gc_command(layout: &PrefixLayout) -> Result<()> {
     let mut entries = collect_cache_files(&cache_root)?;
     entries.sort_by(|left, right| left.path.cmp(&right.path));
 
-    let receipts = read_install_receipts(layout)?;
+    let receipts = read_checked_install_receipts(layout)?;
     let referenced = receipts
         .iter()
         .filter_map(|receipt| receipt.cache_path.as_deref())
@@ -577,7 +645,7 @@ fn run_cache_gc_command(layout: &PrefixLayout) -> Result<()> {
 }
 
 fn safe_artifact_cache_path(layout: &PrefixLayout, cache_path: &str) -> Option<PathBuf> {
-    let path = PathBuf::from(cache_path);
+    let path = layout.resolve_cache_path(cache_path);
     if !path.is_absolute() {
         return None;
     }
@@ -683,7 +751,7 @@ fn run_upgrade_command(
     ensure_upgrade_command_ready(layout)?;
     let backend = select_metadata_backend(registry_root, layout)?;
 
-    let receipts = read_install_receipts(layout)?;
+    let receipts = read_checked_install_receipts(layout)?;
     if receipts.is_empty() {
         println!("No installed packages");
         return Ok(());
@@ -710,6 +778,7 @@ fn run_upgrade_command(
                 let roots = vec![RootInstallRequest {
                     name: installed_receipt.name.clone(),
                     requirement,
+                    features: Vec::new(),
                 }];
                 let resolved = resolve_install_graph(
                     layout,
@@ -826,6 +895,7 @@ fn run_upgrade_command(
                 let roots = vec![RootInstallRequest {
                     name: installed_receipt.name.clone(),
                     requirement,
+                    features: Vec::new(),
                 }];
                 let root_names = Vec::new();
                 let resolved = resolve_install_graph(
@@ -924,8 +994,10 @@ fn run_upgrade_command(
                         InstallResolvedOptions {
                             snapshot_id: snapshot_id.as_deref(),
                             force_redownload: false,
+                            verify_files: false,
                             interaction_policy: options.interaction_policy,
                             install_progress_mode: current_install_progress_mode(output_style),
+                            level_receipts: None,
                         },
                         Some(&mut source_build_journal),
                     )?;
@@ -1094,8 +1166,10 @@ fn run_upgrade_command(
                             InstallResolvedOptions {
                                 snapshot_id: snapshot_id.as_deref(),
                                 force_redownload: false,
+                                verify_files: false,
                                 interaction_policy: options.interaction_policy,
                                 install_progress_mode: current_install_progress_mode(output_style),
+                                level_receipts: None,
                             },
                             Some(&mut source_build_journal),
                         )?;
@@ -1173,44 +1247,7 @@ fn transaction_owner_process_alive(txid: &str) -> Result<bool> {
     let Some(pid) = txid_process_id(txid) else {
         return Ok(false);
     };
-
-    #[cfg(unix)]
-    {
-        let status = Command::new("kill")
-            .arg("-0")
-            .arg(pid.to_string())
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-            .with_context(|| format!("failed executing owner liveness probe for pid={pid}"))?;
-        Ok(status.success())
-    }
-
-    #[cfg(windows)]
-    {
-        let output = Command::new("tasklist")
-            .args(["/FI", &format!("PID eq {pid}"), "/FO", "CSV", "/NH"])
-            .output()
-            .with_context(|| format!("failed executing owner liveness probe for pid={pid}"))?;
-
-        if !output.status.success() {
-            return Err(anyhow!(
-                "owner liveness probe failed for pid={pid}: status={} stderr='{}'",
-                output.status,
-                String::from_utf8_lossy(&output.stderr).trim()
-            ));
-        }
-
-        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-        Ok(stdout.contains(&format!(",\"{pid}\""))
-            && !stdout.to_ascii_lowercase().contains("no tasks are running"))
-    }
-
-    #[cfg(not(any(unix, windows)))]
-    {
-        let _ = pid;
-        Ok(true)
-    }
+    process_is_alive(pid)
 }
 
 fn read_transaction_journal_records(
@@ -1319,6 +1356,12 @@ fn snapshot_receipt_path(snapshot_root: &Path, package_name: &str) -> PathBuf {
         .join(format!("{package_name}.receipt"))
 }
 
+fn snapshot_receipt_signature_path(snapshot_root: &Path, package_name: &str) -> PathBuf {
+    snapshot_root
+        .join("receipt")
+        .join(format!("{package_name}.receipt.sig"))
+}
+
 fn snapshot_bin_path(snapshot_root: &Path, bin_name: &str) -> PathBuf {
     snapshot_root.join("bins").join(bin_name)
 }
@@ -1586,8 +1629,21 @@ fn capture_package_state_snapshot(
                 snapshot_receipt_path(&snapshot_root, package_name).display()
             )
         })?;
+        let signature_path = layout.receipt_signature_path(package_name);
+        if signature_path.exists() {
+            std::fs::copy(
+                &signature_path,
+                snapshot_receipt_signature_path(&snapshot_root, package_name),
+            )
+            .with_context(|| {
+                format!(
+                    "failed copying receipt signature snapshot {}",
+                    snapshot_receipt_signature_path(&snapshot_root, package_name).display()
+                )
+            })?;
+        }
 
-        if let Some(receipt) = read_install_receipts(layout)?
+        if let Some(receipt) = read_checked_install_receipts(layout)?
             .into_iter()
             .find(|receipt| receipt.name == package_name)
         {
@@ -1757,7 +1813,7 @@ fn restore_package_state_snapshot(
     snapshot_root: Option<&Path>,
 ) -> Result<()> {
     let package_root = layout.pkgs_dir().join(package_name);
-    let existing_receipt = read_install_receipts(layout)?
+    let existing_receipt = read_checked_install_receipts(layout)?
         .into_iter()
         .find(|receipt| receipt.name == package_name);
     let native_records = read_gui_native_state(layout, package_name)?;
@@ -1809,6 +1865,7 @@ fn restore_package_state_snapshot(
     }
 
     remove_file_if_exists(&layout.receipt_path(package_name))?;
+    remove_file_if_exists(&layout.receipt_signature_path(package_name))?;
     remove_file_if_exists(&layout.declared_services_state_path(package_name))?;
 
     let Some(snapshot_root) = snapshot_root else {
@@ -1843,6 +1900,16 @@ fn restore_package_state_snapshot(
                 )
             })?;
         }
+        let signature_src = snapshot_receipt_signature_path(snapshot_root, package_name);
+        if signature_src.exists() {
+            std::fs::copy(&signature_src, layout.receipt_signature_path(package_name))
+                .with_context(|| {
+                    format!(
+                        "failed restoring receipt signature from {}",
+                        signature_src.display()
+                    )
+                })?;
+        }
     }
 
     for bin_name in bins {
@@ -1960,7 +2027,7 @@ fn replay_rollback_journal(layout: &PrefixLayout, txid: &str) -> Result<bool> {
                 .map(|package_name| (record.seq, package_name.to_string()))
         })
         .collect::<Vec<_>>();
-    compensating_steps.sort_by(|left, right| right.0.cmp(&left.0));
+    compensating_steps.sort_by_key(|step| std::cmp::Reverse(step.0));
 
     if compensating_steps.is_empty() {
         return Ok(false);
@@ -2131,7 +2198,19 @@ fn run_repair_command(layout: &PrefixLayout) -> Result<()> {
     let output_style = current_output_style();
     layout.ensure_base_dirs()?;
 
-    let Some(txid) = read_active_transaction(layout)? else {
+    let reclaimed_txid = reclaim_stale_transaction(layout)?;
+    if let Some(txid) = &reclaimed_txid {
+        println!(
+            "{}",
+            render_status_line(
+                output_style,
+                "step",
+                &format!("repair: reclaimed marker {txid} from exited owner process")
+            )
+        );
+    }
+
+    let Some(txid) = read_active_transaction(layout)?.or(reclaimed_txid) else {
         println!(
             "{}",
             render_status_line(output_style, "step", "repair: no action needed")
@@ -2185,6 +2264,81 @@ fn run_repair_command(layout: &PrefixLayout) -> Result<()> {
     }
 }
 
+fn run_reexpose_command(layout: &PrefixLayout, backend: &MetadataBackend) -> Result<()> {
+    ensure_no_active_transaction_for(layout, "reexpose")?;
+    let report = reexpose_all(layout, |receipt| {
+        let target = receipt
+            .target
+            .clone()
+            .unwrap_or_else(|| host_target_triple().to_string());
+        let manifest = backend
+            .package_versions(&receipt.name)?
+            .into_iter()
+            .find(|manifest| manifest.version.to_string() == receipt.version)
+            .ok_or_else(|| {
+                anyhow!(
+                    "no registry manifest found for {} {}",
+                    receipt.name,
+                    receipt.version
+                )
+            })?;
+        manifest
+            .artifact_for_target(&target)
+            .cloned()
+            .ok_or_else(|| {
+                anyhow!(
+                    "no artifact available for target {target} in {} {}",
+                    receipt.name,
+                    receipt.version
+                )
+            })
+    })?;
+
+    for line in format_reexpose_report_lines(current_output_style(), &report) {
+        println!("{line}");
+    }
+    if report.has_errors() {
+        let failed = report
+            .packages
+            .iter()
+            .filter(|package| !package.errors.is_empty())
+            .count();
+        return Err(anyhow!("reexpose failed for {failed} package(s)"));
+    }
+    Ok(())
+}
+
+fn format_reexpose_report_lines(style: OutputStyle, report: &ReexposeReport) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut changed = 0;
+    for package in &report.packages {
+        for path in &package.changed {
+            lines.push(render_status_line(
+                style,
+                "ok",
+                &format!("reexposed {} {}: {path}", package.name, package.version),
+            ));
+        }
+        for error in &package.errors {
+            lines.push(render_status_line(
+                style,
+                "error",
+                &format!("reexpose {} {}: {error}", package.name, package.version),
+            ));
+        }
+        changed += package.changed.len();
+    }
+    lines.push(render_status_line(
+        style,
+        "step",
+        &format!(
+            "reexpose: packages={} changed={changed}",
+            report.packages.len()
+        ),
+    ));
+    lines
+}
+
 fn run_uninstall_command(layout: &PrefixLayout, name: String) -> Result<()> {
     let output_style = current_output_style();
     let renderer = TerminalRenderer::from_style(output_style);
@@ -2196,7 +2350,7 @@ fn run_uninstall_command(layout: &PrefixLayout, name: String) -> Result<()> {
     execute_with_transaction(layout, "uninstall", None, |tx| {
         let mut journal_seq = 1_u64;
         let mut snapshot_paths = HashMap::new();
-        for receipt in read_install_receipts(layout)? {
+        for receipt in read_checked_install_receipts(layout)? {
             let snapshot_path = capture_package_state_snapshot(layout, &tx.txid, &receipt.name)?;
             snapshot_paths.insert(receipt.name, snapshot_path);
         }
@@ -2297,10 +2451,14 @@ fn run_uninstall_command(layout: &PrefixLayout, name: String) -> Result<()> {
     Ok(())
 }
 
-fn run_update_command(store: &RegistrySourceStore, registry: &[String]) -> Result<()> {
+fn run_update_command(
+    store: &RegistrySourceStore,
+    registry: &[String],
+    revalidate: bool,
+) -> Result<()> {
     let renderer = TerminalRenderer::current();
     let output_style = renderer.style();
-    let results = store.update_sources(registry)?;
+    let results = store.update_sources_with_revalidate(registry, revalidate)?;
     let report = build_update_report(&results);
     let update_output = plan_update_output(&report, output_style);
     let UpdateOutputPlan {
@@ -2361,7 +2519,7 @@ fn run_self_update_command(
         renderer.print_status("step", "self-update: refreshing source snapshots");
         let source_state_root = registry_state_root(layout);
         let store = RegistrySourceStore::new(&source_state_root);
-        run_update_command(&store, &[])?;
+        run_update_command(&store, &[], false)?;
         completed_steps = 1;
     }
 
@@ -2461,6 +2619,23 @@ fn format_registry_add_lines(
     ]
 }
 
+fn confirm_discovered_fingerprint(
+    mut input: impl std::io::BufRead,
+    mut prompt: impl Write,
+    name: &str,
+) -> Result<bool> {
+    write!(prompt, "record this fingerprint for source '{name}'? [y/N] ")?;
+    prompt.flush()?;
+    let mut answer = String::new();
+    input
+        .read_line(&mut answer)
+        .context("failed reading fingerprint confirmation")?;
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
 fn format_registry_remove_lines(name: &str, purge_cache: bool) -> Vec<String> {
     let cache_state = if purge_cache { "purged" } else { "kept" };
     vec![
@@ -2472,7 +2647,7 @@ fn format_registry_remove_lines(name: &str, purge_cache: bool) -> Vec<String> {
 fn format_registry_list_snapshot_state(snapshot: &RegistrySourceSnapshotState) -> String {
     match snapshot {
         RegistrySourceSnapshotState::None => "none".to_string(),
-        RegistrySourceSnapshotState::Ready { snapshot_id } => format!("ready:{snapshot_id}"),
+        RegistrySourceSnapshotState::Ready { snapshot_id, .. } => format!("ready:{snapshot_id}"),
         RegistrySourceSnapshotState::Error { reason_code, .. } => format!("error:{reason_code}"),
     }
 }
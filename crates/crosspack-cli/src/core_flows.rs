@@ -29,6 +29,34 @@ fn parse_pin_spec(spec: &str) -> Result<(String, VersionReq)> {
     Ok((name.to_string(), requirement))
 }
 
+fn write_pin_checked(
+    layout: &PrefixLayout,
+    name: &str,
+    requirement: &VersionReq,
+    index: &MetadataBackend,
+) -> Result<PathBuf> {
+    let versions = index.package_versions(name)?;
+    if versions.is_empty() {
+        return Err(anyhow!(
+            "cannot pin '{name}': package not found in configured registries"
+        ));
+    }
+    if !versions
+        .iter()
+        .any(|manifest| requirement.matches(&manifest.version))
+    {
+        let available = versions
+            .iter()
+            .map(|manifest| manifest.version.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(anyhow!(
+            "cannot pin '{name}' to '{requirement}': no available version satisfies it (available: {available})"
+        ));
+    }
+    write_pin(layout, name, &requirement.to_string())
+}
+
 fn parse_provider_overrides(values: &[String]) -> Result<BTreeMap<String, String>> {
     let mut overrides = BTreeMap::new();
     for value in values {
@@ -226,6 +254,10 @@ struct ResolvedInstall {
     resolved_target: String,
     archive_type: ArchiveType,
     source_build: Option<SourceBuildPlan>,
+    /// Dependency level from `ResolvedGraph::install_levels`.
+    install_level: usize,
+    /// Non-fatal resolver advisories for this package, such as deprecation notices.
+    advisories: Vec<Advisory>,
 }
 
 #[derive(Debug, Clone)]
@@ -351,6 +383,7 @@ impl TransactionPreviewMode {
 struct RootInstallRequest {
     name: String,
     requirement: VersionReq,
+    features: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -529,6 +562,102 @@ fn ensure_no_active_transaction(layout: &PrefixLayout) -> Result<()> {
     Ok(())
 }
 
+fn doctor_integrity_lines(layout: &PrefixLayout, fix: bool) -> Result<Vec<String>> {
+    let issues = verify_prefix_integrity(layout)?;
+    let mut lines = vec![format!("integrity: issues={}", issues.len())];
+    if !fix {
+        for issue in &issues {
+            let (kind, subject) = integrity_issue_fields(issue);
+            lines.push(format!("integrity_issue kind={kind} subject={subject}"));
+        }
+        return Ok(lines);
+    }
+
+    for outcome in repair_prefix_integrity(layout, &issues)? {
+        let (kind, subject) = integrity_issue_fields(&outcome.issue);
+        let action = match outcome.action {
+            RepairAction::Removed => "removed",
+            RepairAction::AlreadyResolved => "already-resolved",
+            RepairAction::NeedsRedownload => "needs-redownload",
+        };
+        lines.push(format!(
+            "integrity_repair kind={kind} subject={subject} action={action}"
+        ));
+    }
+    Ok(lines)
+}
+
+fn integrity_issue_fields(issue: &IntegrityIssue) -> (&'static str, String) {
+    match issue {
+        IntegrityIssue::OrphanedBinary { name } => ("orphaned-binary", name.clone()),
+        IntegrityIssue::OrphanedCompletion { rel_path } => {
+            ("orphaned-completion", rel_path.clone())
+        }
+        IntegrityIssue::OrphanedGuiAsset { rel_path } => ("orphaned-gui-asset", rel_path.clone()),
+        IntegrityIssue::StaleReceipt { name, version } => {
+            ("stale-receipt", format!("{name}@{version}"))
+        }
+        IntegrityIssue::VersionMismatch {
+            name,
+            recorded,
+            found,
+        } => (
+            "version-mismatch",
+            format!("{name}@{recorded}->{}", found.join(",")),
+        ),
+        IntegrityIssue::MissingCache { name, .. } => ("missing-cache", name.clone()),
+        IntegrityIssue::SizeDrift { name, version, .. } => {
+            ("size-drift", format!("{name}@{version}"))
+        }
+    }
+}
+
+// Local diagnostic snapshot for bug reports; nothing is redacted.
+fn dump_prefix_state(layout: &PrefixLayout, store: &RegistrySourceStore) -> Result<String> {
+    let active_transaction = read_active_transaction(layout).and_then(|active| {
+        active
+            .map(|txid| {
+                let status =
+                    read_transaction_metadata(layout, &txid)?.map(|metadata| metadata.status);
+                Ok(serde_json::json!({ "txid": txid, "status": status }))
+            })
+            .transpose()
+    });
+
+    let sources = store.list_sources_with_snapshot_state().map(|entries| {
+        entries
+            .into_iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "source": entry.source,
+                    "snapshot": format_registry_list_snapshot_state(&entry.snapshot),
+                })
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let state = serde_json::json!({
+        "prefix": layout.prefix().display().to_string(),
+        "receipts": dump_state_section(read_install_receipts(layout)),
+        "pins": dump_state_section(read_all_pins(layout)),
+        "gui_states": dump_state_section(read_all_gui_exposure_states(layout)),
+        "active_transaction": dump_state_section(active_transaction),
+        "sources": dump_state_section(sources),
+    });
+    serde_json::to_string_pretty(&state).context("failed to serialize prefix state")
+}
+
+// A section that cannot be read is reported in place so one corrupt state file
+// does not hide the rest of the snapshot.
+fn dump_state_section<T: serde::Serialize>(section: Result<T>) -> Value {
+    match section.and_then(|value| {
+        serde_json::to_value(value).context("failed to serialize prefix state section")
+    }) {
+        Ok(value) => value,
+        Err(err) => serde_json::json!({ "error": format!("{err:#}") }),
+    }
+}
+
 fn doctor_transaction_health_line(layout: &PrefixLayout) -> Result<String> {
     let active_txid = match read_active_transaction(layout) {
         Ok(active_txid) => active_txid,
@@ -604,23 +733,32 @@ fn resolve_install_graph_with_tokens(
     validate_overrides: bool,
     build_from_source: bool,
 ) -> Result<(Vec<ResolvedInstall>, HashSet<String>)> {
-    let mut pins = BTreeMap::new();
-    for (name, raw_req) in read_all_pins(layout)? {
-        let parsed = VersionReq::parse(&raw_req)
-            .with_context(|| format!("invalid pin requirement for '{name}' in state: {raw_req}"))?;
-        pins.insert(name, parsed);
-    }
+    let pins = read_parsed_pins(layout)?;
 
     let root_reqs: Vec<RootRequirement> = roots
         .iter()
         .map(|root| RootRequirement {
             name: root.name.clone(),
             requirement: root.requirement.clone(),
+            features: root.features.clone(),
         })
         .collect();
 
+    let resolved_target = requested_target
+        .map(ToOwned::to_owned)
+        .unwrap_or_else(|| host_target_triple().to_string());
+
+    // Prefer versions with a binary for the target; when none has one, keep the
+    // full list so artifact selection reports why the package is uninstallable.
     let graph = resolve_dependency_graph(&root_reqs, &pins, |package_name| {
-        let versions = index.package_versions(package_name)?;
+        let mut versions = if build_from_source {
+            Vec::new()
+        } else {
+            index.package_versions_for_target(package_name, &resolved_target)?
+        };
+        if versions.is_empty() {
+            versions = index.package_versions(package_name)?;
+        }
         apply_provider_override(package_name, versions, provider_overrides)
     })?;
 
@@ -629,10 +767,12 @@ fn resolve_install_graph_with_tokens(
         validate_provider_overrides_used(provider_overrides, &resolved_dependency_tokens)?;
     }
 
-    let resolved_target = requested_target
-        .map(ToOwned::to_owned)
-        .unwrap_or_else(|| host_target_triple().to_string());
-
+    let install_levels = graph
+        .install_levels()
+        .into_iter()
+        .enumerate()
+        .flat_map(|(level, names)| names.into_iter().map(move |name| (name, level)))
+        .collect::<HashMap<_, _>>();
     let resolved = graph
         .install_order
         .iter()
@@ -656,6 +796,13 @@ fn resolve_install_graph_with_tokens(
                 resolved_target: resolved_target.clone(),
                 archive_type,
                 source_build,
+                install_level: install_levels.get(name).copied().unwrap_or(0),
+                advisories: graph
+                    .advisories
+                    .iter()
+                    .filter(|advisory| &advisory.package == name)
+                    .cloned()
+                    .collect(),
             })
         })
         .collect::<Result<Vec<_>>>()?;
@@ -700,11 +847,7 @@ fn select_install_plan_for_target(
         return Ok((artifact, Some(plan)));
     }
 
-    if let Some(artifact) = manifest
-        .artifacts
-        .iter()
-        .find(|artifact| artifact.target == resolved_target)
-    {
+    if let Some(artifact) = manifest.artifact_for_target(resolved_target) {
         return Ok((artifact.clone(), None));
     }
 
@@ -729,11 +872,7 @@ fn select_source_build_artifact_template(
     manifest: &PackageManifest,
     resolved_target: &str,
 ) -> Result<Artifact> {
-    if let Some(artifact) = manifest
-        .artifacts
-        .iter()
-        .find(|artifact| artifact.target == resolved_target)
-    {
+    if let Some(artifact) = manifest.artifact_for_target(resolved_target) {
         return Ok(artifact.clone());
     }
 
@@ -830,7 +969,7 @@ fn validate_source_build_plan(
     })?;
     if !matches!(
         archive_type,
-        ArchiveType::Zip | ArchiveType::TarGz | ArchiveType::TarZst
+        ArchiveType::Zip | ArchiveType::TarGz | ArchiveType::TarZst | ArchiveType::Tar
     ) {
         return Err(anyhow!(
             "invalid source_build metadata for {} {} on target {}: archive type '{}' is not supported for source builds",
@@ -1175,7 +1314,11 @@ fn validate_install_preflight_for_resolved(
     resolved: &ResolvedInstall,
     receipts: &[InstallReceipt],
 ) -> Result<()> {
-    let replacement_receipts = collect_replacement_receipts(&resolved.manifest, receipts)?;
+    let (replacement_receipts, _) = collect_replacement_receipts_with_policy(
+        &resolved.manifest,
+        receipts,
+        current_replace_policy()?,
+    )?;
     let replacement_targets = replacement_receipts
         .iter()
         .map(|receipt| receipt.name.as_str())
@@ -1264,10 +1407,12 @@ fn sync_native_gui_registration_state_best_effort(
     let mut warnings = Vec::new();
 
     for app in declared_gui_apps {
-        let (records, app_warnings) = register_native_gui_app_best_effort(
+        let icon_path = managed_gui_icon_path(layout, package_name, app)?;
+        let (records, app_warnings) = register_native_gui_app_best_effort_with_icon(
             package_name,
             app,
             install_root,
+            icon_path.as_deref(),
             &previous_records,
         )?;
         current_records.extend(records);
@@ -1298,11 +1443,129 @@ fn sync_native_gui_registration_state_best_effort(
     Ok((current_records, warnings))
 }
 
+// Unchanged GUI assets reuse the recorded registrations, except on a forced
+// reinstall, which is how users repair a broken OS registration.
+fn native_gui_registration_needed(
+    force_redownload: bool,
+    gui_exposure_changed: bool,
+    previous_records: &[GuiNativeRegistrationRecord],
+) -> bool {
+    force_redownload || gui_exposure_changed || previous_records.is_empty()
+}
+
 struct InstallResolvedOptions<'a> {
     snapshot_id: Option<&'a str>,
     force_redownload: bool,
+    verify_files: bool,
     interaction_policy: InstallInteractionPolicy,
     install_progress_mode: InstallProgressMode,
+    // Receipts read once before a parallel level starts, so workers never read
+    // receipts their siblings are rewriting.
+    level_receipts: Option<&'a [InstallReceipt]>,
+}
+
+const MAX_PARALLEL_INSTALL_WORKERS: usize = 4;
+
+// Levels come out in install order; packages inside a level keep their
+// relative install order.
+fn group_install_levels(resolved: &[ResolvedInstall]) -> Vec<Vec<&ResolvedInstall>> {
+    let mut levels: Vec<Vec<&ResolvedInstall>> = Vec::new();
+    for package in resolved {
+        if levels.len() <= package.install_level {
+            levels.resize_with(package.install_level + 1, Vec::new);
+        }
+        levels[package.install_level].push(package);
+    }
+    levels.retain(|level| !level.is_empty());
+    levels
+}
+
+// Source builds append journal entries mid-install, packages with `replaces`
+// uninstall other receipts, and packages claiming the same binary, completion,
+// or GUI asset must keep the sequential collision checks, so such levels run one
+// package at a time.
+fn level_supports_parallel_install(level: &[&ResolvedInstall]) -> bool {
+    if level.len() < 2
+        || level.iter().any(|package| {
+            package.source_build.is_some() || !package.manifest.replaces.is_empty()
+        })
+    {
+        return false;
+    }
+    let mut seen_bins = HashSet::new();
+    let mut seen_completions = HashSet::new();
+    let mut seen_gui_assets = HashSet::new();
+    level.iter().all(|package| {
+        let name = &package.manifest.name;
+        let bins_unique = package
+            .artifact
+            .binaries
+            .iter()
+            .all(|binary| seen_bins.insert(binary.name.clone()));
+        let completions_unique = package.artifact.completions.iter().all(|completion| {
+            projected_exposed_completion_path(name, completion.shell, &completion.path)
+                .is_ok_and(|path| seen_completions.insert(path))
+        });
+        // One app may project several keys onto the same handler file.
+        let mut gui_assets = HashSet::new();
+        for app in &package.artifact.gui_apps {
+            let Ok(assets) = projected_gui_assets(name, app) else {
+                return false;
+            };
+            gui_assets.extend(assets.into_iter().map(|asset| asset.rel_path));
+        }
+        let gui_assets_unique = gui_assets
+            .into_iter()
+            .all(|rel_path| seen_gui_assets.insert(rel_path));
+        bins_unique && completions_unique && gui_assets_unique
+    })
+}
+
+fn parallel_install_worker_count(level_len: usize) -> usize {
+    std::thread::available_parallelism()
+        .map(usize::from)
+        .unwrap_or(1)
+        .clamp(1, MAX_PARALLEL_INSTALL_WORKERS)
+        .min(level_len.max(1))
+}
+
+// Results come back in item order. After a failure no further items start, and
+// the failure at the lowest position is returned once running items finish.
+fn run_bounded_parallel<T, R, F>(items: &[T], max_workers: usize, run: F) -> Result<Vec<R>>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> Result<R> + Sync,
+{
+    let next_item = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let results = Mutex::new(Vec::with_capacity(items.len()));
+    std::thread::scope(|scope| {
+        for _ in 0..max_workers.clamp(1, items.len().max(1)) {
+            scope.spawn(|| {
+                while !failed.load(Ordering::Relaxed) {
+                    let position = next_item.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(position) else {
+                        break;
+                    };
+                    let result = run(item);
+                    if result.is_err() {
+                        failed.store(true, Ordering::Relaxed);
+                    }
+                    results
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .push((position, result));
+                }
+            });
+        }
+    });
+
+    let mut results = results
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    results.sort_by_key(|(position, _)| *position);
+    results.into_iter().map(|(_, result)| result).collect()
 }
 
 struct SourceBuildJournal<'a> {
@@ -1310,10 +1573,26 @@ struct SourceBuildJournal<'a> {
     seq: &'a mut u64,
 }
 
+fn require_verifiable_file_manifest(resolved: &ResolvedInstall) -> Result<&str> {
+    if resolved.source_build.is_some() {
+        return Err(anyhow!(
+            "--verify is not supported for source builds ({})",
+            resolved.manifest.name
+        ));
+    }
+    resolved.artifact.file_manifest.as_deref().ok_or_else(|| {
+        anyhow!(
+            "--verify requires artifact file_manifest for {} on target {}",
+            resolved.manifest.name,
+            resolved.resolved_target
+        )
+    })
+}
+
 fn install_resolved(
     layout: &PrefixLayout,
     resolved: &ResolvedInstall,
-    dependency_receipts: &[String],
+    dependency_receipts: &[ResolvedDependency],
     root_names: &[String],
     planned_dependency_overrides: &HashMap<String, Vec<String>>,
     options: InstallResolvedOptions<'_>,
@@ -1328,13 +1607,24 @@ fn install_resolved(
     );
     progress.update("preflight", 1, None);
 
-    let receipts = read_install_receipts(layout)?;
+    let receipts = match options.level_receipts {
+        Some(receipts) => receipts.to_vec(),
+        None => read_checked_install_receipts(layout)?,
+    };
     validate_install_preflight_for_resolved(layout, resolved, &receipts)?;
 
-    let replacement_receipts = collect_replacement_receipts(&resolved.manifest, &receipts)?;
+    let (replacement_receipts, replacement_warnings) = collect_replacement_receipts_with_policy(
+        &resolved.manifest,
+        &receipts,
+        current_replace_policy()?,
+    )?;
+    let verify_file_manifest = if options.verify_files {
+        Some(require_verifiable_file_manifest(resolved)?)
+    } else {
+        None
+    };
 
     let exposed_bins = collect_declared_binaries(&resolved.artifact)?;
-    let declared_completions = collect_declared_completions(&resolved.artifact)?;
     let declared_gui_apps = collect_declared_gui_apps(&resolved.artifact)?;
 
     let download_url = if let Some(source_build) = resolved.source_build.as_ref() {
@@ -1350,10 +1640,22 @@ fn install_resolved(
         resolved.archive_type,
         download_url,
     )?;
+    let (expected_sha256, checksum_kind) =
+        if let Some(source_build) = resolved.source_build.as_ref() {
+            (source_build.archive_sha256.as_str(), "source archive")
+        } else {
+            (resolved.artifact.sha256.as_str(), "artifact")
+        };
+    let mut download_urls = vec![download_url];
+    if resolved.source_build.is_none() {
+        download_urls.extend(resolved.artifact.mirrors.iter().map(String::as_str));
+    }
     progress.update("download", 2, Some((0, None)));
-    let download_status = download_artifact_with_progress(
-        download_url,
+    let download_status = download_verified_artifact(
+        &download_urls,
         &cache_path,
+        expected_sha256,
+        checksum_kind,
         options.force_redownload,
         |downloaded_bytes, total_bytes| {
             progress.update("download", 2, Some((downloaded_bytes, total_bytes)));
@@ -1373,24 +1675,9 @@ fn install_resolved(
     }
 
     progress.update("verify", 3, None);
-    let (expected_sha256, checksum_kind) =
-        if let Some(source_build) = resolved.source_build.as_ref() {
-            (source_build.archive_sha256.as_str(), "source archive")
-        } else {
-            (resolved.artifact.sha256.as_str(), "artifact")
-        };
-    let checksum_ok = verify_sha256_file(&cache_path, expected_sha256)?;
-    if !checksum_ok {
-        let _ = remove_file_if_exists(&cache_path);
-        return Err(anyhow!(
-            "{checksum_kind} sha256 mismatch for {} (expected {})",
-            cache_path.display(),
-            expected_sha256
-        ));
-    }
 
     progress.update("install", 4, None);
-    let (install_root, selected_install_mode) = if let Some(source_build) =
+    let (install_root, selected_install_mode, installed_size) = if let Some(source_build) =
         resolved.source_build.as_ref()
     {
         let install_root = install_from_source_archive(
@@ -1419,10 +1706,14 @@ fn install_resolved(
                 Some(install_root.display().to_string()),
             )?;
         }
-        (install_root, InstallMode::Managed)
+        (install_root, InstallMode::Managed, None)
     } else {
-        let install_options = build_artifact_install_options(resolved, options.interaction_policy);
-        let install_root = install_from_artifact(
+        let install_options = build_artifact_install_options(
+            resolved,
+            options.interaction_policy,
+            current_install_permissions_policy()?,
+        );
+        let installed = install_from_artifact_with_result(
             layout,
             &resolved.manifest.name,
             &resolved.manifest.version.to_string(),
@@ -1430,9 +1721,42 @@ fn install_resolved(
             resolved.archive_type,
             install_options,
         )?;
-        (install_root, install_options.install_mode)
+        let install_root = installed.dest;
+        if let Some(file_manifest) = verify_file_manifest {
+            if let Err(err) = verify_installed_file_manifest(&install_root, file_manifest) {
+                let _ = std::fs::remove_dir_all(&install_root);
+                return Err(err).with_context(|| {
+                    format!(
+                        "post-install verification failed for {}",
+                        resolved.manifest.name
+                    )
+                });
+            }
+        }
+        (
+            install_root,
+            install_options.install_mode,
+            Some((installed.file_count, installed.total_bytes)),
+        )
     };
 
+    // The tree was just laid out from the checksum-verified artifact, so its
+    // file manifest is recorded outside `pkgs/` for later verification.
+    match resolved
+        .artifact
+        .file_manifest
+        .as_deref()
+        .filter(|_| resolved.source_build.is_none())
+    {
+        Some(file_manifest) => record_installed_file_digests(
+            layout,
+            &resolved.manifest.name,
+            &install_root,
+            file_manifest,
+        )?,
+        None => clear_installed_file_digests(layout, &resolved.manifest.name)?,
+    }
+
     if let Err(err) =
         apply_replacement_handoff(layout, &replacement_receipts, planned_dependency_overrides)
     {
@@ -1440,30 +1764,23 @@ fn install_resolved(
         return Err(err);
     }
 
-    let receipts = read_install_receipts(layout)?;
+    // Parallel levels exclude `replaces`, so the handoff above left the level
+    // snapshot untouched.
+    let receipts = match options.level_receipts {
+        Some(receipts) => receipts.to_vec(),
+        None => read_checked_install_receipts(layout)?,
+    };
 
     progress.update("expose", 5, None);
-    for binary in &resolved.artifact.binaries {
-        expose_binary(layout, &install_root, &binary.name, &binary.path)?;
-    }
-
-    let mut exposed_completions = Vec::with_capacity(declared_completions.len());
-    for completion in &declared_completions {
-        let storage_path = expose_completion(
-            layout,
-            &install_root,
-            &resolved.manifest.name,
-            completion.shell,
-            &completion.path,
-        )?;
-        exposed_completions.push(storage_path);
-    }
-
-    let mut exposed_gui_assets = Vec::new();
-    for app in &declared_gui_apps {
-        let exposed = expose_gui_app(layout, &install_root, &resolved.manifest.name, app)?;
-        exposed_gui_assets.extend(exposed);
-    }
+    let exposure = expose_artifact(
+        layout,
+        &install_root,
+        &resolved.manifest.name,
+        &resolved.artifact,
+    )?;
+    let exposed_completions = exposure.exposed_completions;
+    let exposed_gui_assets = exposure.gui_assets;
+    let mut gui_exposure_changed = exposure.gui_changed;
 
     if let Some(previous_receipt) = receipts
         .iter()
@@ -1484,6 +1801,7 @@ fn install_resolved(
             remove_exposed_completion(layout, stale_completion)?;
         }
     }
+    let exposed_bins = apply_binary_aliases(layout, &resolved.manifest.name, &exposed_bins)?;
 
     let previous_gui_assets = read_gui_exposure_state(layout, &resolved.manifest.name)?;
     for stale_gui_asset in previous_gui_assets.iter().filter(|old| {
@@ -1492,17 +1810,31 @@ fn install_resolved(
             .any(|current| current.rel_path == old.rel_path)
     }) {
         remove_exposed_gui_asset(layout, stale_gui_asset)?;
+        gui_exposure_changed = true;
     }
     write_gui_exposure_state(layout, &resolved.manifest.name, &exposed_gui_assets)?;
 
-    let (native_gui_records, native_gui_warnings) = sync_native_gui_registration_state_best_effort(
-        layout,
-        &resolved.manifest.name,
-        &install_root,
-        &declared_gui_apps,
-    )?;
+    let previous_native_gui_records = read_gui_native_state(layout, &resolved.manifest.name)?;
+    let (native_gui_records, native_gui_warnings) = if native_gui_registration_needed(
+        options.force_redownload,
+        gui_exposure_changed,
+        &previous_native_gui_records,
+    ) {
+        sync_native_gui_registration_state_best_effort(
+            layout,
+            &resolved.manifest.name,
+            &install_root,
+            &declared_gui_apps,
+        )?
+    } else {
+        (previous_native_gui_records, Vec::new())
+    };
 
     progress.update("receipt", 6, None);
+    let (file_count, installed_bytes) = match installed_size {
+        Some(size) => size,
+        None => measure_install_tree(&install_root)?,
+    };
     let receipt = InstallReceipt {
         name: resolved.manifest.name.clone(),
         version: resolved.manifest.version.to_string(),
@@ -1516,7 +1848,11 @@ fn install_resolved(
                 .map(|plan| plan.archive_sha256.clone())
                 .unwrap_or_else(|| resolved.artifact.sha256.clone()),
         ),
-        cache_path: Some(cache_path.display().to_string()),
+        cache_path: Some(receipt_cache_path(
+            layout,
+            &cache_path,
+            current_receipt_cache_path_style()?,
+        )),
         exposed_bins: exposed_bins.clone(),
         exposed_completions: exposed_completions.clone(),
         snapshot_id: options.snapshot_id.map(ToOwned::to_owned),
@@ -1529,6 +1865,9 @@ fn install_resolved(
         ),
         install_status: "installed".to_string(),
         installed_at_unix: current_unix_timestamp()?,
+        file_count: Some(file_count),
+        installed_bytes: Some(installed_bytes),
+        deprecated: resolved.manifest.deprecated.clone(),
     };
     write_declared_services_state(layout, &resolved.manifest.name, &resolved.manifest.services)?;
     let receipt_path = write_install_receipt(layout, &receipt)?;
@@ -1555,7 +1894,13 @@ fn install_resolved(
             .iter()
             .map(|record| record.key.clone())
             .collect(),
-        warnings: native_gui_warnings,
+        warnings: resolved
+            .advisories
+            .iter()
+            .map(ToString::to_string)
+            .chain(replacement_warnings)
+            .chain(native_gui_warnings)
+            .collect(),
     })
 }
 
@@ -2006,32 +2351,205 @@ fn validate_gui_preflight(
     Ok(())
 }
 
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+enum ReplacePolicy {
+    #[default]
+    Lenient,
+    Strict,
+}
+
+const REPLACE_POLICY_ENV: &str = "CROSSPACK_REPLACE_POLICY";
+
+fn current_replace_policy() -> Result<ReplacePolicy> {
+    parse_replace_policy(
+        std::env::var(REPLACE_POLICY_ENV).ok().as_deref(),
+        REPLACE_POLICY_ENV,
+    )
+}
+
+fn parse_replace_policy(value: Option<&str>, env_var_name: &str) -> Result<ReplacePolicy> {
+    let normalized = value.map(str::trim).unwrap_or("");
+    if normalized.is_empty() || normalized.eq_ignore_ascii_case("lenient") {
+        return Ok(ReplacePolicy::Lenient);
+    }
+    if normalized.eq_ignore_ascii_case("strict") {
+        return Ok(ReplacePolicy::Strict);
+    }
+
+    Err(anyhow!(
+        "invalid {} value '{}': expected 'strict' or 'lenient'",
+        env_var_name,
+        normalized
+    ))
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+enum ReceiptCachePathStyle {
+    #[default]
+    Absolute,
+    Relative,
+}
+
+const RECEIPT_CACHE_PATHS_ENV: &str = "CROSSPACK_RECEIPT_CACHE_PATHS";
+
+fn current_receipt_cache_path_style() -> Result<ReceiptCachePathStyle> {
+    parse_receipt_cache_path_style(
+        std::env::var(RECEIPT_CACHE_PATHS_ENV).ok().as_deref(),
+        RECEIPT_CACHE_PATHS_ENV,
+    )
+}
+
+fn parse_receipt_cache_path_style(
+    value: Option<&str>,
+    env_var_name: &str,
+) -> Result<ReceiptCachePathStyle> {
+    let normalized = value.map(str::trim).unwrap_or("");
+    if normalized.is_empty() || normalized.eq_ignore_ascii_case("absolute") {
+        return Ok(ReceiptCachePathStyle::Absolute);
+    }
+    if normalized.eq_ignore_ascii_case("relative") {
+        return Ok(ReceiptCachePathStyle::Relative);
+    }
+
+    Err(anyhow!(
+        "invalid {} value '{}': expected 'absolute' or 'relative'",
+        env_var_name,
+        normalized
+    ))
+}
+
+// Relative receipt paths are resolved back through `PrefixLayout::resolve_cache_path`
+// by every reader, so a relocated prefix keeps its cache references.
+fn receipt_cache_path(
+    layout: &PrefixLayout,
+    cache_path: &Path,
+    style: ReceiptCachePathStyle,
+) -> String {
+    match style {
+        ReceiptCachePathStyle::Relative => layout
+            .portable_cache_path(cache_path)
+            .unwrap_or_else(|| cache_path.display().to_string()),
+        ReceiptCachePathStyle::Absolute => cache_path.display().to_string(),
+    }
+}
+
+const INSTALL_PERMISSIONS_ENV: &str = "CROSSPACK_INSTALL_PERMISSIONS";
+
+fn current_install_permissions_policy() -> Result<InstallPermissionsPolicy> {
+    parse_install_permissions_policy(
+        std::env::var(INSTALL_PERMISSIONS_ENV).ok().as_deref(),
+        INSTALL_PERMISSIONS_ENV,
+    )
+}
+
+fn parse_install_permissions_policy(
+    value: Option<&str>,
+    env_var_name: &str,
+) -> Result<InstallPermissionsPolicy> {
+    let normalized = value.map(str::trim).unwrap_or("");
+    if normalized.is_empty() || normalized.eq_ignore_ascii_case("preserve") {
+        return Ok(InstallPermissionsPolicy::default());
+    }
+    if normalized.eq_ignore_ascii_case("shared") {
+        return Ok(InstallPermissionsPolicy::shared_prefix());
+    }
+
+    Err(anyhow!(
+        "invalid {} value '{}': expected 'preserve' or 'shared'",
+        env_var_name,
+        normalized
+    ))
+}
+
+const RECEIPT_SIGNATURE_POLICY_ENV: &str = "CROSSPACK_RECEIPT_SIGNATURE_POLICY";
+
+fn current_receipt_signature_policy() -> Result<ReceiptSignaturePolicy> {
+    parse_receipt_signature_policy(
+        std::env::var(RECEIPT_SIGNATURE_POLICY_ENV).ok().as_deref(),
+        RECEIPT_SIGNATURE_POLICY_ENV,
+    )
+}
+
+fn parse_receipt_signature_policy(
+    value: Option<&str>,
+    env_var_name: &str,
+) -> Result<ReceiptSignaturePolicy> {
+    let normalized = value.map(str::trim).unwrap_or("");
+    if normalized.is_empty() || normalized.eq_ignore_ascii_case("warn") {
+        return Ok(ReceiptSignaturePolicy::Warn);
+    }
+    if normalized.eq_ignore_ascii_case("enforce") {
+        return Ok(ReceiptSignaturePolicy::Enforce);
+    }
+    if normalized.eq_ignore_ascii_case("ignore") {
+        return Ok(ReceiptSignaturePolicy::Ignore);
+    }
+
+    Err(anyhow!(
+        "invalid {} value '{}': expected 'ignore', 'warn', or 'enforce'",
+        env_var_name,
+        normalized
+    ))
+}
+
+fn read_checked_install_receipts(layout: &PrefixLayout) -> Result<Vec<InstallReceipt>> {
+    let policy = current_receipt_signature_policy()?;
+    let (receipts, warnings) = read_install_receipts_with_signature_policy(layout, policy)?;
+    for warning in warnings {
+        eprintln!("warning: {warning}");
+    }
+    Ok(receipts)
+}
+
 fn collect_replacement_receipts(
     manifest: &PackageManifest,
     receipts: &[InstallReceipt],
 ) -> Result<Vec<InstallReceipt>> {
-    let mut matched = receipts
-        .iter()
-        .filter_map(|receipt| {
-            let requirement = manifest.replaces.get(&receipt.name)?;
-            let installed = Version::parse(&receipt.version).ok()?;
-            requirement.matches(&installed).then_some(receipt.clone())
-        })
-        .collect::<Vec<_>>();
+    let (matched, _) =
+        collect_replacement_receipts_with_policy(manifest, receipts, ReplacePolicy::Lenient)?;
+    Ok(matched)
+}
 
+fn collect_replacement_receipts_with_policy(
+    manifest: &PackageManifest,
+    receipts: &[InstallReceipt],
+    policy: ReplacePolicy,
+) -> Result<(Vec<InstallReceipt>, Vec<String>)> {
+    let mut matched = Vec::new();
+    let mut warnings = Vec::new();
     for receipt in receipts {
-        if manifest.replaces.contains_key(&receipt.name) {
-            Version::parse(&receipt.version).with_context(|| {
-                format!(
-                    "installed receipt for '{}' has invalid version for replacement preflight: {}",
-                    receipt.name, receipt.version
-                )
-            })?;
+        let Some(requirement) = manifest.replaces.get(&receipt.name) else {
+            continue;
+        };
+        let installed = Version::parse(&receipt.version).with_context(|| {
+            format!(
+                "installed receipt for '{}' has invalid version for replacement preflight: {}",
+                receipt.name, receipt.version
+            )
+        })?;
+        if requirement.matches(&installed) {
+            matched.push(receipt.clone());
+            continue;
+        }
+
+        let mismatch = format!(
+            "'{}' declares replaces {}@{} but installed version is {}",
+            manifest.name, receipt.name, requirement, receipt.version
+        );
+        match policy {
+            ReplacePolicy::Strict => {
+                return Err(anyhow!(
+                    "{mismatch}; set {REPLACE_POLICY_ENV}=lenient to keep it installed and proceed"
+                ));
+            }
+            ReplacePolicy::Lenient => {
+                warnings.push(format!("{mismatch}; leaving it installed"));
+            }
         }
     }
 
     matched.sort_by(|left, right| left.name.cmp(&right.name));
-    Ok(matched)
+    Ok((matched, warnings))
 }
 
 fn apply_replacement_handoff(
@@ -2173,7 +2691,7 @@ fn path_matches_current_exe(current_exe: &Path, candidate: &Path) -> bool {
 fn build_dependency_receipts(
     resolved: &ResolvedInstall,
     selected: &[ResolvedInstall],
-) -> Vec<String> {
+) -> Vec<ResolvedDependency> {
     let mut deps = resolved
         .manifest
         .dependencies
@@ -2183,7 +2701,10 @@ fn build_dependency_receipts(
                 .iter()
                 .find(|candidate| candidate.manifest.name == *name)
                 .map(|candidate| {
-                    format!("{}@{}", candidate.manifest.name, candidate.manifest.version)
+                    ResolvedDependency::new(
+                        candidate.manifest.name.clone(),
+                        candidate.manifest.version.to_string(),
+                    )
                 })
         })
         .collect::<Vec<_>>();
@@ -2249,6 +2770,7 @@ fn build_upgrade_roots(receipts: &[InstallReceipt]) -> Vec<RootInstallRequest> {
         .map(|receipt| RootInstallRequest {
             name: receipt.name.clone(),
             requirement: VersionReq::STAR,
+            features: Vec::new(),
         })
         .collect()
 }
@@ -2277,6 +2799,7 @@ fn build_upgrade_plans(receipts: &[InstallReceipt]) -> Vec<UpgradePlan> {
                 .map(|name| RootInstallRequest {
                     name: name.clone(),
                     requirement: VersionReq::STAR,
+                    features: Vec::new(),
                 })
                 .collect::<Vec<_>>();
 
@@ -2338,6 +2861,12 @@ fn format_uninstall_messages(result: &UninstallResult) -> Vec<String> {
             result.pruned_dependencies.join(", ")
         ));
     }
+    if !result.orphaned_dependencies.is_empty() {
+        lines.push(format!(
+            "kept orphan dependencies: {}",
+            result.orphaned_dependencies.join(", ")
+        ));
+    }
 
     lines
 }
@@ -2419,6 +2948,67 @@ where
     )
 }
 
+// Tries each URL in order until one yields bytes matching the expected checksum;
+// a failed download or checksum mismatch moves on to the next mirror.
+fn download_verified_artifact<F>(
+    urls: &[&str],
+    cache_path: &Path,
+    expected_sha256: &str,
+    checksum_kind: &str,
+    force_redownload: bool,
+    mut on_progress: F,
+) -> Result<&'static str>
+where
+    F: FnMut(u64, Option<u64>),
+{
+    // Registry installs always run under the production checksum policy.
+    if is_dev_sha256_sentinel(expected_sha256) {
+        return Err(anyhow!(
+            "{checksum_kind} sha256 '{expected_sha256}' is a dev-mode sentinel and is invalid under the production checksum policy"
+        ));
+    }
+    if cache_path.exists() && !force_redownload {
+        if !verify_sha256_file(cache_path, expected_sha256)? {
+            let _ = remove_file_if_exists(cache_path);
+            return Err(anyhow!(
+                "{checksum_kind} sha256 mismatch for {} (expected {})",
+                cache_path.display(),
+                expected_sha256
+            ));
+        }
+        return Ok("cache-hit");
+    }
+
+    let mut failures = Vec::with_capacity(urls.len());
+    for url in urls {
+        match download_artifact_with_progress(url, cache_path, true, &mut on_progress) {
+            Ok(status) => {
+                if verify_sha256_file(cache_path, expected_sha256)? {
+                    return Ok(status);
+                }
+                let _ = remove_file_if_exists(cache_path);
+                failures.push(anyhow!(
+                    "{checksum_kind} sha256 mismatch for {url} (expected {expected_sha256})"
+                ));
+            }
+            Err(err) => failures.push(err),
+        }
+    }
+
+    if failures.len() == 1 {
+        return Err(failures.remove(0));
+    }
+    Err(anyhow!(
+        "{checksum_kind} download failed from all {} mirrors: {}",
+        failures.len(),
+        failures
+            .iter()
+            .map(|err| err.to_string())
+            .collect::<Vec<_>>()
+            .join("; ")
+    ))
+}
+
 fn download_artifact_with_progress_using<F, InProcessDownload, ExternalDownload>(
     url: &str,
     cache_path: &Path,
@@ -2534,12 +3124,32 @@ where
     ))
 }
 
+fn file_url_path(url: &str) -> Result<Option<PathBuf>> {
+    let Ok(parsed) = reqwest::Url::parse(url) else {
+        return Ok(None);
+    };
+    if parsed.scheme() != "file" {
+        return Ok(None);
+    }
+    parsed
+        .to_file_path()
+        .map(Some)
+        .map_err(|()| anyhow!("download failed for {url}: not a local file path"))
+}
+
 fn download_http_to_path_attempt<F>(url: &str, out_path: &Path, on_progress: &mut F) -> Result<()>
 where
     F: FnMut(u64, Option<u64>),
 {
     const CONNECT_TIMEOUT_SECS: u64 = 10;
 
+    if let Some(local_path) = file_url_path(url)? {
+        let copied = std::fs::copy(&local_path, out_path)
+            .with_context(|| format!("download failed for {url}"))?;
+        on_progress(copied, Some(copied));
+        return Ok(());
+    }
+
     let mut client_builder = reqwest::blocking::Client::builder()
         .connect_timeout(std::time::Duration::from_secs(CONNECT_TIMEOUT_SECS));
     if let Some(request_timeout_secs) = std::env::var("CROSSPACK_DOWNLOAD_REQUEST_TIMEOUT_SECS")
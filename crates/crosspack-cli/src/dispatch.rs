@@ -57,10 +57,19 @@ fn format_registry_list_status_lines(
 
 fn run_cli(cli: Cli) -> Result<()> {
     match cli.command {
-        Commands::Search { query } => {
+        Commands::Search { query, json } => {
             let prefix = default_user_prefix()?;
             let layout = PrefixLayout::new(prefix);
             let backend = select_metadata_backend(cli.registry_root.as_deref(), &layout)?;
+            if json {
+                let installed = read_checked_install_receipts(&layout)?
+                    .into_iter()
+                    .map(|receipt| receipt.name)
+                    .collect::<BTreeSet<_>>();
+                let hits = run_search_hits_command(&backend, &query, &installed)?;
+                println!("{}", format_search_hits_json(&hits)?);
+                return Ok(());
+            }
             let results = run_search_command(&backend, &query)?;
             let lines = format_search_results(&results, &query);
             if results.is_empty() {
@@ -104,7 +113,10 @@ fn run_cli(cli: Cli) -> Result<()> {
             explain,
             build_from_source,
             force_redownload,
+            verify,
+            parallel,
             provider,
+            features,
             escalation,
         } => {
             let (name, requirement) = parse_spec(&spec)?;
@@ -126,7 +138,11 @@ fn run_cli(cli: Cli) -> Result<()> {
                 None => Some(resolve_transaction_snapshot_id(&layout, "install")?),
             };
             if dry_run {
-                let roots = vec![RootInstallRequest { name, requirement }];
+                let roots = vec![RootInstallRequest {
+                    name,
+                    requirement,
+                    features,
+                }];
                 let resolved = resolve_install_graph(
                     &layout,
                     &backend,
@@ -135,7 +151,7 @@ fn run_cli(cli: Cli) -> Result<()> {
                     &provider_overrides,
                     build_from_source,
                 )?;
-                let receipts = read_install_receipts(&layout)?;
+                let receipts = read_checked_install_receipts(&layout)?;
                 for package in &resolved {
                     validate_install_preflight_for_resolved(&layout, package, &receipts)?;
                 }
@@ -160,7 +176,11 @@ fn run_cli(cli: Cli) -> Result<()> {
 
             execute_with_transaction(&layout, "install", snapshot_id.as_deref(), |tx| {
                 let mut journal_seq = 1_u64;
-                let roots = vec![RootInstallRequest { name, requirement }];
+                let roots = vec![RootInstallRequest {
+                    name,
+                    requirement,
+                    features,
+                }];
                 let root_names = roots
                     .iter()
                     .map(|root| root.name.clone())
@@ -188,57 +208,103 @@ fn run_cli(cli: Cli) -> Result<()> {
 
                 let planned_dependency_overrides = build_planned_dependency_overrides(&resolved);
 
-                for package in &resolved {
-                    let snapshot_path =
-                        capture_package_state_snapshot(&layout, &tx.txid, &package.manifest.name)?;
-                    append_transaction_journal_entry(
-                        &layout,
-                        &tx.txid,
-                        &TransactionJournalEntry {
-                            seq: journal_seq,
-                            step: format!("backup_package_state:{}", package.manifest.name),
-                            state: "done".to_string(),
-                            path: Some(snapshot_path.display().to_string()),
-                        },
-                    )?;
-                    journal_seq += 1;
+                // Every package in a level is backed up and journaled before any of
+                // them starts, so a failure anywhere in the level rolls back all of it.
+                let levels = if parallel {
+                    group_install_levels(&resolved)
+                } else {
+                    resolved.iter().map(|package| vec![package]).collect()
+                };
+                for level in &levels {
+                    for package in level {
+                        let snapshot_path = capture_package_state_snapshot(
+                            &layout,
+                            &tx.txid,
+                            &package.manifest.name,
+                        )?;
+                        append_transaction_journal_entry(
+                            &layout,
+                            &tx.txid,
+                            &TransactionJournalEntry {
+                                seq: journal_seq,
+                                step: format!("backup_package_state:{}", package.manifest.name),
+                                state: "done".to_string(),
+                                path: Some(snapshot_path.display().to_string()),
+                            },
+                        )?;
+                        journal_seq += 1;
 
-                    append_transaction_journal_entry(
-                        &layout,
-                        &tx.txid,
-                        &TransactionJournalEntry {
-                            seq: journal_seq,
-                            step: package_apply_step_name(
-                                "install",
-                                &package.manifest.name,
-                                install_mode_for_archive_type(package.archive_type),
-                            ),
-                            state: "done".to_string(),
-                            path: Some(package.manifest.name.clone()),
-                        },
-                    )?;
-                    journal_seq += 1;
+                        append_transaction_journal_entry(
+                            &layout,
+                            &tx.txid,
+                            &TransactionJournalEntry {
+                                seq: journal_seq,
+                                step: package_apply_step_name(
+                                    "install",
+                                    &package.manifest.name,
+                                    install_mode_for_archive_type(package.archive_type),
+                                ),
+                                state: "done".to_string(),
+                                path: Some(package.manifest.name.clone()),
+                            },
+                        )?;
+                        journal_seq += 1;
+                    }
 
-                    let dependencies = build_dependency_receipts(package, &resolved);
-                    let mut source_build_journal = SourceBuildJournal {
-                        txid: &tx.txid,
-                        seq: &mut journal_seq,
-                    };
-                    let outcome = install_resolved(
-                        &layout,
-                        package,
-                        &dependencies,
-                        &root_names,
-                        &planned_dependency_overrides,
-                        InstallResolvedOptions {
-                            snapshot_id: snapshot_id.as_deref(),
-                            force_redownload,
-                            interaction_policy,
-                            install_progress_mode,
-                        },
-                        Some(&mut source_build_journal),
-                    )?;
-                    print_install_outcome(&outcome, output_style);
+                    if level_supports_parallel_install(level) {
+                        let level_receipts = read_checked_install_receipts(&layout)?;
+                        let outcomes = run_bounded_parallel(
+                            level,
+                            parallel_install_worker_count(level.len()),
+                            |package| {
+                                install_resolved(
+                                    &layout,
+                                    package,
+                                    &build_dependency_receipts(package, &resolved),
+                                    &root_names,
+                                    &planned_dependency_overrides,
+                                    InstallResolvedOptions {
+                                        snapshot_id: snapshot_id.as_deref(),
+                                        force_redownload,
+                                        verify_files: verify,
+                                        interaction_policy,
+                                        install_progress_mode: InstallProgressMode::Disabled,
+                                        level_receipts: Some(&level_receipts),
+                                    },
+                                    None,
+                                )
+                            },
+                        )?;
+                        for outcome in &outcomes {
+                            print_install_outcome(outcome, output_style);
+                        }
+                        continue;
+                    }
+
+                    for package in level {
+                        let dependencies = build_dependency_receipts(package, &resolved);
+                        let mut source_build_journal = SourceBuildJournal {
+                            txid: &tx.txid,
+                            seq: &mut journal_seq,
+                        };
+                        let outcome = install_resolved(
+                            &layout,
+                            package,
+                            &dependencies,
+                            &root_names,
+                            &planned_dependency_overrides,
+                            InstallResolvedOptions {
+                                snapshot_id: snapshot_id.as_deref(),
+                                force_redownload,
+                                verify_files: verify,
+                                interaction_policy,
+                                install_progress_mode,
+                                level_receipts: None,
+                            },
+                            Some(&mut source_build_journal),
+                        )?;
+                        print_install_outcome(&outcome, output_style);
+                    }
                 }
 
                 append_transaction_journal_entry(
@@ -293,11 +359,18 @@ fn run_cli(cli: Cli) -> Result<()> {
             let layout = PrefixLayout::new(prefix);
             run_rollback_command(&layout, txid)?;
         }
-        Commands::Repair { escalation } => {
+        Commands::Repair {
+            reexpose,
+            escalation,
+        } => {
             let _escalation_policy = resolve_escalation_policy(escalation);
             let prefix = default_user_prefix()?;
             let layout = PrefixLayout::new(prefix);
             run_repair_command(&layout)?;
+            if reexpose {
+                let backend = select_metadata_backend(cli.registry_root.as_deref(), &layout)?;
+                run_reexpose_command(&layout, &backend)?;
+            }
         }
         Commands::Uninstall { name, escalation } => {
             let _escalation_policy = resolve_escalation_policy(escalation);
@@ -305,10 +378,13 @@ fn run_cli(cli: Cli) -> Result<()> {
             let layout = PrefixLayout::new(prefix);
             run_uninstall_command(&layout, name)?;
         }
-        Commands::List => {
+        Commands::List { since, sizes } => {
             let prefix = default_user_prefix()?;
             let layout = PrefixLayout::new(prefix);
-            let receipts = read_install_receipts(&layout)?;
+            let receipts = match since {
+                Some(since_unix) => list_installed_since(&layout, since_unix)?,
+                None => read_checked_install_receipts(&layout)?,
+            };
             if receipts.is_empty() {
                 println!(
                     "{}",
@@ -316,7 +392,7 @@ fn run_cli(cli: Cli) -> Result<()> {
                 );
             } else {
                 for receipt in receipts {
-                    println!("{} {}", receipt.name, receipt.version);
+                    println!("{}", format_list_line(&receipt, sizes));
                 }
             }
         }
@@ -325,7 +401,8 @@ fn run_cli(cli: Cli) -> Result<()> {
             let prefix = default_user_prefix()?;
             let layout = PrefixLayout::new(prefix);
             layout.ensure_base_dirs()?;
-            let pin_path = write_pin(&layout, &name, &requirement.to_string())?;
+            let backend = select_metadata_backend(cli.registry_root.as_deref(), &layout)?;
+            let pin_path = write_pin_checked(&layout, &name, &requirement, &backend)?;
             for line in
                 format_pin_status_lines(current_output_style(), &name, &requirement, &pin_path)
             {
@@ -381,28 +458,63 @@ fn run_cli(cli: Cli) -> Result<()> {
                     kind,
                     priority,
                     fingerprint,
+                    discover_fingerprint,
+                    yes,
+                    git_ref,
+                    subdir,
                 } => {
                     let source_kind: RegistrySourceKind = kind.into();
                     let kind_label = format_registry_kind(source_kind.clone());
-                    let output_lines = format_registry_add_status_lines(
-                        output_style,
-                        &name,
-                        kind_label,
-                        priority,
-                        &fingerprint,
-                    );
-                    store.add_source(RegistrySourceRecord {
+                    let mut record = RegistrySourceRecord {
                         name,
                         kind: source_kind,
                         location,
-                        fingerprint_sha256: fingerprint,
+                        fingerprint_sha256: fingerprint.unwrap_or_default(),
                         enabled: true,
                         priority,
                         community: None,
-                    })?;
+                        git_ref,
+                        subdir,
+                    };
+                    if discover_fingerprint {
+                        let discovered = store.discover_source_fingerprint(&record)?;
+                        println!("discovered fingerprint: {discovered}");
+                        if !yes {
+                            if !std::io::stdin().is_terminal() {
+                                anyhow::bail!(
+                                    "registry add requires confirmation of discovered fingerprint for source '{}'; verify it and re-run with --yes",
+                                    record.name
+                                );
+                            }
+                            let confirmed = confirm_discovered_fingerprint(
+                                std::io::stdin().lock(),
+                                std::io::stderr(),
+                                &record.name,
+                            )?;
+                            if !confirmed {
+                                anyhow::bail!(
+                                    "registry add cancelled: discovered fingerprint for source '{}' was not confirmed",
+                                    record.name
+                                );
+                            }
+                        }
+                        record.fingerprint_sha256 = discovered;
+                    }
+                    let warnings = store
+                        .add_source_with_duplicate_policy(record.clone(), DuplicateSourcePolicy::Warn)?;
+                    let output_lines = format_registry_add_status_lines(
+                        output_style,
+                        &record.name,
+                        kind_label,
+                        priority,
+                        &record.fingerprint_sha256,
+                    );
                     for line in output_lines {
                         println!("{line}");
                     }
+                    for warning in warnings {
+                        println!("warning: {warning}");
+                    }
                 }
                 RegistryCommands::List => {
                     let sources = store.list_sources_with_snapshot_state()?;
@@ -420,12 +532,15 @@ fn run_cli(cli: Cli) -> Result<()> {
                 }
             }
         }
-        Commands::Update { registry } => {
+        Commands::Update {
+            registry,
+            revalidate,
+        } => {
             let prefix = default_user_prefix()?;
             let layout = PrefixLayout::new(prefix);
             let source_state_root = registry_state_root(&layout);
             let store = RegistrySourceStore::new(&source_state_root);
-            run_update_command(&store, &registry)?;
+            run_update_command(&store, &registry, revalidate)?;
         }
         Commands::SelfUpdate {
             dry_run,
@@ -442,9 +557,27 @@ fn run_cli(cli: Cli) -> Result<()> {
                 escalation,
             )?;
         }
-        Commands::Doctor => {
+        Commands::Doctor {
+            fix,
+            dump_state,
+            sign_receipts,
+        } => {
             let prefix = default_user_prefix()?;
             let layout = PrefixLayout::new(prefix);
+            if dump_state {
+                let store = RegistrySourceStore::new(registry_state_root(&layout));
+                println!("{}", dump_prefix_state(&layout, &store)?);
+                return Ok(());
+            }
+            if sign_receipts {
+                ensure_no_active_transaction_for(&layout, "doctor --sign-receipts")?;
+                enable_receipt_signing(&layout)?;
+                println!(
+                    "receipt signing enabled: key={}",
+                    layout.receipt_signing_key_path().display()
+                );
+                return Ok(());
+            }
             let output_style = current_output_style();
             println!(
                 "{}",
@@ -478,6 +611,12 @@ fn run_cli(cli: Cli) -> Result<()> {
                     &doctor_transaction_health_line(&layout)?
                 )
             );
+            if fix {
+                ensure_no_active_transaction_for(&layout, "doctor --fix")?;
+            }
+            for line in doctor_integrity_lines(&layout, fix)? {
+                println!("{}", render_status_line(output_style, "step", &line));
+            }
         }
         Commands::Version => {
             println!("{}", env!("CARGO_PKG_VERSION"));
@@ -4,8 +4,8 @@ use std::fs::{self, OpenOptions};
 use std::io::{IsTerminal, Read, Write};
 use std::path::{Component, Path, PathBuf};
 use std::process::Command;
-#[cfg(unix)]
-use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Context, Result};
@@ -18,30 +18,38 @@ use crosspack_core::{
 #[cfg(test)]
 use crosspack_installer::read_declared_services_state;
 use crosspack_installer::{
-    append_transaction_journal_entry, bin_path, clear_active_transaction, current_unix_timestamp,
-    default_user_prefix, expose_binary, expose_completion, expose_gui_app, exposed_completion_path,
-    gui_asset_path, install_from_artifact, install_from_source_archive,
+    append_transaction_journal_entry, apply_binary_aliases, bin_path, clear_active_transaction,
+    clear_installed_file_digests, current_unix_timestamp, default_user_prefix,
+    enable_receipt_signing, expose_artifact, exposed_completion_path, gui_asset_path,
+    install_from_artifact_with_result, install_from_source_archive, is_dev_sha256_sentinel,
+    list_installed_since, managed_gui_icon_path, measure_install_tree, process_is_alive,
     projected_exposed_completion_path, projected_gui_assets, read_active_transaction,
     read_all_declared_services_states, read_all_gui_exposure_states, read_all_pins,
     read_gui_exposure_state, read_gui_native_state, read_install_receipts,
-    read_transaction_metadata, register_native_gui_app_best_effort, remove_exposed_binary,
+    read_install_receipts_with_signature_policy, read_transaction_metadata,
+    reclaim_stale_transaction, record_installed_file_digests, reexpose_all,
+    register_native_gui_app_best_effort_with_icon, remove_exposed_binary,
     remove_exposed_completion, remove_exposed_gui_asset, remove_file_if_exists,
-    remove_native_gui_registration_best_effort, run_native_service_action,
+    remove_native_gui_registration_best_effort, repair_prefix_integrity, run_native_service_action,
     run_package_native_uninstall_actions, set_active_transaction,
     uninstall_blocked_by_roots_with_dependency_overrides_and_ignored_roots, uninstall_package,
     uninstall_package_with_dependency_overrides_and_ignored_roots, update_transaction_status,
-    write_declared_services_state, write_gui_exposure_state, write_gui_native_state,
-    write_install_receipt, write_pin, write_transaction_metadata, ArtifactInstallOptions,
+    verify_installed_file_manifest, verify_prefix_integrity, write_declared_services_state,
+    write_gui_exposure_state, write_gui_native_state, write_install_receipt, write_pin,
+    write_transaction_metadata, ArchiveLimits, ArtifactInstallOptions, ChecksumPolicy,
     GuiExposureAsset, GuiNativeRegistrationRecord, InstallInteractionPolicy, InstallMode,
-    InstallReason, InstallReceipt, NativeServiceAction, NativeServiceOutcome, PrefixLayout,
-    TransactionJournalEntry, TransactionMetadata, UninstallResult, UninstallStatus,
+    InstallPermissionsPolicy, InstallReason, InstallReceipt, IntegrityIssue, NativeServiceAction,
+    NativeServiceOutcome, PrefixLayout, ReceiptSignaturePolicy, ReexposeReport, RepairAction,
+    ResolvedDependency, TransactionJournalEntry, TransactionMetadata, UninstallResult,
+    UninstallStatus,
 };
 use crosspack_registry::{
-    ConfiguredRegistryIndex, RegistryIndex, RegistrySourceKind, RegistrySourceRecord,
-    RegistrySourceSnapshotState, RegistrySourceStore, RegistrySourceWithSnapshotState,
-    SourceUpdateResult, SourceUpdateStatus,
+    ConfiguredRegistryIndex, DuplicateSourcePolicy, RegistryIndex, RegistrySourceKind,
+    RegistrySourceRecord, RegistrySourceSnapshotState, RegistrySourceStore,
+    RegistrySourceWithSnapshotState, SearchHit, SecurityPolicy, SourceUpdateResult,
+    SourceUpdateStatus,
 };
-use crosspack_resolver::{resolve_dependency_graph, RootRequirement};
+use crosspack_resolver::{resolve_dependency_graph, Advisory, RootRequirement};
 use crosspack_security::verify_sha256_file;
 use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
@@ -370,6 +378,9 @@ fn install_mode_for_archive_type(archive_type: ArchiveType) -> InstallMode {
         ArchiveType::Zip
         | ArchiveType::TarGz
         | ArchiveType::TarZst
+        | ArchiveType::TarBz2
+        | ArchiveType::TarLz4
+        | ArchiveType::Tar
         | ArchiveType::Bin
         | ArchiveType::Dmg
         | ArchiveType::AppImage => InstallMode::Managed,
@@ -384,12 +395,19 @@ fn install_mode_for_archive_type(archive_type: ArchiveType) -> InstallMode {
 fn build_artifact_install_options<'a>(
     resolved: &'a ResolvedInstall,
     interaction_policy: InstallInteractionPolicy,
+    permissions_policy: InstallPermissionsPolicy,
 ) -> ArtifactInstallOptions<'a> {
     ArtifactInstallOptions {
         strip_components: resolved.artifact.strip_components.unwrap_or(0),
         artifact_root: resolved.artifact.artifact_root.as_deref(),
+        file_mappings: &resolved.artifact.files,
         install_mode: install_mode_for_archive_type(resolved.archive_type),
         interaction_policy,
+        permissions_policy,
+        // `download_verified_artifact` has already hashed the cached archive.
+        expected_sha256: None,
+        checksum_policy: ChecksumPolicy::Production,
+        archive_limits: ArchiveLimits::default(),
     }
 }
 
@@ -459,6 +477,8 @@ fn current_output_style() -> OutputStyle {
 enum Commands {
     Search {
         query: String,
+        #[arg(long)]
+        json: bool,
     },
     Info {
         name: String,
@@ -475,8 +495,14 @@ enum Commands {
         build_from_source: bool,
         #[arg(long)]
         force_redownload: bool,
+        #[arg(long)]
+        verify: bool,
+        #[arg(long)]
+        parallel: bool,
         #[arg(long = "provider", value_name = "capability=package")]
         provider: Vec<String>,
+        #[arg(long, value_name = "feature", value_delimiter = ',')]
+        features: Vec<String>,
         #[command(flatten)]
         escalation: EscalationArgs,
     },
@@ -499,6 +525,8 @@ enum Commands {
         escalation: EscalationArgs,
     },
     Repair {
+        #[arg(long)]
+        reexpose: bool,
         #[command(flatten)]
         escalation: EscalationArgs,
     },
@@ -507,7 +535,12 @@ enum Commands {
         #[command(flatten)]
         escalation: EscalationArgs,
     },
-    List,
+    List {
+        #[arg(long)]
+        since: Option<u64>,
+        #[arg(long)]
+        sizes: bool,
+    },
     Pin {
         spec: String,
     },
@@ -540,6 +573,8 @@ enum Commands {
     Update {
         #[arg(long = "registry")]
         registry: Vec<String>,
+        #[arg(long)]
+        revalidate: bool,
     },
     SelfUpdate {
         #[arg(long)]
@@ -549,7 +584,14 @@ enum Commands {
         #[command(flatten)]
         escalation: EscalationArgs,
     },
-    Doctor,
+    Doctor {
+        #[arg(long)]
+        fix: bool,
+        #[arg(long, conflicts_with = "fix")]
+        dump_state: bool,
+        #[arg(long, conflicts_with_all = ["fix", "dump_state"])]
+        sign_receipts: bool,
+    },
     Version,
     Completions {
         shell: CliCompletionShell,
@@ -569,8 +611,16 @@ enum RegistryCommands {
         kind: CliRegistryKind,
         #[arg(long)]
         priority: u32,
+        #[arg(long, required_unless_present = "discover_fingerprint")]
+        fingerprint: Option<String>,
+        #[arg(long, conflicts_with = "fingerprint")]
+        discover_fingerprint: bool,
+        #[arg(long, conflicts_with = "fingerprint")]
+        yes: bool,
+        #[arg(long)]
+        git_ref: Option<String>,
         #[arg(long)]
-        fingerprint: String,
+        subdir: Option<String>,
     },
     List,
     Remove {
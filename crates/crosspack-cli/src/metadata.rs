@@ -19,6 +19,17 @@ impl MetadataBackend {
         }
     }
 
+    fn package_versions_for_target(
+        &self,
+        name: &str,
+        target: &str,
+    ) -> Result<Vec<PackageManifest>> {
+        match self {
+            Self::Legacy(index) => index.package_versions_for_target(name, target),
+            Self::Configured(index) => index.package_versions_for_target(name, target),
+        }
+    }
+
     fn package_versions_with_source(
         &self,
         name: &str,
@@ -35,6 +46,28 @@ impl MetadataBackend {
             Self::Configured(index) => index.package_versions_with_source(name),
         }
     }
+
+    fn search_detailed(&self, query: &str, installed: &BTreeSet<String>) -> Result<Vec<SearchHit>> {
+        match self {
+            Self::Legacy(index) => {
+                let mut hits = Vec::new();
+                for name in index.search_names(query)? {
+                    let manifests = index.package_versions(&name)?;
+                    let Some(latest) = manifests.first() else {
+                        continue;
+                    };
+                    hits.push(SearchHit {
+                        installed: installed.contains(&name),
+                        latest_version: latest.version.to_string(),
+                        name,
+                        source: index.root().display().to_string(),
+                    });
+                }
+                Ok(hits)
+            }
+            Self::Configured(index) => index.search_detailed(query, installed),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -91,6 +124,27 @@ fn run_search_command(backend: &MetadataBackend, query: &str) -> Result<Vec<Sear
     Ok(results)
 }
 
+fn run_search_hits_command(
+    backend: &MetadataBackend,
+    query: &str,
+    installed: &BTreeSet<String>,
+) -> Result<Vec<SearchHit>> {
+    let query = query.trim();
+    let mut hits = backend
+        .search_detailed(query, installed)
+        .with_context(|| SEARCH_METADATA_GUIDANCE)?
+        .into_iter()
+        .filter_map(|hit| classify_search_match(&hit.name, query).map(|kind| (kind, hit)))
+        .collect::<Vec<_>>();
+    hits.sort_by(|(left_kind, left), (right_kind, right)| {
+        left_kind
+            .cmp(right_kind)
+            .then_with(|| left.name.cmp(&right.name))
+            .then_with(|| left.source.cmp(&right.source))
+    });
+    Ok(hits.into_iter().map(|(_, hit)| hit).collect())
+}
+
 fn classify_search_match(name: &str, query: &str) -> Option<SearchMatchKind> {
     if name == query {
         return Some(SearchMatchKind::Exact);
@@ -153,12 +207,30 @@ fn format_search_results(results: &[SearchResult], query: &str) -> Vec<String> {
     lines
 }
 
+fn format_search_hits_json(hits: &[SearchHit]) -> Result<String> {
+    let rows = hits
+        .iter()
+        .map(|hit| {
+            serde_json::json!({
+                "name": hit.name,
+                "latest": hit.latest_version,
+                "source": hit.source,
+                "installed": hit.installed,
+            })
+        })
+        .collect::<Vec<_>>();
+    serde_json::to_string_pretty(&rows).context("failed to serialize search results")
+}
+
 fn select_metadata_backend(
     registry_root_override: Option<&Path>,
     layout: &PrefixLayout,
 ) -> Result<MetadataBackend> {
+    let security_policy = SecurityPolicy::default().with_audit_log(security_audit_log_path(layout));
     if let Some(root) = registry_root_override {
-        return Ok(MetadataBackend::Legacy(RegistryIndex::open(root)));
+        return Ok(MetadataBackend::Legacy(
+            RegistryIndex::open_with_security_policy(root, security_policy),
+        ));
     }
 
     let source_state_root = registry_state_root(layout);
@@ -171,7 +243,8 @@ fn select_metadata_backend(
         anyhow::bail!(METADATA_CONFIG_GUIDANCE);
     }
 
-    let configured = ConfiguredRegistryIndex::open(source_state_root)
+    let configured =
+        ConfiguredRegistryIndex::open_with_security_policy(source_state_root, security_policy)
         .with_context(|| "failed loading configured registry snapshots for metadata commands")?;
     Ok(MetadataBackend::Configured(configured))
 }
@@ -185,7 +258,7 @@ fn resolve_transaction_snapshot_id(layout: &PrefixLayout, operation: &str) -> Re
         .into_iter()
         .filter(|source| source.source.enabled)
         .filter_map(|source| match source.snapshot {
-            RegistrySourceSnapshotState::Ready { snapshot_id } => {
+            RegistrySourceSnapshotState::Ready { snapshot_id, .. } => {
                 Some((source.source.name, snapshot_id))
             }
             _ => None,
@@ -215,6 +288,10 @@ fn snapshot_monitor_log_path(layout: &PrefixLayout) -> PathBuf {
     layout.transactions_dir().join("snapshot-monitor.log")
 }
 
+fn security_audit_log_path(layout: &PrefixLayout) -> PathBuf {
+    layout.transactions_dir().join("security-audit.log")
+}
+
 fn record_snapshot_id_mismatch(
     layout: &PrefixLayout,
     operation: &str,
@@ -290,6 +367,10 @@ fn build_update_report(results: &[SourceUpdateResult]) -> UpdateReport {
                 up_to_date += 1;
                 lines.push(format!("{}: up-to-date", result.name));
             }
+            SourceUpdateStatus::NoChange => {
+                up_to_date += 1;
+                lines.push(format!("{}: up-to-date (revalidated)", result.name));
+            }
             SourceUpdateStatus::Failed => {
                 failed += 1;
                 let reason = update_failure_reason_code(result.error.as_deref());
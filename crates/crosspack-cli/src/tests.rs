@@ -2,6 +2,7 @@
 mod tests {
     use super::*;
     use clap::error::ErrorKind;
+    use crosspack_installer::{expose_binary, ReexposeResult};
     use crosspack_registry::RegistrySourceWithSnapshotStatus;
     use ed25519_dalek::{Signer, SigningKey};
     use std::sync::{
@@ -30,7 +31,8 @@ mod tests {
 
         let active =
             std::fs::read_to_string(layout.transaction_active_path()).expect("must read active");
-        assert_eq!(active.trim(), tx.txid);
+        assert_eq!(active.lines().next(), Some(tx.txid.as_str()));
+        assert!(active.contains(&format!("pid={}", std::process::id())));
 
         let metadata = std::fs::read_to_string(layout.transaction_metadata_path(&tx.txid))
             .expect("must read metadata");
@@ -401,6 +403,47 @@ mod tests {
         let _ = std::fs::remove_dir_all(layout.prefix());
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn run_repair_command_reclaims_marker_owned_by_exited_process() {
+        let layout = test_layout();
+        layout.ensure_base_dirs().expect("must create dirs");
+
+        let mut child = std::process::Command::new("true")
+            .spawn()
+            .expect("must spawn child");
+        let dead_pid = child.id();
+        child.wait().expect("must reap child");
+
+        let metadata = TransactionMetadata {
+            version: 1,
+            txid: "tx-orphaned-repair".to_string(),
+            operation: "install".to_string(),
+            status: "applying".to_string(),
+            started_at_unix: 1_771_001_266,
+            snapshot_id: None,
+        };
+        write_transaction_metadata(&layout, &metadata).expect("must write metadata");
+        std::fs::write(
+            layout.transaction_active_path(),
+            format!("tx-orphaned-repair\npid={dead_pid}\nstarted_at_unix=1771001266\n"),
+        )
+        .expect("must write active marker");
+
+        run_repair_command(&layout).expect("repair must recover orphaned tx");
+
+        let updated = read_transaction_metadata(&layout, "tx-orphaned-repair")
+            .expect("must read updated metadata")
+            .expect("metadata should still exist");
+        assert_eq!(updated.status, "rolled_back");
+        assert_eq!(
+            read_active_transaction(&layout).expect("must read active marker"),
+            None
+        );
+
+        let _ = std::fs::remove_dir_all(layout.prefix());
+    }
+
     #[test]
     fn run_rollback_command_fails_when_journal_replay_required() {
         let layout = test_layout();
@@ -514,6 +557,7 @@ mod tests {
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
         )
         .expect("must write install receipt");
@@ -594,6 +638,7 @@ mod tests {
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         };
         write_install_receipt(&layout, &previous_receipt).expect("must write previous receipt");
         std::fs::write(bin_path(&layout, "demo"), "old-bin").expect("must write old binary");
@@ -689,6 +734,7 @@ mod tests {
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 2,
+            ..InstallReceipt::default()
         };
         write_install_receipt(&layout, &new_receipt).expect("must write new receipt");
         std::fs::write(bin_path(&layout, "demo"), "new-bin").expect("must write new binary");
@@ -882,6 +928,7 @@ mod tests {
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
         )
         .expect("must seed old receipt");
@@ -912,6 +959,7 @@ mod tests {
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 2,
+                ..InstallReceipt::default()
             },
         )
         .expect("must seed current native receipt");
@@ -1334,6 +1382,7 @@ mod tests {
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
         )
         .expect("must seed interrupted native receipt");
@@ -2336,6 +2385,97 @@ mod tests {
         let _ = std::fs::remove_dir_all(layout.prefix());
     }
 
+    #[test]
+    fn dump_prefix_state_includes_every_section() {
+        let layout = test_layout();
+        layout.ensure_base_dirs().expect("must create dirs");
+        configure_ready_source(&layout, "official");
+        write_install_receipt(
+            &layout,
+            &InstallReceipt {
+                name: "ripgrep".to_string(),
+                version: "14.1.0".to_string(),
+                dependencies: Vec::new(),
+                target: Some("x86_64-unknown-linux-gnu".to_string()),
+                artifact_url: None,
+                artifact_sha256: None,
+                cache_path: None,
+                exposed_bins: vec!["rg".to_string()],
+                exposed_completions: Vec::new(),
+                snapshot_id: Some("fs:test".to_string()),
+                install_mode: InstallMode::Managed,
+                install_reason: InstallReason::Root,
+                install_status: "installed".to_string(),
+                installed_at_unix: 1,
+                ..InstallReceipt::default()
+            },
+        )
+        .expect("must write receipt");
+        write_pin(&layout, "ripgrep", "^14").expect("must write pin");
+        write_gui_exposure_state(
+            &layout,
+            "zed",
+            &[GuiExposureAsset {
+                key: "app:dev.zed.zed".to_string(),
+                rel_path: "launchers/zed--dev.zed.Zed.desktop".to_string(),
+            }],
+        )
+        .expect("must write gui state");
+        write_transaction_metadata(
+            &layout,
+            &TransactionMetadata {
+                version: 1,
+                txid: "tx-dump".to_string(),
+                operation: "install".to_string(),
+                status: "applying".to_string(),
+                started_at_unix: 1,
+                snapshot_id: None,
+            },
+        )
+        .expect("must write metadata");
+        set_active_transaction(&layout, "tx-dump").expect("must write active marker");
+
+        let store = RegistrySourceStore::new(registry_state_root(&layout));
+        let dump = dump_prefix_state(&layout, &store).expect("must dump prefix state");
+        let value: Value = serde_json::from_str(&dump).expect("dump must be valid JSON");
+
+        assert_eq!(value["receipts"][0]["name"], "ripgrep");
+        assert_eq!(value["receipts"][0]["exposed_bins"][0], "rg");
+        assert_eq!(value["receipts"][0]["install_reason"], "root");
+        assert_eq!(value["receipts"][0]["install_mode"], "managed");
+        assert_eq!(value["pins"]["ripgrep"], "^14");
+        assert_eq!(value["gui_states"]["zed"][0]["key"], "app:dev.zed.zed");
+        assert_eq!(value["active_transaction"]["txid"], "tx-dump");
+        assert_eq!(value["active_transaction"]["status"], "applying");
+        assert_eq!(value["sources"][0]["source"]["name"], "official");
+        assert_eq!(value["sources"][0]["snapshot"], "ready:fs:test");
+
+        let _ = std::fs::remove_dir_all(layout.prefix());
+    }
+
+    #[test]
+    fn dump_prefix_state_reports_unreadable_section_in_place() {
+        let layout = test_layout();
+        layout.ensure_base_dirs().expect("must create dirs");
+        write_pin(&layout, "ripgrep", "^14").expect("must write pin");
+        std::fs::write(layout.pins_dir().join("broken.pin"), [0xff, 0xfe])
+            .expect("must write unreadable pin");
+
+        let store = RegistrySourceStore::new(registry_state_root(&layout));
+        let dump = dump_prefix_state(&layout, &store).expect("must dump prefix state");
+        let value: Value = serde_json::from_str(&dump).expect("dump must be valid JSON");
+
+        let pins_error = value["pins"]["error"]
+            .as_str()
+            .expect("unreadable pins must be reported as an error");
+        assert!(pins_error.contains("failed to read pin"), "{pins_error}");
+        assert_eq!(value["receipts"], serde_json::json!([]));
+        assert_eq!(value["active_transaction"], Value::Null);
+        assert_eq!(value["sources"], serde_json::json!([]));
+
+        let _ = std::fs::remove_dir_all(layout.prefix());
+    }
+
     #[test]
     fn doctor_transaction_health_line_reports_failed_state() {
         let layout = test_layout();
@@ -2564,6 +2704,88 @@ mod tests {
         let _ = std::fs::remove_dir_all(layout.prefix());
     }
 
+    #[test]
+    fn doctor_integrity_lines_reports_and_repairs_orphaned_binary() {
+        let layout = test_layout();
+        layout.ensure_base_dirs().expect("must create dirs");
+        std::fs::write(
+            bin_path(&layout, "orphan"),
+            format!(
+                "#!/bin/sh\nexec '{}' \"$@\"\n",
+                layout.package_dir("gone", "1.0.0").join("orphan").display()
+            ),
+        )
+        .expect("must write orphan shim");
+        std::fs::write(bin_path(&layout, "user-tool"), b"#!/bin/sh\n")
+            .expect("must write user-owned bin entry");
+
+        let report = doctor_integrity_lines(&layout, false).expect("must report integrity");
+        assert_eq!(
+            report,
+            vec![
+                "integrity: issues=1",
+                "integrity_issue kind=orphaned-binary subject=orphan",
+            ]
+        );
+        assert!(bin_path(&layout, "orphan").exists());
+
+        let repaired = doctor_integrity_lines(&layout, true).expect("must repair integrity");
+        assert_eq!(
+            repaired,
+            vec![
+                "integrity: issues=1",
+                "integrity_repair kind=orphaned-binary subject=orphan action=removed",
+            ]
+        );
+        assert!(!bin_path(&layout, "orphan").exists());
+        assert!(bin_path(&layout, "user-tool").exists());
+        assert_eq!(
+            doctor_integrity_lines(&layout, true).expect("must rerun repair"),
+            vec!["integrity: issues=0"]
+        );
+
+        let _ = std::fs::remove_dir_all(layout.prefix());
+    }
+
+    #[test]
+    fn write_pin_checked_rejects_requirement_without_available_version() {
+        let layout = test_layout();
+        layout.ensure_base_dirs().expect("must create dirs");
+        configure_ready_source(&layout, "official");
+        for version in ["1.0.0", "1.2.0"] {
+            write_signed_test_manifest(&layout, "official", "tool", version, None, None, &[]);
+        }
+        let backend = select_metadata_backend(None, &layout).expect("backend must load");
+
+        let err = write_pin_checked(
+            &layout,
+            "tool",
+            &VersionReq::parse("^2").expect("valid requirement"),
+            &backend,
+        )
+        .expect_err("unsatisfiable pin must be rejected");
+        assert_eq!(
+            err.to_string(),
+            "cannot pin 'tool' to '^2': no available version satisfies it (available: 1.2.0, 1.0.0)"
+        );
+        assert!(!layout.pin_path("tool").exists());
+
+        let pin_path = write_pin_checked(
+            &layout,
+            "tool",
+            &VersionReq::parse("~1.2").expect("valid requirement"),
+            &backend,
+        )
+        .expect("satisfiable pin must be written");
+        assert_eq!(pin_path, layout.pin_path("tool"));
+        assert_eq!(
+            std::fs::read_to_string(&pin_path).expect("must read pin"),
+            "~1.2"
+        );
+
+        let _ = std::fs::remove_dir_all(layout.prefix());
+    }
+
     #[test]
     fn parse_pin_spec_requires_constraint() {
         let err = parse_pin_spec("ripgrep").expect_err("must require constraint");
@@ -2632,6 +2854,8 @@ path = "rg"
             resolved_target: "x86_64-unknown-linux-gnu".to_string(),
             archive_type: ArchiveType::TarGz,
             source_build: None,
+            install_level: 0,
+            advisories: Vec::new(),
         };
 
         let err = validate_install_preflight_for_resolved(&layout, &resolved, &[])
@@ -2663,6 +2887,7 @@ path = "rg"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         }];
 
         let err = validate_binary_preflight(
@@ -2724,6 +2949,7 @@ path = "rg"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         }];
 
         let replacement_targets = HashSet::from(["ripgrep-legacy"]);
@@ -2781,6 +3007,7 @@ path = "rg"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         }];
 
         let err = validate_completion_preflight(
@@ -2846,6 +3073,7 @@ path = "rg"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         }];
 
         validate_completion_preflight(
@@ -3033,6 +3261,7 @@ ripgrep-legacy = "<2.0.0"
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
             InstallReceipt {
                 name: "other".to_string(),
@@ -3049,6 +3278,7 @@ ripgrep-legacy = "<2.0.0"
                 install_reason: InstallReason::Dependency,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
         ];
 
@@ -3058,6 +3288,147 @@ ripgrep-legacy = "<2.0.0"
         assert_eq!(replacements[0].name, "ripgrep-legacy");
     }
 
+    #[test]
+    fn collect_replacement_receipts_applies_replace_policy_at_range_boundary() {
+        let manifest = PackageManifest::from_toml_str(
+            r#"
+name = "ripgrep"
+version = "2.0.0"
+
+[replaces]
+ripgrep-legacy = "<2.0.0"
+"#,
+        )
+        .expect("manifest should parse");
+        let legacy_receipt = |version: &str| InstallReceipt {
+            name: "ripgrep-legacy".to_string(),
+            version: version.to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: vec!["rg".to_string()],
+            exposed_completions: Vec::new(),
+            snapshot_id: None,
+            install_mode: InstallMode::Managed,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 1,
+            ..InstallReceipt::default()
+        };
+
+        let inside = vec![legacy_receipt("1.9.9")];
+        for policy in [ReplacePolicy::Strict, ReplacePolicy::Lenient] {
+            let (matched, warnings) =
+                collect_replacement_receipts_with_policy(&manifest, &inside, policy)
+                    .expect("in-range installed version must be replaced");
+            assert_eq!(matched.len(), 1);
+            assert!(warnings.is_empty());
+        }
+
+        let outside = vec![legacy_receipt("2.0.0")];
+        let err =
+            collect_replacement_receipts_with_policy(&manifest, &outside, ReplacePolicy::Strict)
+                .expect_err("strict policy must reject out-of-range installed version");
+        assert!(err.to_string().contains(
+            "'ripgrep' declares replaces ripgrep-legacy@<2.0.0 but installed version is 2.0.0"
+        ));
+
+        let (matched, warnings) =
+            collect_replacement_receipts_with_policy(&manifest, &outside, ReplacePolicy::Lenient)
+                .expect("lenient policy must proceed");
+        assert!(matched.is_empty());
+        assert_eq!(
+            warnings,
+            vec!["'ripgrep' declares replaces ripgrep-legacy@<2.0.0 but installed version is 2.0.0; leaving it installed"]
+        );
+
+        assert_eq!(
+            parse_replace_policy(None, "CROSSPACK_REPLACE_POLICY").expect("default must parse"),
+            ReplacePolicy::Lenient
+        );
+        assert_eq!(
+            parse_replace_policy(Some("Strict"), "CROSSPACK_REPLACE_POLICY")
+                .expect("strict must parse"),
+            ReplacePolicy::Strict
+        );
+        assert!(parse_replace_policy(Some("loose"), "CROSSPACK_REPLACE_POLICY").is_err());
+    }
+
+    #[test]
+    fn parse_receipt_signature_policy_defaults_to_warn() {
+        let env = "CROSSPACK_RECEIPT_SIGNATURE_POLICY";
+        assert_eq!(
+            parse_receipt_signature_policy(None, env).expect("default must parse"),
+            ReceiptSignaturePolicy::Warn
+        );
+        assert_eq!(
+            parse_receipt_signature_policy(Some("Enforce"), env).expect("enforce must parse"),
+            ReceiptSignaturePolicy::Enforce
+        );
+        assert_eq!(
+            parse_receipt_signature_policy(Some("ignore"), env).expect("ignore must parse"),
+            ReceiptSignaturePolicy::Ignore
+        );
+        let err = parse_receipt_signature_policy(Some("strict"), env)
+            .expect_err("unknown policy must fail");
+        assert!(err.to_string().contains(env));
+    }
+
+    #[test]
+    fn receipt_cache_path_style_relative_round_trips_through_layout() {
+        let env = "CROSSPACK_RECEIPT_CACHE_PATHS";
+        assert_eq!(
+            parse_receipt_cache_path_style(None, env).expect("default must parse"),
+            ReceiptCachePathStyle::Absolute
+        );
+        assert_eq!(
+            parse_receipt_cache_path_style(Some("Relative"), env).expect("relative must parse"),
+            ReceiptCachePathStyle::Relative
+        );
+        let err = parse_receipt_cache_path_style(Some("portable"), env)
+            .expect_err("unknown style must fail");
+        assert!(err.to_string().contains(env));
+
+        let layout = test_layout();
+        let cache_path = layout.artifact_cache_path(
+            "ripgrep",
+            "14.1.0",
+            "x86_64-unknown-linux-gnu",
+            ArchiveType::TarGz,
+        );
+        let relative = receipt_cache_path(&layout, &cache_path, ReceiptCachePathStyle::Relative);
+        assert_eq!(
+            relative,
+            "cache/artifacts/ripgrep/14.1.0/x86_64-unknown-linux-gnu/artifact.tar.gz"
+        );
+        assert_eq!(layout.resolve_cache_path(&relative), cache_path);
+        assert_eq!(
+            safe_artifact_cache_path(&layout, &relative),
+            Some(cache_path.clone())
+        );
+        assert_eq!(
+            receipt_cache_path(&layout, &cache_path, ReceiptCachePathStyle::Absolute),
+            cache_path.display().to_string()
+        );
+    }
+
+    #[test]
+    fn cli_parses_doctor_sign_receipts_and_rejects_fix_combination() {
+        let cli = Cli::try_parse_from(["crosspack", "doctor", "--sign-receipts"])
+            .expect("command must parse");
+        assert!(matches!(
+            cli.command,
+            Commands::Doctor {
+                sign_receipts: true,
+                fix: false,
+                dump_state: false,
+            }
+        ));
+        assert!(Cli::try_parse_from(["crosspack", "doctor", "--sign-receipts", "--fix"]).is_err());
+    }
+
     #[test]
     fn collect_replacement_receipts_rejects_invalid_installed_version() {
         let manifest = PackageManifest::from_toml_str(
@@ -3086,6 +3457,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         }];
 
         let err = collect_replacement_receipts(&manifest, &receipts)
@@ -3103,7 +3475,7 @@ ripgrep-legacy = "*"
         let app = InstallReceipt {
             name: "app".to_string(),
             version: "1.0.0".to_string(),
-            dependencies: vec!["ripgrep-legacy@1.0.0".to_string()],
+            dependencies: vec![ResolvedDependency::new("ripgrep-legacy", "1.0.0")],
             target: None,
             artifact_url: None,
             artifact_sha256: None,
@@ -3115,6 +3487,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         };
         let replaced = InstallReceipt {
             name: "ripgrep-legacy".to_string(),
@@ -3131,6 +3504,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         };
         write_install_receipt(&layout, &app).expect("must seed app receipt");
         write_install_receipt(&layout, &replaced).expect("must seed replaced receipt");
@@ -3158,7 +3532,7 @@ ripgrep-legacy = "*"
         let app = InstallReceipt {
             name: "app".to_string(),
             version: "1.0.0".to_string(),
-            dependencies: vec!["legacy-b@1.0.0".to_string()],
+            dependencies: vec![ResolvedDependency::new("legacy-b", "1.0.0")],
             target: None,
             artifact_url: None,
             artifact_sha256: None,
@@ -3170,6 +3544,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         };
         let legacy_a = InstallReceipt {
             name: "legacy-a".to_string(),
@@ -3186,6 +3561,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Dependency,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         };
         let legacy_b = InstallReceipt {
             name: "legacy-b".to_string(),
@@ -3202,6 +3578,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Dependency,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         };
         write_install_receipt(&layout, &app).expect("must seed app receipt");
         write_install_receipt(&layout, &legacy_a).expect("must seed first replacement target");
@@ -3236,7 +3613,7 @@ ripgrep-legacy = "*"
         let legacy_a = InstallReceipt {
             name: "legacy-a".to_string(),
             version: "1.0.0".to_string(),
-            dependencies: vec!["legacy-b@1.0.0".to_string()],
+            dependencies: vec![ResolvedDependency::new("legacy-b", "1.0.0")],
             target: None,
             artifact_url: None,
             artifact_sha256: None,
@@ -3248,6 +3625,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         };
         let legacy_b = InstallReceipt {
             name: "legacy-b".to_string(),
@@ -3264,6 +3642,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         };
         write_install_receipt(&layout, &legacy_a).expect("must seed first replacement root");
         write_install_receipt(&layout, &legacy_b).expect("must seed second replacement root");
@@ -3292,7 +3671,7 @@ ripgrep-legacy = "*"
         let app = InstallReceipt {
             name: "app".to_string(),
             version: "1.0.0".to_string(),
-            dependencies: vec!["ripgrep-legacy@1.0.0".to_string()],
+            dependencies: vec![ResolvedDependency::new("ripgrep-legacy", "1.0.0")],
             target: None,
             artifact_url: None,
             artifact_sha256: None,
@@ -3304,6 +3683,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         };
         let replaced = InstallReceipt {
             name: "ripgrep-legacy".to_string(),
@@ -3320,6 +3700,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Dependency,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         };
         write_install_receipt(&layout, &app).expect("must seed app receipt");
         write_install_receipt(&layout, &replaced).expect("must seed replaced receipt");
@@ -3361,6 +3742,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         };
         write_install_receipt(&layout, &replaced).expect("must seed replaced receipt");
 
@@ -3393,6 +3775,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         }];
         let resolved = vec![resolved_install("tool", "1.9.0")];
 
@@ -3417,6 +3800,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         }];
         let resolved = vec![resolved_install("tool", "1.2.0")];
         enforce_no_downgrades(&receipts, &resolved, "upgrade").expect("must pass");
@@ -3451,6 +3835,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         }];
 
         let reason = determine_install_reason("shared", &["app".to_string()], &existing, &[]);
@@ -3474,6 +3859,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Dependency,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         }];
 
         let reason = determine_install_reason("shared", &["shared".to_string()], &existing, &[]);
@@ -3497,6 +3883,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Dependency,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         }];
         let replacement = vec![InstallReceipt {
             name: "ripgrep-legacy".to_string(),
@@ -3513,6 +3900,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         }];
 
         let reason = determine_install_reason("ripgrep", &[], &existing, &replacement);
@@ -3536,6 +3924,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         }];
 
         let reason = determine_install_reason("ripgrep", &[], &[], &replacement);
@@ -3548,7 +3937,7 @@ ripgrep-legacy = "*"
             InstallReceipt {
                 name: "app".to_string(),
                 version: "1.0.0".to_string(),
-                dependencies: vec!["shared@1.0.0".to_string()],
+                dependencies: vec![ResolvedDependency::new("shared", "1.0.0")],
                 target: None,
                 artifact_url: None,
                 artifact_sha256: None,
@@ -3560,6 +3949,7 @@ ripgrep-legacy = "*"
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
             InstallReceipt {
                 name: "shared".to_string(),
@@ -3576,6 +3966,7 @@ ripgrep-legacy = "*"
                 install_reason: InstallReason::Dependency,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
         ];
 
@@ -3601,6 +3992,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Dependency,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         }];
 
         let roots = build_upgrade_roots(&receipts);
@@ -3625,6 +4017,7 @@ ripgrep-legacy = "*"
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
             InstallReceipt {
                 name: "mac-tool".to_string(),
@@ -3641,6 +4034,7 @@ ripgrep-legacy = "*"
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
         ];
 
@@ -3658,7 +4052,7 @@ ripgrep-legacy = "*"
             InstallReceipt {
                 name: "app".to_string(),
                 version: "1.0.0".to_string(),
-                dependencies: vec!["shared@1.0.0".to_string()],
+                dependencies: vec![ResolvedDependency::new("shared", "1.0.0")],
                 target: Some("x86_64-unknown-linux-gnu".to_string()),
                 artifact_url: None,
                 artifact_sha256: None,
@@ -3670,6 +4064,7 @@ ripgrep-legacy = "*"
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
             InstallReceipt {
                 name: "shared".to_string(),
@@ -3686,6 +4081,7 @@ ripgrep-legacy = "*"
                 install_reason: InstallReason::Dependency,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
         ];
 
@@ -3713,6 +4109,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Dependency,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         }];
 
         let plans = build_upgrade_plans(&receipts);
@@ -3761,6 +4158,7 @@ ripgrep-legacy = "*"
             version: Some("1.0.0".to_string()),
             status: UninstallStatus::BlockedByDependents,
             pruned_dependencies: Vec::new(),
+            orphaned_dependencies: Vec::new(),
             blocked_by_roots: vec!["app-a".to_string(), "app-b".to_string()],
         };
 
@@ -3778,6 +4176,7 @@ ripgrep-legacy = "*"
             version: Some("1.0.0".to_string()),
             status: UninstallStatus::Uninstalled,
             pruned_dependencies: vec!["shared".to_string(), "zlib".to_string()],
+            orphaned_dependencies: Vec::new(),
             blocked_by_roots: Vec::new(),
         };
 
@@ -3786,6 +4185,27 @@ ripgrep-legacy = "*"
         assert_eq!(lines[1], "pruned orphan dependencies: shared, zlib");
     }
 
+    #[test]
+    fn format_uninstall_messages_reports_kept_orphans() {
+        let result = UninstallResult {
+            name: "app".to_string(),
+            version: Some("1.0.0".to_string()),
+            status: UninstallStatus::Uninstalled,
+            pruned_dependencies: Vec::new(),
+            orphaned_dependencies: vec!["shared".to_string()],
+            blocked_by_roots: Vec::new(),
+        };
+
+        let lines = format_uninstall_messages(&result);
+        assert_eq!(
+            lines,
+            vec![
+                "uninstalled app 1.0.0".to_string(),
+                "kept orphan dependencies: shared".to_string(),
+            ]
+        );
+    }
+
     #[test]
     fn install_defaults_to_auto_escalation_when_interactive() {
         let cli =
@@ -3859,6 +4279,7 @@ ripgrep-legacy = "*"
             ArchiveType::Zip,
             ArchiveType::TarGz,
             ArchiveType::TarZst,
+            ArchiveType::Tar,
             ArchiveType::Bin,
             ArchiveType::Dmg,
             ArchiveType::AppImage,
@@ -3881,6 +4302,20 @@ ripgrep-legacy = "*"
         assert!(interaction_policy.allow_non_prompt_escalation);
     }
 
+    #[test]
+    fn native_gui_registration_needed_always_refreshes_forced_reinstalls() {
+        let recorded = vec![GuiNativeRegistrationRecord {
+            key: "app:demo".to_string(),
+            kind: "desktop-entry".to_string(),
+            path: "/tmp/demo.desktop".to_string(),
+        }];
+
+        assert!(!native_gui_registration_needed(false, false, &recorded));
+        assert!(native_gui_registration_needed(true, false, &recorded));
+        assert!(native_gui_registration_needed(false, true, &recorded));
+        assert!(native_gui_registration_needed(false, false, &[]));
+    }
+
     #[test]
     fn build_artifact_install_options_carries_mode_and_interaction_policy() {
         let mut resolved = resolved_install("demo", "1.0.0");
@@ -3892,12 +4327,37 @@ ripgrep-legacy = "*"
             allow_prompt_escalation: false,
             allow_non_prompt_escalation: true,
         });
-        let options = build_artifact_install_options(&resolved, interaction_policy);
+        let options = build_artifact_install_options(
+            &resolved,
+            interaction_policy,
+            InstallPermissionsPolicy::shared_prefix(),
+        );
 
         assert_eq!(options.strip_components, 2);
         assert_eq!(options.artifact_root, Some("payload"));
         assert_eq!(options.install_mode, InstallMode::Native);
         assert_eq!(options.interaction_policy, interaction_policy);
+        assert_eq!(
+            options.permissions_policy,
+            InstallPermissionsPolicy::shared_prefix()
+        );
+        assert_eq!(options.expected_sha256, None);
+    }
+
+    #[test]
+    fn parse_install_permissions_policy_accepts_preserve_and_shared() {
+        let env = "CROSSPACK_INSTALL_PERMISSIONS";
+        assert_eq!(
+            parse_install_permissions_policy(None, env).expect("default must parse"),
+            InstallPermissionsPolicy::default()
+        );
+        assert_eq!(
+            parse_install_permissions_policy(Some(" Shared "), env).expect("shared must parse"),
+            InstallPermissionsPolicy::shared_prefix()
+        );
+        let err = parse_install_permissions_policy(Some("0755"), env)
+            .expect_err("unknown policy must fail");
+        assert!(err.to_string().contains(env));
     }
 
     #[test]
@@ -3987,6 +4447,29 @@ ripgrep-legacy = "*"
         }
     }
 
+    #[test]
+    fn cli_parses_install_verify_flag_and_requires_file_manifest() {
+        let cli = Cli::try_parse_from(["crosspack", "install", "ripgrep", "--verify"])
+            .expect("command must parse");
+        match cli.command {
+            Commands::Install { verify, .. } => assert!(verify),
+            other => panic!("unexpected command: {other:?}"),
+        }
+
+        let mut resolved = resolved_install("ripgrep", "14.1.0");
+        let err = require_verifiable_file_manifest(&resolved)
+            .expect_err("artifact without file manifest must fail verification preflight");
+        assert!(err
+            .to_string()
+            .contains("--verify requires artifact file_manifest for ripgrep"));
+
+        resolved.artifact.file_manifest = Some("crosspack-files.sha256".to_string());
+        assert_eq!(
+            require_verifiable_file_manifest(&resolved).expect("file manifest must be accepted"),
+            "crosspack-files.sha256"
+        );
+    }
+
     #[test]
     fn cli_parses_install_with_dry_run_explain_flag() {
         let cli =
@@ -4283,7 +4766,11 @@ ripgrep-legacy = "*"
         .expect("command must parse");
 
         match cli.command {
-            Commands::Repair { escalation } => {
+            Commands::Repair {
+                reexpose,
+                escalation,
+            } => {
+                assert!(!reexpose);
                 assert!(escalation.non_interactive);
                 assert!(escalation.allow_escalation);
                 assert!(!escalation.no_escalation);
@@ -4293,15 +4780,50 @@ ripgrep-legacy = "*"
     }
 
     #[test]
-    fn cli_parses_completions_for_each_supported_shell() {
-        let cases = vec![
-            ("bash", CliCompletionShell::Bash),
-            ("zsh", CliCompletionShell::Zsh),
-            ("fish", CliCompletionShell::Fish),
-            ("powershell", CliCompletionShell::Powershell),
-        ];
+    fn cli_parses_repair_reexpose_and_formats_report() {
+        let cli = Cli::try_parse_from(["crosspack", "repair", "--reexpose"])
+            .expect("command must parse");
+        assert!(matches!(
+            cli.command,
+            Commands::Repair { reexpose: true, .. }
+        ));
 
-        for (shell, expected) in cases {
+        let report = ReexposeReport {
+            packages: vec![
+                ReexposeResult {
+                    name: "zed".to_string(),
+                    version: "1.0.0".to_string(),
+                    changed: vec!["bin/zed".to_string()],
+                    errors: Vec::new(),
+                },
+                ReexposeResult {
+                    name: "fd".to_string(),
+                    version: "10.2.0".to_string(),
+                    changed: Vec::new(),
+                    errors: vec!["package directory is missing: /tmp/fd".to_string()],
+                },
+            ],
+        };
+        assert_eq!(
+            format_reexpose_report_lines(OutputStyle::Plain, &report),
+            vec![
+                "reexposed zed 1.0.0: bin/zed",
+                "reexpose fd 10.2.0: package directory is missing: /tmp/fd",
+                "reexpose: packages=2 changed=1",
+            ]
+        );
+    }
+
+    #[test]
+    fn cli_parses_completions_for_each_supported_shell() {
+        let cases = vec![
+            ("bash", CliCompletionShell::Bash),
+            ("zsh", CliCompletionShell::Zsh),
+            ("fish", CliCompletionShell::Fish),
+            ("powershell", CliCompletionShell::Powershell),
+        ];
+
+        for (shell, expected) in cases {
             let cli =
                 Cli::try_parse_from(["crosspack", "completions", shell]).expect("command parses");
             match cli.command {
@@ -4488,7 +5010,7 @@ ripgrep-legacy = "*"
         let root_a = InstallReceipt {
             name: "root-a".to_string(),
             version: "1.0.0".to_string(),
-            dependencies: vec!["shared@1.0.0".to_string()],
+            dependencies: vec![ResolvedDependency::new("shared", "1.0.0")],
             target: Some("x86_64-unknown-linux-gnu".to_string()),
             artifact_url: None,
             artifact_sha256: None,
@@ -4500,11 +5022,12 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         };
         let shared = InstallReceipt {
             name: "shared".to_string(),
             version: "1.0.0".to_string(),
-            dependencies: vec!["leaf@1.0.0".to_string()],
+            dependencies: vec![ResolvedDependency::new("leaf", "1.0.0")],
             target: Some("x86_64-unknown-linux-gnu".to_string()),
             artifact_url: None,
             artifact_sha256: None,
@@ -4516,6 +5039,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Dependency,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         };
         let leaf = InstallReceipt {
             name: "leaf".to_string(),
@@ -4532,6 +5056,7 @@ ripgrep-legacy = "*"
             install_reason: InstallReason::Dependency,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         };
 
         let receipt_map = HashMap::from([
@@ -4586,6 +5111,7 @@ ripgrep-legacy = "*"
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
         )
         .expect("must write receipt");
@@ -4702,6 +5228,7 @@ ripgrep-legacy = "*"
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
         )
         .expect("must write receipt");
@@ -4738,6 +5265,7 @@ ripgrep-legacy = "*"
                     install_reason: InstallReason::Root,
                     install_status: "installed".to_string(),
                     installed_at_unix: 1,
+                    ..InstallReceipt::default()
                 },
             )
             .expect("must write receipt");
@@ -4801,6 +5329,7 @@ ripgrep-legacy = "*"
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
         )
         .expect("must write receipt");
@@ -5030,6 +5559,8 @@ sha256 = "abc"
                 resolved_target: "x86_64-unknown-linux-gnu".to_string(),
                 archive_type: ArchiveType::TarZst,
                 source_build: None,
+                install_level: 0,
+                advisories: Vec::new(),
             },
             ResolvedInstall {
                 artifact: tool_manifest.artifacts[0].clone(),
@@ -5037,6 +5568,8 @@ sha256 = "abc"
                 resolved_target: "x86_64-unknown-linux-gnu".to_string(),
                 archive_type: ArchiveType::TarZst,
                 source_build: None,
+                install_level: 0,
+                advisories: Vec::new(),
             },
         ];
         let receipts = vec![InstallReceipt {
@@ -5054,10 +5587,12 @@ sha256 = "abc"
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         }];
         let roots = vec![RootInstallRequest {
             name: "tool".to_string(),
             requirement: VersionReq::STAR,
+            features: Vec::new(),
         }];
 
         let explainability = build_dependency_policy_explainability(&resolved, &receipts, &roots)
@@ -5127,6 +5662,8 @@ sha256 = "abc"
                 resolved_target: "x86_64-unknown-linux-gnu".to_string(),
                 archive_type: ArchiveType::TarZst,
                 source_build: None,
+                install_level: 0,
+                advisories: Vec::new(),
             },
             ResolvedInstall {
                 artifact: zigcc_manifest.artifacts[0].clone(),
@@ -5134,6 +5671,8 @@ sha256 = "abc"
                 resolved_target: "x86_64-unknown-linux-gnu".to_string(),
                 archive_type: ArchiveType::TarZst,
                 source_build: None,
+                install_level: 0,
+                advisories: Vec::new(),
             },
             ResolvedInstall {
                 artifact: clang_manifest.artifacts[0].clone(),
@@ -5141,12 +5680,15 @@ sha256 = "abc"
                 resolved_target: "x86_64-unknown-linux-gnu".to_string(),
                 archive_type: ArchiveType::TarZst,
                 source_build: None,
+                install_level: 0,
+                advisories: Vec::new(),
             },
         ];
 
         let roots = vec![RootInstallRequest {
             name: "app".to_string(),
             requirement: VersionReq::STAR,
+            features: Vec::new(),
         }];
         let explainability = build_dependency_policy_explainability(&resolved, &[], &roots)
             .expect("must build explainability");
@@ -5219,6 +5761,7 @@ sha256 = "abc"
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 2,
+                ..InstallReceipt::default()
             },
         )
         .expect("must write zeta receipt");
@@ -5239,6 +5782,7 @@ sha256 = "abc"
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 3,
+                ..InstallReceipt::default()
             },
         )
         .expect("must write alpha receipt");
@@ -5259,6 +5803,7 @@ sha256 = "abc"
                 install_reason: InstallReason::Dependency,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
         )
         .expect("must write dependency receipt");
@@ -5306,6 +5851,146 @@ requirement = "^14"
         );
     }
 
+    #[test]
+    fn resolve_install_graph_falls_back_to_version_with_target_artifact() {
+        let layout = test_layout();
+        layout.ensure_base_dirs().expect("must create dirs");
+        configure_ready_source(&layout, "official");
+        for (version, target) in [
+            ("1.0.0", "x86_64-unknown-linux-gnu"),
+            ("2.0.0", "aarch64-apple-darwin"),
+        ] {
+            write_signed_test_manifest_with_targets(
+                &layout,
+                TestManifestSpec {
+                    source_name: "official",
+                    package_name: "tool",
+                    version,
+                    license: None,
+                    homepage: None,
+                    provides: &[],
+                    targets: &[target],
+                },
+            );
+        }
+
+        let backend = select_metadata_backend(None, &layout).expect("backend must load");
+        let roots = vec![RootInstallRequest {
+            name: "tool".to_string(),
+            requirement: VersionReq::STAR,
+            features: Vec::new(),
+        }];
+        let resolved = resolve_install_graph(
+            &layout,
+            &backend,
+            &roots,
+            Some("x86_64-unknown-linux-gnu"),
+            &BTreeMap::new(),
+            false,
+        )
+        .expect("must resolve older installable version");
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].manifest.version.to_string(), "1.0.0");
+        assert_eq!(resolved[0].artifact.target, "x86_64-unknown-linux-gnu");
+
+        let _ = std::fs::remove_dir_all(layout.prefix());
+    }
+
+    #[test]
+    fn install_features_flag_enables_optional_dependencies() {
+        let cli = Cli::try_parse_from(["crosspack", "install", "app", "--features", "tls,h2"])
+            .expect("command must parse");
+        let Commands::Install { features, .. } = cli.command else {
+            panic!("expected install command");
+        };
+        assert_eq!(features, vec!["tls", "h2"]);
+
+        let layout = test_layout();
+        layout.ensure_base_dirs().expect("must create dirs");
+        configure_ready_source(&layout, "official");
+        let target = "x86_64-unknown-linux-gnu";
+        write_signed_test_manifest_content(
+            &layout,
+            "official",
+            "app",
+            "1.0.0",
+            &format!(
+                "{}\n[dependencies]\nopenssl = {{ version = \"^3\", optional = true }}\n\n[features]\ntls = [\"openssl\"]\nh2 = []\n",
+                manifest_toml("app", "1.0.0", None, None, &[], &[target]).trim_end()
+            ),
+        );
+        write_signed_test_manifest(&layout, "official", "openssl", "3.0.0", None, None, &[]);
+        let backend = select_metadata_backend(None, &layout).expect("backend must load");
+
+        let resolve_names = |features: Vec<String>| {
+            let roots = vec![RootInstallRequest {
+                name: "app".to_string(),
+                requirement: VersionReq::STAR,
+                features,
+            }];
+            resolve_install_graph(
+                &layout,
+                &backend,
+                &roots,
+                Some(target),
+                &BTreeMap::new(),
+                false,
+            )
+            .expect("must resolve")
+            .into_iter()
+            .map(|package| package.manifest.name)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(resolve_names(Vec::new()), vec!["app"]);
+        assert_eq!(resolve_names(features), vec!["openssl", "app"]);
+
+        let _ = std::fs::remove_dir_all(layout.prefix());
+    }
+
+    #[test]
+    fn resolve_install_graph_attaches_deprecation_advisories_to_packages() {
+        let layout = test_layout();
+        layout.ensure_base_dirs().expect("must create dirs");
+        configure_ready_source(&layout, "official");
+        let target = "x86_64-unknown-linux-gnu";
+        write_signed_test_manifest_content(
+            &layout,
+            "official",
+            "oldtool",
+            "1.0.0",
+            &format!(
+                "deprecated = \"use newtool instead\"\n{}",
+                manifest_toml("oldtool", "1.0.0", None, None, &[], &[target])
+            ),
+        );
+        let backend = select_metadata_backend(None, &layout).expect("backend must load");
+
+        let resolved = resolve_install_graph(
+            &layout,
+            &backend,
+            &[RootInstallRequest {
+                name: "oldtool".to_string(),
+                requirement: VersionReq::STAR,
+                features: Vec::new(),
+            }],
+            Some(target),
+            &BTreeMap::new(),
+            false,
+        )
+        .expect("deprecated package must still resolve");
+
+        assert_eq!(
+            resolved[0]
+                .advisories
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["oldtool 1.0.0 is deprecated: use newtool instead".to_string()]
+        );
+
+        let _ = std::fs::remove_dir_all(layout.prefix());
+    }
+
     #[test]
     fn bundle_apply_group_plans_reject_cross_target_overlap() {
         let layout = test_layout();
@@ -5797,6 +6482,45 @@ description = "   \n\t"
         );
     }
 
+    #[test]
+    fn cli_parses_list_since_filter() {
+        let cli = Cli::try_parse_from(["crosspack", "list", "--since", "1700000000"])
+            .expect("command must parse");
+        match cli.command {
+            Commands::List { since, sizes } => {
+                assert_eq!(since, Some(1_700_000_000));
+                assert!(!sizes);
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn format_list_line_reports_recorded_sizes_only_when_requested() {
+        let measured = InstallReceipt {
+            name: "ripgrep".to_string(),
+            version: "14.1.0".to_string(),
+            file_count: Some(3),
+            installed_bytes: Some(4096),
+            ..InstallReceipt::default()
+        };
+        let legacy = InstallReceipt {
+            name: "jq".to_string(),
+            version: "1.7.1".to_string(),
+            ..InstallReceipt::default()
+        };
+
+        assert_eq!(format_list_line(&measured, false), "ripgrep 14.1.0");
+        assert_eq!(
+            format_list_line(&measured, true),
+            "ripgrep 14.1.0 files=3 bytes=4096"
+        );
+        assert_eq!(
+            format_list_line(&legacy, true),
+            "jq 1.7.1 files=unknown bytes=unknown"
+        );
+    }
+
     #[test]
     fn cli_parses_registry_add_command() {
         let cli = Cli::try_parse_from([
@@ -5823,21 +6547,107 @@ description = "   \n\t"
                         kind,
                         priority,
                         fingerprint,
+                        discover_fingerprint,
+                        yes,
+                        git_ref,
+                        subdir,
                     },
             } => {
+                assert!(!discover_fingerprint);
+                assert!(!yes);
+                assert_eq!(git_ref, None);
+                assert_eq!(subdir, None);
                 assert_eq!(name, "official");
                 assert_eq!(location, "https://example.com/official.git");
                 assert_eq!(kind, CliRegistryKind::Git);
                 assert_eq!(priority, 10);
                 assert_eq!(
-                    fingerprint,
-                    "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef"
+                    fingerprint.as_deref(),
+                    Some("0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef")
                 );
             }
             other => panic!("unexpected command: {other:?}"),
         }
     }
 
+    #[test]
+    fn cli_registry_add_requires_fingerprint_or_discovery() {
+        let cli = Cli::try_parse_from([
+            "crosspack",
+            "registry",
+            "add",
+            "local",
+            "/srv/registry",
+            "--kind",
+            "filesystem",
+            "--priority",
+            "10",
+            "--discover-fingerprint",
+        ])
+        .expect("discovery must parse without --fingerprint");
+        assert!(matches!(
+            cli.command,
+            Commands::Registry {
+                command: super::RegistryCommands::Add {
+                    fingerprint: None,
+                    discover_fingerprint: true,
+                    ..
+                }
+            }
+        ));
+
+        let err = Cli::try_parse_from([
+            "crosspack",
+            "registry",
+            "add",
+            "local",
+            "/srv/registry",
+            "--kind",
+            "filesystem",
+            "--priority",
+            "10",
+        ])
+        .expect_err("missing fingerprint source should fail");
+        assert!(err.to_string().contains("--fingerprint <FINGERPRINT>"));
+
+        let err = Cli::try_parse_from([
+            "crosspack",
+            "registry",
+            "add",
+            "local",
+            "/srv/registry",
+            "--kind",
+            "filesystem",
+            "--priority",
+            "10",
+            "--fingerprint",
+            "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+            "--yes",
+        ])
+        .expect_err("--yes without discovery should fail");
+        assert!(err.to_string().contains("--yes"));
+    }
+
+    #[test]
+    fn confirm_discovered_fingerprint_accepts_only_explicit_yes() {
+        for (answer, expected) in [
+            ("y\n", true),
+            ("YES\n", true),
+            ("\n", false),
+            ("n\n", false),
+            ("", false),
+        ] {
+            let mut prompt = Vec::new();
+            let confirmed = confirm_discovered_fingerprint(answer.as_bytes(), &mut prompt, "local")
+                .expect("confirmation must read answer");
+            assert_eq!(confirmed, expected, "answer {answer:?}");
+            assert_eq!(
+                String::from_utf8(prompt).expect("prompt must be utf8"),
+                "record this fingerprint for source 'local'? [y/N] "
+            );
+        }
+    }
+
     #[test]
     fn cli_parses_registry_remove_with_purge_cache() {
         let cli = Cli::try_parse_from([
@@ -5943,8 +6753,12 @@ description = "   \n\t"
         .expect("command must parse");
 
         match cli.command {
-            Commands::Update { registry } => {
+            Commands::Update {
+                registry,
+                revalidate,
+            } => {
                 assert_eq!(registry, vec!["official", "mirror"]);
+                assert!(!revalidate);
             }
             other => panic!("unexpected command: {other:?}"),
         }
@@ -5964,9 +6778,12 @@ description = "   \n\t"
                     enabled: true,
                     priority: 10,
                     community: None,
+                    git_ref: None,
+                    subdir: None,
                 },
                 snapshot: RegistrySourceSnapshotState::Ready {
                     snapshot_id: "git:0123456789abcdef".to_string(),
+                    total_bytes: Some(4096),
                 },
             },
             RegistrySourceWithSnapshotState {
@@ -5980,6 +6797,8 @@ description = "   \n\t"
                     enabled: true,
                     priority: 1,
                     community: None,
+                    git_ref: None,
+                    subdir: None,
                 },
                 snapshot: RegistrySourceSnapshotState::None,
             },
@@ -6051,6 +6870,8 @@ description = "   \n\t"
                 enabled: true,
                 priority: 1,
                 community: None,
+                git_ref: None,
+                subdir: None,
             })
             .expect("must add alpha source");
         store
@@ -6063,6 +6884,8 @@ description = "   \n\t"
                 enabled: false,
                 priority: 2,
                 community: None,
+                git_ref: None,
+                subdir: None,
             })
             .expect("must add beta source");
 
@@ -6105,6 +6928,8 @@ description = "   \n\t"
                 enabled: true,
                 priority: 1,
                 community: None,
+                git_ref: None,
+                subdir: None,
             })
             .expect("must add alpha source");
         store
@@ -6117,6 +6942,8 @@ description = "   \n\t"
                 enabled: true,
                 priority: 2,
                 community: None,
+                git_ref: None,
+                subdir: None,
             })
             .expect("must add beta source");
 
@@ -6171,6 +6998,8 @@ description = "   \n\t"
                 enabled: true,
                 priority: 1,
                 community: None,
+                git_ref: None,
+                subdir: None,
             })
             .expect("must add alpha source");
         store
@@ -6183,6 +7012,8 @@ description = "   \n\t"
                 enabled: true,
                 priority: 2,
                 community: None,
+                git_ref: None,
+                subdir: None,
             })
             .expect("must add beta source");
 
@@ -6231,6 +7062,8 @@ description = "   \n\t"
                 enabled: true,
                 priority: 1,
                 community: None,
+                git_ref: None,
+                subdir: None,
             })
             .expect("must add alpha source");
 
@@ -6261,6 +7094,8 @@ description = "   \n\t"
                 enabled: true,
                 priority: 1,
                 community: None,
+                git_ref: None,
+                subdir: None,
             })
             .expect("must add ok source");
         store
@@ -6273,10 +7108,12 @@ description = "   \n\t"
                 enabled: true,
                 priority: 2,
                 community: None,
+                git_ref: None,
+                subdir: None,
             })
             .expect("must add bad source");
 
-        let err = run_update_command(&store, &[]).expect_err("partial failure must return err");
+        let err = run_update_command(&store, &[], false).expect_err("partial failure must return err");
         assert_eq!(err.to_string(), "source update failed");
 
         let _ = std::fs::remove_dir_all(root.prefix());
@@ -6378,6 +7215,125 @@ description = "   \n\t"
         let _ = std::fs::remove_dir_all(layout.prefix());
     }
 
+    #[test]
+    fn list_outdated_reports_pin_held_and_freely_upgradable_roots() {
+        let layout = test_layout();
+        layout.ensure_base_dirs().expect("must create dirs");
+        configure_ready_source(&layout, "official");
+        for (package, version) in [
+            ("current", "1.0.0"),
+            ("pinned", "1.0.0"),
+            ("pinned", "1.4.0"),
+            ("pinned", "2.1.0"),
+            ("free", "1.0.0"),
+            ("free", "3.2.0"),
+        ] {
+            write_signed_test_manifest(&layout, "official", package, version, None, None, &[]);
+        }
+        for (package, version) in [("current", "1.0.0"), ("pinned", "1.0.0"), ("free", "1.0.0")] {
+            write_install_receipt(
+                &layout,
+                &InstallReceipt {
+                    name: package.to_string(),
+                    version: version.to_string(),
+                    dependencies: Vec::new(),
+                    target: Some("x86_64-unknown-linux-gnu".to_string()),
+                    artifact_url: None,
+                    artifact_sha256: None,
+                    cache_path: None,
+                    exposed_bins: Vec::new(),
+                    exposed_completions: Vec::new(),
+                    snapshot_id: None,
+                    install_mode: InstallMode::Managed,
+                    install_reason: InstallReason::Root,
+                    install_status: "installed".to_string(),
+                    installed_at_unix: 1,
+                    ..InstallReceipt::default()
+                },
+            )
+            .expect("must write receipt");
+        }
+        write_pin(&layout, "pinned", "^1").expect("must write pin");
+
+        let backend = select_metadata_backend(None, &layout).expect("configured backend must load");
+        let pins = read_parsed_pins(&layout).expect("pins must parse");
+        let receipts = read_install_receipts(&layout).expect("receipts must load");
+        let entries = list_outdated(&receipts, &backend, &pins).expect("outdated must compute");
+
+        assert_eq!(
+            entries,
+            vec![
+                OutdatedEntry {
+                    name: "free".to_string(),
+                    installed: Version::parse("1.0.0").expect("valid version"),
+                    latest_compatible: Some(Version::parse("3.2.0").expect("valid version")),
+                    latest_available: Version::parse("3.2.0").expect("valid version"),
+                    source: "official".to_string(),
+                },
+                OutdatedEntry {
+                    name: "pinned".to_string(),
+                    installed: Version::parse("1.0.0").expect("valid version"),
+                    latest_compatible: Some(Version::parse("1.4.0").expect("valid version")),
+                    latest_available: Version::parse("2.1.0").expect("valid version"),
+                    source: "official".to_string(),
+                },
+            ]
+        );
+
+        let _ = std::fs::remove_dir_all(layout.prefix());
+    }
+
+    #[test]
+    fn run_search_hits_command_reports_version_source_and_installed_state() {
+        let layout = test_layout();
+        configure_ready_source(&layout, "official");
+        write_signed_test_manifest(&layout, "official", "roundrip", "0.9.0", None, None, &[]);
+        write_signed_test_manifest(&layout, "official", "ripgrep", "14.1.0", None, None, &[]);
+        write_signed_test_manifest(&layout, "official", "rip", "1.0.1", None, None, &[]);
+
+        let backend = select_metadata_backend(None, &layout).expect("configured backend must load");
+        let installed = BTreeSet::from(["ripgrep".to_string()]);
+        let hits = run_search_hits_command(&backend, " rip ", &installed).expect("search must succeed");
+
+        let json = format_search_hits_json(&hits).expect("must serialize");
+        let value: Value = serde_json::from_str(&json).expect("must parse output");
+        assert_eq!(
+            value,
+            serde_json::json!([
+                {
+                    "name": "rip",
+                    "latest": "1.0.1",
+                    "source": "official",
+                    "installed": false
+                },
+                {
+                    "name": "ripgrep",
+                    "latest": "14.1.0",
+                    "source": "official",
+                    "installed": true
+                },
+                {
+                    "name": "roundrip",
+                    "latest": "0.9.0",
+                    "source": "official",
+                    "installed": false
+                }
+            ])
+        );
+
+        let cli = Cli::try_parse_from(["crosspack", "search", "rip", "--json"])
+            .expect("command must parse");
+        match cli.command {
+            Commands::Search { query, json } => {
+                assert_eq!(query, "rip");
+                assert!(json);
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+
+        let _ = std::fs::remove_dir_all(layout.prefix());
+    }
+
     #[test]
     fn best_available_short_description_prefers_manifest_description() {
         let manifest = PackageManifest::from_toml_str(
@@ -6501,6 +7457,31 @@ sha256 = "abc"
         assert_eq!(report.failed, 1);
     }
 
+    #[test]
+    fn build_update_report_distinguishes_revalidated_sources() {
+        let results = vec![
+            SourceUpdateResult {
+                name: "mirror".to_string(),
+                status: SourceUpdateStatus::UpToDate,
+                snapshot_id: "git:abc".to_string(),
+                error: None,
+            },
+            SourceUpdateResult {
+                name: "official".to_string(),
+                status: SourceUpdateStatus::NoChange,
+                snapshot_id: "git:def".to_string(),
+                error: None,
+            },
+        ];
+
+        let report = build_update_report(&results);
+        assert_eq!(
+            report.lines,
+            vec!["mirror: up-to-date", "official: up-to-date (revalidated)"]
+        );
+        assert_eq!(report.up_to_date, 2);
+    }
+
     #[test]
     fn ensure_update_succeeded_returns_err_when_any_source_failed() {
         let err = ensure_update_succeeded(1).expect_err("must return err when failures exist");
@@ -6879,6 +7860,85 @@ sha256 = "abc"
         let _ = std::fs::remove_dir_all(layout.prefix());
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn file_url_path_decodes_local_urls_and_ignores_remote_schemes() {
+        assert_eq!(
+            file_url_path("file:///tmp/with%20space/tool.bin").expect("must parse"),
+            Some(PathBuf::from("/tmp/with space/tool.bin"))
+        );
+        assert_eq!(
+            file_url_path("https://example.test/tool.bin").expect("must parse"),
+            None
+        );
+        let err = file_url_path("file://mirror.example.test/tool.bin")
+            .expect_err("remote file host must fail");
+        assert!(err.to_string().contains("not a local file path"));
+    }
+
+    #[test]
+    fn download_verified_artifact_fails_over_to_mirror_with_matching_checksum() {
+        let _env_lock = download_backend_env_lock()
+            .lock()
+            .expect("download backend env lock must be available");
+        let layout = test_layout();
+        layout.ensure_base_dirs().expect("must create dirs");
+
+        let payload = b"crosspack-mirror-payload".to_vec();
+        let expected_sha256 = crosspack_security::sha256_hex(&payload);
+        let corrupt_path = layout.prefix().join("corrupt.bin");
+        let good_path = layout.prefix().join("good.bin");
+        std::fs::write(&corrupt_path, b"tampered").expect("must write corrupt mirror");
+        std::fs::write(&good_path, &payload).expect("must write good mirror");
+        let file_url = |path: &Path| {
+            reqwest::Url::from_file_path(path)
+                .expect("must build file url")
+                .to_string()
+        };
+        let missing_url = file_url(&layout.prefix().join("missing.bin"));
+        let corrupt_url = file_url(&corrupt_path);
+        let good_url = file_url(&good_path);
+        let cache_path = layout.prefix().join("cache").join("mirrored.bin");
+
+        let status = download_verified_artifact(
+            &[&missing_url, &corrupt_url, &good_url],
+            &cache_path,
+            &expected_sha256,
+            "artifact",
+            false,
+            |_downloaded, _total| {},
+        )
+        .expect("later mirror must succeed");
+        assert_eq!(status, "downloaded");
+        assert_eq!(
+            std::fs::read(&cache_path).expect("must read cache file"),
+            payload
+        );
+
+        std::fs::remove_file(&cache_path).expect("must clear cache file");
+        let err = download_verified_artifact(
+            &[&missing_url, &corrupt_url],
+            &cache_path,
+            &expected_sha256,
+            "artifact",
+            false,
+            |_downloaded, _total| {},
+        )
+        .expect_err("all mirrors failing must be reported");
+        let message = err.to_string();
+        assert!(
+            message.contains("artifact download failed from all 2 mirrors"),
+            "unexpected error: {message}"
+        );
+        assert!(
+            message.contains(&format!("artifact sha256 mismatch for {corrupt_url}")),
+            "unexpected error: {message}"
+        );
+        assert!(!cache_path.exists());
+
+        let _ = std::fs::remove_dir_all(layout.prefix());
+    }
+
     #[test]
     fn parse_download_backend_preference_defaults_to_in_process() {
         let backend = parse_download_backend_preference(None, "CROSSPACK_DOWNLOAD_BACKEND")
@@ -7354,8 +8414,10 @@ sha256 = "abc"
             InstallResolvedOptions {
                 snapshot_id: None,
                 force_redownload: false,
+                verify_files: false,
                 interaction_policy: InstallInteractionPolicy::default(),
                 install_progress_mode: InstallProgressMode::Disabled,
+                level_receipts: None,
             },
             None,
         )
@@ -7390,8 +8452,10 @@ sha256 = "abc"
             InstallResolvedOptions {
                 snapshot_id: None,
                 force_redownload: false,
+                verify_files: false,
                 interaction_policy: InstallInteractionPolicy::default(),
                 install_progress_mode: InstallProgressMode::Disabled,
+                level_receipts: None,
             },
             None,
         )
@@ -7410,11 +8474,12 @@ sha256 = "abc"
             &str,
             &ArtifactGuiApp,
             &Path,
+            Option<&Path>,
             &[GuiNativeRegistrationRecord],
         )
             -> Result<(Vec<GuiNativeRegistrationRecord>, Vec<String>)>;
 
-        let _register: RegisterNativeGuiFn = register_native_gui_app_best_effort;
+        let _register: RegisterNativeGuiFn = register_native_gui_app_best_effort_with_icon;
     }
 
     #[test]
@@ -7585,6 +8650,7 @@ sha256 = "abc"
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
         )
         .expect("must write receipt");
@@ -7714,6 +8780,7 @@ install_commands = ["cargo", "install", "--path", "."]
                 install_reason: InstallReason::Root,
                 install_status: "installed".to_string(),
                 installed_at_unix: 1,
+                ..InstallReceipt::default()
             },
         )
         .expect("must write installed receipt");
@@ -8179,6 +9246,8 @@ install_commands = ["sh", "-c", "true"]
                     .archive_type()
                     .expect("artifact archive type must parse"),
                 source_build: None,
+                install_level: 0,
+                advisories: Vec::new(),
             }],
             &[],
         )
@@ -8199,6 +9268,8 @@ install_commands = ["sh", "-c", "true"]
                 resolved_target: target,
                 archive_type: ArchiveType::Zip,
                 source_build,
+                install_level: 0,
+                advisories: Vec::new(),
             }],
             &[],
         )
@@ -8216,6 +9287,264 @@ install_commands = ["sh", "-c", "true"]
         assert!(source_lines[3].starts_with("change_add "));
     }
 
+    #[test]
+    fn parallel_install_levels_run_independent_roots_together_after_dependencies() {
+        let lib = resolved_install("lib", "1.0.0");
+        let mut app_a = resolved_install("app-a", "1.0.0");
+        app_a.install_level = 1;
+        let mut app_b = resolved_install("app-b", "1.0.0");
+        app_b.install_level = 1;
+        let resolved = vec![lib, app_a, app_b];
+
+        let levels = group_install_levels(&resolved);
+        let level_names = levels
+            .iter()
+            .map(|level| {
+                level
+                    .iter()
+                    .map(|package| package.manifest.name.as_str())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(level_names, vec![vec!["lib"], vec!["app-a", "app-b"]]);
+        assert!(!level_supports_parallel_install(&levels[0]));
+        assert!(level_supports_parallel_install(&levels[1]));
+
+        // Both roots must be inside `run` at once to pass the barrier.
+        let barrier = std::sync::Barrier::new(2);
+        let events = Mutex::new(Vec::new());
+        for level in &levels {
+            let names = run_bounded_parallel(level, 2, |package| {
+                let name = package.manifest.name.clone();
+                events
+                    .lock()
+                    .expect("events lock")
+                    .push(format!("start:{name}"));
+                if package.install_level == 1 {
+                    barrier.wait();
+                }
+                events.lock().expect("events lock").push(format!("end:{name}"));
+                Ok(name)
+            })
+            .expect("level must install");
+            assert_eq!(
+                names,
+                level
+                    .iter()
+                    .map(|package| package.manifest.name.clone())
+                    .collect::<Vec<_>>()
+            );
+        }
+
+        let events = events.into_inner().expect("events lock");
+        let position = |event: &str| {
+            events
+                .iter()
+                .position(|recorded| recorded == event)
+                .expect("event must be recorded")
+        };
+        assert!(position("end:lib") < position("start:app-a"));
+        assert!(position("end:lib") < position("start:app-b"));
+    }
+
+    #[test]
+    fn parallel_install_level_rejects_replaces_and_shared_binaries() {
+        let app_a = resolved_install("app-a", "1.0.0");
+        let app_b = resolved_install("app-b", "1.0.0");
+        assert!(level_supports_parallel_install(&[&app_a, &app_b]));
+
+        let mut replacing = resolved_install("app-b", "1.0.0");
+        replacing
+            .manifest
+            .replaces
+            .insert("app-legacy".to_string(), VersionReq::STAR);
+        assert!(!level_supports_parallel_install(&[&app_a, &replacing]));
+
+        let mut shared_a = resolved_install("app-a", "1.0.0");
+        let mut shared_b = resolved_install("app-b", "1.0.0");
+        for package in [&mut shared_a, &mut shared_b] {
+            package.artifact.binaries.push(crosspack_core::ArtifactBinary {
+                name: "app".to_string(),
+                path: "bin/app".to_string(),
+                interpreter: None,
+                env: BTreeMap::new(),
+            });
+        }
+        assert!(!level_supports_parallel_install(&[&shared_a, &shared_b]));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn parallel_install_level_installs_real_artifacts_concurrently() {
+        let layout = test_layout();
+        layout.ensure_base_dirs().expect("must create dirs");
+        let target = "x86_64-unknown-linux-gnu";
+        let mut level_packages = Vec::new();
+        for name in ["app-a", "app-b"] {
+            let fixture_root = layout.tmp_state_dir().join(format!("fixture-{name}"));
+            std::fs::create_dir_all(fixture_root.join("bin")).expect("must create fixture bin");
+            std::fs::write(
+                fixture_root.join("bin").join(name),
+                format!("#!/bin/sh\necho {name}\n"),
+            )
+            .expect("must write fixture binary");
+            let cache_path = layout.artifact_cache_path(name, "1.0.0", target, ArchiveType::TarGz);
+            std::fs::create_dir_all(cache_path.parent().expect("cache path must have parent"))
+                .expect("must create cache dir");
+            let status = std::process::Command::new("tar")
+                .arg("-czf")
+                .arg(&cache_path)
+                .arg("-C")
+                .arg(&fixture_root)
+                .arg("bin")
+                .status()
+                .expect("must run tar");
+            assert!(status.success());
+            let sha256 = crosspack_security::sha256_hex(
+                &std::fs::read(&cache_path).expect("must read fixture archive"),
+            );
+
+            let mut resolved = resolved_install(name, "1.0.0");
+            resolved.archive_type = ArchiveType::TarGz;
+            resolved.artifact.url = format!("https://example.test/{name}-1.0.0.tar.gz");
+            resolved.artifact.sha256 = sha256;
+            resolved.artifact.binaries.push(crosspack_core::ArtifactBinary {
+                name: name.to_string(),
+                path: format!("bin/{name}"),
+                interpreter: None,
+                env: BTreeMap::new(),
+            });
+            level_packages.push(resolved);
+        }
+        let level = level_packages.iter().collect::<Vec<_>>();
+        assert!(level_supports_parallel_install(&level));
+
+        let root_names = vec!["app-a".to_string(), "app-b".to_string()];
+        let level_receipts =
+            read_checked_install_receipts(&layout).expect("must read level receipts");
+        let outcomes = run_bounded_parallel(&level, 2, |package| {
+            install_resolved(
+                &layout,
+                package,
+                &[],
+                &root_names,
+                &HashMap::new(),
+                InstallResolvedOptions {
+                    snapshot_id: None,
+                    force_redownload: false,
+                    verify_files: false,
+                    interaction_policy: InstallInteractionPolicy::default(),
+                    install_progress_mode: InstallProgressMode::Disabled,
+                    level_receipts: Some(&level_receipts),
+                },
+                None,
+            )
+        })
+        .expect("parallel level must install");
+
+        assert_eq!(
+            outcomes
+                .iter()
+                .map(|outcome| outcome.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["app-a", "app-b"]
+        );
+        let receipts = read_install_receipts(&layout).expect("must read receipts");
+        assert_eq!(receipts.len(), 2);
+        for name in ["app-a", "app-b"] {
+            assert!(bin_path(&layout, name).exists(), "{name} must be exposed");
+            let receipt = receipts
+                .iter()
+                .find(|receipt| receipt.name == name)
+                .expect("receipt must exist");
+            assert_eq!(receipt.exposed_bins, vec![name.to_string()]);
+        }
+
+        let _ = std::fs::remove_dir_all(layout.prefix());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn install_resolved_records_measured_size_of_installed_tree() {
+        let layout = test_layout();
+        layout.ensure_base_dirs().expect("must create dirs");
+        let fixture_root = layout.tmp_state_dir().join("fixture-sized");
+        std::fs::create_dir_all(fixture_root.join("bin")).expect("must create fixture bin");
+        std::fs::create_dir_all(fixture_root.join("share")).expect("must create fixture share");
+        std::fs::write(fixture_root.join("bin/sized"), "#!/bin/sh\necho sized\n")
+            .expect("must write fixture binary");
+        std::fs::write(fixture_root.join("share/README"), vec![b'x'; 1000])
+            .expect("must write fixture readme");
+
+        let mut resolved = resolved_install("sized", "1.0.0");
+        resolved.archive_type = ArchiveType::TarGz;
+        let cache_path = layout.artifact_cache_path(
+            "sized",
+            "1.0.0",
+            &resolved.resolved_target,
+            ArchiveType::TarGz,
+        );
+        std::fs::create_dir_all(cache_path.parent().expect("cache path must have parent"))
+            .expect("must create cache dir");
+        let status = std::process::Command::new("tar")
+            .arg("-czf")
+            .arg(&cache_path)
+            .arg("-C")
+            .arg(&fixture_root)
+            .arg("bin")
+            .arg("share")
+            .status()
+            .expect("must run tar");
+        assert!(status.success());
+        resolved.artifact.url = "https://example.test/sized-1.0.0.tar.gz".to_string();
+        resolved.artifact.sha256 = crosspack_security::sha256_hex(
+            &std::fs::read(&cache_path).expect("must read fixture archive"),
+        );
+
+        install_resolved(
+            &layout,
+            &resolved,
+            &[],
+            &["sized".to_string()],
+            &HashMap::new(),
+            InstallResolvedOptions {
+                snapshot_id: None,
+                force_redownload: false,
+                verify_files: false,
+                interaction_policy: InstallInteractionPolicy::default(),
+                install_progress_mode: InstallProgressMode::Disabled,
+                level_receipts: None,
+            },
+            None,
+        )
+        .expect("install must succeed");
+
+        let receipt = read_install_receipts(&layout)
+            .expect("must read receipts")
+            .into_iter()
+            .find(|receipt| receipt.name == "sized")
+            .expect("receipt must exist");
+        assert_eq!(receipt.file_count, Some(2));
+        assert_eq!(receipt.installed_bytes, Some(1021));
+        assert!(verify_prefix_integrity(&layout)
+            .expect("must verify prefix")
+            .is_empty());
+
+        let _ = std::fs::remove_dir_all(layout.prefix());
+    }
+
+    #[test]
+    fn run_bounded_parallel_propagates_item_failure() {
+        let err = run_bounded_parallel(&[1, 2, 3], 3, |item| {
+            if *item == 2 {
+                return Err(anyhow!("item {item} failed"));
+            }
+            Ok(*item)
+        })
+        .expect_err("failure must propagate");
+        assert_eq!(err.to_string(), "item 2 failed");
+    }
+
     fn resolved_install(name: &str, version: &str) -> ResolvedInstall {
         let manifest = PackageManifest::from_toml_str(&format!(
             r#"
@@ -8236,6 +9565,8 @@ sha256 = "abc"
             resolved_target: "x86_64-unknown-linux-gnu".to_string(),
             archive_type: ArchiveType::TarZst,
             source_build: None,
+            install_level: 0,
+            advisories: Vec::new(),
         }
     }
 
@@ -8582,13 +9913,35 @@ sha256 = "abc"
     }
 
     fn write_signed_test_manifest_with_targets(layout: &PrefixLayout, spec: TestManifestSpec<'_>) {
-        let cache_root = registry_state_root(layout)
-            .join("cache")
-            .join(spec.source_name);
+        let manifest = manifest_toml(
+            spec.package_name,
+            spec.version,
+            spec.license,
+            spec.homepage,
+            spec.provides,
+            spec.targets,
+        );
+        write_signed_test_manifest_content(
+            layout,
+            spec.source_name,
+            spec.package_name,
+            spec.version,
+            &manifest,
+        );
+    }
+
+    fn write_signed_test_manifest_content(
+        layout: &PrefixLayout,
+        source_name: &str,
+        package_name: &str,
+        version: &str,
+        manifest: &str,
+    ) {
+        let cache_root = registry_state_root(layout).join("cache").join(source_name);
         let package_template_path = cache_root
             .join("packages")
-            .join(format!("{}.toml", spec.package_name));
-        let package_dir = cache_root.join("releases").join(spec.package_name);
+            .join(format!("{package_name}.toml"));
+        let package_dir = cache_root.join("releases").join(package_name);
         std::fs::create_dir_all(&package_dir).expect("must create package directory");
         std::fs::create_dir_all(cache_root.join("packages"))
             .expect("must create package template directory");
@@ -8599,7 +9952,7 @@ sha256 = "abc"
             public_key_hex(&signing_key),
         )
         .expect("must write registry key");
-        let package_template = format!("name = \"{}\"\n", spec.package_name);
+        let package_template = format!("name = \"{package_name}\"\n");
         std::fs::write(&package_template_path, package_template.as_bytes())
             .expect("must write package template");
         let package_signature = signing_key.sign(package_template.as_bytes());
@@ -8609,15 +9962,7 @@ sha256 = "abc"
         )
         .expect("must write package template signature");
 
-        let manifest = manifest_toml(
-            spec.package_name,
-            spec.version,
-            spec.license,
-            spec.homepage,
-            spec.provides,
-            spec.targets,
-        );
-        let manifest_path = package_dir.join(format!("{}.toml", spec.version));
+        let manifest_path = package_dir.join(format!("{version}.toml"));
         std::fs::write(&manifest_path, manifest.as_bytes()).expect("must write manifest");
 
         let signature = signing_key.sign(manifest.as_bytes());
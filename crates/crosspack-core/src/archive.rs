@@ -3,6 +3,9 @@ pub enum ArchiveType {
     Zip,
     TarGz,
     TarZst,
+    TarBz2,
+    TarLz4,
+    Tar,
     Bin,
     Msi,
     Dmg,
@@ -14,11 +17,31 @@ pub enum ArchiveType {
 }
 
 impl ArchiveType {
+    pub const ALL: [Self; 14] = [
+        Self::Zip,
+        Self::TarGz,
+        Self::TarZst,
+        Self::TarBz2,
+        Self::TarLz4,
+        Self::Tar,
+        Self::Bin,
+        Self::Msi,
+        Self::Dmg,
+        Self::AppImage,
+        Self::Exe,
+        Self::Pkg,
+        Self::Msix,
+        Self::Appx,
+    ];
+
     pub fn as_str(self) -> &'static str {
         match self {
             Self::Zip => "zip",
             Self::TarGz => "tar.gz",
             Self::TarZst => "tar.zst",
+            Self::TarBz2 => "tar.bz2",
+            Self::TarLz4 => "tar.lz4",
+            Self::Tar => "tar",
             Self::Bin => "bin",
             Self::Msi => "msi",
             Self::Dmg => "dmg",
@@ -35,6 +58,9 @@ impl ArchiveType {
             Self::Zip => "zip",
             Self::TarGz => "tar.gz",
             Self::TarZst => "tar.zst",
+            Self::TarBz2 => "tar.bz2",
+            Self::TarLz4 => "tar.lz4",
+            Self::Tar => "tar",
             Self::Bin => "bin",
             Self::Msi => "msi",
             Self::Dmg => "dmg",
@@ -51,6 +77,9 @@ impl ArchiveType {
             "zip" => Some(Self::Zip),
             "tar.gz" | "tgz" => Some(Self::TarGz),
             "tar.zst" | "tzst" => Some(Self::TarZst),
+            "tar.bz2" | "tbz2" | "tbz" => Some(Self::TarBz2),
+            "tar.lz4" | "tlz4" => Some(Self::TarLz4),
+            "tar" | ".tar" => Some(Self::Tar),
             "bin" => Some(Self::Bin),
             "msi" => Some(Self::Msi),
             "dmg" => Some(Self::Dmg),
@@ -74,6 +103,15 @@ impl ArchiveType {
         if lower.ends_with(".tar.zst") || lower.ends_with(".tzst") {
             return Some(Self::TarZst);
         }
+        if lower.ends_with(".tar.bz2") || lower.ends_with(".tbz2") || lower.ends_with(".tbz") {
+            return Some(Self::TarBz2);
+        }
+        if lower.ends_with(".tar.lz4") || lower.ends_with(".tlz4") {
+            return Some(Self::TarLz4);
+        }
+        if lower.ends_with(".tar") {
+            return Some(Self::Tar);
+        }
         if lower.ends_with(".bin") {
             return Some(Self::Bin);
         }
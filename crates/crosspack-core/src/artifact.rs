@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 
@@ -8,6 +11,16 @@ use crate::gui::ArtifactGuiApp;
 pub struct ArtifactBinary {
     pub name: String,
     pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interpreter: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ArtifactFileMapping {
+    pub from: String,
+    pub to: String,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -20,6 +33,8 @@ pub enum ArtifactCompletionShell {
 }
 
 impl ArtifactCompletionShell {
+    pub const ALL: [Self; 4] = [Self::Bash, Self::Zsh, Self::Fish, Self::Powershell];
+
     pub fn as_str(self) -> &'static str {
         match self {
             Self::Bash => "bash",
@@ -30,6 +45,20 @@ impl ArtifactCompletionShell {
     }
 }
 
+impl FromStr for ArtifactCompletionShell {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|shell| shell.as_str() == value)
+            .ok_or_else(|| {
+                let supported = Self::ALL.map(Self::as_str).join(", ");
+                anyhow!("unsupported completion shell '{value}'; supported: {supported}")
+            })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct ArtifactCompletion {
     pub shell: ArtifactCompletionShell,
@@ -40,12 +69,17 @@ pub struct ArtifactCompletion {
 pub struct Artifact {
     pub target: String,
     pub url: String,
+    #[serde(default)]
+    pub mirrors: Vec<String>,
     pub sha256: String,
     pub size: Option<u64>,
     pub signature: Option<String>,
     pub archive: Option<String>,
     pub strip_components: Option<u32>,
     pub artifact_root: Option<String>,
+    pub file_manifest: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<ArtifactFileMapping>,
     #[serde(default)]
     pub binaries: Vec<ArtifactBinary>,
     #[serde(default)]
@@ -58,8 +92,13 @@ impl Artifact {
     pub fn archive_type(&self) -> anyhow::Result<ArchiveType> {
         if let Some(archive) = &self.archive {
             return ArchiveType::parse(archive).ok_or_else(|| {
+                let supported = ArchiveType::ALL
+                    .iter()
+                    .map(|kind| kind.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
                 anyhow!(
-                    "unsupported archive type '{archive}' for target '{}'; supported: zip, tar.gz, tar.zst, bin, msi, dmg, appimage, exe, pkg, msix, appx",
+                    "unsupported archive type '{archive}' for target '{}'; supported: {supported}",
                     self.target
                 )
             });
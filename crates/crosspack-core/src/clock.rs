@@ -0,0 +1,64 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> SystemTime;
+
+    fn unix_timestamp(&self) -> u64 {
+        self.now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+
+    fn unix_nanos(&self) -> u128 {
+        self.now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+// Stands still until moved explicitly, so stamped values are exact in tests.
+#[derive(Debug, Default)]
+pub struct FakeClock {
+    unix_nanos: AtomicU64,
+}
+
+impl FakeClock {
+    pub fn at_unix(secs: u64) -> Self {
+        let clock = Self::default();
+        clock.set_unix(secs);
+        clock
+    }
+
+    pub fn set_unix(&self, secs: u64) {
+        self.unix_nanos
+            .store(secs.saturating_mul(1_000_000_000), Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let by = u64::try_from(by.as_nanos()).unwrap_or(u64::MAX);
+        let _ = self
+            .unix_nanos
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |nanos| {
+                Some(nanos.saturating_add(by))
+            });
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_nanos(self.unix_nanos.load(Ordering::SeqCst))
+    }
+}
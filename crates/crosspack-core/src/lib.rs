@@ -1,10 +1,14 @@
 mod archive;
 mod artifact;
+mod clock;
 mod gui;
 mod manifest;
 
 pub use archive::ArchiveType;
-pub use artifact::{Artifact, ArtifactBinary, ArtifactCompletion, ArtifactCompletionShell};
+pub use artifact::{
+    Artifact, ArtifactBinary, ArtifactCompletion, ArtifactCompletionShell, ArtifactFileMapping,
+};
+pub use clock::{Clock, FakeClock, SystemClock};
 pub use gui::{ArtifactGuiApp, ArtifactGuiFileAssociation, ArtifactGuiProtocol};
 pub use manifest::{PackageManifest, ServiceDeclaration, SourceBuildMetadata};
 
@@ -1,32 +1,314 @@
 use std::collections::{BTreeMap, HashSet};
+use std::fmt;
 
 use anyhow::{anyhow, Context};
 use semver::{Version, VersionReq};
-use serde::{Deserialize, Serialize};
+use serde::de::{self, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::artifact::Artifact;
+use crate::gui::is_registered_gui_category;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(from = "RawPackageManifest", into = "SerializedPackageManifest")]
 pub struct PackageManifest {
     pub name: String,
     pub version: Version,
     pub description: Option<String>,
     pub license: Option<String>,
     pub homepage: Option<String>,
-    #[serde(default)]
+    /// Deprecation notice, possibly naming a successor. Deprecated packages
+    /// still resolve; the notice is surfaced as an advisory.
+    pub deprecated: Option<String>,
     pub provides: Vec<String>,
-    #[serde(default)]
     pub conflicts: BTreeMap<String, VersionReq>,
-    #[serde(default)]
     pub replaces: BTreeMap<String, VersionReq>,
-    #[serde(default)]
     pub dependencies: BTreeMap<String, VersionReq>,
-    #[serde(default)]
     pub artifacts: Vec<Artifact>,
-    #[serde(default)]
     pub source_build: Option<SourceBuildMetadata>,
-    #[serde(default)]
     pub services: Vec<ServiceDeclaration>,
+    pub optional_dependencies: BTreeMap<String, VersionReq>,
+    pub dependency_features: BTreeMap<String, Vec<String>>,
+    pub features: BTreeMap<String, Vec<String>>,
+}
+
+// Schema-shaped form used for serialization: optional dependencies and
+// dependency features fold back into `[dependencies]` entries.
+#[derive(Serialize)]
+struct SerializedPackageManifest {
+    name: String,
+    version: Version,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    license: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    homepage: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deprecated: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    provides: Vec<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    conflicts: BTreeMap<String, VersionReq>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    replaces: BTreeMap<String, VersionReq>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    dependencies: BTreeMap<String, SerializedDependency>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    artifacts: Vec<Artifact>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_build: Option<SourceBuildMetadata>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    services: Vec<ServiceDeclaration>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    features: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum SerializedDependency {
+    Requirement(VersionReq),
+    Detailed {
+        version: VersionReq,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        features: Vec<String>,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        optional: bool,
+    },
+}
+
+impl From<PackageManifest> for SerializedPackageManifest {
+    fn from(manifest: PackageManifest) -> Self {
+        let mut dependency_features = manifest.dependency_features;
+        let required = manifest
+            .dependencies
+            .into_iter()
+            .map(|(name, version)| (name, version, false));
+        let optional = manifest
+            .optional_dependencies
+            .into_iter()
+            .map(|(name, version)| (name, version, true));
+        let dependencies = required
+            .chain(optional)
+            .map(|(name, version, optional)| {
+                let features = dependency_features.remove(&name).unwrap_or_default();
+                let spec = if features.is_empty() && !optional {
+                    SerializedDependency::Requirement(version)
+                } else {
+                    SerializedDependency::Detailed {
+                        version,
+                        features,
+                        optional,
+                    }
+                };
+                (name, spec)
+            })
+            .collect();
+
+        Self {
+            name: manifest.name,
+            version: manifest.version,
+            description: manifest.description,
+            license: manifest.license,
+            homepage: manifest.homepage,
+            deprecated: manifest.deprecated,
+            provides: manifest.provides,
+            conflicts: manifest.conflicts,
+            replaces: manifest.replaces,
+            dependencies,
+            artifacts: manifest.artifacts,
+            source_build: manifest.source_build,
+            services: manifest.services,
+            features: manifest.features,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawPackageManifest {
+    name: String,
+    version: Version,
+    description: Option<String>,
+    license: Option<String>,
+    homepage: Option<String>,
+    deprecated: Option<String>,
+    #[serde(default)]
+    provides: Vec<String>,
+    #[serde(default)]
+    conflicts: BTreeMap<String, VersionReq>,
+    #[serde(default)]
+    replaces: BTreeMap<String, VersionReq>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, DependencySpec>,
+    #[serde(default)]
+    artifacts: Vec<Artifact>,
+    #[serde(default)]
+    source_build: Option<SourceBuildMetadata>,
+    #[serde(default)]
+    services: Vec<ServiceDeclaration>,
+    #[serde(default)]
+    optional_dependencies: BTreeMap<String, VersionReq>,
+    #[serde(default)]
+    dependency_features: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    features: BTreeMap<String, Vec<String>>,
+}
+
+impl From<RawPackageManifest> for PackageManifest {
+    fn from(raw: RawPackageManifest) -> Self {
+        let mut dependencies = BTreeMap::new();
+        let mut optional_dependencies = raw.optional_dependencies;
+        let mut dependency_features = raw.dependency_features;
+        for (name, spec) in raw.dependencies {
+            if !spec.features.is_empty() {
+                let requested: &mut Vec<String> =
+                    dependency_features.entry(name.clone()).or_default();
+                requested.extend(spec.features);
+                requested.sort();
+                requested.dedup();
+            }
+            if spec.optional {
+                optional_dependencies.insert(name, spec.version);
+            } else {
+                dependencies.insert(name, spec.version);
+            }
+        }
+
+        Self {
+            name: raw.name,
+            version: raw.version,
+            description: raw.description,
+            license: raw.license,
+            homepage: raw.homepage,
+            deprecated: raw.deprecated,
+            provides: raw.provides,
+            conflicts: raw.conflicts,
+            replaces: raw.replaces,
+            dependencies,
+            artifacts: raw.artifacts,
+            source_build: raw.source_build,
+            services: raw.services,
+            optional_dependencies,
+            dependency_features,
+            features: raw.features,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct DependencySpec {
+    version: VersionReq,
+    features: Vec<String>,
+    optional: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DetailedDependencySpec {
+    version: VersionReq,
+    #[serde(default)]
+    features: Vec<String>,
+    #[serde(default)]
+    optional: bool,
+}
+
+impl<'de> Deserialize<'de> for DependencySpec {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct DependencySpecVisitor;
+
+        impl<'de> Visitor<'de> for DependencySpecVisitor {
+            type Value = DependencySpec;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a version requirement string or dependency table")
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                let version = VersionReq::parse(value).map_err(E::custom)?;
+                Ok(DependencySpec {
+                    version,
+                    features: Vec::new(),
+                    optional: false,
+                })
+            }
+
+            fn visit_map<M>(self, map: M) -> Result<Self::Value, M::Error>
+            where
+                M: MapAccess<'de>,
+            {
+                let detailed = DetailedDependencySpec::deserialize(
+                    de::value::MapAccessDeserializer::new(map),
+                )?;
+                Ok(DependencySpec {
+                    version: detailed.version,
+                    features: detailed.features,
+                    optional: detailed.optional,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(DependencySpecVisitor)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawRequirementTables {
+    name: Option<toml::Value>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, toml::Spanned<toml::Value>>,
+    #[serde(default)]
+    optional_dependencies: BTreeMap<String, toml::Spanned<toml::Value>>,
+    #[serde(default)]
+    conflicts: BTreeMap<String, toml::Spanned<toml::Value>>,
+    #[serde(default)]
+    replaces: BTreeMap<String, toml::Spanned<toml::Value>>,
+}
+
+// serde reports a bad requirement without saying which table or key held it,
+// so requirement strings are checked up front with that context attached.
+fn validate_requirement_tables(input: &str) -> anyhow::Result<()> {
+    let Ok(raw) = toml::from_str::<RawRequirementTables>(input) else {
+        return Ok(());
+    };
+    let manifest_name = match &raw.name {
+        Some(toml::Value::String(name)) => name.as_str(),
+        _ => "<unnamed>",
+    };
+    let tables = [
+        ("dependency", "dependencies", &raw.dependencies),
+        (
+            "optional dependency",
+            "optional_dependencies",
+            &raw.optional_dependencies,
+        ),
+        ("conflict", "conflicts", &raw.conflicts),
+        ("replacement", "replaces", &raw.replaces),
+    ];
+    for (kind, table, entries) in tables {
+        for (key, value) in entries {
+            let requirement = match value.get_ref() {
+                toml::Value::String(requirement) => requirement.as_str(),
+                toml::Value::Table(detailed) => match detailed.get("version") {
+                    Some(toml::Value::String(requirement)) => requirement.as_str(),
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            if let Err(err) = VersionReq::parse(requirement) {
+                let line = input[..value.span().start].matches('\n').count() + 1;
+                return Err(anyhow!(
+                    "invalid version requirement for {kind} '{key}' in [{table}] of manifest '{manifest_name}' (line {line}): {err}"
+                ));
+            }
+        }
+    }
+    Ok(())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -52,6 +334,7 @@ pub struct SourceBuildMetadata {
 
 impl PackageManifest {
     pub fn from_toml_str(input: &str) -> anyhow::Result<Self> {
+        validate_requirement_tables(input)?;
         let manifest: Self = toml::from_str(input).context("failed to parse crosspack manifest")?;
         if manifest.conflicts.contains_key(&manifest.name) {
             return Err(anyhow!(
@@ -88,6 +371,7 @@ impl PackageManifest {
                 }
             }
         }
+        validate_features(&manifest)?;
         let mut seen_service_names = HashSet::new();
         for service in &manifest.services {
             validate_service_name_token(&service.name)?;
@@ -104,6 +388,80 @@ impl PackageManifest {
         }
         Ok(manifest)
     }
+
+    pub fn validate(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for artifact in &self.artifacts {
+            for gui_app in &artifact.gui_apps {
+                for category in &gui_app.categories {
+                    if !is_registered_gui_category(category) {
+                        warnings.push(format!(
+                            "gui app '{}' for target '{}' declares unregistered freedesktop category '{}'",
+                            gui_app.app_id, artifact.target, category
+                        ));
+                    }
+                }
+            }
+        }
+        warnings
+    }
+
+    pub fn artifact_for_target(&self, target: &str) -> Option<&Artifact> {
+        self.artifacts
+            .iter()
+            .find(|artifact| artifact.target == target)
+    }
+
+    pub fn to_toml_str(&self) -> anyhow::Result<String> {
+        toml::to_string(self).context("failed to serialize crosspack manifest")
+    }
+}
+
+fn validate_features(manifest: &PackageManifest) -> anyhow::Result<()> {
+    for name in manifest.optional_dependencies.keys() {
+        if manifest.dependencies.contains_key(name) {
+            return Err(anyhow!(
+                "dependency '{}' is declared as both required and optional in manifest '{}'",
+                name,
+                manifest.name
+            ));
+        }
+    }
+    for name in manifest.dependency_features.keys() {
+        if !manifest.dependencies.contains_key(name)
+            && !manifest.optional_dependencies.contains_key(name)
+        {
+            return Err(anyhow!(
+                "dependency features reference undeclared dependency '{}' in manifest '{}'",
+                name,
+                manifest.name
+            ));
+        }
+    }
+    for (feature, entries) in &manifest.features {
+        for entry in entries {
+            let valid = match entry.split_once('/') {
+                Some((dependency, dependency_feature)) => {
+                    !dependency_feature.is_empty()
+                        && (manifest.dependencies.contains_key(dependency)
+                            || manifest.optional_dependencies.contains_key(dependency))
+                }
+                None => {
+                    manifest.features.contains_key(entry)
+                        || manifest.optional_dependencies.contains_key(entry)
+                }
+            };
+            if !valid {
+                return Err(anyhow!(
+                    "feature '{}' references unknown entry '{}' in manifest '{}'",
+                    feature,
+                    entry,
+                    manifest.name
+                ));
+            }
+        }
+    }
+    Ok(())
 }
 
 fn validate_service_name_token(value: &str) -> anyhow::Result<()> {
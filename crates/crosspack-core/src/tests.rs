@@ -2,11 +2,14 @@ use semver::VersionReq;
 
 use super::*;
 
-fn all_archive_types() -> [ArchiveType; 11] {
+fn all_archive_types() -> [ArchiveType; 14] {
     [
         ArchiveType::Zip,
         ArchiveType::TarGz,
         ArchiveType::TarZst,
+        ArchiveType::TarBz2,
+        ArchiveType::TarLz4,
+        ArchiveType::Tar,
         ArchiveType::Bin,
         ArchiveType::Msi,
         ArchiveType::Dmg,
@@ -18,6 +21,31 @@ fn all_archive_types() -> [ArchiveType; 11] {
     ]
 }
 
+#[test]
+fn completion_shell_from_str_accepts_canonical_tokens() {
+    for shell in ArtifactCompletionShell::ALL {
+        let parsed: ArtifactCompletionShell = shell.as_str().parse().expect("token must parse");
+        assert_eq!(parsed, shell);
+    }
+    assert_eq!(
+        "powershell"
+            .parse::<ArtifactCompletionShell>()
+            .expect("must parse"),
+        ArtifactCompletionShell::Powershell
+    );
+}
+
+#[test]
+fn completion_shell_from_str_rejects_unknown_shell_with_supported_list() {
+    let err = "elvish"
+        .parse::<ArtifactCompletionShell>()
+        .expect_err("elvish must be rejected");
+    assert_eq!(
+        err.to_string(),
+        "unsupported completion shell 'elvish'; supported: bash, zsh, fish, powershell"
+    );
+}
+
 #[test]
 fn parse_manifest() {
     let content = r#"
@@ -40,6 +68,7 @@ zlib = ">=1.2.0, <2.0.0"
 target = "x86_64-unknown-linux-gnu"
 url = "https://example.test/ripgrep-14.1.0-x86_64-unknown-linux-gnu.tar.zst"
 sha256 = "abc123"
+file_manifest = "crosspack-files.sha256"
 
 [[artifacts.binaries]]
 name = "rg"
@@ -68,6 +97,10 @@ path = "completions/rg.bash"
     );
     assert!(parsed.dependencies.contains_key("zlib"));
     assert_eq!(parsed.artifacts.len(), 1);
+    assert_eq!(
+        parsed.artifacts[0].file_manifest.as_deref(),
+        Some("crosspack-files.sha256")
+    );
     assert_eq!(parsed.artifacts[0].binaries.len(), 1);
     assert_eq!(parsed.artifacts[0].binaries[0].name, "rg");
     assert_eq!(parsed.artifacts[0].binaries[0].path, "ripgrep");
@@ -82,6 +115,50 @@ path = "completions/rg.bash"
     );
 }
 
+#[test]
+fn parse_manifest_splits_detailed_dependency_specs() {
+    let content = r#"
+name = "net"
+version = "1.0.0"
+
+[dependencies]
+zlib = "^1"
+http = { version = "^2", features = ["h2"] }
+openssl = { version = "^3", optional = true }
+
+[features]
+tls = ["openssl", "http/tls"]
+"#;
+
+    let parsed = PackageManifest::from_toml_str(content).expect("manifest should parse");
+    assert_eq!(
+        parsed.dependencies.keys().collect::<Vec<_>>(),
+        vec!["http", "zlib"]
+    );
+    assert_eq!(
+        parsed.optional_dependencies.get("openssl"),
+        Some(&VersionReq::parse("^3").expect("valid version req"))
+    );
+    assert_eq!(parsed.dependency_features["http"], vec!["h2"]);
+    assert_eq!(parsed.features["tls"], vec!["openssl", "http/tls"]);
+}
+
+#[test]
+fn parse_manifest_rejects_feature_with_unknown_entry() {
+    let content = r#"
+name = "net"
+version = "1.0.0"
+
+[features]
+tls = ["openssl"]
+"#;
+
+    let err = PackageManifest::from_toml_str(content).expect_err("unknown feature entry must fail");
+    assert!(err
+        .to_string()
+        .contains("feature 'tls' references unknown entry 'openssl'"));
+}
+
 #[test]
 fn parse_manifest_without_description_defaults_to_none() {
     let content = r#"
@@ -260,6 +337,39 @@ path = "completions/_zoxide.ps1"
     assert_eq!(completions[3].shell, ArtifactCompletionShell::Powershell);
 }
 
+#[test]
+fn validate_warns_on_unregistered_gui_categories() {
+    let content = r#"
+name = "zed"
+version = "0.190.5"
+
+[[artifacts]]
+target = "x86_64-unknown-linux-gnu"
+url = "https://example.test/zed-linux.tar.gz"
+sha256 = "abc123"
+
+[[artifacts.gui_apps]]
+app_id = "dev.zed.Zed"
+display_name = "Zed"
+exec = "zed"
+categories = ["Development", "TextEditor", "X-ZedEditor", "Developmnet"]
+"#;
+
+    let parsed =
+        PackageManifest::from_toml_str(content).expect("unknown category must not fail parsing");
+    assert_eq!(
+        parsed.validate(),
+        vec![
+            "gui app 'dev.zed.Zed' for target 'x86_64-unknown-linux-gnu' declares unregistered freedesktop category 'Developmnet'"
+                .to_string()
+        ]
+    );
+
+    let mut valid = parsed.clone();
+    valid.artifacts[0].gui_apps[0].categories.pop();
+    assert!(valid.validate().is_empty());
+}
+
 #[test]
 fn parse_manifest_with_gui_apps() {
     let content = r#"
@@ -379,6 +489,38 @@ path = "completions/zoxide.elvish"
     );
 }
 
+#[test]
+fn invalid_requirements_name_their_table_key_and_line() {
+    let cases = [
+        (
+            "[dependencies]\nzlib = \"not-a-req\"\n",
+            "invalid version requirement for dependency 'zlib' in [dependencies] of manifest 'app' (line 5)",
+        ),
+        (
+            "[dependencies]\nzlib = { version = \"not-a-req\", features = [\"gz\"] }\n",
+            "invalid version requirement for dependency 'zlib' in [dependencies] of manifest 'app' (line 5)",
+        ),
+        (
+            "[conflicts]\nlegacy-app = \">>1\"\n",
+            "invalid version requirement for conflict 'legacy-app' in [conflicts] of manifest 'app' (line 5)",
+        ),
+        (
+            "[replaces]\nold-app = \"1.x.y.z\"\n",
+            "invalid version requirement for replacement 'old-app' in [replaces] of manifest 'app' (line 5)",
+        ),
+    ];
+
+    for (table, expected) in cases {
+        let content = format!("name = \"app\"\nversion = \"1.0.0\"\n\n{table}");
+        let err =
+            PackageManifest::from_toml_str(&content).expect_err("manifest should be rejected");
+        assert!(
+            err.to_string().starts_with(expected),
+            "unexpected error for {table:?}: {err}"
+        );
+    }
+}
+
 #[test]
 fn reject_self_conflict() {
     let content = r#"
@@ -413,6 +555,104 @@ ripgrep = "*"
     );
 }
 
+#[test]
+fn manifest_to_toml_str_round_trips_rich_manifest() {
+    let content = r#"
+name = "zed"
+version = "0.150.0"
+description = "Code editor"
+license = "GPL-3.0"
+homepage = "https://zed.dev"
+deprecated = "use zed-preview instead"
+provides = ["zed-editor"]
+
+[conflicts]
+zed-nightly = "*"
+
+[replaces]
+zed-legacy = "<0.100.0"
+
+[dependencies]
+ripgrep = ">=14.0.0"
+fd = { version = "^10", optional = true, features = ["color"] }
+tree-sitter = { version = "^0.22", features = ["wasm"] }
+
+[features]
+search = ["fd"]
+
+[[artifacts]]
+target = "aarch64-apple-darwin"
+url = "https://example.test/zed-0.150.0.dmg"
+sha256 = "abc123"
+strip_components = 1
+
+[[artifacts.binaries]]
+name = "zed"
+path = "Zed.app/Contents/MacOS/cli"
+env = { ZED_CHANNEL = "stable" }
+
+[[artifacts.completions]]
+shell = "zsh"
+path = "completions/_zed"
+
+[[artifacts.gui_apps]]
+app_id = "dev.zed.Zed"
+display_name = "Zed"
+exec = "Zed.app"
+categories = ["Development"]
+
+[[artifacts.gui_apps.file_associations]]
+mime_type = "text/plain"
+extensions = [".txt"]
+
+[[artifacts.gui_apps.protocols]]
+scheme = "zed"
+
+[[services]]
+name = "zed-agent"
+"#;
+
+    let parsed = PackageManifest::from_toml_str(content).expect("manifest should parse");
+    assert_eq!(
+        parsed.artifacts[0].binaries[0].env.get("ZED_CHANNEL"),
+        Some(&"stable".to_string())
+    );
+    let rendered = parsed.to_toml_str().expect("manifest should serialize");
+    assert!(rendered.contains("version = \"0.150.0\""));
+    assert!(rendered.contains("zed-legacy = \"<0.100.0\""));
+    let schema_keys = [
+        "name",
+        "version",
+        "description",
+        "license",
+        "homepage",
+        "deprecated",
+        "provides",
+        "conflicts",
+        "replaces",
+        "dependencies",
+        "artifacts",
+        "source_build",
+        "services",
+        "features",
+    ];
+    let rendered_table: toml::Table = toml::from_str(&rendered).expect("rendered must be TOML");
+    for key in rendered_table.keys() {
+        assert!(
+            schema_keys.contains(&key.as_str()),
+            "non-schema key '{key}' rendered"
+        );
+    }
+
+    let reparsed =
+        PackageManifest::from_toml_str(&rendered).expect("rendered manifest should parse");
+    assert_eq!(reparsed, parsed);
+    assert_eq!(
+        reparsed.to_toml_str().expect("manifest should serialize"),
+        rendered
+    );
+}
+
 #[test]
 fn archive_type_from_manifest_value() {
     assert_eq!(ArchiveType::parse("zip"), Some(ArchiveType::Zip));
@@ -455,6 +695,26 @@ fn archive_type_parse_rejects_deb_rpm() {
     assert_eq!(ArchiveType::parse("rpm"), None);
 }
 
+#[test]
+fn archive_type_parse_distinguishes_plain_tar_from_compressed_tar() {
+    assert_eq!(ArchiveType::parse("tar"), Some(ArchiveType::Tar));
+    assert_eq!(ArchiveType::parse(".tar"), Some(ArchiveType::Tar));
+    assert_eq!(ArchiveType::parse("TAR"), Some(ArchiveType::Tar));
+    assert_eq!(ArchiveType::parse("tar.gz"), Some(ArchiveType::TarGz));
+    assert_eq!(
+        ArchiveType::infer_from_url("https://example.test/pkg.tar"),
+        Some(ArchiveType::Tar)
+    );
+    assert_eq!(
+        ArchiveType::infer_from_url("https://example.test/pkg.tar.gz"),
+        Some(ArchiveType::TarGz)
+    );
+    assert_eq!(
+        ArchiveType::infer_from_url("https://example.test/pkg.tar.zst"),
+        Some(ArchiveType::TarZst)
+    );
+}
+
 #[test]
 fn archive_type_from_url() {
     assert_eq!(
@@ -529,6 +789,41 @@ fn archive_type_infer_from_url_supports_exe_pkg_msix_appx() {
     );
 }
 
+#[test]
+fn archive_type_parses_and_infers_bz2_and_lz4_tarballs() {
+    for (token, expected) in [
+        ("tar.bz2", ArchiveType::TarBz2),
+        ("tbz2", ArchiveType::TarBz2),
+        ("tbz", ArchiveType::TarBz2),
+        ("TAR.LZ4", ArchiveType::TarLz4),
+        ("tlz4", ArchiveType::TarLz4),
+    ] {
+        assert_eq!(ArchiveType::parse(token), Some(expected), "token '{token}'");
+    }
+    for (url, expected) in [
+        (
+            "https://example.test/tool-1.0.tar.bz2",
+            Some(ArchiveType::TarBz2),
+        ),
+        (
+            "https://example.test/tool-1.0.tbz2",
+            Some(ArchiveType::TarBz2),
+        ),
+        (
+            "https://example.test/tool-1.0.tar.lz4",
+            Some(ArchiveType::TarLz4),
+        ),
+        (
+            "https://example.test/tool-1.0.tlz4",
+            Some(ArchiveType::TarLz4),
+        ),
+        ("https://example.test/tool-1.0.tar", Some(ArchiveType::Tar)),
+        ("https://example.test/tool-1.0.bz2", None),
+    ] {
+        assert_eq!(ArchiveType::infer_from_url(url), expected, "url '{url}'");
+    }
+}
+
 #[test]
 fn archive_type_infer_from_url_rejects_deb_rpm() {
     assert_eq!(
@@ -546,12 +841,15 @@ fn archive_type_error_message_excludes_deb_rpm() {
     let artifact = Artifact {
         target: "x86_64-unknown-linux-gnu".to_string(),
         url: "https://example.test/pkg.unknown".to_string(),
+        mirrors: Vec::new(),
         sha256: "abc123".to_string(),
         size: None,
         signature: None,
         archive: Some("unknown".to_string()),
         strip_components: None,
         artifact_root: None,
+        file_manifest: None,
+        files: Vec::new(),
         binaries: vec![],
         completions: vec![],
         gui_apps: vec![],
@@ -563,6 +861,14 @@ fn archive_type_error_message_excludes_deb_rpm() {
     let msg = err.to_string();
     assert!(!msg.contains("deb"), "error should not mention deb: {msg}");
     assert!(!msg.contains("rpm"), "error should not mention rpm: {msg}");
+    for kind in ArchiveType::ALL {
+        assert!(
+            msg.contains(kind.as_str()),
+            "error should list {}: {msg}",
+            kind.as_str()
+        );
+        assert_eq!(ArchiveType::parse(kind.as_str()), Some(kind));
+    }
 }
 
 #[test]
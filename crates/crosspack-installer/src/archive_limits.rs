@@ -0,0 +1,163 @@
+use std::io::{self, Write};
+
+use crate::{ArchiveLimitExceeded, ArchiveLimitKind, ArchiveLimits};
+
+const TAR_BLOCK_SIZE: u64 = 512;
+const MAX_PAX_HEADER_BYTES: u64 = 64 * 1024;
+
+// Inspects tar headers as the decompressed stream passes through, so oversized
+// archives are rejected before their payload reaches the extractor.
+pub(crate) struct TarLimitWriter<W> {
+    inner: W,
+    limits: ArchiveLimits,
+    header: Vec<u8>,
+    remaining: u64,
+    pax: Option<(Vec<u8>, u64)>,
+    pax_size: Option<u64>,
+    entries: u64,
+    total_bytes: u64,
+    exceeded: Option<ArchiveLimitExceeded>,
+}
+
+impl<W: Write> TarLimitWriter<W> {
+    pub(crate) fn new(inner: W, limits: ArchiveLimits) -> Self {
+        Self {
+            inner,
+            limits,
+            header: Vec::with_capacity(TAR_BLOCK_SIZE as usize),
+            remaining: 0,
+            pax: None,
+            pax_size: None,
+            entries: 0,
+            total_bytes: 0,
+            exceeded: None,
+        }
+    }
+
+    pub(crate) fn exceeded(&self) -> Option<ArchiveLimitExceeded> {
+        self.exceeded
+    }
+
+    fn inspect_header(&mut self) -> Result<(), ArchiveLimitExceeded> {
+        if self.header.iter().all(|byte| *byte == 0) {
+            return Ok(());
+        }
+
+        let typeflag = self.header[156];
+        let is_metadata = matches!(typeflag, b'x' | b'g' | b'L' | b'K');
+        let mut size = parse_tar_size(&self.header[124..136]);
+        if !is_metadata {
+            if let Some(pax_size) = self.pax_size.take() {
+                size = pax_size;
+            }
+            self.entries += 1;
+            if self.entries > self.limits.max_entries {
+                return Err(self.limit_exceeded(ArchiveLimitKind::Entries));
+            }
+        }
+        if size > self.limits.max_entry_bytes {
+            return Err(self.limit_exceeded(ArchiveLimitKind::EntryBytes));
+        }
+        self.total_bytes = self.total_bytes.saturating_add(size);
+        if self.total_bytes > self.limits.max_total_bytes {
+            return Err(self.limit_exceeded(ArchiveLimitKind::TotalBytes));
+        }
+
+        if typeflag == b'x' && size <= MAX_PAX_HEADER_BYTES {
+            self.pax = Some((Vec::with_capacity(size as usize), size));
+        }
+        self.remaining = size.div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE;
+        Ok(())
+    }
+
+    fn limit_exceeded(&self, kind: ArchiveLimitKind) -> ArchiveLimitExceeded {
+        let limit = match kind {
+            ArchiveLimitKind::TotalBytes => self.limits.max_total_bytes,
+            ArchiveLimitKind::Entries => self.limits.max_entries,
+            ArchiveLimitKind::EntryBytes => self.limits.max_entry_bytes,
+        };
+        ArchiveLimitExceeded { kind, limit }
+    }
+
+    fn collect_pax(&mut self, data: &[u8]) {
+        let Some((buffer, size)) = self.pax.as_mut() else {
+            return;
+        };
+        let wanted = (*size as usize).saturating_sub(buffer.len());
+        buffer.extend_from_slice(&data[..wanted.min(data.len())]);
+        if buffer.len() as u64 == *size {
+            self.pax_size = parse_pax_size(buffer);
+            self.pax = None;
+        }
+    }
+}
+
+impl<W: Write> Write for TarLimitWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(exceeded) = self.exceeded {
+            return Err(io::Error::other(exceeded));
+        }
+
+        let mut offset = 0;
+        while offset < buf.len() {
+            if self.remaining > 0 {
+                let take = (self.remaining.min((buf.len() - offset) as u64)) as usize;
+                let chunk = &buf[offset..offset + take];
+                self.collect_pax(chunk);
+                self.inner.write_all(chunk)?;
+                self.remaining -= take as u64;
+                offset += take;
+                continue;
+            }
+
+            let take = (TAR_BLOCK_SIZE as usize - self.header.len()).min(buf.len() - offset);
+            self.header.extend_from_slice(&buf[offset..offset + take]);
+            offset += take;
+            if self.header.len() < TAR_BLOCK_SIZE as usize {
+                continue;
+            }
+
+            if let Err(exceeded) = self.inspect_header() {
+                self.exceeded = Some(exceeded);
+                return Err(io::Error::other(exceeded));
+            }
+            self.inner.write_all(&self.header)?;
+            self.header.clear();
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn parse_tar_size(field: &[u8]) -> u64 {
+    if field[0] & 0x80 != 0 {
+        return field[1..]
+            .iter()
+            .fold(u64::from(field[0] & 0x7f), |acc, byte| {
+                acc.saturating_mul(256).saturating_add(u64::from(*byte))
+            });
+    }
+
+    field
+        .iter()
+        .skip_while(|byte| **byte == b' ')
+        .take_while(|byte| (b'0'..=b'7').contains(*byte))
+        .fold(0u64, |acc, digit| {
+            acc.saturating_mul(8)
+                .saturating_add(u64::from(digit - b'0'))
+        })
+}
+
+fn parse_pax_size(records: &[u8]) -> Option<u64> {
+    records
+        .split(|byte| *byte == b'\n')
+        .filter_map(|record| {
+            let space = record.iter().position(|byte| *byte == b' ')?;
+            record[space + 1..].strip_prefix(b"size=")
+        })
+        .filter_map(|value| std::str::from_utf8(value).ok()?.parse::<u64>().ok())
+        .next_back()
+}
@@ -1,13 +1,24 @@
 use anyhow::{anyhow, Context, Result};
-use crosspack_core::ArchiveType;
+use bzip2::read::MultiBzDecoder;
+use crosspack_core::{ArchiveType, ArtifactFileMapping};
+use crosspack_security::{hash_sha256_reader, Sha256Reader};
+use flate2::read::MultiGzDecoder;
+use std::collections::HashMap;
 use std::ffi::OsString;
 use std::fs;
-use std::io;
+use std::io::{self, Read};
 use std::path::{Component, Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
 
+use crate::archive_limits::TarLimitWriter;
+use crate::exposure::prune_empty_subdirs;
+use crate::integrity::measure_install_tree;
 use crate::transactions::current_unix_timestamp;
-use crate::{ArtifactInstallOptions, InstallInteractionPolicy, InstallMode, PrefixLayout};
+use crate::{
+    ArchiveLimits, ArtifactInstallOptions, ChecksumPolicy, EnvPolicy, InstallArtifactResult,
+    InstallInteractionPolicy, InstallMode, InstallPermissionsPolicy, PrefixLayout,
+};
 
 pub fn install_from_artifact(
     layout: &PrefixLayout,
@@ -17,6 +28,57 @@ pub fn install_from_artifact(
     archive_type: ArchiveType,
     options: ArtifactInstallOptions<'_>,
 ) -> Result<PathBuf> {
+    install_from_artifact_with_result(layout, name, version, archive_path, archive_type, options)
+        .map(|result| result.dest)
+}
+
+pub fn install_from_artifact_with_result(
+    layout: &PrefixLayout,
+    name: &str,
+    version: &str,
+    archive_path: &Path,
+    archive_type: ArchiveType,
+    mut options: ArtifactInstallOptions<'_>,
+) -> Result<InstallArtifactResult> {
+    let mut warnings = Vec::new();
+    if let Some(expected_sha256) = options.expected_sha256 {
+        if is_dev_sha256_sentinel(expected_sha256) {
+            match options.checksum_policy {
+                ChecksumPolicy::Production => {
+                    return Err(anyhow!(
+                        "artifact sha256 '{}' for {}@{} is a dev-mode sentinel and is invalid under the production checksum policy",
+                        expected_sha256,
+                        name,
+                        version
+                    ));
+                }
+                ChecksumPolicy::DevMode => {
+                    warnings.push(format!(
+                        "WARNING: artifact sha256 verification SKIPPED for {name}@{version} (dev-mode sentinel '{expected_sha256}'); do not publish this manifest"
+                    ));
+                    options.expected_sha256 = None;
+                }
+            }
+        }
+    }
+
+    let streamed_sha256 = options
+        .expected_sha256
+        .filter(|_| supports_streamed_verification(archive_type));
+    if let Some(expected_sha256) = options.expected_sha256 {
+        if streamed_sha256.is_none() {
+            verify_artifact_sha256(archive_path, expected_sha256)?;
+        }
+    }
+
+    if supports_streamed_verification(archive_type)
+        && options.artifact_root.is_none()
+        && options.file_mappings.is_empty()
+    {
+        return install_tar_in_place(layout, name, version, archive_path, archive_type, options)
+            .map(|result| InstallArtifactResult { warnings, ..result });
+    }
+
     let install_tmp = make_tmp_dir(layout, "install")?;
     let raw_dir = install_tmp.join("raw");
     let staged_dir = install_tmp.join("staged");
@@ -25,28 +87,21 @@ pub fn install_from_artifact(
     fs::create_dir_all(&staged_dir)
         .with_context(|| format!("failed to create {}", staged_dir.display()))?;
 
-    stage_artifact_payload(
+    let copied = stage_artifact_tree(
         archive_path,
-        &raw_dir,
         archive_type,
-        options.strip_components,
-        options.artifact_root,
-        options.install_mode,
-        options.interaction_policy,
-    )?;
-
-    if let Some(root) = options.artifact_root {
-        let root_path = raw_dir.join(root);
-        if !root_path.exists() {
-            return Err(anyhow!(
-                "artifact_root '{}' was not found after extraction: {}",
-                root,
-                root_path.display()
-            ));
+        &options,
+        streamed_sha256,
+        &raw_dir,
+        &staged_dir,
+    );
+    let tally = match copied {
+        Ok(tally) => tally,
+        Err(err) => {
+            let _ = fs::remove_dir_all(&install_tmp);
+            return Err(err);
         }
-    }
-
-    copy_with_strip(&raw_dir, &staged_dir, options.strip_components as usize)?;
+    };
 
     let dst = layout.package_dir(name, version);
     if dst.exists() {
@@ -57,7 +112,232 @@ pub fn install_from_artifact(
     move_dir_or_copy(&staged_dir, &dst)?;
 
     let _ = fs::remove_dir_all(&install_tmp);
-    Ok(dst)
+    Ok(InstallArtifactResult {
+        dest: dst,
+        file_count: tally.files,
+        total_bytes: tally.bytes,
+        warnings,
+    })
+}
+
+// Extracts the artifact into `raw_dir` and lays out `staged_dir` the way it is
+// installed: `artifact_root`, `strip_components`, and file mappings all apply.
+pub(crate) fn stage_artifact_tree(
+    archive_path: &Path,
+    archive_type: ArchiveType,
+    options: &ArtifactInstallOptions<'_>,
+    streamed_sha256: Option<&str>,
+    raw_dir: &Path,
+    staged_dir: &Path,
+) -> Result<CopyTally> {
+    match streamed_sha256 {
+        Some(expected_sha256) => extract_tar_verifying_sha256(
+            archive_path,
+            raw_dir,
+            archive_type,
+            expected_sha256,
+            0,
+            options.archive_limits,
+        )?,
+        None => stage_artifact_payload(
+            archive_path,
+            raw_dir,
+            archive_type,
+            options.strip_components,
+            options.artifact_root,
+            options.install_mode,
+            options.interaction_policy,
+            options.archive_limits,
+        )?,
+    }
+
+    let copy_root = match options.artifact_root {
+        Some(root) => {
+            let root_path = Path::new(root);
+            if !root_path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+            {
+                return Err(anyhow!(
+                    "artifact_root '{}' must be a relative path inside the artifact",
+                    root
+                ));
+            }
+            let root_path = raw_dir.join(root_path);
+            if !root_path.is_dir() {
+                return Err(anyhow!(
+                    "artifact_root '{}' was not found after extraction: {}",
+                    root,
+                    root_path.display()
+                ));
+            }
+            root_path
+        }
+        None => raw_dir.to_path_buf(),
+    };
+
+    let mappings = resolve_file_mappings(options.file_mappings)?;
+    copy_with_strip(
+        &copy_root,
+        staged_dir,
+        options.strip_components as usize,
+        mappings.as_deref(),
+        options.permissions_policy,
+    )
+}
+
+// Tar payloads without an artifact_root let tar apply strip_components itself, so
+// they extract into a hidden sibling of the package dir and land with one rename
+// instead of going through the raw -> staged copy under tmp/.
+fn install_tar_in_place(
+    layout: &PrefixLayout,
+    name: &str,
+    version: &str,
+    archive_path: &Path,
+    archive_type: ArchiveType,
+    options: ArtifactInstallOptions<'_>,
+) -> Result<InstallArtifactResult> {
+    let dst = layout.package_dir(name, version);
+    let parent = dst
+        .parent()
+        .with_context(|| format!("package dir has no parent: {}", dst.display()))?;
+    let partial = parent.join(format!(
+        ".{}.partial-{}-{}",
+        version,
+        std::process::id(),
+        current_unix_timestamp()?
+    ));
+    fs::create_dir_all(&partial)
+        .with_context(|| format!("failed to create {}", partial.display()))?;
+
+    let staged =
+        stage_tar_in_place(archive_path, &partial, archive_type, &options).and_then(|()| {
+            if dst.exists() {
+                fs::remove_dir_all(&dst).with_context(|| {
+                    format!("failed to remove existing package dir: {}", dst.display())
+                })?;
+            }
+            move_dir_or_copy(&partial, &dst)
+        });
+    if let Err(err) = staged {
+        let _ = fs::remove_dir_all(&partial);
+        return Err(err);
+    }
+    // tar wrote the tree directly, so there is no copy pass to count during.
+    let (file_count, total_bytes) = measure_install_tree(&dst)?;
+    Ok(InstallArtifactResult {
+        dest: dst,
+        file_count,
+        total_bytes,
+        warnings: Vec::new(),
+    })
+}
+
+fn stage_tar_in_place(
+    archive_path: &Path,
+    dst: &Path,
+    archive_type: ArchiveType,
+    options: &ArtifactInstallOptions<'_>,
+) -> Result<()> {
+    match options.expected_sha256 {
+        Some(expected_sha256) => extract_tar_verifying_sha256(
+            archive_path,
+            dst,
+            archive_type,
+            expected_sha256,
+            options.strip_components,
+            options.archive_limits,
+        )?,
+        None => extract_tar_archive_stripped(
+            archive_path,
+            dst,
+            archive_type,
+            options.strip_components,
+            options.archive_limits,
+        )?,
+    }
+
+    // tar keeps the directories it strips down to; the copy path only creates
+    // parents of copied files, so drop empty ones to produce the same tree.
+    if prune_empty_subdirs(dst)? {
+        return Err(anyhow!(
+            "no files copied during extraction; strip_components={} may be too large",
+            options.strip_components
+        ));
+    }
+    apply_permissions_policy(dst, options.permissions_policy)
+}
+
+/// True for the placeholder checksums packagers use before computing a real digest.
+pub fn is_dev_sha256_sentinel(value: &str) -> bool {
+    let value = value.trim();
+    value.is_empty() || value.eq_ignore_ascii_case("dev")
+}
+
+fn verify_artifact_sha256(archive_path: &Path, expected_sha256: &str) -> Result<()> {
+    let mut file = fs::File::open(archive_path)
+        .with_context(|| format!("failed to read artifact: {}", archive_path.display()))?;
+    let actual_sha256 = hash_sha256_reader(&mut file)?;
+    if actual_sha256.eq_ignore_ascii_case(expected_sha256) {
+        return Ok(());
+    }
+
+    Err(anyhow!(
+        "artifact sha256 mismatch for {}: expected {}, got {}",
+        archive_path.display(),
+        expected_sha256,
+        actual_sha256
+    ))
+}
+
+fn supports_streamed_verification(archive_type: ArchiveType) -> bool {
+    is_tar_archive(archive_type)
+}
+
+pub(crate) fn is_tar_archive(archive_type: ArchiveType) -> bool {
+    matches!(
+        archive_type,
+        ArchiveType::Tar
+            | ArchiveType::TarGz
+            | ArchiveType::TarZst
+            | ArchiveType::TarBz2
+            | ArchiveType::TarLz4
+    )
+}
+
+// Hashes the artifact while tar consumes it so checksum and extraction share one read.
+fn extract_tar_verifying_sha256(
+    archive_path: &Path,
+    dst: &Path,
+    archive_type: ArchiveType,
+    expected_sha256: &str,
+    strip_components: u32,
+    limits: ArchiveLimits,
+) -> Result<()> {
+    let input = fs::File::open(archive_path)
+        .with_context(|| format!("failed to open artifact: {}", archive_path.display()))?;
+    let mut reader = Sha256Reader::new(input);
+    let extracted = extract_tar_stream(
+        &mut reader,
+        archive_path,
+        dst,
+        archive_type,
+        strip_components,
+        limits,
+    );
+
+    io::copy(&mut reader, &mut io::sink())
+        .with_context(|| format!("failed to read artifact: {}", archive_path.display()))?;
+    let actual_sha256 = reader.finalize_hex();
+    if !actual_sha256.eq_ignore_ascii_case(expected_sha256) {
+        return Err(anyhow!(
+            "artifact sha256 mismatch for {}: expected {}, got {}",
+            archive_path.display(),
+            expected_sha256,
+            actual_sha256
+        ));
+    }
+    extracted
 }
 
 pub fn install_from_source_archive(
@@ -68,13 +348,36 @@ pub fn install_from_source_archive(
     source_archive_type: ArchiveType,
     build_commands: &[String],
     install_commands: &[String],
+) -> Result<PathBuf> {
+    install_from_source_archive_with_env_policy(
+        layout,
+        name,
+        version,
+        source_archive_path,
+        source_archive_type,
+        build_commands,
+        install_commands,
+        &EnvPolicy::default(),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn install_from_source_archive_with_env_policy(
+    layout: &PrefixLayout,
+    name: &str,
+    version: &str,
+    source_archive_path: &Path,
+    source_archive_type: ArchiveType,
+    build_commands: &[String],
+    install_commands: &[String],
+    env_policy: &EnvPolicy,
 ) -> Result<PathBuf> {
     if !matches!(
         source_archive_type,
-        ArchiveType::Zip | ArchiveType::TarGz | ArchiveType::TarZst
+        ArchiveType::Zip | ArchiveType::TarGz | ArchiveType::TarZst | ArchiveType::Tar
     ) {
         return Err(anyhow!(
-            "unsupported source build archive type '{}': expected one of zip, tar.gz, tar.zst",
+            "unsupported source build archive type '{}': expected one of zip, tar.gz, tar.zst, tar",
             source_archive_type.as_str()
         ));
     }
@@ -98,7 +401,7 @@ pub fn install_from_source_archive(
     fs::create_dir_all(&staged_dir)
         .with_context(|| format!("failed to create {}", staged_dir.display()))?;
 
-    stage_artifact_payload(
+    if let Err(err) = stage_artifact_payload(
         source_archive_path,
         &source_raw_dir,
         source_archive_type,
@@ -106,11 +409,27 @@ pub fn install_from_source_archive(
         None,
         InstallMode::Managed,
         InstallInteractionPolicy::default(),
-    )?;
+        ArchiveLimits::default(),
+    ) {
+        let _ = fs::remove_dir_all(&install_tmp);
+        return Err(err);
+    }
 
     let source_root = infer_source_root(&source_raw_dir)?;
-    run_source_build_command("build", build_commands, &source_root, &staged_dir)?;
-    run_source_build_command("install", install_commands, &source_root, &staged_dir)?;
+    run_source_build_command(
+        "build",
+        build_commands,
+        &source_root,
+        &staged_dir,
+        env_policy,
+    )?;
+    run_source_build_command(
+        "install",
+        install_commands,
+        &source_root,
+        &staged_dir,
+        env_policy,
+    )?;
 
     let dst = layout.package_dir(name, version);
     if dst.exists() {
@@ -160,6 +479,7 @@ fn run_source_build_command(
     command_tokens: &[String],
     source_root: &Path,
     staged_dir: &Path,
+    env_policy: &EnvPolicy,
 ) -> Result<()> {
     let program = command_tokens
         .first()
@@ -181,6 +501,7 @@ fn run_source_build_command(
     if command_tokens.len() > 1 {
         command.args(&command_tokens[1..]);
     }
+    env_policy.apply_to_command(&mut command)?;
     command
         .current_dir(source_root)
         .env("CROSSPACK_SOURCE_ROOT", source_root)
@@ -198,19 +519,25 @@ fn run_source_build_command(
     })
 }
 
-fn make_tmp_dir(layout: &PrefixLayout, prefix: &str) -> Result<PathBuf> {
+// The sequence number keeps dirs distinct when one process stages several
+// packages concurrently within the same second.
+static TMP_DIR_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
+pub(crate) fn make_tmp_dir(layout: &PrefixLayout, prefix: &str) -> Result<PathBuf> {
     let mut dir = layout.tmp_state_dir();
     dir.push(format!(
-        "{}-{}-{}",
+        "{}-{}-{}-{}",
         prefix,
         std::process::id(),
-        current_unix_timestamp()?
+        current_unix_timestamp()?,
+        TMP_DIR_SEQUENCE.fetch_add(1, Ordering::Relaxed)
     ));
     fs::create_dir_all(&dir)
         .with_context(|| format!("failed creating tmp dir: {}", dir.display()))?;
     Ok(dir)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn stage_artifact_payload(
     artifact_path: &Path,
     raw_dir: &Path,
@@ -219,6 +546,7 @@ fn stage_artifact_payload(
     artifact_root: Option<&str>,
     install_mode: InstallMode,
     interaction_policy: InstallInteractionPolicy,
+    archive_limits: ArchiveLimits,
 ) -> Result<()> {
     if install_mode == InstallMode::Native
         && is_native_default_archive_type(artifact_type)
@@ -233,7 +561,13 @@ fn stage_artifact_payload(
 
     match artifact_type {
         ArchiveType::Zip => extract_zip(artifact_path, raw_dir),
-        ArchiveType::TarGz | ArchiveType::TarZst => extract_tar(artifact_path, raw_dir),
+        ArchiveType::TarGz
+        | ArchiveType::Tar
+        | ArchiveType::TarZst
+        | ArchiveType::TarBz2
+        | ArchiveType::TarLz4 => {
+            extract_tar_archive(artifact_path, raw_dir, artifact_type, archive_limits)
+        }
         ArchiveType::Bin => {
             stage_bin_payload(artifact_path, raw_dir, strip_components, artifact_root)
         }
@@ -787,18 +1121,171 @@ pub(crate) fn copy_dmg_payload(mount_point: &Path, raw_dir: &Path) -> Result<()>
     Ok(())
 }
 
-fn extract_tar(archive_path: &Path, dst: &Path) -> Result<()> {
-    run_command(
-        Command::new("tar")
-            .arg("-xf")
-            .arg(archive_path)
-            .arg("-C")
-            .arg(dst),
-        "failed to extract tar archive",
+// Long-mode artifacts (`zstd --long=N`) declare windows beyond the decoder's default limit.
+const ZSTD_WINDOW_LOG_MAX: u32 = if cfg!(target_pointer_width = "64") {
+    31
+} else {
+    30
+};
+
+pub(crate) fn extract_tar_archive(
+    archive_path: &Path,
+    dst: &Path,
+    archive_type: ArchiveType,
+    limits: ArchiveLimits,
+) -> Result<()> {
+    extract_tar_archive_stripped(archive_path, dst, archive_type, 0, limits)
+}
+
+fn extract_tar_archive_stripped(
+    archive_path: &Path,
+    dst: &Path,
+    archive_type: ArchiveType,
+    strip_components: u32,
+    limits: ArchiveLimits,
+) -> Result<()> {
+    let mut input = fs::File::open(archive_path)
+        .with_context(|| format!("failed to open artifact: {}", archive_path.display()))?;
+    extract_tar_stream(
+        &mut input,
+        archive_path,
+        dst,
+        archive_type,
+        strip_components,
+        limits,
     )
 }
 
-fn extract_zip(archive_path: &Path, dst: &Path) -> Result<()> {
+// Decompresses in-process so archive limits see the tar stream before tar writes it.
+fn extract_tar_stream<R: Read>(
+    reader: &mut R,
+    archive_path: &Path,
+    dst: &Path,
+    archive_type: ArchiveType,
+    strip_components: u32,
+    limits: ArchiveLimits,
+) -> Result<()> {
+    let mut command = Command::new("tar");
+    command.arg("-xf").arg("-").arg("-C").arg(dst);
+    if strip_components > 0 {
+        command.arg(format!("--strip-components={strip_components}"));
+    }
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to extract tar archive: command failed to start")?;
+    let stdin = child
+        .stdin
+        .take()
+        .context("failed to open tar extraction input")?;
+    // Drain stderr concurrently so a chatty tar cannot fill the pipe and
+    // block while we are still writing its stdin.
+    let mut stderr = child
+        .stderr
+        .take()
+        .context("failed to open tar extraction error output")?;
+    let stderr_reader = std::thread::spawn(move || {
+        let mut captured = Vec::new();
+        let _ = stderr.read_to_end(&mut captured);
+        captured
+    });
+    let mut writer = TarLimitWriter::new(stdin, limits);
+
+    let streamed = match archive_type {
+        ArchiveType::TarZst => zstd_decoder(&mut *reader, archive_path).and_then(|mut decoder| {
+            io::copy(&mut decoder, &mut writer).with_context(|| {
+                format!(
+                    "failed to decompress zstd archive (supports long-mode windows up to 2^{} bytes): {}",
+                    ZSTD_WINDOW_LOG_MAX,
+                    archive_path.display()
+                )
+            })
+        }),
+        ArchiveType::TarGz => io::copy(&mut MultiGzDecoder::new(&mut *reader), &mut writer)
+            .with_context(|| {
+                format!(
+                    "failed to decompress gzip archive: {}",
+                    archive_path.display()
+                )
+            }),
+        ArchiveType::TarBz2 => io::copy(&mut MultiBzDecoder::new(&mut *reader), &mut writer)
+            .with_context(|| {
+                format!(
+                    "failed to decompress bzip2 archive: {}",
+                    archive_path.display()
+                )
+            }),
+        ArchiveType::TarLz4 => lz4::Decoder::new(&mut *reader)
+            .with_context(|| {
+                format!(
+                    "failed to initialize lz4 decoder: {}",
+                    archive_path.display()
+                )
+            })
+            .and_then(|mut decoder| {
+                io::copy(&mut decoder, &mut writer).with_context(|| {
+                    format!(
+                        "failed to decompress lz4 archive: {}",
+                        archive_path.display()
+                    )
+                })
+            }),
+        _ => io::copy(reader, &mut writer).map_err(anyhow::Error::from),
+    };
+    let exceeded = writer.exceeded();
+    drop(writer);
+    let status = child.wait().context("failed to wait for tar extraction")?;
+    let stderr = stderr_reader.join().unwrap_or_default();
+
+    if let Some(exceeded) = exceeded {
+        return Err(anyhow::Error::new(exceeded)).with_context(|| {
+            format!(
+                "refusing to extract tar archive: {}",
+                archive_path.display()
+            )
+        });
+    }
+    streamed.with_context(|| {
+        format!(
+            "failed to stream artifact into tar: {}",
+            archive_path.display()
+        )
+    })?;
+    if !status.success() {
+        return Err(anyhow!(
+            "failed to extract tar archive: status={} stderr='{}'",
+            status,
+            String::from_utf8_lossy(&stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+fn zstd_decoder<R: Read>(
+    input: R,
+    archive_path: &Path,
+) -> Result<zstd::stream::read::Decoder<'static, io::BufReader<R>>> {
+    let mut decoder = zstd::stream::read::Decoder::new(input).with_context(|| {
+        format!(
+            "failed to initialize zstd decoder: {}",
+            archive_path.display()
+        )
+    })?;
+    decoder
+        .window_log_max(ZSTD_WINDOW_LOG_MAX)
+        .with_context(|| {
+            format!(
+                "failed to configure zstd decoder window (2^{} bytes): {}",
+                ZSTD_WINDOW_LOG_MAX,
+                archive_path.display()
+            )
+        })?;
+    Ok(decoder)
+}
+
+pub(crate) fn extract_zip(archive_path: &Path, dst: &Path) -> Result<()> {
     if cfg!(windows) {
         let mut command = Command::new("powershell");
         command.arg("-NoProfile").arg("-Command").arg(format!(
@@ -856,19 +1343,37 @@ pub(crate) fn run_command(command: &mut Command, context_message: &str) -> Resul
 }
 
 fn move_dir_or_copy(src: &Path, dst: &Path) -> Result<()> {
+    move_dir_or_copy_using(src, dst, |from, to| fs::rename(from, to))
+}
+
+// Only a cross-device rename falls back to copying; any other rename failure
+// (permissions, a busy destination) is reported as-is rather than retried as a copy.
+pub(crate) fn move_dir_or_copy_using<R>(src: &Path, dst: &Path, rename: R) -> Result<()>
+where
+    R: Fn(&Path, &Path) -> io::Result<()>,
+{
     if let Some(parent) = dst.parent() {
         fs::create_dir_all(parent)
             .with_context(|| format!("failed to create install parent: {}", parent.display()))?;
     }
 
-    match fs::rename(src, dst) {
-        Ok(_) => Ok(()),
-        Err(_) => {
-            copy_dir_recursive(src, dst)?;
+    match rename(src, dst) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+            if let Err(copy_err) = copy_dir_recursive(src, dst) {
+                let _ = fs::remove_dir_all(dst);
+                return Err(copy_err.context(format!(
+                    "cross-device copy from {} to {} failed",
+                    src.display(),
+                    dst.display()
+                )));
+            }
             fs::remove_dir_all(src)
                 .with_context(|| format!("failed to cleanup staging dir: {}", src.display()))?;
             Ok(())
         }
+        Err(err) => Err(err)
+            .with_context(|| format!("failed to move {} to {}", src.display(), dst.display())),
     }
 }
 
@@ -910,21 +1415,132 @@ pub(crate) fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
     Ok(())
 }
 
-fn copy_with_strip(src_root: &Path, dst_root: &Path, strip_components: usize) -> Result<()> {
-    let mut copied_any = false;
+// Files and symlinks copied, sized the way `measure_install_tree` sizes them.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct CopyTally {
+    pub(crate) files: u64,
+    pub(crate) bytes: u64,
+}
+
+// `from` paths are matched after strip_components; both sides must stay inside
+// their roots.
+fn resolve_file_mappings(
+    file_mappings: &[ArtifactFileMapping],
+) -> Result<Option<Vec<(PathBuf, PathBuf)>>> {
+    if file_mappings.is_empty() {
+        return Ok(None);
+    }
+
+    let mut resolved: Vec<(PathBuf, PathBuf)> = Vec::with_capacity(file_mappings.len());
+    for mapping in file_mappings {
+        let from = normalize_mapping_path(&mapping.from, "from")?;
+        let to = normalize_mapping_path(&mapping.to, "to")?;
+        if resolved
+            .iter()
+            .any(|(existing_from, existing_to)| *existing_from == from || *existing_to == to)
+        {
+            return Err(anyhow!(
+                "artifact files list '{}' -> '{}' more than once",
+                mapping.from,
+                mapping.to
+            ));
+        }
+        resolved.push((from, to));
+    }
+    Ok(Some(resolved))
+}
+
+fn normalize_mapping_path(value: &str, field: &str) -> Result<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in Path::new(value).components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::CurDir => {}
+            _ => {
+                return Err(anyhow!(
+                    "artifact file {field} '{value}' must be a relative path without '..'"
+                ))
+            }
+        }
+    }
+    if normalized.as_os_str().is_empty() {
+        return Err(anyhow!("artifact file {field} must not be empty"));
+    }
+    Ok(normalized)
+}
+
+pub(crate) fn copy_with_strip(
+    src_root: &Path,
+    dst_root: &Path,
+    strip_components: usize,
+    file_mappings: Option<&[(PathBuf, PathBuf)]>,
+    permissions_policy: InstallPermissionsPolicy,
+) -> Result<CopyTally> {
+    let mut tally = CopyTally::default();
+    let mut hardlinks = HashMap::new();
     copy_with_strip_recursive(
         src_root,
         src_root,
         dst_root,
         strip_components,
-        &mut copied_any,
+        file_mappings,
+        &mut tally,
+        &mut hardlinks,
     )?;
-    if !copied_any {
+    for (from, to) in file_mappings.unwrap_or_default() {
+        if fs::symlink_metadata(dst_root.join(to)).is_err() {
+            return Err(anyhow!(
+                "artifact file '{}' was not found after extraction",
+                from.display()
+            ));
+        }
+    }
+    if tally.files == 0 {
         return Err(anyhow!(
             "no files copied during extraction; strip_components={} may be too large",
             strip_components
         ));
     }
+    apply_permissions_policy(dst_root, permissions_policy)?;
+    Ok(tally)
+}
+
+#[cfg(unix)]
+fn apply_permissions_policy(root: &Path, policy: InstallPermissionsPolicy) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    if policy == InstallPermissionsPolicy::default() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(root).with_context(|| format!("failed to read {}", root.display()))? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = fs::symlink_metadata(&path)
+            .with_context(|| format!("failed to stat {}", path.display()))?;
+        if metadata.file_type().is_symlink() {
+            continue;
+        }
+
+        let current_mode = metadata.permissions().mode() & 0o7777;
+        let next_mode = if metadata.is_dir() {
+            apply_permissions_policy(&path, policy)?;
+            policy
+                .directory_mode
+                .unwrap_or(current_mode & !policy.file_mode_mask)
+        } else {
+            current_mode & !policy.file_mode_mask
+        };
+        if next_mode != current_mode {
+            fs::set_permissions(&path, fs::Permissions::from_mode(next_mode))
+                .with_context(|| format!("failed to set permissions on {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn apply_permissions_policy(_root: &Path, _policy: InstallPermissionsPolicy) -> Result<()> {
     Ok(())
 }
 
@@ -933,7 +1549,9 @@ fn copy_with_strip_recursive(
     current: &Path,
     dst_root: &Path,
     strip_components: usize,
-    copied_any: &mut bool,
+    file_mappings: Option<&[(PathBuf, PathBuf)]>,
+    tally: &mut CopyTally,
+    hardlinks: &mut HashMap<(u64, u64), PathBuf>,
 ) -> Result<()> {
     for entry in
         fs::read_dir(current).with_context(|| format!("failed to read {}", current.display()))?
@@ -944,7 +1562,15 @@ fn copy_with_strip_recursive(
             .with_context(|| format!("failed to stat {}", path.display()))?;
 
         if metadata.is_dir() {
-            copy_with_strip_recursive(src_root, &path, dst_root, strip_components, copied_any)?;
+            copy_with_strip_recursive(
+                src_root,
+                &path,
+                dst_root,
+                strip_components,
+                file_mappings,
+                tally,
+                hardlinks,
+            )?;
             continue;
         }
 
@@ -955,8 +1581,15 @@ fn copy_with_strip_recursive(
         let Some(stripped_rel) = stripped else {
             continue;
         };
+        let dst_rel = match file_mappings {
+            Some(mappings) => match mappings.iter().find(|(from, _)| *from == stripped_rel) {
+                Some((_, to)) => to.clone(),
+                None => continue,
+            },
+            None => stripped_rel,
+        };
 
-        let dst_path = dst_root.join(&stripped_rel);
+        let dst_path = dst_root.join(&dst_rel);
         if let Some(parent) = dst_path.parent() {
             fs::create_dir_all(parent)
                 .with_context(|| format!("failed to create {}", parent.display()))?;
@@ -973,23 +1606,61 @@ fn copy_with_strip_recursive(
                     target.display()
                 )
             })?;
-            *copied_any = true;
+            tally.files += 1;
+            tally.bytes += metadata.len();
             continue;
         }
 
-        fs::copy(&path, &dst_path).with_context(|| {
-            format!(
-                "failed to copy {} to {}",
-                path.display(),
-                dst_path.display()
-            )
-        })?;
-        *copied_any = true;
+        copy_preserving_hardlinks(&path, &dst_path, &metadata, hardlinks)?;
+        tally.files += 1;
+        tally.bytes += metadata.len();
     }
 
     Ok(())
 }
 
+// Entries sharing an inode in the staging tree are recreated as hardlinks to the
+// first installed copy; linking falls back to a plain copy (e.g. across devices).
+#[cfg(unix)]
+fn copy_preserving_hardlinks(
+    src: &Path,
+    dst: &Path,
+    metadata: &fs::Metadata,
+    hardlinks: &mut HashMap<(u64, u64), PathBuf>,
+) -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let key = (metadata.dev(), metadata.ino());
+    if metadata.nlink() > 1 {
+        if let Some(first) = hardlinks.get(&key) {
+            if fs::hard_link(first, dst).is_ok() {
+                return Ok(());
+            }
+        }
+    }
+    copy_file(src, dst)?;
+    if metadata.nlink() > 1 {
+        hardlinks.entry(key).or_insert_with(|| dst.to_path_buf());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn copy_preserving_hardlinks(
+    src: &Path,
+    dst: &Path,
+    _metadata: &fs::Metadata,
+    _hardlinks: &mut HashMap<(u64, u64), PathBuf>,
+) -> Result<()> {
+    copy_file(src, dst)
+}
+
+fn copy_file(src: &Path, dst: &Path) -> Result<()> {
+    fs::copy(src, dst)
+        .with_context(|| format!("failed to copy {} to {}", src.display(), dst.display()))?;
+    Ok(())
+}
+
 pub(crate) fn strip_rel_components(path: &Path, strip_components: usize) -> Option<PathBuf> {
     let components: Vec<_> = path
         .components()
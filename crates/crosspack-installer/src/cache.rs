@@ -0,0 +1,83 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::fs_utils::remove_file_if_exists;
+use crate::receipts::read_install_receipts;
+use crate::uninstall::safe_cache_prune_path;
+use crate::PrefixLayout;
+
+struct CacheBlob {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+pub fn enforce_cache_quota(layout: &PrefixLayout, max_bytes: u64) -> Result<Vec<PathBuf>> {
+    let referenced = read_install_receipts(layout)?
+        .into_iter()
+        .filter_map(|receipt| receipt.cache_path)
+        .filter_map(|cache_path| safe_cache_prune_path(layout, &cache_path))
+        .collect::<HashSet<_>>();
+
+    let blobs = collect_cache_blobs(&layout.artifacts_cache_dir())?;
+    let mut total_bytes = blobs.iter().map(|blob| blob.size).sum::<u64>();
+    let mut candidates = blobs
+        .into_iter()
+        .filter(|blob| !referenced.contains(&blob.path))
+        .collect::<Vec<_>>();
+    candidates.sort_by(|left, right| {
+        left.modified
+            .cmp(&right.modified)
+            .then_with(|| left.path.cmp(&right.path))
+    });
+
+    let mut evicted = Vec::new();
+    for blob in candidates {
+        if total_bytes <= max_bytes {
+            break;
+        }
+        let Some(path) = safe_cache_prune_path(layout, &blob.path) else {
+            continue;
+        };
+        remove_file_if_exists(&path)
+            .with_context(|| format!("failed to evict cache file: {}", path.display()))?;
+        total_bytes = total_bytes.saturating_sub(blob.size);
+        evicted.push(path);
+    }
+
+    Ok(evicted)
+}
+
+fn collect_cache_blobs(root: &Path) -> Result<Vec<CacheBlob>> {
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut blobs = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in
+            fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))?
+        {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(entry.path());
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            blobs.push(CacheBlob {
+                path: entry.path(),
+                size: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            });
+        }
+    }
+    Ok(blobs)
+}
@@ -1,12 +1,17 @@
 use anyhow::{anyhow, Context, Result};
-use crosspack_core::{ArtifactCompletionShell, ArtifactGuiApp};
-use std::collections::{BTreeMap, HashSet};
+use crosspack_core::{Artifact, ArtifactBinary, ArtifactCompletionShell, ArtifactGuiApp};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ffi::OsStr;
 use std::fs;
 use std::io;
 use std::path::{Component, Path, PathBuf};
 
 use crate::fs_utils::remove_file_if_exists;
-use crate::{GuiExposureAsset, PrefixLayout};
+use crate::receipts::{read_install_receipts, write_install_receipt};
+use crate::{
+    EnvPolicy, ExposureResult, GuiExposureAsset, GuiExposureOutcome, InstallReceipt, PrefixLayout,
+    ReexposeReport, ReexposeResult,
+};
 
 pub fn write_gui_exposure_state(
     layout: &PrefixLayout,
@@ -122,16 +127,266 @@ pub fn bin_path(layout: &PrefixLayout, binary_name: &str) -> PathBuf {
     layout.bin_dir().join(file_name)
 }
 
+// Exposes every declared binary, completion, and GUI app; if any step fails,
+// the entries this call created are removed before the error is returned.
+// Entries that already existed (e.g. an upgraded package's shims) are left in
+// place for the caller's transaction rollback to restore.
+pub fn expose_artifact(
+    layout: &PrefixLayout,
+    install_root: &Path,
+    package_name: &str,
+    artifact: &Artifact,
+) -> Result<ExposureResult> {
+    let mut result = ExposureResult::default();
+    let mut created = ExposureResult::default();
+    let exposed = expose_artifact_into(
+        layout,
+        install_root,
+        package_name,
+        artifact,
+        &mut result,
+        &mut created,
+    );
+    if let Err(err) = exposed {
+        for binary_name in &created.exposed_bins {
+            let _ = remove_exposed_binary(layout, binary_name);
+        }
+        for completion in &created.exposed_completions {
+            let _ = remove_exposed_completion(layout, completion);
+        }
+        for asset in &created.gui_assets {
+            let _ = remove_exposed_gui_asset(layout, asset);
+        }
+        return Err(err);
+    }
+    Ok(result)
+}
+
+fn expose_artifact_into(
+    layout: &PrefixLayout,
+    install_root: &Path,
+    package_name: &str,
+    artifact: &Artifact,
+    result: &mut ExposureResult,
+    created: &mut ExposureResult,
+) -> Result<()> {
+    for binary in &artifact.binaries {
+        let existed = fs::symlink_metadata(bin_path(layout, &binary.name)).is_ok();
+        expose_declared_binary(layout, install_root, &binary.name, binary)?;
+        result.exposed_bins.push(binary.name.clone());
+        if !existed {
+            created.exposed_bins.push(binary.name.clone());
+        }
+    }
+    for completion in &artifact.completions {
+        let projected =
+            projected_exposed_completion_path(package_name, completion.shell, &completion.path)?;
+        let existed = exposed_completion_path(layout, &projected)?.exists();
+        let storage_path = expose_completion(
+            layout,
+            install_root,
+            package_name,
+            completion.shell,
+            &completion.path,
+        )?;
+        if !existed {
+            created.exposed_completions.push(storage_path.clone());
+        }
+        result.exposed_completions.push(storage_path);
+    }
+    for app in &artifact.gui_apps {
+        let projected = projected_gui_assets(package_name, app)?;
+        let mut preexisting = BTreeSet::new();
+        for asset in &projected {
+            if gui_asset_path(layout, &asset.rel_path)?.exists() {
+                preexisting.insert(asset.rel_path.clone());
+            }
+        }
+        let exposed = match expose_gui_app(layout, install_root, package_name, app) {
+            Ok(exposed) => exposed,
+            Err(err) => {
+                created.gui_assets.extend(
+                    projected
+                        .into_iter()
+                        .filter(|asset| !preexisting.contains(&asset.rel_path)),
+                );
+                return Err(err);
+            }
+        };
+        result.gui_changed |= exposed.changed;
+        created.gui_assets.extend(
+            exposed
+                .assets
+                .iter()
+                .filter(|asset| !preexisting.contains(&asset.rel_path))
+                .cloned(),
+        );
+        result.gui_assets.extend(exposed.assets);
+    }
+    Ok(())
+}
+
+// Rebuilds every receipt's bins, completions, and GUI assets from its live package
+// dir with the current exposure logic. Receipts only record exposed names, so
+// `artifact_for` supplies the artifact each package was installed from.
+pub fn reexpose_all<F>(layout: &PrefixLayout, mut artifact_for: F) -> Result<ReexposeReport>
+where
+    F: FnMut(&InstallReceipt) -> Result<Artifact>,
+{
+    let mut report = ReexposeReport::default();
+    for receipt in read_install_receipts(layout)? {
+        let mut result = ReexposeResult {
+            name: receipt.name.clone(),
+            version: receipt.version.clone(),
+            changed: Vec::new(),
+            errors: Vec::new(),
+        };
+        let install_root = layout.package_dir(&receipt.name, &receipt.version);
+        if !install_root.is_dir() {
+            result.errors.push(format!(
+                "package directory is missing: {}",
+                install_root.display()
+            ));
+        } else {
+            match artifact_for(&receipt) {
+                Ok(artifact) => {
+                    reexpose_package(layout, &receipt, &install_root, &artifact, &mut result)?
+                }
+                Err(err) => result.errors.push(format!("{err:#}")),
+            }
+        }
+        report.packages.push(result);
+    }
+    Ok(report)
+}
+
+fn reexpose_package(
+    layout: &PrefixLayout,
+    receipt: &InstallReceipt,
+    install_root: &Path,
+    artifact: &Artifact,
+    result: &mut ReexposeResult,
+) -> Result<()> {
+    let aliases = read_binary_aliases(layout, &receipt.name)?;
+    for exposed in &receipt.exposed_bins {
+        let declared = aliases.get(exposed).unwrap_or(exposed);
+        let Some(binary) = artifact
+            .binaries
+            .iter()
+            .find(|binary| &binary.name == declared)
+        else {
+            result.errors.push(format!(
+                "binary '{declared}' is not declared by the installed artifact"
+            ));
+            continue;
+        };
+        let path = bin_path(layout, exposed);
+        let before = ExposedEntry::read(&path);
+        match expose_declared_binary(layout, install_root, exposed, binary) {
+            Ok(()) => record_reexposed_entry(layout, &path, before, result),
+            Err(err) => result.errors.push(format!("{err:#}")),
+        }
+    }
+
+    for storage_rel_path in &receipt.exposed_completions {
+        let Some(completion) = artifact.completions.iter().find(|completion| {
+            projected_exposed_completion_path(&receipt.name, completion.shell, &completion.path)
+                .is_ok_and(|projected| &projected == storage_rel_path)
+        }) else {
+            result.errors.push(format!(
+                "completion '{storage_rel_path}' is not declared by the installed artifact"
+            ));
+            continue;
+        };
+        let path = exposed_completion_path(layout, storage_rel_path)?;
+        let before = ExposedEntry::read(&path);
+        match expose_completion(
+            layout,
+            install_root,
+            &receipt.name,
+            completion.shell,
+            &completion.path,
+        ) {
+            Ok(_) => record_reexposed_entry(layout, &path, before, result),
+            Err(err) => result.errors.push(format!("{err:#}")),
+        }
+    }
+
+    if read_gui_exposure_state(layout, &receipt.name)?.is_empty() {
+        return Ok(());
+    }
+    let mut assets = Vec::new();
+    for app in &artifact.gui_apps {
+        match expose_gui_app(layout, install_root, &receipt.name, app) {
+            Ok(outcome) => {
+                if outcome.changed {
+                    for asset in &outcome.assets {
+                        let path = gui_asset_path(layout, &asset.rel_path)?;
+                        result.changed.push(prefix_relative_display(layout, &path));
+                    }
+                }
+                assets.extend(outcome.assets);
+            }
+            Err(err) => result.errors.push(format!("{err:#}")),
+        }
+    }
+    write_gui_exposure_state(layout, &receipt.name, &assets)?;
+    Ok(())
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ExposedEntry {
+    Link(PathBuf),
+    File(Vec<u8>),
+}
+
+impl ExposedEntry {
+    fn read(path: &Path) -> Option<Self> {
+        match fs::read_link(path) {
+            Ok(target) => Some(Self::Link(target)),
+            Err(_) => fs::read(path).ok().map(Self::File),
+        }
+    }
+}
+
+fn record_reexposed_entry(
+    layout: &PrefixLayout,
+    path: &Path,
+    before: Option<ExposedEntry>,
+    result: &mut ReexposeResult,
+) {
+    if ExposedEntry::read(path) != before {
+        result.changed.push(prefix_relative_display(layout, path));
+    }
+}
+
+fn prefix_relative_display(layout: &PrefixLayout, path: &Path) -> String {
+    path.strip_prefix(layout.prefix())
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
 pub fn expose_binary(
     layout: &PrefixLayout,
     install_root: &Path,
     binary_name: &str,
     binary_rel_path: &str,
+) -> Result<()> {
+    expose_binary_with_interpreter(layout, install_root, binary_name, binary_rel_path, None)
+}
+
+pub fn expose_binary_with_interpreter(
+    layout: &PrefixLayout,
+    install_root: &Path,
+    binary_name: &str,
+    binary_rel_path: &str,
+    interpreter: Option<&str>,
 ) -> Result<()> {
     let source_path = resolve_binary_source_path(install_root, binary_rel_path)?;
 
     let destination = bin_path(layout, binary_name);
-    if destination.exists() {
+    if fs::symlink_metadata(&destination).is_ok() {
         fs::remove_file(&destination).with_context(|| {
             format!(
                 "failed to replace existing binary entry: {}",
@@ -140,7 +395,108 @@ pub fn expose_binary(
         })?;
     }
 
-    create_binary_entry(&source_path, &destination)
+    match interpreter {
+        Some(interpreter) if !is_directly_executable(&source_path)? => {
+            let wrapper = render_interpreter_wrapper(interpreter, &source_path)?;
+            write_binary_wrapper(&destination, &wrapper)
+        }
+        _ => create_binary_entry(&source_path, &destination),
+    }
+}
+
+// Binaries that declare `env` are exposed through a wrapper that starts from the
+// default `EnvPolicy` allowlist plus those variables; all others keep plain shims.
+fn expose_declared_binary(
+    layout: &PrefixLayout,
+    install_root: &Path,
+    exposed_name: &str,
+    binary: &ArtifactBinary,
+) -> Result<()> {
+    if binary.env.is_empty() {
+        return expose_binary_with_interpreter(
+            layout,
+            install_root,
+            exposed_name,
+            &binary.path,
+            binary.interpreter.as_deref(),
+        );
+    }
+    let env_policy = binary
+        .env
+        .iter()
+        .fold(EnvPolicy::default(), |policy, (name, value)| {
+            policy.with_declared(name, value)
+        });
+    expose_env_wrapped_binary(
+        layout,
+        install_root,
+        exposed_name,
+        &binary.path,
+        binary.interpreter.as_deref(),
+        &env_policy,
+    )
+}
+
+pub fn expose_binary_with_env_policy(
+    layout: &PrefixLayout,
+    install_root: &Path,
+    binary_name: &str,
+    binary_rel_path: &str,
+    env_policy: &EnvPolicy,
+) -> Result<()> {
+    expose_env_wrapped_binary(
+        layout,
+        install_root,
+        binary_name,
+        binary_rel_path,
+        None,
+        env_policy,
+    )
+}
+
+fn expose_env_wrapped_binary(
+    layout: &PrefixLayout,
+    install_root: &Path,
+    binary_name: &str,
+    binary_rel_path: &str,
+    interpreter: Option<&str>,
+    env_policy: &EnvPolicy,
+) -> Result<()> {
+    let source_path = resolve_binary_source_path(install_root, binary_rel_path)?;
+    let interpreter = match interpreter {
+        Some(interpreter) if !is_directly_executable(&source_path)? => Some(interpreter),
+        _ => None,
+    };
+    let wrapper = render_env_wrapper(&source_path, interpreter, env_policy)?;
+
+    let destination = bin_path(layout, binary_name);
+    if fs::symlink_metadata(&destination).is_ok() {
+        fs::remove_file(&destination).with_context(|| {
+            format!(
+                "failed to replace existing binary entry: {}",
+                destination.display()
+            )
+        })?;
+    }
+    write_binary_wrapper(&destination, &wrapper)
+}
+
+fn write_binary_wrapper(destination: &Path, wrapper: &str) -> Result<()> {
+    fs::write(destination, wrapper.as_bytes())
+        .with_context(|| format!("failed to write wrapper: {}", destination.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(destination, fs::Permissions::from_mode(0o755)).with_context(|| {
+            format!(
+                "failed setting wrapper permissions: {}",
+                destination.display()
+            )
+        })?;
+    }
+
+    Ok(())
 }
 
 pub fn remove_exposed_binary(layout: &PrefixLayout, binary_name: &str) -> Result<()> {
@@ -154,12 +510,188 @@ pub fn remove_exposed_binary(layout: &PrefixLayout, binary_name: &str) -> Result
     Ok(())
 }
 
+// Alias state maps each alias shim name back to the binary name the package
+// declares, so removing an alias can restore the declared shim.
+pub fn read_binary_aliases(
+    layout: &PrefixLayout,
+    package_name: &str,
+) -> Result<BTreeMap<String, String>> {
+    let path = layout.binary_aliases_state_path(package_name);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let raw = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read binary alias state: {}", path.display()))?;
+    let mut aliases = BTreeMap::new();
+    for line in raw.lines().filter(|line| !line.trim().is_empty()) {
+        let (alias, original) = line
+            .strip_prefix("alias=")
+            .and_then(|value| value.split_once('\t'))
+            .ok_or_else(|| anyhow!("invalid binary alias state line: {line}"))
+            .with_context(|| format!("failed to parse binary alias state: {}", path.display()))?;
+        aliases.insert(alias.to_string(), original.to_string());
+    }
+    Ok(aliases)
+}
+
+fn write_binary_aliases(
+    layout: &PrefixLayout,
+    package_name: &str,
+    aliases: &BTreeMap<String, String>,
+) -> Result<()> {
+    let path = layout.binary_aliases_state_path(package_name);
+    if aliases.is_empty() {
+        remove_file_if_exists(&path)?;
+        return Ok(());
+    }
+
+    let payload = aliases
+        .iter()
+        .map(|(alias, original)| format!("alias={alias}\t{original}\n"))
+        .collect::<String>();
+    fs::write(&path, payload.as_bytes())
+        .with_context(|| format!("failed to write binary alias state: {}", path.display()))
+}
+
+pub fn clear_binary_aliases(layout: &PrefixLayout, package_name: &str) -> Result<()> {
+    let path = layout.binary_aliases_state_path(package_name);
+    remove_file_if_exists(&path)?;
+    Ok(())
+}
+
+// Renames the shim for `original_bin` to `alias` and records the new name in the
+// package receipt. Aliasing an alias back to its declared name removes it.
+pub fn set_binary_alias(
+    layout: &PrefixLayout,
+    package_name: &str,
+    original_bin: &str,
+    alias: &str,
+) -> Result<()> {
+    validate_binary_alias_name(alias)?;
+    let receipts = read_install_receipts(layout)?;
+    let mut receipt = receipts
+        .iter()
+        .find(|receipt| receipt.name == package_name)
+        .cloned()
+        .ok_or_else(|| anyhow!("package '{package_name}' is not installed"))?;
+    let Some(position) = receipt
+        .exposed_bins
+        .iter()
+        .position(|bin| bin == original_bin)
+    else {
+        return Err(anyhow!(
+            "package '{package_name}' does not expose binary '{original_bin}'"
+        ));
+    };
+    if original_bin == alias {
+        return Ok(());
+    }
+    if let Some(owner) = receipts
+        .iter()
+        .find(|receipt| receipt.exposed_bins.iter().any(|bin| bin == alias))
+    {
+        return Err(anyhow!(
+            "binary '{alias}' is already exposed by package '{}'",
+            owner.name
+        ));
+    }
+    let destination = bin_path(layout, alias);
+    if fs::symlink_metadata(&destination).is_ok() {
+        return Err(anyhow!(
+            "binary '{alias}' already exists and is not managed by crosspack: {}",
+            destination.display()
+        ));
+    }
+
+    let mut aliases = read_binary_aliases(layout, package_name)?;
+    aliases.retain(|existing, _| receipt.exposed_bins.contains(existing));
+    let declared = aliases
+        .remove(original_bin)
+        .unwrap_or_else(|| original_bin.to_string());
+    if declared != alias {
+        aliases.insert(alias.to_string(), declared);
+    }
+
+    let source = bin_path(layout, original_bin);
+    fs::rename(&source, &destination).with_context(|| {
+        format!(
+            "failed to rename exposed binary {} to {}",
+            source.display(),
+            destination.display()
+        )
+    })?;
+    receipt.exposed_bins[position] = alias.to_string();
+    if let Err(err) = write_install_receipt(layout, &receipt) {
+        let _ = fs::rename(&destination, &source);
+        return Err(err);
+    }
+    write_binary_aliases(layout, package_name, &aliases)
+}
+
+// Re-applies the package's stored aliases after its declared shims were exposed,
+// as install and upgrade expose declared names. Returns the exposed bin names
+// with aliases substituted; aliases whose declared binary is gone or whose name
+// is taken are dropped from the alias state.
+pub fn apply_binary_aliases(
+    layout: &PrefixLayout,
+    package_name: &str,
+    exposed_bins: &[String],
+) -> Result<Vec<String>> {
+    let aliases = read_binary_aliases(layout, package_name)?;
+    if aliases.is_empty() {
+        return Ok(exposed_bins.to_vec());
+    }
+
+    let mut applied = exposed_bins.to_vec();
+    let mut kept = BTreeMap::new();
+    for (alias, declared) in aliases {
+        let Some(position) = applied.iter().position(|bin| *bin == declared) else {
+            continue;
+        };
+        let destination = bin_path(layout, &alias);
+        if applied.contains(&alias) || fs::symlink_metadata(&destination).is_ok() {
+            continue;
+        }
+        let source = bin_path(layout, &declared);
+        fs::rename(&source, &destination).with_context(|| {
+            format!(
+                "failed to rename exposed binary {} to {}",
+                source.display(),
+                destination.display()
+            )
+        })?;
+        applied[position] = alias.clone();
+        kept.insert(alias, declared);
+    }
+    write_binary_aliases(layout, package_name, &kept)?;
+    Ok(applied)
+}
+
+// Restores the declared shim name for a binary previously exposed under `alias`.
+pub fn remove_binary_alias(layout: &PrefixLayout, package_name: &str, alias: &str) -> Result<()> {
+    let Some(declared) = read_binary_aliases(layout, package_name)?.remove(alias) else {
+        return Err(anyhow!(
+            "binary '{alias}' is not an alias for package '{package_name}'"
+        ));
+    };
+    set_binary_alias(layout, package_name, alias, &declared)
+}
+
+fn validate_binary_alias_name(alias: &str) -> Result<()> {
+    if alias.is_empty() || alias == "." || alias == ".." || alias.contains(['/', '\\', '\t', '\n'])
+    {
+        return Err(anyhow!("invalid binary alias name: '{alias}'"));
+    }
+    Ok(())
+}
+
 pub fn projected_exposed_completion_path(
     package_name: &str,
     shell: ArtifactCompletionShell,
-    completion_rel_path: &str,
+    completion_rel_path: impl AsRef<Path>,
 ) -> Result<String> {
-    let relative = validated_relative_completion_source_path(completion_rel_path)?;
+    let relative = validated_relative_completion_source_path(completion_rel_path.as_ref())?;
     let normalized_package = normalize_completion_token(package_name);
     let normalized_path = normalize_completion_source_path(relative);
     Ok(format!(
@@ -183,14 +715,14 @@ pub fn expose_completion(
     install_root: &Path,
     package_name: &str,
     shell: ArtifactCompletionShell,
-    completion_rel_path: &str,
+    completion_rel_path: impl AsRef<Path>,
 ) -> Result<String> {
-    let source_rel = validated_relative_completion_source_path(completion_rel_path)?;
+    let source_rel = validated_relative_completion_source_path(completion_rel_path.as_ref())?;
     let source_path = install_root.join(source_rel);
     if !source_path.exists() {
         return Err(anyhow!(
             "declared completion path '{}' was not found in install root: {}",
-            completion_rel_path,
+            source_rel.display(),
             source_path.display()
         ));
     }
@@ -204,19 +736,19 @@ pub fn expose_completion(
     if !metadata.is_file() {
         return Err(anyhow!(
             "declared completion path '{}' must be a file: {}",
-            completion_rel_path,
+            source_rel.display(),
             source_path.display()
         ));
     }
 
-    let storage_rel_path =
-        projected_exposed_completion_path(package_name, shell, completion_rel_path)?;
+    let storage_rel_path = projected_exposed_completion_path(package_name, shell, source_rel)?;
     let destination = exposed_completion_path(layout, &storage_rel_path)?;
     if let Some(parent) = destination.parent() {
         fs::create_dir_all(parent)
             .with_context(|| format!("failed to create completion dir: {}", parent.display()))?;
     }
     if destination.exists() {
+        ensure_completion_not_owned_by_other_package(layout, package_name, &storage_rel_path)?;
         fs::remove_file(&destination).with_context(|| {
             format!(
                 "failed to replace existing completion file: {}",
@@ -238,6 +770,14 @@ pub fn expose_completion(
 pub fn remove_exposed_completion(
     layout: &PrefixLayout,
     completion_storage_rel_path: &str,
+) -> Result<()> {
+    remove_exposed_completion_with_prune(layout, completion_storage_rel_path, true)
+}
+
+pub fn remove_exposed_completion_with_prune(
+    layout: &PrefixLayout,
+    completion_storage_rel_path: &str,
+    prune: bool,
 ) -> Result<()> {
     let destination = exposed_completion_path(layout, completion_storage_rel_path)?;
     if !destination.exists() {
@@ -251,7 +791,9 @@ pub fn remove_exposed_completion(
         )
     })?;
 
-    prune_empty_completion_dirs(layout, destination.parent())?;
+    if prune {
+        prune_empty_completion_dirs(layout, destination.parent())?;
+    }
     Ok(())
 }
 
@@ -260,6 +802,23 @@ pub fn gui_asset_path(layout: &PrefixLayout, gui_storage_rel_path: &str) -> Resu
     Ok(layout.gui_dir().join(relative))
 }
 
+// Managed copy of the app's declared icon written by `expose_gui_app`, if it
+// exists on disk.
+pub fn managed_gui_icon_path(
+    layout: &PrefixLayout,
+    package_name: &str,
+    app: &ArtifactGuiApp,
+) -> Result<Option<PathBuf>> {
+    let Some(icon_asset) = projected_gui_assets(package_name, app)?
+        .into_iter()
+        .find(|asset| asset.key.starts_with("icon:"))
+    else {
+        return Ok(None);
+    };
+    let icon_path = gui_asset_path(layout, &icon_asset.rel_path)?;
+    Ok(icon_path.is_file().then_some(icon_path))
+}
+
 pub fn projected_gui_assets(
     package_name: &str,
     app: &ArtifactGuiApp,
@@ -309,6 +868,21 @@ pub fn projected_gui_assets(
         format!("handler:{}", app.app_id.trim().to_ascii_lowercase()),
         &handler_rel,
     )?;
+    if let Some(icon) = &app.icon {
+        let icon_rel = validated_relative_binary_path(icon)
+            .with_context(|| format!("gui app '{}' icon path is invalid", app.app_id))?;
+        let icon_rel = match icon_rel.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) => format!(
+                "icons/{package_token}--{app_token}.{}",
+                normalize_gui_token(&ext.to_ascii_lowercase())
+            ),
+            None => format!("icons/{package_token}--{app_token}"),
+        };
+        push_asset(
+            format!("icon:{}", app.app_id.trim().to_ascii_lowercase()),
+            &icon_rel,
+        )?;
+    }
 
     for protocol in &app.protocols {
         let scheme = normalized_protocol_scheme(&protocol.scheme)
@@ -344,7 +918,7 @@ pub fn expose_gui_app(
     install_root: &Path,
     package_name: &str,
     app: &ArtifactGuiApp,
-) -> Result<Vec<GuiExposureAsset>> {
+) -> Result<GuiExposureOutcome> {
     let projected = projected_gui_assets(package_name, app)?;
     let launcher_asset = projected
         .iter()
@@ -367,34 +941,72 @@ pub fn expose_gui_app(
         ));
     }
 
+    // Desktop environments cannot resolve a package-relative icon, so the icon is
+    // copied to a managed path and the launcher points at it.
+    let mut icon_changed = false;
+    let icon_path = match (
+        &app.icon,
+        projected.iter().find(|a| a.key.starts_with("icon:")),
+    ) {
+        (Some(icon), Some(icon_asset)) => {
+            let icon_source = install_root.join(validated_relative_binary_path(icon)?);
+            if !icon_source.is_file() {
+                return Err(anyhow!(
+                    "declared gui app icon '{}' was not found in install root: {}",
+                    icon,
+                    icon_source.display()
+                ));
+            }
+            let icon_bytes = fs::read(&icon_source).with_context(|| {
+                format!("failed to read gui app icon: {}", icon_source.display())
+            })?;
+            let icon_path = gui_asset_path(layout, &icon_asset.rel_path)?;
+            if fs::read(&icon_path).ok().as_deref() != Some(icon_bytes.as_slice()) {
+                if let Some(parent) = icon_path.parent() {
+                    fs::create_dir_all(parent).with_context(|| {
+                        format!("failed to create gui icon dir: {}", parent.display())
+                    })?;
+                }
+                fs::write(&icon_path, &icon_bytes)
+                    .with_context(|| format!("failed writing gui icon: {}", icon_path.display()))?;
+                icon_changed = true;
+            }
+            Some(icon_path)
+        }
+        _ => None,
+    };
+
     let launcher_path = gui_asset_path(layout, &launcher_asset.rel_path)?;
     if let Some(parent) = launcher_path.parent() {
         fs::create_dir_all(parent)
             .with_context(|| format!("failed to create gui launcher dir: {}", parent.display()))?;
     }
 
-    let launcher = render_gui_launcher(app, &source_path);
-    fs::write(&launcher_path, launcher.as_bytes())
-        .with_context(|| format!("failed writing gui launcher: {}", launcher_path.display()))?;
+    let launcher = render_gui_launcher(app, &source_path, icon_path.as_deref());
+    let launcher_changed = !gui_asset_matches(&launcher_path, &launcher, true)?;
+    if launcher_changed {
+        fs::write(&launcher_path, launcher.as_bytes())
+            .with_context(|| format!("failed writing gui launcher: {}", launcher_path.display()))?;
 
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut permissions = fs::metadata(&launcher_path)
-            .with_context(|| {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut permissions = fs::metadata(&launcher_path)
+                .with_context(|| {
+                    format!(
+                        "failed to inspect gui launcher: {}",
+                        launcher_path.display()
+                    )
+                })?
+                .permissions();
+            permissions.set_mode(0o755);
+            fs::set_permissions(&launcher_path, permissions).with_context(|| {
                 format!(
-                    "failed to inspect gui launcher: {}",
+                    "failed setting gui launcher permissions: {}",
                     launcher_path.display()
                 )
-            })?
-            .permissions();
-        permissions.set_mode(0o755);
-        fs::set_permissions(&launcher_path, permissions).with_context(|| {
-            format!(
-                "failed setting gui launcher permissions: {}",
-                launcher_path.display()
-            )
-        })?;
+            })?;
+        }
     }
 
     let handler_path = gui_asset_path(layout, &handler_asset.rel_path)?;
@@ -443,29 +1055,77 @@ pub fn expose_gui_app(
             ));
         }
     }
-    fs::write(&handler_path, metadata.as_bytes()).with_context(|| {
-        format!(
-            "failed writing gui handler metadata: {}",
-            handler_path.display()
-        )
-    })?;
+    let metadata_changed = !gui_asset_matches(&handler_path, &metadata, false)?;
+    if metadata_changed {
+        fs::write(&handler_path, metadata.as_bytes()).with_context(|| {
+            format!(
+                "failed writing gui handler metadata: {}",
+                handler_path.display()
+            )
+        })?;
+    }
+
+    Ok(GuiExposureOutcome {
+        assets: projected,
+        changed: launcher_changed || metadata_changed || icon_changed,
+    })
+}
+
+fn gui_asset_matches(path: &Path, expected: &str, executable: bool) -> Result<bool> {
+    let existing = match fs::read(path) {
+        Ok(existing) => existing,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("failed to read gui asset: {}", path.display()))
+        }
+    };
+    if existing != expected.as_bytes() {
+        return Ok(false);
+    }
 
-    Ok(projected)
+    #[cfg(unix)]
+    if executable {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(path)
+            .with_context(|| format!("failed to inspect gui asset: {}", path.display()))?
+            .permissions()
+            .mode();
+        return Ok(mode & 0o777 == 0o755);
+    }
+    #[cfg(not(unix))]
+    let _ = executable;
+
+    Ok(true)
 }
 
 pub fn remove_exposed_gui_asset(layout: &PrefixLayout, asset: &GuiExposureAsset) -> Result<()> {
+    remove_exposed_gui_asset_with_prune(layout, asset, true)
+}
+
+pub fn remove_exposed_gui_asset_with_prune(
+    layout: &PrefixLayout,
+    asset: &GuiExposureAsset,
+    prune: bool,
+) -> Result<()> {
     let path = gui_asset_path(layout, &asset.rel_path)?;
     if !path.exists() {
         return Ok(());
     }
     fs::remove_file(&path)
         .with_context(|| format!("failed to remove exposed gui asset: {}", path.display()))?;
-    prune_empty_gui_dirs(layout, path.parent())?;
+    if prune {
+        prune_empty_gui_dirs(layout, path.parent())?;
+    }
     Ok(())
 }
 
 #[cfg(windows)]
-pub(crate) fn render_gui_launcher(app: &ArtifactGuiApp, source_path: &Path) -> String {
+pub(crate) fn render_gui_launcher(
+    app: &ArtifactGuiApp,
+    source_path: &Path,
+    _icon_path: Option<&Path>,
+) -> String {
     format!(
         "@echo off\r\nREM {}\r\n\"{}\" %*\r\n",
         sanitize_gui_metadata_value(&app.display_name),
@@ -474,7 +1134,11 @@ pub(crate) fn render_gui_launcher(app: &ArtifactGuiApp, source_path: &Path) -> S
 }
 
 #[cfg(target_os = "linux")]
-pub(crate) fn render_gui_launcher(app: &ArtifactGuiApp, source_path: &Path) -> String {
+pub(crate) fn render_gui_launcher(
+    app: &ArtifactGuiApp,
+    source_path: &Path,
+    icon_path: Option<&Path>,
+) -> String {
     let mut mime_entries = app
         .file_associations
         .iter()
@@ -496,7 +1160,12 @@ pub(crate) fn render_gui_launcher(app: &ArtifactGuiApp, source_path: &Path) -> S
         sanitize_gui_metadata_value(&app.display_name)
     ));
     desktop.push_str(&format!("Exec=\"{}\" %U\n", source_path.display()));
-    if let Some(icon) = &app.icon {
+    if let Some(icon_path) = icon_path {
+        desktop.push_str(&format!(
+            "Icon={}\n",
+            sanitize_gui_metadata_value(&icon_path.display().to_string())
+        ));
+    } else if let Some(icon) = &app.icon {
         desktop.push_str(&format!("Icon={}\n", sanitize_gui_metadata_value(icon)));
     }
     if !app.categories.is_empty() {
@@ -517,7 +1186,11 @@ pub(crate) fn render_gui_launcher(app: &ArtifactGuiApp, source_path: &Path) -> S
 }
 
 #[cfg(all(not(windows), not(target_os = "linux")))]
-pub(crate) fn render_gui_launcher(app: &ArtifactGuiApp, source_path: &Path) -> String {
+pub(crate) fn render_gui_launcher(
+    app: &ArtifactGuiApp,
+    source_path: &Path,
+    _icon_path: Option<&Path>,
+) -> String {
     #[cfg(target_os = "macos")]
     {
         if source_path
@@ -655,6 +1328,37 @@ fn validated_relative_gui_storage_path(path: &str) -> Result<&Path> {
     Ok(relative)
 }
 
+pub fn prune_all_empty_dirs(layout: &PrefixLayout) -> Result<()> {
+    for root in [layout.completions_dir(), layout.gui_dir()] {
+        if root.exists() {
+            prune_empty_subdirs(&root)?;
+        }
+    }
+    Ok(())
+}
+
+// Removes empty directories below `dir` bottom-up and reports whether `dir`
+// itself ended up empty; the root is left in place for the caller.
+pub(crate) fn prune_empty_subdirs(dir: &Path) -> Result<bool> {
+    let mut empty = true;
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("failed reading dir: {}", dir.display()))?
+    {
+        let entry = entry.with_context(|| format!("failed reading dir: {}", dir.display()))?;
+        let path = entry.path();
+        let file_type = entry
+            .file_type()
+            .with_context(|| format!("failed reading file type: {}", path.display()))?;
+        if file_type.is_dir() && prune_empty_subdirs(&path)? {
+            fs::remove_dir(&path)
+                .with_context(|| format!("failed pruning empty dir: {}", path.display()))?;
+        } else {
+            empty = false;
+        }
+    }
+    Ok(empty)
+}
+
 fn prune_empty_gui_dirs(layout: &PrefixLayout, start: Option<&Path>) -> Result<()> {
     let mut current = start.map(PathBuf::from);
     let gui_root = layout.gui_dir();
@@ -716,16 +1420,32 @@ fn prune_empty_completion_dirs(layout: &PrefixLayout, start: Option<&Path>) -> R
     Ok(())
 }
 
+fn ensure_completion_not_owned_by_other_package(
+    layout: &PrefixLayout,
+    package_name: &str,
+    storage_rel_path: &str,
+) -> Result<()> {
+    if let Some(owner) = read_install_receipts(layout)?.into_iter().find(|receipt| {
+        receipt.name != package_name
+            && receipt
+                .exposed_completions
+                .iter()
+                .any(|owned| owned == storage_rel_path)
+    }) {
+        return Err(anyhow!(
+            "completion '{}' for package '{}' collides with completion owned by package '{}'",
+            storage_rel_path,
+            package_name,
+            owner.name
+        ));
+    }
+    Ok(())
+}
+
 fn normalize_completion_token(value: &str) -> String {
     let mut normalized = value
         .chars()
-        .map(|ch| {
-            if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' || ch == '.' {
-                ch
-            } else {
-                '_'
-            }
-        })
+        .map(normalize_completion_char)
         .collect::<String>();
     if normalized.is_empty() {
         normalized.push('_');
@@ -733,11 +1453,34 @@ fn normalize_completion_token(value: &str) -> String {
     normalized
 }
 
+fn normalize_completion_char(ch: char) -> char {
+    if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' || ch == '.' {
+        ch
+    } else {
+        '_'
+    }
+}
+
+// Normalizes a raw path component without a lossy UTF-8 round trip: valid text
+// maps exactly as `normalize_completion_token` does and every byte of an
+// invalid sequence becomes its own `_`.
+fn normalize_completion_os_token(value: &OsStr) -> String {
+    if let Some(value) = value.to_str() {
+        return normalize_completion_token(value);
+    }
+    let mut normalized = String::new();
+    for chunk in value.as_encoded_bytes().utf8_chunks() {
+        normalized.extend(chunk.valid().chars().map(normalize_completion_char));
+        normalized.extend(std::iter::repeat_n('_', chunk.invalid().len()));
+    }
+    normalized
+}
+
 fn normalize_completion_source_path(path: &Path) -> String {
     let mut parts = path
         .components()
         .filter_map(|component| match component {
-            Component::Normal(value) => Some(normalize_completion_token(&value.to_string_lossy())),
+            Component::Normal(value) => Some(normalize_completion_os_token(value)),
             _ => None,
         })
         .collect::<Vec<_>>();
@@ -747,10 +1490,12 @@ fn normalize_completion_source_path(path: &Path) -> String {
     parts.join("--")
 }
 
-fn validated_relative_completion_source_path(path: &str) -> Result<&Path> {
-    let relative = Path::new(path);
+fn validated_relative_completion_source_path(relative: &Path) -> Result<&Path> {
     if relative.is_absolute() {
-        return Err(anyhow!("completion path must be relative: {}", path));
+        return Err(anyhow!(
+            "completion path must be relative: {}",
+            relative.display()
+        ));
     }
     if relative.as_os_str().is_empty() {
         return Err(anyhow!("completion path must not be empty"));
@@ -759,7 +1504,10 @@ fn validated_relative_completion_source_path(path: &str) -> Result<&Path> {
         .components()
         .any(|component| matches!(component, Component::ParentDir))
     {
-        return Err(anyhow!("completion path must not include '..': {}", path));
+        return Err(anyhow!(
+            "completion path must not include '..': {}",
+            relative.display()
+        ));
     }
     Ok(relative)
 }
@@ -862,6 +1610,145 @@ fn stripped_macos_bundle_exec_rel_path(path: &Path) -> Option<PathBuf> {
     Some(stripped)
 }
 
+#[cfg(unix)]
+pub(crate) fn render_env_wrapper(
+    source_path: &Path,
+    interpreter: Option<&str>,
+    env_policy: &EnvPolicy,
+) -> Result<String> {
+    env_policy.validate()?;
+    let mut words = Vec::new();
+    for name in &env_policy.inherited {
+        words.push(format!("${{{name}+\"{name}=${name}\"}}"));
+    }
+    for (name, value) in &env_policy.declared {
+        words.push(shell_single_quote(&format!("{name}={value}")));
+    }
+    if let Some(interpreter) = interpreter {
+        validate_interpreter(interpreter)?;
+        words.push(shell_single_quote(interpreter));
+    }
+    words.push(shell_single_quote(&source_path.display().to_string()));
+    Ok(format!(
+        "#!/bin/sh\nexec /usr/bin/env -i {} \"$@\"\n",
+        words.join(" ")
+    ))
+}
+
+#[cfg(unix)]
+fn shell_single_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+// Mirrors the Unix `env -i` wrapper: every variable outside `inherited` is
+// unset before the declared ones are applied. Values that `set "NAME=value"`
+// cannot carry literally are rejected instead of being rewritten.
+#[cfg(windows)]
+pub(crate) fn render_env_wrapper(
+    source_path: &Path,
+    interpreter: Option<&str>,
+    env_policy: &EnvPolicy,
+) -> Result<String> {
+    env_policy.validate()?;
+    for (name, value) in &env_policy.declared {
+        if value.contains(['"', '%', '!', '^']) {
+            return Err(anyhow!(
+                "environment variable '{name}' value contains characters unsupported by Windows wrappers (\", %, !, ^)"
+            ));
+        }
+    }
+    let mut wrapper = String::from("@echo off\r\nsetlocal DisableDelayedExpansion\r\n");
+    let keep = env_policy
+        .inherited
+        .iter()
+        .map(|name| format!("if /i not \"%%V\"==\"{name}\" "))
+        .collect::<String>();
+    wrapper.push_str(&format!(
+        "for /f \"delims==\" %%V in ('set') do {keep}set \"%%V=\"\r\n"
+    ));
+    for (name, value) in &env_policy.declared {
+        wrapper.push_str(&format!("set \"{name}={value}\"\r\n"));
+    }
+    if let Some(interpreter) = interpreter {
+        validate_interpreter(interpreter)?;
+        wrapper.push_str(&format!("\"{interpreter}\" "));
+    }
+    wrapper.push_str(&format!("\"{}\" %*\r\n", source_path.display()));
+    Ok(wrapper)
+}
+
+fn validate_interpreter(interpreter: &str) -> Result<()> {
+    if interpreter.trim().is_empty()
+        || interpreter
+            .chars()
+            .any(|ch| ch.is_control() || matches!(ch, '"' | '\''))
+    {
+        return Err(anyhow!("invalid binary interpreter: '{interpreter}'"));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+pub(crate) fn render_interpreter_wrapper(interpreter: &str, source_path: &Path) -> Result<String> {
+    validate_interpreter(interpreter)?;
+    Ok(format!(
+        "#!/bin/sh\nexec {} {} \"$@\"\n",
+        shell_single_quote(interpreter),
+        shell_single_quote(&source_path.display().to_string())
+    ))
+}
+
+#[cfg(windows)]
+pub(crate) fn render_interpreter_wrapper(interpreter: &str, source_path: &Path) -> Result<String> {
+    validate_interpreter(interpreter)?;
+    Ok(format!(
+        "@echo off\r\n\"{interpreter}\" \"{}\" %*\r\n",
+        source_path.display()
+    ))
+}
+
+// A file counts as directly executable when the OS can launch it without help:
+// on Unix it needs an execute bit plus a shebang or native binary header.
+#[cfg(unix)]
+fn is_directly_executable(path: &Path) -> Result<bool> {
+    use std::io::Read;
+    use std::os::unix::fs::PermissionsExt;
+
+    let metadata = fs::metadata(path)
+        .with_context(|| format!("failed reading binary metadata: {}", path.display()))?;
+    if !metadata.is_file() || metadata.permissions().mode() & 0o111 == 0 {
+        return Ok(false);
+    }
+
+    let mut header = [0_u8; 4];
+    let mut file =
+        fs::File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let read = file
+        .read(&mut header)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    let header = &header[..read];
+    Ok(header.starts_with(b"#!")
+        || header == b"\x7fELF"
+        || matches!(
+            header,
+            [0xfe, 0xed, 0xfa, 0xce | 0xcf]
+                | [0xce | 0xcf, 0xfa, 0xed, 0xfe]
+                | [0xca, 0xfe, 0xba, 0xbe]
+        ))
+}
+
+#[cfg(windows)]
+fn is_directly_executable(path: &Path) -> Result<bool> {
+    Ok(path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            ["exe", "com", "bat", "cmd"]
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        }))
+}
+
 fn create_binary_entry(source_path: &Path, destination: &Path) -> Result<()> {
     #[cfg(unix)]
     {
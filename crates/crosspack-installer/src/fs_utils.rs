@@ -1,6 +1,9 @@
 use std::fs;
 use std::io;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static ATOMIC_WRITE_SEQUENCE: AtomicU64 = AtomicU64::new(0);
 
 pub fn remove_file_if_exists(path: &Path) -> io::Result<()> {
     if path.exists() {
@@ -8,3 +11,26 @@ pub fn remove_file_if_exists(path: &Path) -> io::Result<()> {
     }
     Ok(())
 }
+
+/// Writes `contents` to a sibling temp file and renames it over `path`, so
+/// readers observe either the previous file or the complete new one.
+pub(crate) fn write_file_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let tmp_path = atomic_write_tmp_path(path);
+    if let Err(err) = fs::write(&tmp_path, contents).and_then(|()| fs::rename(&tmp_path, path)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(err);
+    }
+    Ok(())
+}
+
+fn atomic_write_tmp_path(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(
+        ".{file_name}.tmp-{}-{}",
+        std::process::id(),
+        ATOMIC_WRITE_SEQUENCE.fetch_add(1, Ordering::Relaxed)
+    ))
+}
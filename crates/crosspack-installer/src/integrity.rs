@@ -0,0 +1,820 @@
+use anyhow::{anyhow, Context, Result};
+use crosspack_core::ArchiveType;
+use crosspack_security::hash_sha256_reader;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::artifact::{
+    extract_tar_archive, extract_zip, is_dev_sha256_sentinel, is_tar_archive, make_tmp_dir,
+    stage_artifact_tree,
+};
+use crate::exposure::{
+    bin_path, exposed_completion_path, gui_asset_path, read_all_gui_exposure_states,
+    read_gui_exposure_state, remove_exposed_completion, remove_exposed_gui_asset,
+};
+use crate::fs_utils::remove_file_if_exists;
+use crate::receipts::read_install_receipts;
+use crate::uninstall::remove_receipt_artifacts;
+use crate::{
+    ArchiveLimits, ArtifactInstallOptions, GuiExposureAsset, InstallMode, InstallReceipt,
+    IntegrityIssue, PrefixLayout, RepairAction, RepairOutcome, VerifyResult, VerifyStatus,
+};
+
+pub fn verify_prefix_integrity(layout: &PrefixLayout) -> Result<Vec<IntegrityIssue>> {
+    let receipts = read_install_receipts(layout)?;
+    let mut issues = Vec::new();
+
+    for receipt in &receipts {
+        let package_dir = layout.package_dir(&receipt.name, &receipt.version);
+        if receipt.install_mode == InstallMode::Managed && !package_dir.exists() {
+            let found = unreferenced_version_dirs(layout, &receipts, &receipt.name)?;
+            if found.is_empty() {
+                issues.push(IntegrityIssue::StaleReceipt {
+                    name: receipt.name.clone(),
+                    version: receipt.version.clone(),
+                });
+            } else {
+                issues.push(IntegrityIssue::VersionMismatch {
+                    name: receipt.name.clone(),
+                    recorded: receipt.version.clone(),
+                    found,
+                });
+            }
+        } else if let (Some(recorded_file_count), Some(recorded_bytes)) =
+            (receipt.file_count, receipt.installed_bytes)
+        {
+            if package_dir.exists() {
+                let (actual_file_count, actual_bytes) = measure_install_tree(&package_dir)?;
+                if (actual_file_count, actual_bytes) != (recorded_file_count, recorded_bytes) {
+                    issues.push(IntegrityIssue::SizeDrift {
+                        name: receipt.name.clone(),
+                        version: receipt.version.clone(),
+                        recorded_file_count,
+                        recorded_bytes,
+                        actual_file_count,
+                        actual_bytes,
+                    });
+                }
+            }
+        }
+        if let Some(cache_path) = &receipt.cache_path {
+            if !layout.resolve_cache_path(cache_path).exists() {
+                issues.push(IntegrityIssue::MissingCache {
+                    name: receipt.name.clone(),
+                    cache_path: cache_path.clone(),
+                });
+            }
+        }
+    }
+
+    let referenced_bins = receipts
+        .iter()
+        .flat_map(|receipt| receipt.exposed_bins.iter().cloned())
+        .collect::<BTreeSet<_>>();
+    for name in list_bin_entry_names(&layout.bin_dir())? {
+        if !referenced_bins.contains(&name)
+            && is_crosspack_bin_shim(layout, &bin_path(layout, &name))
+        {
+            issues.push(IntegrityIssue::OrphanedBinary { name });
+        }
+    }
+
+    let referenced_completions = receipts
+        .iter()
+        .flat_map(|receipt| receipt.exposed_completions.iter().cloned())
+        .collect::<BTreeSet<_>>();
+    for rel_path in collect_relative_files(&layout.completions_dir(), "packages")? {
+        if !referenced_completions.contains(&rel_path) {
+            issues.push(IntegrityIssue::OrphanedCompletion { rel_path });
+        }
+    }
+
+    let referenced_gui_assets = read_all_gui_exposure_states(layout)?
+        .into_values()
+        .flatten()
+        .map(|asset| asset.rel_path)
+        .collect::<BTreeSet<_>>();
+    for subdir in ["launchers", "handlers"] {
+        for rel_path in collect_relative_files(&layout.gui_dir(), subdir)? {
+            if !referenced_gui_assets.contains(&rel_path) {
+                issues.push(IntegrityIssue::OrphanedGuiAsset { rel_path });
+            }
+        }
+    }
+
+    Ok(issues)
+}
+
+// Version dirs under `pkgs/<name>` that no receipt for `name` records; hidden
+// `.partial-*` staging dirs are not versions.
+fn unreferenced_version_dirs(
+    layout: &PrefixLayout,
+    receipts: &[InstallReceipt],
+    name: &str,
+) -> Result<Vec<String>> {
+    let package_root = layout.pkgs_dir().join(name);
+    let entries = match fs::read_dir(&package_root) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {}", package_root.display()))
+        }
+    };
+
+    let mut found = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let version = entry.file_name().to_string_lossy().to_string();
+        if version.starts_with('.') || !entry.file_type()?.is_dir() {
+            continue;
+        }
+        if !receipts
+            .iter()
+            .any(|receipt| receipt.name == name && receipt.version == version)
+        {
+            found.push(version);
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+// Re-extracts each managed package's cached archive and compares it entry by
+// entry with `pkgs/<name>/<version>`. Packages with recorded file digests are
+// checked against those instead. Without artifact options the comparison
+// descends through single-directory wrappers until the extracted tree lines up
+// with the installed one.
+pub fn verify_installed(layout: &PrefixLayout) -> Result<Vec<VerifyResult>> {
+    verify_installed_with_artifact_options(layout, &BTreeMap::new())
+}
+
+// `artifact_options` maps package names to the options their artifact was
+// installed with, so the cached archive is staged with the same
+// `artifact_root`, `strip_components`, and file mappings as install used.
+// Failures while checking one package are reported as that package's status.
+pub fn verify_installed_with_artifact_options(
+    layout: &PrefixLayout,
+    artifact_options: &BTreeMap<String, ArtifactInstallOptions<'_>>,
+) -> Result<Vec<VerifyResult>> {
+    let mut results = Vec::new();
+    for receipt in read_install_receipts(layout)? {
+        let status =
+            verify_installed_receipt(layout, &receipt, artifact_options.get(&receipt.name))
+                .unwrap_or_else(|err| VerifyStatus::Failed {
+                    reason: format!("{err:#}"),
+                });
+        results.push(VerifyResult {
+            name: receipt.name,
+            version: receipt.version,
+            status,
+        });
+    }
+    Ok(results)
+}
+
+fn verify_installed_receipt(
+    layout: &PrefixLayout,
+    receipt: &InstallReceipt,
+    artifact_options: Option<&ArtifactInstallOptions<'_>>,
+) -> Result<VerifyStatus> {
+    if receipt.install_mode == InstallMode::Native {
+        return Ok(skipped(
+            "native installs are owned by the platform installer",
+        ));
+    }
+    let install_root = layout.package_dir(&receipt.name, &receipt.version);
+    if !install_root.exists() {
+        return Ok(VerifyStatus::MissingPackageDir);
+    }
+    if let Some(digests) = read_installed_file_digests(layout, &receipt.name)? {
+        let (missing, modified, extra) = diff_tree_against_digests(&install_root, &digests, None)?;
+        return Ok(verify_status(modified, missing, extra));
+    }
+    let Some(cache_path) = receipt.cache_path.as_deref() else {
+        return Ok(skipped("receipt does not record a cached artifact"));
+    };
+    let archive_path = layout.resolve_cache_path(cache_path);
+    if !archive_path.exists() {
+        return Ok(VerifyStatus::MissingCache {
+            cache_path: cache_path.to_string(),
+        });
+    }
+    let Some(archive_type) = ArchiveType::infer_from_url(&archive_path.to_string_lossy()) else {
+        return Ok(skipped("cached artifact type cannot be inferred"));
+    };
+    if archive_type != ArchiveType::Zip && !is_tar_archive(archive_type) {
+        return Ok(skipped(&format!(
+            "archive type '{}' cannot be re-extracted for verification",
+            archive_type.as_str()
+        )));
+    }
+    if let Some(expected) = receipt
+        .artifact_sha256
+        .as_deref()
+        .filter(|expected| !is_dev_sha256_sentinel(expected))
+    {
+        let actual = file_sha256(&archive_path)?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Ok(VerifyStatus::CacheChecksumMismatch {
+                cache_path: cache_path.to_string(),
+                expected: expected.to_string(),
+                actual,
+            });
+        }
+    }
+
+    let extract_dir = make_tmp_dir(layout, &format!("verify-{}", receipt.name))?;
+    let status = compare_with_cached_archive(
+        &install_root,
+        &archive_path,
+        &extract_dir,
+        archive_type,
+        artifact_options,
+    );
+    let _ = fs::remove_dir_all(&extract_dir);
+    status
+}
+
+fn compare_with_cached_archive(
+    install_root: &Path,
+    archive_path: &Path,
+    extract_dir: &Path,
+    archive_type: ArchiveType,
+    artifact_options: Option<&ArtifactInstallOptions<'_>>,
+) -> Result<VerifyStatus> {
+    let payload_root = match artifact_options {
+        Some(options) => {
+            let raw_dir = extract_dir.join("raw");
+            let staged_dir = extract_dir.join("staged");
+            for dir in [&raw_dir, &staged_dir] {
+                fs::create_dir_all(dir)
+                    .with_context(|| format!("failed to create {}", dir.display()))?;
+            }
+            let options = ArtifactInstallOptions {
+                expected_sha256: None,
+                permissions_policy: Default::default(),
+                ..*options
+            };
+            stage_artifact_tree(
+                archive_path,
+                archive_type,
+                &options,
+                None,
+                &raw_dir,
+                &staged_dir,
+            )?;
+            staged_dir
+        }
+        None => {
+            if archive_type == ArchiveType::Zip {
+                extract_zip(archive_path, extract_dir)?;
+            } else {
+                extract_tar_archive(
+                    archive_path,
+                    extract_dir,
+                    archive_type,
+                    ArchiveLimits::default(),
+                )?;
+            }
+            unwrap_single_directory_wrappers(install_root, extract_dir)?
+        }
+    };
+
+    let installed = collect_tree_entries(install_root)?;
+    let expected = collect_tree_entries(&payload_root)?;
+
+    // A tree that shares no files with the archive is reported as drift like
+    // any other divergence: every archive file is missing, every installed
+    // file is extra.
+    let mut modified = Vec::new();
+    let mut extra = Vec::new();
+    for (rel_path, installed_entry) in &installed {
+        let Some(expected_entry) = expected.get(rel_path) else {
+            extra.push(rel_path.clone());
+            continue;
+        };
+        let same = match (installed_entry, expected_entry) {
+            (TreeEntry::File, TreeEntry::File) => {
+                let installed_bytes = fs::read(install_root.join(rel_path))
+                    .with_context(|| format!("failed to read installed file: {rel_path}"))?;
+                let expected_bytes = fs::read(payload_root.join(rel_path))
+                    .with_context(|| format!("failed to read extracted file: {rel_path}"))?;
+                installed_bytes == expected_bytes
+            }
+            (TreeEntry::Symlink(installed_target), TreeEntry::Symlink(expected_target)) => {
+                installed_target == expected_target
+            }
+            _ => false,
+        };
+        if !same {
+            modified.push(rel_path.clone());
+        }
+    }
+    let missing = expected
+        .keys()
+        .filter(|rel_path| !installed.contains_key(*rel_path))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    Ok(verify_status(modified, missing, extra))
+}
+
+fn unwrap_single_directory_wrappers(install_root: &Path, extract_dir: &Path) -> Result<PathBuf> {
+    let installed_top = top_level_names(install_root)?;
+    let mut payload_root = extract_dir.to_path_buf();
+    loop {
+        if top_level_names(&payload_root)? == installed_top {
+            return Ok(payload_root);
+        }
+        let mut entries = fs::read_dir(&payload_root)
+            .with_context(|| format!("failed to read {}", payload_root.display()))?
+            .collect::<std::io::Result<Vec<_>>>()?;
+        if entries.len() != 1 || !entries[0].file_type()?.is_dir() {
+            return Ok(payload_root);
+        }
+        payload_root = entries.remove(0).path();
+    }
+}
+
+fn verify_status(modified: Vec<String>, missing: Vec<String>, extra: Vec<String>) -> VerifyStatus {
+    if modified.is_empty() && missing.is_empty() && extra.is_empty() {
+        return VerifyStatus::Verified;
+    }
+    VerifyStatus::Drifted {
+        modified,
+        missing,
+        extra,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum TreeEntry {
+    File,
+    Symlink(PathBuf),
+}
+
+// Symlinks are recorded with their target and never followed, so links to
+// directories and dangling links compare like any other entry.
+fn collect_tree_entries(root: &Path) -> Result<BTreeMap<String, TreeEntry>> {
+    let mut entries = BTreeMap::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in
+            fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))?
+        {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let tree_entry = if file_type.is_symlink() {
+                TreeEntry::Symlink(
+                    fs::read_link(&path)
+                        .with_context(|| format!("failed to read symlink {}", path.display()))?,
+                )
+            } else {
+                TreeEntry::File
+            };
+            entries.insert(relative_key(root, &path)?, tree_entry);
+        }
+    }
+    Ok(entries)
+}
+
+fn relative_key(root: &Path, path: &Path) -> Result<String> {
+    let rel = path
+        .strip_prefix(root)
+        .with_context(|| format!("failed to relativize {}", path.display()))?;
+    Ok(rel
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+fn file_sha256(path: &Path) -> Result<String> {
+    let mut file =
+        fs::File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    hash_sha256_reader(&mut file).with_context(|| format!("failed to hash {}", path.display()))
+}
+
+fn top_level_names(dir: &Path) -> Result<BTreeSet<String>> {
+    let mut names = BTreeSet::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))? {
+        names.insert(entry?.file_name().to_string_lossy().to_string());
+    }
+    Ok(names)
+}
+
+fn skipped(reason: &str) -> VerifyStatus {
+    VerifyStatus::Skipped {
+        reason: reason.to_string(),
+    }
+}
+
+pub fn repair_prefix_integrity(
+    layout: &PrefixLayout,
+    issues: &[IntegrityIssue],
+) -> Result<Vec<RepairOutcome>> {
+    let mut outcomes = Vec::with_capacity(issues.len());
+    for issue in issues {
+        let action = match issue {
+            IntegrityIssue::OrphanedBinary { name } => {
+                let path = bin_path(layout, name);
+                let existed = is_crosspack_bin_shim(layout, &path);
+                if existed {
+                    fs::remove_file(&path).with_context(|| {
+                        format!("failed to remove orphaned binary: {}", path.display())
+                    })?;
+                }
+                removed_or_resolved(existed)
+            }
+            IntegrityIssue::OrphanedCompletion { rel_path } => {
+                let existed = layout.completions_dir().join(rel_path).exists();
+                remove_exposed_completion(layout, rel_path)?;
+                removed_or_resolved(existed)
+            }
+            IntegrityIssue::OrphanedGuiAsset { rel_path } => {
+                let existed = layout.gui_dir().join(rel_path).exists();
+                remove_exposed_gui_asset(
+                    layout,
+                    &GuiExposureAsset {
+                        key: String::new(),
+                        rel_path: rel_path.clone(),
+                    },
+                )?;
+                removed_or_resolved(existed)
+            }
+            IntegrityIssue::StaleReceipt { name, version } => {
+                let receipts = read_install_receipts(layout)?;
+                match receipts
+                    .iter()
+                    .find(|receipt| &receipt.name == name && &receipt.version == version)
+                {
+                    Some(receipt) if !layout.package_dir(name, version).exists() => {
+                        remove_receipt_artifacts(layout, receipt)?;
+                        RepairAction::Removed
+                    }
+                    _ => RepairAction::AlreadyResolved,
+                }
+            }
+            // Dropping the receipt would orphan the files on disk; reinstalling
+            // the recorded version is the only safe fix.
+            IntegrityIssue::VersionMismatch { .. }
+            | IntegrityIssue::MissingCache { .. }
+            | IntegrityIssue::SizeDrift { .. } => RepairAction::NeedsRedownload,
+        };
+        outcomes.push(RepairOutcome {
+            issue: issue.clone(),
+            action,
+        });
+    }
+    Ok(outcomes)
+}
+
+pub fn package_files(layout: &PrefixLayout, name: &str) -> Result<Vec<PathBuf>> {
+    let receipt = read_install_receipts(layout)?
+        .into_iter()
+        .find(|receipt| receipt.name == name)
+        .ok_or_else(|| anyhow!("package '{name}' is not installed"))?;
+
+    let package_rel = format!("pkgs/{}/{}", receipt.name, receipt.version);
+    let mut files = collect_relative_files(layout.prefix(), &package_rel)?
+        .into_iter()
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+
+    files.extend(exposed_relative_paths(layout, &receipt)?);
+
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+pub fn owner_of_file(layout: &PrefixLayout, path: &Path) -> Result<Option<String>> {
+    let rel = path.strip_prefix(layout.prefix()).unwrap_or(path);
+
+    let mut package_dirs = BTreeMap::new();
+    let mut exposed = BTreeMap::new();
+    for receipt in read_install_receipts(layout)? {
+        for exposed_path in exposed_relative_paths(layout, &receipt)? {
+            exposed.insert(exposed_path, receipt.name.clone());
+        }
+        package_dirs.insert(Path::new("pkgs").join(&receipt.name), receipt.name);
+    }
+
+    if let Some(owner) = exposed.get(rel) {
+        return Ok(Some(owner.clone()));
+    }
+    Ok(package_dirs
+        .into_iter()
+        .find(|(dir, _)| rel.starts_with(dir) && rel != dir.as_path())
+        .map(|(_, owner)| owner))
+}
+
+fn exposed_relative_paths(layout: &PrefixLayout, receipt: &InstallReceipt) -> Result<Vec<PathBuf>> {
+    let mut exposed = receipt
+        .exposed_bins
+        .iter()
+        .map(|bin| bin_path(layout, bin))
+        .collect::<Vec<_>>();
+    for completion in &receipt.exposed_completions {
+        exposed.push(exposed_completion_path(layout, completion)?);
+    }
+    for asset in read_gui_exposure_state(layout, &receipt.name)? {
+        exposed.push(gui_asset_path(layout, &asset.rel_path)?);
+    }
+    Ok(exposed
+        .into_iter()
+        .map(|path| {
+            path.strip_prefix(layout.prefix())
+                .map(Path::to_path_buf)
+                .unwrap_or(path)
+        })
+        .collect())
+}
+
+// Returns `(file_count, installed_bytes)` for an install tree; symlinks count as
+// files sized by their link metadata and are never followed.
+pub fn measure_install_tree(install_root: &Path) -> Result<(u64, u64)> {
+    let mut file_count = 0u64;
+    let mut installed_bytes = 0u64;
+    let mut pending = vec![install_root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in
+            fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))?
+        {
+            let entry = entry?;
+            let metadata = fs::symlink_metadata(entry.path())
+                .with_context(|| format!("failed to stat {}", entry.path().display()))?;
+            if metadata.is_dir() {
+                pending.push(entry.path());
+                continue;
+            }
+            file_count += 1;
+            installed_bytes += metadata.len();
+        }
+    }
+    Ok((file_count, installed_bytes))
+}
+
+pub fn verify_installed_file_manifest(install_root: &Path, manifest_rel_path: &str) -> Result<()> {
+    let (missing, mismatched, extra) = diff_against_file_manifest(install_root, manifest_rel_path)?;
+    if missing.is_empty() && mismatched.is_empty() && extra.is_empty() {
+        return Ok(());
+    }
+
+    let mut problems = Vec::new();
+    if !missing.is_empty() {
+        problems.push(format!("missing: {}", missing.join(", ")));
+    }
+    if !mismatched.is_empty() {
+        problems.push(format!("mismatched: {}", mismatched.join(", ")));
+    }
+    if !extra.is_empty() {
+        problems.push(format!("extra: {}", extra.join(", ")));
+    }
+    Err(anyhow!(
+        "installed files do not match file manifest '{}' ({})",
+        manifest_rel_path,
+        problems.join("; ")
+    ))
+}
+
+// Returns `(missing, mismatched, extra)` relative paths. The manifest lives in
+// the tree it describes, so it is only trusted right after install extracted
+// it from a checksum-verified artifact.
+fn diff_against_file_manifest(
+    install_root: &Path,
+    manifest_rel_path: &str,
+) -> Result<(Vec<String>, Vec<String>, Vec<String>)> {
+    let (manifest_key, expected) = read_file_manifest(install_root, manifest_rel_path)?;
+    diff_tree_against_digests(install_root, &expected, Some(&manifest_key))
+}
+
+fn read_file_manifest(
+    install_root: &Path,
+    manifest_rel_path: &str,
+) -> Result<(String, BTreeMap<String, String>)> {
+    let manifest_rel = Path::new(manifest_rel_path);
+    if manifest_rel_path.is_empty()
+        || !manifest_rel
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(anyhow!(
+            "file manifest path must be relative and stay inside the package: {manifest_rel_path}"
+        ));
+    }
+
+    let manifest_path = install_root.join(manifest_rel);
+    let raw = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("failed to read file manifest: {}", manifest_path.display()))?;
+    let expected = parse_file_digests(&raw).context("invalid file manifest")?;
+    Ok((relative_key(install_root, &manifest_path)?, expected))
+}
+
+fn parse_file_digests(raw: &str) -> Result<BTreeMap<String, String>> {
+    let mut expected = BTreeMap::new();
+    for (index, line) in raw.lines().enumerate() {
+        let line = line.trim_end();
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some((digest, rel_path)) = line.split_once(char::is_whitespace) else {
+            return Err(anyhow!(
+                "invalid file manifest line {}: expected '<sha256> <path>'",
+                index + 1
+            ));
+        };
+        let rel_path = rel_path.trim_start().trim_start_matches('*');
+        if digest.len() != 64 || rel_path.is_empty() {
+            return Err(anyhow!(
+                "invalid file manifest line {}: expected '<sha256> <path>'",
+                index + 1
+            ));
+        }
+        expected.insert(rel_path.to_string(), digest.to_ascii_lowercase());
+    }
+    Ok(expected)
+}
+
+// Returns `(missing, mismatched, extra)` relative paths. Symlinks are hashed
+// through their target; one that cannot be read (dangling, or pointing at a
+// directory) counts as mismatched instead of failing the check.
+fn diff_tree_against_digests(
+    install_root: &Path,
+    expected: &BTreeMap<String, String>,
+    skip: Option<&str>,
+) -> Result<(Vec<String>, Vec<String>, Vec<String>)> {
+    let mut missing = Vec::new();
+    let mut mismatched = Vec::new();
+    let mut extra = Vec::new();
+    let mut seen = BTreeSet::new();
+    for (rel_path, entry) in collect_tree_entries(install_root)? {
+        if skip == Some(rel_path.as_str()) {
+            continue;
+        }
+        let Some(digest) = expected.get(&rel_path) else {
+            extra.push(rel_path);
+            continue;
+        };
+        let path = install_root.join(&rel_path);
+        let actual = match entry {
+            TreeEntry::File => Some(file_sha256(&path)?),
+            TreeEntry::Symlink(_) => file_sha256(&path).ok(),
+        };
+        if actual.as_deref() != Some(digest.as_str()) {
+            mismatched.push(rel_path.clone());
+        }
+        seen.insert(rel_path);
+    }
+    for rel_path in expected.keys() {
+        if !seen.contains(rel_path) {
+            missing.push(rel_path.clone());
+        }
+    }
+    Ok((missing, mismatched, extra))
+}
+
+// Records the artifact's `file_manifest` digests, plus the manifest's own
+// digest, under `state/installed/` so `verify_installed` never trusts the copy
+// inside `pkgs/`. Call it right after install, while the tree still matches the
+// checksum-verified artifact.
+pub fn record_installed_file_digests(
+    layout: &PrefixLayout,
+    name: &str,
+    install_root: &Path,
+    manifest_rel_path: &str,
+) -> Result<()> {
+    let (manifest_key, mut digests) = read_file_manifest(install_root, manifest_rel_path)?;
+    digests.insert(
+        manifest_key.clone(),
+        file_sha256(&install_root.join(&manifest_key))?,
+    );
+    let payload = digests
+        .iter()
+        .map(|(rel_path, digest)| format!("{digest}  {rel_path}\n"))
+        .collect::<String>();
+    let path = layout.file_digests_state_path(name);
+    fs::write(&path, payload.as_bytes())
+        .with_context(|| format!("failed to write file digest state: {}", path.display()))
+}
+
+pub fn clear_installed_file_digests(layout: &PrefixLayout, name: &str) -> Result<()> {
+    let path = layout.file_digests_state_path(name);
+    remove_file_if_exists(&path)
+        .with_context(|| format!("failed to remove file digest state: {}", path.display()))
+}
+
+fn read_installed_file_digests(
+    layout: &PrefixLayout,
+    name: &str,
+) -> Result<Option<BTreeMap<String, String>>> {
+    let path = layout.file_digests_state_path(name);
+    let raw = match fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("failed to read file digest state: {}", path.display()))
+        }
+    };
+    parse_file_digests(&raw)
+        .with_context(|| format!("failed to parse file digest state: {}", path.display()))
+        .map(Some)
+}
+
+fn removed_or_resolved(existed: bool) -> RepairAction {
+    if existed {
+        RepairAction::Removed
+    } else {
+        RepairAction::AlreadyResolved
+    }
+}
+
+const MAX_BIN_SHIM_BYTES: u64 = 64 * 1024;
+
+// Only entries crosspack itself writes count as shims: symlinks into `pkgs/`
+// and small wrapper scripts that invoke a path under `pkgs/`. Anything else in
+// `bin/` belongs to the user and is never reported or removed.
+fn is_crosspack_bin_shim(layout: &PrefixLayout, path: &Path) -> bool {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return false;
+    };
+    let pkgs_dir = layout.pkgs_dir();
+    if metadata.file_type().is_symlink() {
+        return fs::read_link(path)
+            .is_ok_and(|target| layout.bin_dir().join(target).starts_with(&pkgs_dir));
+    }
+    if !metadata.is_file() || metadata.len() > MAX_BIN_SHIM_BYTES {
+        return false;
+    }
+    fs::read(path).is_ok_and(|contents| {
+        String::from_utf8_lossy(&contents).contains(&pkgs_dir.display().to_string())
+    })
+}
+
+fn list_bin_entry_names(bin_dir: &Path) -> Result<Vec<String>> {
+    if !bin_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut names = Vec::new();
+    for entry in fs::read_dir(bin_dir)
+        .with_context(|| format!("failed to read bin directory: {}", bin_dir.display()))?
+    {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            continue;
+        }
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let name = if cfg!(windows) {
+            match file_name.strip_suffix(".cmd") {
+                Some(name) => name.to_string(),
+                None => continue,
+            }
+        } else {
+            file_name
+        };
+        names.push(name);
+    }
+    names.sort();
+    Ok(names)
+}
+
+fn collect_relative_files(root: &Path, subdir: &str) -> Result<Vec<String>> {
+    let start = root.join(subdir);
+    if !start.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut files = Vec::new();
+    let mut pending = vec![start];
+    while let Some(dir) = pending.pop() {
+        for entry in
+            fs::read_dir(&dir).with_context(|| format!("failed to read {}", dir.display()))?
+        {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let rel = path
+                .strip_prefix(root)
+                .with_context(|| format!("failed to relativize {}", path.display()))?;
+            let rel = rel
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+                .join("/");
+            files.push(rel);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
@@ -1,4 +1,4 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use crosspack_core::{ArchiveType, ArtifactCompletionShell};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -6,12 +6,81 @@ use std::path::{Path, PathBuf};
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PrefixLayout {
     prefix: PathBuf,
+    allow_relative: bool,
+    xdg: Option<XdgDirs>,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct XdgDirs {
+    bin: PathBuf,
+    cache: PathBuf,
+    state: PathBuf,
+}
+
 impl PrefixLayout {
     pub fn new(prefix: impl Into<PathBuf>) -> Self {
         Self {
             prefix: prefix.into(),
+            allow_relative: false,
+            xdg: None,
+        }
+    }
+
+    // Splits the layout across XDG base directories: `pkgs`/`share` live under the
+    // data dir (which also serves as `prefix()`), while cache, state, and bin move out.
+    pub fn xdg() -> Result<Self> {
+        Self::xdg_with_env(|key| std::env::var(key).ok())
+    }
+
+    pub fn xdg_with_env(env: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let home = env("HOME")
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+            .context("HOME is not set; cannot resolve XDG layout")?;
+        let base_dir = |key: &str, fallback: &[&str]| {
+            env(key)
+                .map(PathBuf::from)
+                .filter(|path| path.is_absolute())
+                .unwrap_or_else(|| {
+                    fallback
+                        .iter()
+                        .fold(home.clone(), |dir, part| dir.join(part))
+                })
+        };
+
+        Ok(Self {
+            prefix: base_dir("XDG_DATA_HOME", &[".local", "share"]).join("crosspack"),
+            allow_relative: false,
+            xdg: Some(XdgDirs {
+                bin: home.join(".local").join("bin"),
+                cache: base_dir("XDG_CACHE_HOME", &[".cache"]).join("crosspack"),
+                state: base_dir("XDG_STATE_HOME", &[".local", "state"]).join("crosspack"),
+            }),
+        })
+    }
+
+    pub fn allowing_relative_prefix(mut self) -> Self {
+        self.allow_relative = true;
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.prefix.as_os_str().is_empty() {
+            return Err(anyhow!("refusing to use an empty install prefix"));
         }
+        if self.prefix.is_absolute() && self.prefix.parent().is_none() {
+            return Err(anyhow!(
+                "refusing to use filesystem root as install prefix: {}",
+                self.prefix.display()
+            ));
+        }
+        if !self.prefix.is_absolute() && !self.allow_relative {
+            return Err(anyhow!(
+                "install prefix must be an absolute path: {}",
+                self.prefix.display()
+            ));
+        }
+        Ok(())
     }
 
     pub fn prefix(&self) -> &Path {
@@ -23,15 +92,24 @@ impl PrefixLayout {
     }
 
     pub fn bin_dir(&self) -> PathBuf {
-        self.prefix.join("bin")
+        match &self.xdg {
+            Some(xdg) => xdg.bin.clone(),
+            None => self.prefix.join("bin"),
+        }
     }
 
     pub fn state_dir(&self) -> PathBuf {
-        self.prefix.join("state")
+        match &self.xdg {
+            Some(xdg) => xdg.state.clone(),
+            None => self.prefix.join("state"),
+        }
     }
 
     pub fn cache_dir(&self) -> PathBuf {
-        self.prefix.join("cache")
+        match &self.xdg {
+            Some(xdg) => xdg.cache.clone(),
+            None => self.prefix.join("cache"),
+        }
     }
 
     pub fn share_dir(&self) -> PathBuf {
@@ -90,6 +168,15 @@ impl PrefixLayout {
         self.installed_state_dir().join(format!("{name}.receipt"))
     }
 
+    pub fn receipt_signature_path(&self, name: &str) -> PathBuf {
+        self.installed_state_dir()
+            .join(format!("{name}.receipt.sig"))
+    }
+
+    pub fn receipt_signing_key_path(&self) -> PathBuf {
+        self.state_dir().join("receipt-signing.key")
+    }
+
     pub fn gui_state_path(&self, name: &str) -> PathBuf {
         self.installed_state_dir().join(format!("{name}.gui"))
     }
@@ -99,6 +186,14 @@ impl PrefixLayout {
             .join(format!("{name}.gui-native"))
     }
 
+    pub fn binary_aliases_state_path(&self, name: &str) -> PathBuf {
+        self.installed_state_dir().join(format!("{name}.aliases"))
+    }
+
+    pub fn file_digests_state_path(&self, name: &str) -> PathBuf {
+        self.installed_state_dir().join(format!("{name}.files"))
+    }
+
     pub fn declared_services_state_path(&self, name: &str) -> PathBuf {
         self.installed_state_dir().join(format!("{name}.services"))
     }
@@ -141,7 +236,31 @@ impl PrefixLayout {
             .join(format!("artifact.{}", archive_type.cache_extension()))
     }
 
+    // Receipts may record `cache_path` relative to the prefix (`cache/...`) so a
+    // relocated prefix keeps its cache references; absolute paths pass through.
+    pub fn resolve_cache_path(&self, cache_path: impl AsRef<Path>) -> PathBuf {
+        let path = cache_path.as_ref();
+        if path.is_absolute() {
+            return path.to_path_buf();
+        }
+        match path.strip_prefix("cache") {
+            Ok(rest) => self.cache_dir().join(rest),
+            Err(_) => self.prefix.join(path),
+        }
+    }
+
+    pub fn portable_cache_path(&self, path: &Path) -> Option<String> {
+        let rel = path.strip_prefix(self.cache_dir()).ok()?;
+        let mut parts = vec!["cache".to_string()];
+        parts.extend(
+            rel.components()
+                .map(|component| component.as_os_str().to_string_lossy().to_string()),
+        );
+        Some(parts.join("/"))
+    }
+
     pub fn ensure_base_dirs(&self) -> Result<()> {
+        self.validate()?;
         for dir in [
             self.pkgs_dir(),
             self.bin_dir(),
@@ -1,6 +1,9 @@
+mod archive_limits;
 mod artifact;
+mod cache;
 mod exposure;
 mod fs_utils;
+mod integrity;
 mod layout;
 mod native;
 mod pins;
@@ -9,42 +12,65 @@ mod transactions;
 mod types;
 mod uninstall;
 
-pub use artifact::{install_from_artifact, install_from_source_archive};
+pub use artifact::{
+    install_from_artifact, install_from_artifact_with_result, install_from_source_archive,
+    install_from_source_archive_with_env_policy, is_dev_sha256_sentinel,
+};
+pub use cache::enforce_cache_quota;
 pub use exposure::{
-    bin_path, clear_gui_exposure_state, expose_binary, expose_completion, expose_gui_app,
-    exposed_completion_path, gui_asset_path, projected_exposed_completion_path,
-    projected_gui_assets, read_all_gui_exposure_states, read_gui_exposure_state,
-    remove_exposed_binary, remove_exposed_completion, remove_exposed_gui_asset,
-    write_gui_exposure_state,
+    apply_binary_aliases, bin_path, clear_binary_aliases, clear_gui_exposure_state,
+    expose_artifact, expose_binary, expose_binary_with_env_policy, expose_binary_with_interpreter,
+    expose_completion, expose_gui_app, exposed_completion_path, gui_asset_path,
+    managed_gui_icon_path, projected_exposed_completion_path, projected_gui_assets,
+    prune_all_empty_dirs, read_all_gui_exposure_states, read_binary_aliases,
+    read_gui_exposure_state, reexpose_all, remove_binary_alias, remove_exposed_binary,
+    remove_exposed_completion, remove_exposed_completion_with_prune, remove_exposed_gui_asset,
+    remove_exposed_gui_asset_with_prune, set_binary_alias, write_gui_exposure_state,
 };
 pub use fs_utils::remove_file_if_exists;
+pub use integrity::{
+    clear_installed_file_digests, measure_install_tree, owner_of_file, package_files,
+    record_installed_file_digests, repair_prefix_integrity, verify_installed,
+    verify_installed_file_manifest, verify_installed_with_artifact_options,
+    verify_prefix_integrity,
+};
 pub use layout::{default_user_prefix, PrefixLayout};
 pub use native::{
     clear_gui_native_state, clear_native_sidecar_state, read_all_gui_native_states,
     read_all_native_sidecar_states, read_gui_native_state, read_native_sidecar_state,
-    register_native_gui_app_best_effort, remove_native_gui_registration_best_effort,
+    register_native_gui_app_best_effort, register_native_gui_app_best_effort_with_icon,
+    remove_native_gui_registration_best_effort,
     remove_package_native_gui_registrations_best_effort, run_native_service_action,
     run_package_native_uninstall_actions, write_gui_native_state, write_native_sidecar_state,
 };
 pub use pins::{read_all_pins, read_pin, remove_pin, write_pin};
 pub use receipts::{
-    clear_declared_services_state, read_all_declared_services_states, read_declared_services_state,
-    read_install_receipts, write_declared_services_state, write_install_receipt,
+    clear_declared_services_state, enable_receipt_signing, list_installed_since,
+    read_all_declared_services_states, read_declared_services_state, read_install_receipts,
+    read_install_receipts_with_signature_policy, remove_install_receipt_signature,
+    write_declared_services_state, write_install_receipt,
 };
 pub use transactions::{
     append_transaction_journal_entry, clear_active_transaction, current_unix_timestamp,
-    read_active_transaction, read_transaction_metadata, set_active_transaction,
+    current_unix_timestamp_with_clock, process_is_alive, process_start_ticks,
+    read_active_transaction, read_active_transaction_marker, read_transaction_metadata,
+    reclaim_stale_transaction, set_active_transaction, set_active_transaction_with_clock,
     update_transaction_status, write_transaction_metadata,
 };
 pub use types::{
-    ArtifactInstallOptions, GuiExposureAsset, GuiNativeRegistrationRecord,
-    InstallInteractionPolicy, InstallMode, InstallReason, InstallReceipt, NativeServiceAction,
-    NativeServiceOutcome, NativeSidecarState, NativeUninstallAction, TransactionJournalEntry,
-    TransactionMetadata, UninstallResult, UninstallStatus,
+    ActiveTransactionMarker, ArchiveLimitExceeded, ArchiveLimitKind, ArchiveLimits,
+    ArtifactInstallOptions, AutoremovePolicy, ChecksumPolicy, EnvPolicy, ExposureResult,
+    GuiExposureAsset, GuiExposureOutcome, GuiNativeRegistrationRecord, InstallArtifactResult,
+    InstallInteractionPolicy, InstallMode, InstallPermissionsPolicy, InstallReason, InstallReceipt,
+    IntegrityIssue, NativeServiceAction, NativeServiceOutcome, NativeSidecarState,
+    NativeUninstallAction, ReceiptSignaturePolicy, ReexposeReport, ReexposeResult, RepairAction,
+    RepairOutcome, ResolvedDependency, TransactionJournalEntry, TransactionMetadata,
+    UninstallResult, UninstallStatus, VerifyResult, VerifyStatus,
 };
 pub use uninstall::{
     uninstall_blocked_by_roots_with_dependency_overrides,
     uninstall_blocked_by_roots_with_dependency_overrides_and_ignored_roots, uninstall_package,
+    uninstall_package_version, uninstall_package_with_autoremove_policy,
     uninstall_package_with_dependency_overrides,
     uninstall_package_with_dependency_overrides_and_ignored_roots,
 };
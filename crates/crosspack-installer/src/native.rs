@@ -519,11 +519,31 @@ pub fn register_native_gui_app_best_effort(
     app: &ArtifactGuiApp,
     install_root: &Path,
     previous_records: &[GuiNativeRegistrationRecord],
+) -> Result<(Vec<GuiNativeRegistrationRecord>, Vec<String>)> {
+    register_native_gui_app_best_effort_with_icon(
+        package_name,
+        app,
+        install_root,
+        None,
+        previous_records,
+    )
+}
+
+// `icon_path` is the managed icon copy from `managed_gui_icon_path`; native
+// entries point at it because desktop environments cannot resolve the
+// package-relative `icon` declared in the manifest.
+pub fn register_native_gui_app_best_effort_with_icon(
+    package_name: &str,
+    app: &ArtifactGuiApp,
+    install_root: &Path,
+    icon_path: Option<&Path>,
+    previous_records: &[GuiNativeRegistrationRecord],
 ) -> Result<(Vec<GuiNativeRegistrationRecord>, Vec<String>)> {
     register_native_gui_app_best_effort_with_executor(
         package_name,
         app,
         install_root,
+        icon_path,
         previous_records,
         run_command,
     )
@@ -539,6 +559,7 @@ pub(crate) fn register_native_gui_app_best_effort_with_executor<RunCommand>(
     package_name: &str,
     app: &ArtifactGuiApp,
     install_root: &Path,
+    icon_path: Option<&Path>,
     previous_records: &[GuiNativeRegistrationRecord],
     mut run_command_executor: RunCommand,
 ) -> Result<(Vec<GuiNativeRegistrationRecord>, Vec<String>)>
@@ -580,7 +601,7 @@ where
         }
 
         let desktop_path = applications_dir.join(native_gui_launcher_filename(package_name, app));
-        let desktop_entry = render_linux_native_desktop_entry(app, &source_path);
+        let desktop_entry = render_linux_native_desktop_entry(app, &source_path, icon_path);
         if let Err(err) = fs::write(&desktop_path, desktop_entry.as_bytes()) {
             warnings.push(format!(
                 "native GUI registration warning: failed to write Linux desktop entry {}: {}",
@@ -633,7 +654,7 @@ where
             "{}.cmd",
             normalize_gui_token(&format!("{}-{}", package_name, app.app_id))
         ));
-        let launcher = render_gui_launcher(app, &source_path);
+        let launcher = render_gui_launcher(app, &source_path, icon_path);
         if let Err(err) = fs::write(&launcher_path, launcher.as_bytes()) {
             warnings.push(format!(
                 "native GUI registration warning: failed to write Windows Start Menu launcher {}: {}",
@@ -1278,7 +1299,11 @@ fn native_gui_launcher_filename(package_name: &str, app: &ArtifactGuiApp) -> Str
     )
 }
 
-fn render_linux_native_desktop_entry(app: &ArtifactGuiApp, source_path: &Path) -> String {
+pub(crate) fn render_linux_native_desktop_entry(
+    app: &ArtifactGuiApp,
+    source_path: &Path,
+    icon_path: Option<&Path>,
+) -> String {
     let mut mime_entries = app
         .file_associations
         .iter()
@@ -1300,7 +1325,12 @@ fn render_linux_native_desktop_entry(app: &ArtifactGuiApp, source_path: &Path) -
         sanitize_gui_metadata_value(&app.display_name)
     ));
     desktop.push_str(&format!("Exec=\"{}\" %U\n", source_path.display()));
-    if let Some(icon) = &app.icon {
+    if let Some(icon_path) = icon_path {
+        desktop.push_str(&format!(
+            "Icon={}\n",
+            sanitize_gui_metadata_value(&icon_path.display().to_string())
+        ));
+    } else if let Some(icon) = &app.icon {
         desktop.push_str(&format!("Icon={}\n", sanitize_gui_metadata_value(icon)));
     }
     if !app.categories.is_empty() {
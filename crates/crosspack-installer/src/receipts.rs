@@ -1,17 +1,25 @@
 use anyhow::{anyhow, Context, Result};
 use crosspack_core::ServiceDeclaration;
+use crosspack_security::{hmac_sha256_hex, verify_hmac_sha256_hex};
 use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
-use crate::{InstallMode, InstallReason, InstallReceipt, PrefixLayout};
+use crate::fs_utils::{remove_file_if_exists, write_file_atomic};
+use crate::{
+    InstallMode, InstallReason, InstallReceipt, PrefixLayout, ReceiptSignaturePolicy,
+    ResolvedDependency,
+};
 
 pub fn write_install_receipt(layout: &PrefixLayout, receipt: &InstallReceipt) -> Result<PathBuf> {
     let mut payload = String::new();
     payload.push_str(&format!("name={}\n", receipt.name));
     payload.push_str(&format!("version={}\n", receipt.version));
     for dependency in &receipt.dependencies {
-        payload.push_str(&format!("dependency={}\n", dependency));
+        payload.push_str(&format!(
+            "resolved_dependency={}\n",
+            dependency.receipt_token()
+        ));
     }
     if let Some(target) = &receipt.target {
         payload.push_str(&format!("target={}\n", target));
@@ -25,10 +33,10 @@ pub fn write_install_receipt(layout: &PrefixLayout, receipt: &InstallReceipt) ->
     if let Some(cache_path) = &receipt.cache_path {
         payload.push_str(&format!("cache_path={}\n", cache_path));
     }
-    for exposed_bin in &receipt.exposed_bins {
+    for exposed_bin in sorted_entries(&receipt.exposed_bins) {
         payload.push_str(&format!("exposed_bin={}\n", exposed_bin));
     }
-    for exposed_completion in &receipt.exposed_completions {
+    for exposed_completion in sorted_entries(&receipt.exposed_completions) {
         payload.push_str(&format!("exposed_completion={}\n", exposed_completion));
     }
     if let Some(snapshot_id) = &receipt.snapshot_id {
@@ -44,20 +52,142 @@ pub fn write_install_receipt(layout: &PrefixLayout, receipt: &InstallReceipt) ->
         "installed_at_unix={}\n",
         receipt.installed_at_unix
     ));
+    if let Some(file_count) = receipt.file_count {
+        payload.push_str(&format!("file_count={}\n", file_count));
+    }
+    if let Some(installed_bytes) = receipt.installed_bytes {
+        payload.push_str(&format!("installed_bytes={}\n", installed_bytes));
+    }
+    if let Some(deprecated) = &receipt.deprecated {
+        let deprecated = deprecated.replace(['\r', '\n'], " ");
+        payload.push_str(&format!("deprecated={}\n", deprecated));
+    }
 
+    // The signature is replaced before the receipt so a concurrent reader sees
+    // at worst a fresh signature beside the previous receipt, which it re-reads.
+    if let Some(key) = read_receipt_signing_key(layout)? {
+        write_receipt_signature(layout, &receipt.name, &key, payload.as_bytes())?;
+    }
     let path = layout.receipt_path(&receipt.name);
-    fs::write(&path, payload.as_bytes())
+    write_file_atomic(&path, payload.as_bytes())
         .with_context(|| format!("failed to write install receipt: {}", path.display()))?;
     Ok(path)
 }
 
-pub fn read_install_receipts(layout: &PrefixLayout) -> Result<Vec<InstallReceipt>> {
+pub fn enable_receipt_signing(layout: &PrefixLayout) -> Result<()> {
+    let key_path = layout.receipt_signing_key_path();
+    if key_path.exists() {
+        return Ok(());
+    }
+
+    let mut key = [0u8; 32];
+    getrandom::getrandom(&mut key)
+        .map_err(|err| anyhow!("failed to generate receipt signing key: {err}"))?;
+    if let Some(parent) = key_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    fs::write(&key_path, key).with_context(|| {
+        format!(
+            "failed to write receipt signing key: {}",
+            key_path.display()
+        )
+    })?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&key_path, fs::Permissions::from_mode(0o600)).with_context(|| {
+            format!(
+                "failed to restrict receipt signing key permissions: {}",
+                key_path.display()
+            )
+        })?;
+    }
+
+    for (name, path) in receipt_files(layout)? {
+        let raw = fs::read(&path)
+            .with_context(|| format!("failed to read install receipt: {}", path.display()))?;
+        write_receipt_signature(layout, &name, &key, &raw)?;
+    }
+    Ok(())
+}
+
+fn read_receipt_signing_key(layout: &PrefixLayout) -> Result<Option<Vec<u8>>> {
+    let key_path = layout.receipt_signing_key_path();
+    match fs::read(&key_path) {
+        Ok(key) => Ok(Some(key)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err)
+            .with_context(|| format!("failed to read receipt signing key: {}", key_path.display())),
+    }
+}
+
+fn write_receipt_signature(
+    layout: &PrefixLayout,
+    name: &str,
+    key: &[u8],
+    payload: &[u8],
+) -> Result<()> {
+    let sig_path = layout.receipt_signature_path(name);
+    let signature = format!("{}\n", hmac_sha256_hex(key, payload));
+    write_file_atomic(&sig_path, signature.as_bytes()).with_context(|| {
+        format!(
+            "failed to write install receipt signature: {}",
+            sig_path.display()
+        )
+    })
+}
+
+pub fn remove_install_receipt_signature(layout: &PrefixLayout, name: &str) -> Result<()> {
+    let sig_path = layout.receipt_signature_path(name);
+    remove_file_if_exists(&sig_path).with_context(|| {
+        format!(
+            "failed to remove install receipt signature: {}",
+            sig_path.display()
+        )
+    })
+}
+
+fn receipt_signature_issue(
+    layout: &PrefixLayout,
+    name: &str,
+    key: &[u8],
+    raw: &[u8],
+) -> Result<Option<String>> {
+    let sig_path = layout.receipt_signature_path(name);
+    let signature = match fs::read_to_string(&sig_path) {
+        Ok(signature) => signature,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(Some(format!(
+                "install receipt for '{name}' is missing its signature: {}",
+                sig_path.display()
+            )));
+        }
+        Err(err) => {
+            return Err(err).with_context(|| {
+                format!(
+                    "failed to read install receipt signature: {}",
+                    sig_path.display()
+                )
+            });
+        }
+    };
+    if verify_hmac_sha256_hex(key, raw, &signature) {
+        return Ok(None);
+    }
+    Ok(Some(format!(
+        "install receipt for '{name}' does not match its signature: {}",
+        layout.receipt_path(name).display()
+    )))
+}
+
+fn receipt_files(layout: &PrefixLayout) -> Result<Vec<(String, PathBuf)>> {
     let dir = layout.installed_state_dir();
     if !dir.exists() {
         return Ok(Vec::new());
     }
 
-    let mut receipts = Vec::new();
+    let mut files = Vec::new();
     for entry in fs::read_dir(&dir)
         .with_context(|| format!("failed to read install state directory: {}", dir.display()))?
     {
@@ -70,15 +200,83 @@ pub fn read_install_receipts(layout: &PrefixLayout) -> Result<Vec<InstallReceipt
         if path.extension().and_then(|v| v.to_str()) != Some("receipt") {
             continue;
         }
+        let Some(name) = path.file_stem().and_then(|v| v.to_str()) else {
+            continue;
+        };
+        files.push((name.to_string(), path));
+    }
+    Ok(files)
+}
+
+fn sorted_entries(entries: &[String]) -> Vec<&String> {
+    let mut sorted = entries.iter().collect::<Vec<_>>();
+    sorted.sort();
+    sorted
+}
+
+pub fn read_install_receipts(layout: &PrefixLayout) -> Result<Vec<InstallReceipt>> {
+    read_install_receipts_with_signature_policy(layout, ReceiptSignaturePolicy::Ignore)
+        .map(|(receipts, _)| receipts)
+}
+
+pub fn read_install_receipts_with_signature_policy(
+    layout: &PrefixLayout,
+    policy: ReceiptSignaturePolicy,
+) -> Result<(Vec<InstallReceipt>, Vec<String>)> {
+    let key = match policy {
+        ReceiptSignaturePolicy::Ignore => None,
+        ReceiptSignaturePolicy::Warn => read_receipt_signing_key(layout)?,
+        // Enforce fails closed: without a key nothing could be verified.
+        ReceiptSignaturePolicy::Enforce => Some(read_receipt_signing_key(layout)?.ok_or_else(
+            || {
+                anyhow!(
+                    "receipt signature policy 'enforce' requires a receipt signing key: {} is missing",
+                    layout.receipt_signing_key_path().display()
+                )
+            },
+        )?),
+    };
 
-        let raw = fs::read_to_string(&path)
+    let mut receipts = Vec::new();
+    let mut warnings = Vec::new();
+    for (name, path) in receipt_files(layout)? {
+        let mut raw = fs::read_to_string(&path)
             .with_context(|| format!("failed to read install receipt: {}", path.display()))?;
+        if let Some(key) = &key {
+            let mut issue = receipt_signature_issue(layout, &name, key, raw.as_bytes())?;
+            if issue.is_some() {
+                // A writer may have replaced the signature but not yet the receipt.
+                raw = fs::read_to_string(&path).with_context(|| {
+                    format!("failed to read install receipt: {}", path.display())
+                })?;
+                issue = receipt_signature_issue(layout, &name, key, raw.as_bytes())?;
+            }
+            if let Some(issue) = issue {
+                if policy == ReceiptSignaturePolicy::Enforce {
+                    return Err(anyhow!(issue));
+                }
+                warnings.push(issue);
+            }
+        }
         let receipt = parse_receipt(&raw)
             .with_context(|| format!("failed to parse install receipt: {}", path.display()))?;
         receipts.push(receipt);
     }
 
     receipts.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok((receipts, warnings))
+}
+
+pub fn list_installed_since(layout: &PrefixLayout, since_unix: u64) -> Result<Vec<InstallReceipt>> {
+    let mut receipts = read_install_receipts(layout)?
+        .into_iter()
+        .filter(|receipt| receipt.installed_at_unix >= since_unix)
+        .collect::<Vec<_>>();
+    receipts.sort_by(|a, b| {
+        b.installed_at_unix
+            .cmp(&a.installed_at_unix)
+            .then_with(|| a.name.cmp(&b.name))
+    });
     Ok(receipts)
 }
 
@@ -97,6 +295,9 @@ pub(crate) fn parse_receipt(raw: &str) -> Result<InstallReceipt> {
     let mut install_reason = None;
     let mut install_status = None;
     let mut installed_at_unix = None;
+    let mut file_count = None;
+    let mut installed_bytes = None;
+    let mut deprecated = None;
 
     for line in raw.lines().filter(|line| !line.trim().is_empty()) {
         let Some((k, v)) = line.split_once('=') else {
@@ -105,7 +306,8 @@ pub(crate) fn parse_receipt(raw: &str) -> Result<InstallReceipt> {
         match k {
             "name" => name = Some(v.to_string()),
             "version" => version = Some(v.to_string()),
-            "dependency" => dependencies.push(v.to_string()),
+            "resolved_dependency" => dependencies.push(ResolvedDependency::parse_receipt_token(v)?),
+            "dependency" => dependencies.extend(ResolvedDependency::parse_legacy_receipt_token(v)),
             "target" => target = Some(v.to_string()),
             "artifact_url" => artifact_url = Some(v.to_string()),
             "artifact_sha256" => artifact_sha256 = Some(v.to_string()),
@@ -119,6 +321,11 @@ pub(crate) fn parse_receipt(raw: &str) -> Result<InstallReceipt> {
             "installed_at_unix" => {
                 installed_at_unix = Some(v.parse().context("installed_at_unix must be u64")?)
             }
+            "file_count" => file_count = Some(v.parse().context("file_count must be u64")?),
+            "installed_bytes" => {
+                installed_bytes = Some(v.parse().context("installed_bytes must be u64")?)
+            }
+            "deprecated" => deprecated = Some(v.to_string()),
             _ => {}
         }
     }
@@ -138,6 +345,9 @@ pub(crate) fn parse_receipt(raw: &str) -> Result<InstallReceipt> {
         install_reason: install_reason.unwrap_or(InstallReason::Root),
         install_status: install_status.unwrap_or_else(|| "installed".to_string()),
         installed_at_unix: installed_at_unix.context("missing installed_at_unix")?,
+        file_count,
+        installed_bytes,
+        deprecated,
     })
 }
 
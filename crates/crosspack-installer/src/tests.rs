@@ -2,8 +2,11 @@ use super::*;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use anyhow::anyhow;
-use crosspack_core::{ArchiveType, ArtifactCompletionShell, ArtifactGuiApp, ServiceDeclaration};
-use std::collections::HashMap;
+use crosspack_core::{
+    ArchiveType, ArtifactCompletionShell, ArtifactFileMapping, ArtifactGuiApp, FakeClock,
+    ServiceDeclaration,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
@@ -17,11 +20,13 @@ use crate::artifact::stage_appimage_payload;
 use crate::artifact::{
     build_appx_unpack_command, build_dmg_attach_command, build_dmg_detach_command,
     build_exe_extract_command, build_msi_admin_extract_command, build_msix_unpack_command,
-    build_pkg_copy_command, build_pkg_expand_command, discover_pkg_payload_roots,
-    stage_appx_payload_with_runner, stage_bin_payload, stage_dmg_payload_with_hooks,
-    stage_exe_payload_with_runner, stage_msix_payload_with_runner, stage_pkg_payload_with_hooks,
-    strip_rel_components,
+    build_pkg_copy_command, build_pkg_expand_command, copy_with_strip, discover_pkg_payload_roots,
+    extract_tar_archive, move_dir_or_copy_using, stage_appx_payload_with_runner, stage_bin_payload,
+    stage_dmg_payload_with_hooks, stage_exe_payload_with_runner, stage_msix_payload_with_runner,
+    stage_pkg_payload_with_hooks, strip_rel_components,
 };
+#[cfg(target_os = "linux")]
+use crate::native::render_linux_native_desktop_entry;
 use crate::native::{
     macos_registration_destination_candidates, macos_registration_source_path,
     parse_native_sidecar_state, project_linux_user_applications_dir,
@@ -32,6 +37,75 @@ use crate::native::{
     select_macos_registration_destination, MACOS_LSREGISTER_PATH,
 };
 use crate::receipts::parse_receipt;
+use crate::transactions::parse_proc_stat_start_ticks;
+
+#[cfg(unix)]
+#[test]
+fn xdg_layout_uses_default_base_dirs_when_vars_unset() {
+    let layout = PrefixLayout::xdg_with_env(|key| match key {
+        "HOME" => Some("/home/demo".to_string()),
+        _ => None,
+    })
+    .expect("must resolve xdg layout");
+
+    assert_eq!(
+        layout.prefix(),
+        Path::new("/home/demo/.local/share/crosspack")
+    );
+    assert_eq!(
+        layout.pkgs_dir(),
+        PathBuf::from("/home/demo/.local/share/crosspack/pkgs")
+    );
+    assert_eq!(
+        layout.share_dir(),
+        PathBuf::from("/home/demo/.local/share/crosspack/share")
+    );
+    assert_eq!(
+        layout.cache_dir(),
+        PathBuf::from("/home/demo/.cache/crosspack")
+    );
+    assert_eq!(
+        layout.state_dir(),
+        PathBuf::from("/home/demo/.local/state/crosspack")
+    );
+    assert_eq!(layout.bin_dir(), PathBuf::from("/home/demo/.local/bin"));
+    assert_eq!(
+        PrefixLayout::new("/home/demo/.crosspack").bin_dir(),
+        PathBuf::from("/home/demo/.crosspack/bin")
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn xdg_layout_honors_base_dir_vars() {
+    let layout = PrefixLayout::xdg_with_env(|key| match key {
+        "HOME" => Some("/home/demo".to_string()),
+        "XDG_DATA_HOME" => Some("/data".to_string()),
+        "XDG_CACHE_HOME" => Some("/var/cache/demo".to_string()),
+        "XDG_STATE_HOME" => Some("relative/state".to_string()),
+        _ => None,
+    })
+    .expect("must resolve xdg layout");
+
+    assert_eq!(layout.pkgs_dir(), PathBuf::from("/data/crosspack/pkgs"));
+    assert_eq!(
+        layout.completions_dir(),
+        PathBuf::from("/data/crosspack/share/completions")
+    );
+    assert_eq!(
+        layout.artifacts_cache_dir(),
+        PathBuf::from("/var/cache/demo/crosspack/artifacts")
+    );
+    assert_eq!(
+        layout.installed_state_dir(),
+        PathBuf::from("/home/demo/.local/state/crosspack/installed"),
+        "relative XDG paths must fall back to the default"
+    );
+    assert_eq!(layout.bin_dir(), PathBuf::from("/home/demo/.local/bin"));
+
+    let err = PrefixLayout::xdg_with_env(|_| None).expect_err("HOME is required");
+    assert!(err.to_string().contains("HOME is not set"));
+}
 
 #[test]
 fn parse_old_receipt_shape() {
@@ -45,13 +119,22 @@ fn parse_old_receipt_shape() {
     assert!(receipt.snapshot_id.is_none());
     assert!(receipt.exposed_completions.is_empty());
     assert_eq!(receipt.install_reason, InstallReason::Root);
+    assert_eq!(receipt.file_count, None);
+    assert_eq!(receipt.installed_bytes, None);
+    assert_eq!(receipt.deprecated, None);
 }
 
 #[test]
 fn parse_new_receipt_shape() {
     let raw = "name=fd\nversion=10.2.0\ndependency=zlib@2.1.0\ndependency=pcre2@10.44.0\ntarget=x86_64-unknown-linux-gnu\nartifact_url=https://example.test/fd.tgz\nartifact_sha256=abc\ncache_path=/tmp/fd.tgz\nexposed_bin=fd\nexposed_bin=fdfind\nexposed_completion=packages/bash/fd--completions--fd.bash\nsnapshot_id=git:5f1b3d8a1f2a4d0e\ninstall_reason=dependency\ninstall_status=installed\ninstalled_at_unix=123\n";
     let receipt = parse_receipt(raw).expect("must parse");
-    assert_eq!(receipt.dependencies, vec!["zlib@2.1.0", "pcre2@10.44.0"]);
+    assert_eq!(
+        receipt.dependencies,
+        vec![
+            ResolvedDependency::new("zlib", "2.1.0"),
+            ResolvedDependency::new("pcre2", "10.44.0"),
+        ]
+    );
     assert_eq!(receipt.target.as_deref(), Some("x86_64-unknown-linux-gnu"));
     assert_eq!(receipt.artifact_sha256.as_deref(), Some("abc"));
     assert_eq!(receipt.exposed_bins, vec!["fd", "fdfind"]);
@@ -73,7 +156,7 @@ fn receipt_round_trip_with_install_mode_native() {
         &InstallReceipt {
             name: "zed".to_string(),
             version: "0.150.0".to_string(),
-            dependencies: vec!["ripgrep@14.0.0".to_string()],
+            dependencies: vec![ResolvedDependency::new("ripgrep", "14.0.0")],
             target: Some("x86_64-unknown-linux-gnu".to_string()),
             artifact_url: Some("https://example.test/zed.tar.zst".to_string()),
             artifact_sha256: Some("abc123".to_string()),
@@ -85,6 +168,7 @@ fn receipt_round_trip_with_install_mode_native() {
             install_reason: InstallReason::Root,
             install_status: "installed".to_string(),
             installed_at_unix: 123,
+            ..InstallReceipt::default()
         },
     )
     .expect("must write receipt");
@@ -92,6 +176,372 @@ fn receipt_round_trip_with_install_mode_native() {
     let receipts = read_install_receipts(&layout).expect("must read receipts");
     assert_eq!(receipts.len(), 1);
     assert_eq!(receipts[0].install_mode, InstallMode::Native);
+    assert_eq!(
+        receipts[0].dependencies,
+        vec![ResolvedDependency::new("ripgrep", "14.0.0")]
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn receipt_persists_resolved_dependencies_distinctly_from_legacy_entries() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let path = write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "fd".to_string(),
+            version: "10.2.0".to_string(),
+            dependencies: vec![
+                ResolvedDependency::new("pcre2", "10.44.0"),
+                ResolvedDependency::new("zlib", "2.1.0"),
+            ],
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: Vec::new(),
+            exposed_completions: Vec::new(),
+            snapshot_id: None,
+            install_mode: InstallMode::Managed,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 1,
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
+
+    let raw = fs::read_to_string(&path).expect("must read receipt");
+    assert!(raw.contains("resolved_dependency=pcre2@10.44.0\n"));
+    assert!(raw.contains("resolved_dependency=zlib@2.1.0\n"));
+    assert!(!raw.contains("\ndependency="));
+    let parsed = parse_receipt(&raw).expect("must parse written receipt");
+    assert_eq!(
+        parsed.dependencies,
+        vec![
+            ResolvedDependency::new("pcre2", "10.44.0"),
+            ResolvedDependency::new("zlib", "2.1.0"),
+        ]
+    );
+
+    let legacy = "name=fd\nversion=10.2.0\ndependency=zlib@2.1.0\ndependency=malformed\ninstalled_at_unix=1\n";
+    let parsed = parse_receipt(legacy).expect("must parse legacy receipt");
+    assert_eq!(
+        parsed.dependencies,
+        vec![ResolvedDependency::new("zlib", "2.1.0")]
+    );
+
+    let invalid = "name=fd\nversion=10.2.0\nresolved_dependency=zlib\ninstalled_at_unix=1\n";
+    let err = parse_receipt(invalid).expect_err("malformed resolved dependency must fail");
+    assert!(err
+        .to_string()
+        .contains("invalid resolved_dependency: zlib"));
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn signed_receipt_verifies_and_detects_manual_edits() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    enable_receipt_signing(&layout).expect("must enable receipt signing");
+
+    let path = write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "tools".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: Vec::new(),
+            exposed_completions: Vec::new(),
+            snapshot_id: None,
+            install_mode: InstallMode::Managed,
+            install_reason: InstallReason::Dependency,
+            install_status: "installed".to_string(),
+            installed_at_unix: 123,
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
+    assert!(layout.receipt_signature_path("tools").exists());
+
+    let (receipts, warnings) =
+        read_install_receipts_with_signature_policy(&layout, ReceiptSignaturePolicy::Enforce)
+            .expect("valid signature must verify");
+    assert_eq!(receipts.len(), 1);
+    assert!(warnings.is_empty());
+
+    let raw = fs::read_to_string(&path).expect("must read receipt");
+    fs::write(
+        &path,
+        raw.replace("install_reason=dependency", "install_reason=root"),
+    )
+    .expect("must edit receipt");
+
+    let (receipts, warnings) =
+        read_install_receipts_with_signature_policy(&layout, ReceiptSignaturePolicy::Warn)
+            .expect("warn policy must still read receipts");
+    assert_eq!(receipts[0].install_reason, InstallReason::Root);
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("does not match its signature"));
+
+    let err = read_install_receipts_with_signature_policy(&layout, ReceiptSignaturePolicy::Enforce)
+        .expect_err("edited receipt must fail verification");
+    assert!(err.to_string().contains("install receipt for 'tools'"));
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn signed_receipt_rewrite_replaces_files_without_leaving_temp_files() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    enable_receipt_signing(&layout).expect("must enable receipt signing");
+
+    for version in ["1.0.0", "1.1.0"] {
+        write_install_receipt(
+            &layout,
+            &InstallReceipt {
+                name: "tools".to_string(),
+                version: version.to_string(),
+                install_status: "installed".to_string(),
+                ..InstallReceipt::default()
+            },
+        )
+        .expect("must write receipt");
+    }
+
+    let mut entries = fs::read_dir(layout.installed_state_dir())
+        .expect("must read installed state dir")
+        .map(|entry| {
+            entry
+                .expect("must read entry")
+                .file_name()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect::<Vec<_>>();
+    entries.sort();
+    assert_eq!(entries, vec!["tools.receipt", "tools.receipt.sig"]);
+
+    let (receipts, _) =
+        read_install_receipts_with_signature_policy(&layout, ReceiptSignaturePolicy::Enforce)
+            .expect("rewritten receipt must verify");
+    assert_eq!(receipts[0].version, "1.1.0");
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn enforce_receipt_signature_policy_fails_without_signing_key() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let err = read_install_receipts_with_signature_policy(&layout, ReceiptSignaturePolicy::Enforce)
+        .expect_err("enforce must fail closed without a signing key");
+    assert!(err.to_string().contains("requires a receipt signing key"));
+
+    let (receipts, warnings) =
+        read_install_receipts_with_signature_policy(&layout, ReceiptSignaturePolicy::Warn)
+            .expect("warn policy must tolerate a missing key");
+    assert!(receipts.is_empty());
+    assert!(warnings.is_empty());
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn verify_prefix_integrity_reports_size_drift_from_recorded_receipt() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let install_root = layout.package_dir("tools", "1.0.0");
+    fs::create_dir_all(install_root.join("bin")).expect("must create bin dir");
+    fs::create_dir_all(install_root.join("share/empty")).expect("must create empty dir");
+    fs::write(install_root.join("bin/tools"), b"#!/bin/sh\n").expect("must write bin");
+    fs::write(install_root.join("README"), vec![b'x'; 1000]).expect("must write readme");
+
+    let (file_count, installed_bytes) =
+        measure_install_tree(&install_root).expect("must measure install tree");
+    assert_eq!((file_count, installed_bytes), (2, 1010));
+
+    write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "tools".to_string(),
+            version: "1.0.0".to_string(),
+            install_status: "installed".to_string(),
+            file_count: Some(file_count),
+            installed_bytes: Some(installed_bytes),
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
+    assert!(verify_prefix_integrity(&layout)
+        .expect("must verify prefix")
+        .is_empty());
+
+    fs::write(install_root.join("share/extra"), b"extra").expect("must write extra file");
+    let issues = verify_prefix_integrity(&layout).expect("must verify prefix");
+    assert_eq!(
+        issues,
+        vec![IntegrityIssue::SizeDrift {
+            name: "tools".to_string(),
+            version: "1.0.0".to_string(),
+            recorded_file_count: 2,
+            recorded_bytes: 1010,
+            actual_file_count: 3,
+            actual_bytes: 1015,
+        }]
+    );
+    let outcomes = repair_prefix_integrity(&layout, &issues).expect("must repair prefix");
+    assert_eq!(outcomes[0].action, RepairAction::NeedsRedownload);
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn install_receipt_round_trips_single_line_deprecation_notice() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "oldtool".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: Vec::new(),
+            exposed_completions: Vec::new(),
+            snapshot_id: None,
+            install_mode: InstallMode::Managed,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 123,
+            deprecated: Some("use newtool\ninstead".to_string()),
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
+
+    let receipts = read_install_receipts(&layout).expect("must read receipts");
+    assert_eq!(
+        receipts[0].deprecated.as_deref(),
+        Some("use newtool instead")
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn write_install_receipt_sorts_exposure_lists() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let path = write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "tools".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: vec!["zz".to_string(), "aa".to_string(), "mm".to_string()],
+            exposed_completions: vec![
+                "packages/zsh/tools--completions--_tools".to_string(),
+                "packages/bash/tools--completions--tools.bash".to_string(),
+            ],
+            snapshot_id: None,
+            install_mode: InstallMode::Managed,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 123,
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
+
+    let raw = fs::read_to_string(&path).expect("must read receipt");
+    let exposure_lines = raw
+        .lines()
+        .filter(|line| line.starts_with("exposed_"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        exposure_lines,
+        vec![
+            "exposed_bin=aa",
+            "exposed_bin=mm",
+            "exposed_bin=zz",
+            "exposed_completion=packages/bash/tools--completions--tools.bash",
+            "exposed_completion=packages/zsh/tools--completions--_tools",
+        ]
+    );
+
+    let receipt = parse_receipt(&raw).expect("must parse receipt");
+    assert_eq!(receipt.exposed_bins, vec!["aa", "mm", "zz"]);
+    assert_eq!(
+        receipt.exposed_completions,
+        vec![
+            "packages/bash/tools--completions--tools.bash",
+            "packages/zsh/tools--completions--_tools",
+        ]
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn list_installed_since_filters_and_orders_newest_first() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    for (name, installed_at_unix) in [("old", 100), ("boundary", 200), ("new", 300), ("tie", 300)] {
+        write_install_receipt(
+            &layout,
+            &InstallReceipt {
+                name: name.to_string(),
+                version: "1.0.0".to_string(),
+                dependencies: Vec::new(),
+                target: None,
+                artifact_url: None,
+                artifact_sha256: None,
+                cache_path: None,
+                exposed_bins: Vec::new(),
+                exposed_completions: Vec::new(),
+                snapshot_id: None,
+                install_mode: InstallMode::Managed,
+                install_reason: InstallReason::Root,
+                install_status: "installed".to_string(),
+                installed_at_unix,
+                ..InstallReceipt::default()
+            },
+        )
+        .expect("must write receipt");
+    }
+
+    let receipts = list_installed_since(&layout, 200).expect("must list receipts");
+    assert_eq!(
+        receipts
+            .iter()
+            .map(|receipt| (receipt.name.as_str(), receipt.installed_at_unix))
+            .collect::<Vec<_>>(),
+        vec![("new", 300), ("tie", 300), ("boundary", 200)]
+    );
+    assert!(list_installed_since(&layout, 301)
+        .expect("must list receipts")
+        .is_empty());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
@@ -227,7 +677,8 @@ fn write_transaction_metadata_and_active_file() {
 
     let active_raw =
         fs::read_to_string(layout.transaction_active_path()).expect("must read active file");
-    assert_eq!(active_raw.trim(), "tx-1771001234-000042");
+    assert_eq!(active_raw.lines().next(), Some("tx-1771001234-000042"));
+    assert!(active_raw.contains("started_at_unix="));
 
     clear_active_transaction(&layout).expect("must clear active transaction");
     assert!(!layout.transaction_active_path().exists());
@@ -281,42 +732,88 @@ fn read_transaction_metadata_rejects_truncated_quoted_value() {
 }
 
 #[test]
-fn update_transaction_status_rewrites_metadata_status() {
+fn read_transaction_metadata_skips_unknown_scalar_and_nested_fields() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let metadata = TransactionMetadata {
-        version: 1,
-        txid: "tx-status-1".to_string(),
-        operation: "install".to_string(),
-        status: "planning".to_string(),
-        started_at_unix: 1_771_001_250,
-        snapshot_id: None,
-    };
-
-    write_transaction_metadata(&layout, &metadata).expect("must write metadata");
-    update_transaction_status(&layout, "tx-status-1", "applying").expect("must update status");
+    let txid = "tx-unknown-fields";
+    let raw = "{\n  \"version\": 1,\n  \"txid\": \"tx-unknown-fields\",\n  \"operation\": \"install\",\n  \"retries\": 2,\n  \"host\": {\"name\": \"ci, {runner}\", \"tags\": [\"a\", [1, 2]]},\n  \"status\": \"planning\",\n  \"interactive\": false,\n  \"started_at_unix\": 1771001250\n}\n";
+    fs::write(layout.transaction_metadata_path(txid), raw).expect("must write metadata file");
 
-    let loaded = read_transaction_metadata(&layout, "tx-status-1")
-        .expect("must read metadata")
+    let loaded = read_transaction_metadata(&layout, txid)
+        .expect("unknown fields must be skipped")
         .expect("metadata should exist");
-    assert_eq!(loaded.status, "applying");
+    assert_eq!(
+        loaded,
+        TransactionMetadata {
+            version: 1,
+            txid: txid.to_string(),
+            operation: "install".to_string(),
+            status: "planning".to_string(),
+            started_at_unix: 1_771_001_250,
+            snapshot_id: None,
+        }
+    );
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn read_active_transaction_round_trip() {
+fn read_transaction_metadata_rejects_unsupported_future_version() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    assert!(read_active_transaction(&layout)
-        .expect("must read active transaction")
-        .is_none());
+    let txid = "tx-future-version";
+    let raw = "{\"version\": 2, \"txid\": \"tx-future-version\", \"operation\": \"install\", \"status\": \"planning\", \"started_at_unix\": 1771001250}";
+    fs::write(layout.transaction_metadata_path(txid), raw).expect("must write metadata file");
 
-    set_active_transaction(&layout, "tx-abc").expect("must write active transaction");
-    assert_eq!(
-        read_active_transaction(&layout)
+    let err = read_transaction_metadata(&layout, txid).expect_err("future version must fail");
+    let err_text = format!("{err:#}");
+    assert!(
+        err_text.contains("unsupported transaction metadata version 2 (supported: 1)"),
+        "unexpected error: {err_text}"
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn update_transaction_status_rewrites_metadata_status() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let metadata = TransactionMetadata {
+        version: 1,
+        txid: "tx-status-1".to_string(),
+        operation: "install".to_string(),
+        status: "planning".to_string(),
+        started_at_unix: 1_771_001_250,
+        snapshot_id: None,
+    };
+
+    write_transaction_metadata(&layout, &metadata).expect("must write metadata");
+    update_transaction_status(&layout, "tx-status-1", "applying").expect("must update status");
+
+    let loaded = read_transaction_metadata(&layout, "tx-status-1")
+        .expect("must read metadata")
+        .expect("metadata should exist");
+    assert_eq!(loaded.status, "applying");
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn read_active_transaction_round_trip() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    assert!(read_active_transaction(&layout)
+        .expect("must read active transaction")
+        .is_none());
+
+    set_active_transaction(&layout, "tx-abc").expect("must write active transaction");
+    assert_eq!(
+        read_active_transaction(&layout)
             .expect("must read active transaction")
             .as_deref(),
         Some("tx-abc")
@@ -356,6 +853,149 @@ fn set_active_transaction_rejects_when_marker_already_exists() {
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(unix)]
+#[test]
+fn reclaim_stale_transaction_clears_marker_owned_by_dead_process() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let mut child = Command::new("true").spawn().expect("must spawn child");
+    let dead_pid = child.id();
+    child.wait().expect("must reap child");
+
+    let metadata = TransactionMetadata {
+        version: 1,
+        txid: "tx-dead".to_string(),
+        operation: "install".to_string(),
+        status: "applying".to_string(),
+        started_at_unix: 1_771_001_234,
+        snapshot_id: None,
+    };
+    write_transaction_metadata(&layout, &metadata).expect("must write metadata");
+    fs::write(
+        layout.transaction_active_path(),
+        format!("tx-dead\npid={dead_pid}\nstarted_at_unix=1771001234\n"),
+    )
+    .expect("must write marker");
+
+    assert_eq!(
+        read_active_transaction_marker(&layout).expect("must read marker"),
+        Some(ActiveTransactionMarker {
+            txid: "tx-dead".to_string(),
+            pid: Some(dead_pid),
+            started_at_unix: Some(1_771_001_234),
+            process_start_ticks: None,
+        })
+    );
+    assert_eq!(
+        reclaim_stale_transaction(&layout)
+            .expect("must reclaim")
+            .as_deref(),
+        Some("tx-dead")
+    );
+    assert!(read_active_transaction(&layout)
+        .expect("must read active transaction")
+        .is_none());
+    assert_eq!(
+        read_transaction_metadata(&layout, "tx-dead")
+            .expect("must read metadata")
+            .expect("metadata must exist")
+            .status,
+        "failed"
+    );
+    set_active_transaction(&layout, "tx-next").expect("must claim reclaimed marker");
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn reclaim_stale_transaction_keeps_marker_owned_by_live_process() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    set_active_transaction(&layout, "tx-live").expect("must write active transaction");
+    let marker = read_active_transaction_marker(&layout)
+        .expect("must read marker")
+        .expect("marker must exist");
+    assert_eq!(marker.pid, Some(std::process::id()));
+    assert!(marker.started_at_unix.is_some());
+    assert_eq!(
+        marker.process_start_ticks,
+        process_start_ticks(std::process::id())
+    );
+
+    assert!(reclaim_stale_transaction(&layout)
+        .expect("must check marker")
+        .is_none());
+    assert_eq!(
+        read_active_transaction(&layout)
+            .expect("must read active transaction")
+            .as_deref(),
+        Some("tx-live")
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn reclaim_stale_transaction_clears_marker_whose_pid_was_reused() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let pid = std::process::id();
+    let start_ticks = process_start_ticks(pid).expect("must read own start time");
+    fs::write(
+        layout.transaction_active_path(),
+        format!(
+            "tx-reused\npid={pid}\nstarted_at_unix=1771001234\nprocess_start_ticks={}\n",
+            start_ticks + 1
+        ),
+    )
+    .expect("must write marker");
+
+    assert_eq!(
+        reclaim_stale_transaction(&layout)
+            .expect("must reclaim")
+            .as_deref(),
+        Some("tx-reused")
+    );
+    assert!(read_active_transaction(&layout)
+        .expect("must read active transaction")
+        .is_none());
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn parse_proc_stat_start_ticks_counts_fields_after_command_name() {
+    let stat = "4242 (tool (with) spaces) S 1 4242 4242 0 -1 4194560 100 0 0 0 1 2 0 0 20 0 1 0 987654 1000 10";
+    assert_eq!(parse_proc_stat_start_ticks(stat), Some(987_654));
+    assert_eq!(parse_proc_stat_start_ticks("4242 (truncated) S 1"), None);
+}
+
+#[test]
+fn set_active_transaction_with_clock_stamps_exact_start_time() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let clock = FakeClock::at_unix(1_771_001_234);
+
+    set_active_transaction_with_clock(&layout, "tx-clock", &clock)
+        .expect("must write active transaction");
+    let marker = read_active_transaction_marker(&layout)
+        .expect("must read marker")
+        .expect("marker must exist");
+    assert_eq!(marker.started_at_unix, Some(1_771_001_234));
+
+    clock.advance(std::time::Duration::from_secs(5));
+    assert_eq!(
+        current_unix_timestamp_with_clock(&clock).expect("must read clock"),
+        1_771_001_239
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
 #[test]
 fn append_transaction_journal_entries_in_order() {
     let layout = test_layout();
@@ -420,6 +1060,97 @@ fn expose_and_remove_binary_round_trip() {
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[test]
+fn set_binary_alias_renames_shim_and_updates_receipt() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    write_receipt(&layout, "fd", "10.2.0", &[], InstallReason::Root, None);
+    write_receipt(&layout, "ripgrep", "14.1.0", &[], InstallReason::Root, None);
+    let package_dir = layout.package_dir("fd", "10.2.0");
+    fs::write(package_dir.join("fd"), b"#!/bin/sh\n").expect("must write binary");
+    expose_binary(&layout, &package_dir, "fd", "fd").expect("must expose binary");
+    for (name, bins) in [("fd", vec!["fd"]), ("ripgrep", vec!["rg"])] {
+        let mut receipt = read_install_receipts(&layout)
+            .expect("must read receipts")
+            .into_iter()
+            .find(|receipt| receipt.name == name)
+            .expect("receipt must exist");
+        receipt.exposed_bins = bins.into_iter().map(ToString::to_string).collect();
+        write_install_receipt(&layout, &receipt).expect("must write receipt");
+    }
+    let fd_bins = |layout: &PrefixLayout| {
+        read_install_receipts(layout)
+            .expect("must read receipts")
+            .into_iter()
+            .find(|receipt| receipt.name == "fd")
+            .expect("fd receipt must exist")
+            .exposed_bins
+    };
+
+    set_binary_alias(&layout, "fd", "fd", "fdfind").expect("must alias binary");
+    assert!(bin_path(&layout, "fdfind").exists());
+    assert!(fs::symlink_metadata(bin_path(&layout, "fd")).is_err());
+    assert_eq!(fd_bins(&layout), vec!["fdfind"]);
+    assert_eq!(
+        read_binary_aliases(&layout, "fd").expect("must read aliases"),
+        BTreeMap::from([("fdfind".to_string(), "fd".to_string())])
+    );
+
+    let err = set_binary_alias(&layout, "fd", "fdfind", "rg")
+        .expect_err("alias colliding with another package must fail");
+    assert!(
+        err.to_string()
+            .contains("binary 'rg' is already exposed by package 'ripgrep'"),
+        "unexpected error: {err}"
+    );
+    assert!(bin_path(&layout, "fdfind").exists());
+
+    remove_binary_alias(&layout, "fd", "fdfind").expect("must remove alias");
+    assert!(bin_path(&layout, "fd").exists());
+    assert!(fs::symlink_metadata(bin_path(&layout, "fdfind")).is_err());
+    assert_eq!(fd_bins(&layout), vec!["fd"]);
+    assert!(!layout.binary_aliases_state_path("fd").exists());
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn apply_binary_aliases_renames_reexposed_declared_shims() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    write_receipt(&layout, "fd", "10.2.0", &[], InstallReason::Root, None);
+    let package_dir = layout.package_dir("fd", "10.2.0");
+    fs::write(package_dir.join("fd"), b"#!/bin/sh\n").expect("must write binary");
+    expose_binary(&layout, &package_dir, "fd", "fd").expect("must expose binary");
+    let mut receipt = read_install_receipts(&layout)
+        .expect("must read receipts")
+        .remove(0);
+    receipt.exposed_bins = vec!["fd".to_string()];
+    write_install_receipt(&layout, &receipt).expect("must write receipt");
+    set_binary_alias(&layout, "fd", "fd", "fdfind").expect("must alias binary");
+
+    // A reinstall exposes the declared name and drops the old alias shim.
+    remove_exposed_binary(&layout, "fdfind").expect("must remove stale alias shim");
+    expose_binary(&layout, &package_dir, "fd", "fd").expect("must re-expose binary");
+
+    let applied = apply_binary_aliases(&layout, "fd", &["fd".to_string()])
+        .expect("must apply stored aliases");
+    assert_eq!(applied, vec!["fdfind"]);
+    assert!(bin_path(&layout, "fdfind").exists());
+    assert!(fs::symlink_metadata(bin_path(&layout, "fd")).is_err());
+    assert_eq!(
+        read_binary_aliases(&layout, "fd").expect("must read aliases"),
+        BTreeMap::from([("fdfind".to_string(), "fd".to_string())])
+    );
+
+    let applied = apply_binary_aliases(&layout, "fd", &["fd-ng".to_string()])
+        .expect("aliases for undeclared binaries must be dropped");
+    assert_eq!(applied, vec!["fd-ng"]);
+    assert!(!layout.binary_aliases_state_path("fd").exists());
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
 #[test]
 fn expose_binary_accepts_flattened_macos_app_bundle_exec_path() {
     let layout = test_layout();
@@ -509,99 +1240,591 @@ fn expose_and_remove_completion_round_trip() {
 }
 
 #[test]
-fn expose_completion_rejects_invalid_relative_path() {
+fn deferred_pruning_keeps_empty_dirs_until_sweep() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
     let package_dir = layout.package_dir("zoxide", "1.0.0");
-    fs::create_dir_all(&package_dir).expect("must create package dir");
+    fs::create_dir_all(package_dir.join("completions")).expect("must create completion dir");
+    fs::write(
+        package_dir.join("completions").join("zoxide.bash"),
+        b"# bash completion\n",
+    )
+    .expect("must write completion file");
+    fs::write(package_dir.join("zoxide"), b"#!/bin/sh\n").expect("must write gui app exec");
 
-    let err = expose_completion(
+    let completion = expose_completion(
         &layout,
         &package_dir,
         "zoxide",
-        ArtifactCompletionShell::Zsh,
-        "../outside/_zoxide",
+        ArtifactCompletionShell::Bash,
+        "completions/zoxide.bash",
     )
-    .expect_err("path traversal should be rejected");
-    assert!(err.to_string().contains("must not include '..'"));
-
-    let _ = fs::remove_dir_all(layout.prefix());
-}
-
-#[test]
-fn expose_gui_app_and_state_round_trip() {
-    let layout = test_layout();
-    layout.ensure_base_dirs().expect("must create dirs");
-    let package_dir = layout.package_dir("zed", "1.0.0");
-    fs::create_dir_all(&package_dir).expect("must create package dir");
-    fs::write(package_dir.join("zed"), b"#!/bin/sh\n").expect("must write gui app exec");
-
+    .expect("must expose completion");
     let app = ArtifactGuiApp {
-        app_id: "dev.zed.Zed".to_string(),
-        display_name: "Zed".to_string(),
-        exec: "zed".to_string(),
+        app_id: "dev.zoxide.Zoxide".to_string(),
+        display_name: "Zoxide".to_string(),
+        exec: "zoxide".to_string(),
         icon: None,
-        categories: vec!["Development".to_string()],
-        file_associations: vec![crosspack_core::ArtifactGuiFileAssociation {
-            mime_type: "text/plain".to_string(),
-            extensions: vec![".txt".to_string()],
-        }],
-        protocols: vec![crosspack_core::ArtifactGuiProtocol {
-            scheme: "zed".to_string(),
-        }],
+        categories: Vec::new(),
+        file_associations: Vec::new(),
+        protocols: Vec::new(),
     };
+    let assets = expose_gui_app(&layout, &package_dir, "zoxide", &app)
+        .expect("must expose gui app")
+        .assets;
+
+    let completion_parent = exposed_completion_path(&layout, &completion)
+        .expect("must resolve completion path")
+        .parent()
+        .expect("completion must have parent")
+        .to_path_buf();
+    let gui_parents = assets
+        .iter()
+        .map(|asset| {
+            gui_asset_path(&layout, &asset.rel_path)
+                .expect("must resolve gui path")
+                .parent()
+                .expect("gui asset must have parent")
+                .to_path_buf()
+        })
+        .collect::<Vec<_>>();
 
-    let assets = expose_gui_app(&layout, &package_dir, "zed", &app).expect("must expose gui app");
-    assert!(
-        assets.iter().any(|asset| asset.key == "app:dev.zed.zed"),
-        "launcher ownership key must be present"
-    );
-
+    remove_exposed_completion_with_prune(&layout, &completion, false)
+        .expect("must remove completion");
     for asset in &assets {
-        let path = gui_asset_path(&layout, &asset.rel_path).expect("must resolve gui path");
+        remove_exposed_gui_asset_with_prune(&layout, asset, false).expect("must remove gui asset");
+    }
+    assert!(completion_parent.exists(), "deferred prune must keep dir");
+    for parent in &gui_parents {
         assert!(
-            path.exists(),
-            "gui asset path should exist: {}",
-            path.display()
+            parent.exists(),
+            "deferred prune must keep {}",
+            parent.display()
         );
     }
 
-    write_gui_exposure_state(&layout, "zed", &assets).expect("must write gui state");
-    let loaded = read_gui_exposure_state(&layout, "zed").expect("must read gui state");
-    assert_eq!(loaded, assets);
-
-    for asset in &assets {
-        remove_exposed_gui_asset(&layout, asset).expect("must remove gui asset");
-    }
-    clear_gui_exposure_state(&layout, "zed").expect("must remove gui state file");
-
+    prune_all_empty_dirs(&layout).expect("must sweep empty dirs");
     assert!(
-        read_gui_exposure_state(&layout, "zed")
-            .expect("must read removed gui state")
-            .is_empty(),
-        "gui state should be empty after clear"
+        !completion_parent.exists(),
+        "sweep must remove completion dir"
     );
+    for parent in &gui_parents {
+        assert!(!parent.exists(), "sweep must remove {}", parent.display());
+    }
+    assert!(layout.completions_dir().exists());
+    assert!(layout.gui_dir().exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn native_gui_state_round_trip() {
+fn expose_completion_rejects_storage_path_owned_by_other_package() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
+    for (package, version) in [("my+tool", "1.0.0"), ("my_tool", "2.0.0")] {
+        let package_dir = layout.package_dir(package, version);
+        fs::create_dir_all(package_dir.join("completions")).expect("must create completion dir");
+        fs::write(
+            package_dir.join("completions").join("tool.bash"),
+            format!("# {package} completion\n"),
+        )
+        .expect("must write completion file");
+    }
 
-    let records = vec![
-        GuiNativeRegistrationRecord {
-            key: "app:dev.zed.zed".to_string(),
-            kind: "desktop-entry".to_string(),
-            path: "/tmp/dev.zed.zed.desktop".to_string(),
-        },
-        GuiNativeRegistrationRecord {
-            key: "protocol:zed".to_string(),
-            kind: "protocol-handler".to_string(),
-            path: "HKCU\\Software\\Classes\\zed".to_string(),
+    let first = expose_completion(
+        &layout,
+        &layout.package_dir("my+tool", "1.0.0"),
+        "my+tool",
+        ArtifactCompletionShell::Bash,
+        "completions/tool.bash",
+    )
+    .expect("must expose first completion");
+    write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "my+tool".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: Vec::new(),
+            exposed_completions: vec![first.clone()],
+            snapshot_id: None,
+            install_mode: InstallMode::Managed,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 1,
+            ..InstallReceipt::default()
         },
-    ];
+    )
+    .expect("must write receipt");
+
+    let err = expose_completion(
+        &layout,
+        &layout.package_dir("my_tool", "2.0.0"),
+        "my_tool",
+        ArtifactCompletionShell::Bash,
+        "completions/tool.bash",
+    )
+    .expect_err("colliding completion must be rejected");
+    assert!(err
+        .to_string()
+        .contains("collides with completion owned by package 'my+tool'"));
+
+    let exposed_path =
+        exposed_completion_path(&layout, &first).expect("must resolve exposed completion path");
+    assert_eq!(
+        fs::read_to_string(exposed_path).expect("must read exposed completion"),
+        "# my+tool completion\n"
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn expose_completion_rejects_invalid_relative_path() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let package_dir = layout.package_dir("zoxide", "1.0.0");
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+
+    let err = expose_completion(
+        &layout,
+        &package_dir,
+        "zoxide",
+        ArtifactCompletionShell::Zsh,
+        "../outside/_zoxide",
+    )
+    .expect_err("path traversal should be rejected");
+    assert!(err.to_string().contains("must not include '..'"));
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[cfg(unix)]
+#[test]
+fn expose_completion_projects_non_utf8_source_name_without_lossy_conversion() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let package_dir = layout.package_dir("demo", "1.0.0");
+    fs::create_dir_all(package_dir.join("completions")).expect("must create package dir");
+    let source_rel = Path::new("completions").join(OsStr::from_bytes(b"demo\xff\xfe.bash"));
+    fs::write(package_dir.join(&source_rel), b"complete -F _demo demo\n")
+        .expect("must write non-UTF-8 completion");
+
+    let storage_rel_path = expose_completion(
+        &layout,
+        &package_dir,
+        "demo",
+        ArtifactCompletionShell::Bash,
+        &source_rel,
+    )
+    .expect("must expose non-UTF-8 completion");
+    assert_eq!(
+        storage_rel_path,
+        "packages/bash/demo--completions--demo__.bash"
+    );
+    assert_eq!(
+        projected_exposed_completion_path("demo", ArtifactCompletionShell::Bash, &source_rel)
+            .expect("must project non-UTF-8 completion"),
+        storage_rel_path
+    );
+    let exposed = exposed_completion_path(&layout, &storage_rel_path)
+        .expect("must resolve exposed completion");
+    assert_eq!(
+        fs::read(exposed).expect("must read exposed completion"),
+        b"complete -F _demo demo\n"
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn expose_gui_app_reports_unchanged_and_preserves_mtimes_on_rerun() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let package_dir = layout.package_dir("zed", "1.0.0");
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+    fs::write(package_dir.join("zed"), b"#!/bin/sh\n").expect("must write gui app exec");
+
+    let app = ArtifactGuiApp {
+        app_id: "dev.zed.Zed".to_string(),
+        display_name: "Zed".to_string(),
+        exec: "zed".to_string(),
+        icon: None,
+        categories: Vec::new(),
+        file_associations: Vec::new(),
+        protocols: Vec::new(),
+    };
+
+    let first = expose_gui_app(&layout, &package_dir, "zed", &app).expect("must expose gui app");
+    assert!(first.changed, "first exposure must write assets");
+    let mtimes = first
+        .assets
+        .iter()
+        .map(|asset| {
+            let path = gui_asset_path(&layout, &asset.rel_path).expect("must resolve gui path");
+            fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .expect("must read mtime")
+        })
+        .collect::<Vec<_>>();
+
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    let second = expose_gui_app(&layout, &package_dir, "zed", &app).expect("must re-expose");
+    assert!(!second.changed, "unchanged app must report no-op");
+    assert_eq!(second.assets, first.assets);
+    for (asset, before) in second.assets.iter().zip(mtimes) {
+        let path = gui_asset_path(&layout, &asset.rel_path).expect("must resolve gui path");
+        let after = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .expect("must read mtime");
+        assert_eq!(after, before, "mtime must be preserved: {}", path.display());
+    }
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn expose_artifact_exposes_everything_and_rolls_back_on_failure() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let package_dir = layout.package_dir("zed", "1.0.0");
+    fs::create_dir_all(package_dir.join("completions")).expect("must create package dir");
+    fs::write(package_dir.join("zed"), b"#!/bin/sh\n").expect("must write binary");
+    fs::write(
+        package_dir.join("completions/zed.bash"),
+        b"complete -F _zed zed\n",
+    )
+    .expect("must write completion");
+
+    let mut artifact = crosspack_core::Artifact {
+        target: "x86_64-unknown-linux-gnu".to_string(),
+        url: "https://example.test/zed.tar.gz".to_string(),
+        mirrors: Vec::new(),
+        sha256: "0".repeat(64),
+        size: None,
+        signature: None,
+        archive: None,
+        strip_components: None,
+        artifact_root: None,
+        file_manifest: None,
+        files: Vec::new(),
+        binaries: vec![crosspack_core::ArtifactBinary {
+            name: "zed".to_string(),
+            path: "zed".to_string(),
+            interpreter: None,
+            env: BTreeMap::new(),
+        }],
+        completions: vec![crosspack_core::ArtifactCompletion {
+            shell: ArtifactCompletionShell::Bash,
+            path: "completions/zed.bash".to_string(),
+        }],
+        gui_apps: vec![ArtifactGuiApp {
+            app_id: "dev.zed.Zed".to_string(),
+            display_name: "Zed".to_string(),
+            exec: "zed".to_string(),
+            icon: None,
+            categories: Vec::new(),
+            file_associations: Vec::new(),
+            protocols: Vec::new(),
+        }],
+    };
+
+    let exposure =
+        expose_artifact(&layout, &package_dir, "zed", &artifact).expect("must expose artifact");
+    assert_eq!(exposure.exposed_bins, vec!["zed"]);
+    assert_eq!(
+        exposure.exposed_completions,
+        vec![projected_exposed_completion_path(
+            "zed",
+            ArtifactCompletionShell::Bash,
+            "completions/zed.bash"
+        )
+        .expect("must project completion path")]
+    );
+    assert!(exposure.gui_changed);
+    assert!(exposure
+        .gui_assets
+        .iter()
+        .any(|asset| asset.key == "app:dev.zed.zed"));
+    let completion_path = exposed_completion_path(&layout, &exposure.exposed_completions[0])
+        .expect("must resolve completion path");
+    assert!(bin_path(&layout, "zed").exists());
+    assert!(completion_path.exists());
+
+    for asset in &exposure.gui_assets {
+        remove_exposed_gui_asset(&layout, asset).expect("must remove gui asset");
+    }
+    remove_exposed_completion(&layout, &exposure.exposed_completions[0])
+        .expect("must remove completion");
+    remove_exposed_binary(&layout, "zed").expect("must remove binary");
+
+    artifact.gui_apps[0].exec = "missing-zed".to_string();
+    let err = expose_artifact(&layout, &package_dir, "zed", &artifact)
+        .expect_err("missing gui exec must fail");
+    assert!(err.to_string().contains("missing-zed"), "{err:#}");
+    assert!(!bin_path(&layout, "zed").exists());
+    assert!(!completion_path.exists());
+
+    artifact.gui_apps.clear();
+    expose_artifact(&layout, &package_dir, "zed", &artifact).expect("must expose artifact");
+    artifact.gui_apps.push(ArtifactGuiApp {
+        app_id: "dev.zed.Zed".to_string(),
+        display_name: "Zed".to_string(),
+        exec: "missing-zed".to_string(),
+        icon: None,
+        categories: Vec::new(),
+        file_associations: Vec::new(),
+        protocols: Vec::new(),
+    });
+    expose_artifact(&layout, &package_dir, "zed", &artifact)
+        .expect_err("missing gui exec must fail");
+    assert!(
+        bin_path(&layout, "zed").exists(),
+        "pre-existing binary must survive rollback"
+    );
+    assert!(
+        completion_path.exists(),
+        "pre-existing completion must survive rollback"
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[cfg(unix)]
+#[test]
+fn reexpose_all_restores_corrupted_shims_and_reports_missing_sources() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let package_dir = layout.package_dir("zed", "1.0.0");
+    fs::create_dir_all(package_dir.join("completions")).expect("must create package dir");
+    fs::write(package_dir.join("zed"), b"#!/bin/sh\n").expect("must write binary");
+    fs::write(
+        package_dir.join("completions/zed.bash"),
+        b"complete -F _zed zed\n",
+    )
+    .expect("must write completion");
+    let mut artifact = crosspack_core::Artifact {
+        target: "x86_64-unknown-linux-gnu".to_string(),
+        url: "https://example.test/zed.tar.gz".to_string(),
+        mirrors: Vec::new(),
+        sha256: "0".repeat(64),
+        size: None,
+        signature: None,
+        archive: None,
+        strip_components: None,
+        artifact_root: None,
+        file_manifest: None,
+        files: Vec::new(),
+        binaries: vec![crosspack_core::ArtifactBinary {
+            name: "zed".to_string(),
+            path: "zed".to_string(),
+            interpreter: None,
+            env: BTreeMap::new(),
+        }],
+        completions: vec![crosspack_core::ArtifactCompletion {
+            shell: ArtifactCompletionShell::Bash,
+            path: "completions/zed.bash".to_string(),
+        }],
+        gui_apps: Vec::new(),
+    };
+    let exposure =
+        expose_artifact(&layout, &package_dir, "zed", &artifact).expect("must expose artifact");
+    write_receipt(&layout, "zed", "1.0.0", &[], InstallReason::Root, None);
+    let mut receipt = read_install_receipts(&layout)
+        .expect("must read receipts")
+        .remove(0);
+    receipt.exposed_bins = exposure.exposed_bins.clone();
+    receipt.exposed_completions = exposure.exposed_completions.clone();
+    write_install_receipt(&layout, &receipt).expect("must write receipt");
+
+    let shim = bin_path(&layout, "zed");
+    fs::remove_file(&shim).expect("must remove shim");
+    fs::write(&shim, b"corrupted").expect("must corrupt shim");
+    let untouched = exposed_completion_path(&layout, &exposure.exposed_completions[0])
+        .expect("must resolve completion path");
+
+    let report = reexpose_all(&layout, |_| Ok(artifact.clone())).expect("must reexpose");
+    assert!(!report.has_errors(), "{report:?}");
+    assert_eq!(report.packages[0].changed, vec!["bin/zed"]);
+    assert_ne!(fs::read(&shim).expect("must read shim"), b"corrupted");
+    assert!(untouched.exists());
+
+    artifact.binaries[0].path = "bin/zed".to_string();
+    let report = reexpose_all(&layout, |_| Ok(artifact.clone())).expect("must reexpose");
+    assert!(report.has_errors());
+    assert!(
+        report.packages[0].errors[0].contains("declared binary path 'bin/zed' was not found"),
+        "{report:?}"
+    );
+    assert!(shim.exists());
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn expose_gui_app_and_state_round_trip() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let package_dir = layout.package_dir("zed", "1.0.0");
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+    fs::write(package_dir.join("zed"), b"#!/bin/sh\n").expect("must write gui app exec");
+
+    let app = ArtifactGuiApp {
+        app_id: "dev.zed.Zed".to_string(),
+        display_name: "Zed".to_string(),
+        exec: "zed".to_string(),
+        icon: None,
+        categories: vec!["Development".to_string()],
+        file_associations: vec![crosspack_core::ArtifactGuiFileAssociation {
+            mime_type: "text/plain".to_string(),
+            extensions: vec![".txt".to_string()],
+        }],
+        protocols: vec![crosspack_core::ArtifactGuiProtocol {
+            scheme: "zed".to_string(),
+        }],
+    };
+
+    let assets = expose_gui_app(&layout, &package_dir, "zed", &app)
+        .expect("must expose gui app")
+        .assets;
+    assert!(
+        assets.iter().any(|asset| asset.key == "app:dev.zed.zed"),
+        "launcher ownership key must be present"
+    );
+
+    for asset in &assets {
+        let path = gui_asset_path(&layout, &asset.rel_path).expect("must resolve gui path");
+        assert!(
+            path.exists(),
+            "gui asset path should exist: {}",
+            path.display()
+        );
+    }
+
+    write_gui_exposure_state(&layout, "zed", &assets).expect("must write gui state");
+    let loaded = read_gui_exposure_state(&layout, "zed").expect("must read gui state");
+    assert_eq!(loaded, assets);
+
+    for asset in &assets {
+        remove_exposed_gui_asset(&layout, asset).expect("must remove gui asset");
+    }
+    clear_gui_exposure_state(&layout, "zed").expect("must remove gui state file");
+
+    assert!(
+        read_gui_exposure_state(&layout, "zed")
+            .expect("must read removed gui state")
+            .is_empty(),
+        "gui state should be empty after clear"
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn expose_gui_app_installs_icon_and_uninstall_removes_it() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let package_dir = layout.package_dir("zed", "1.0.0");
+    fs::create_dir_all(package_dir.join("share")).expect("must create package dir");
+    fs::write(package_dir.join("zed"), b"#!/bin/sh\n").expect("must write gui app exec");
+    fs::write(package_dir.join("share").join("Zed.PNG"), b"png-bytes").expect("must write icon");
+
+    let app = ArtifactGuiApp {
+        app_id: "dev.zed.Zed".to_string(),
+        display_name: "Zed".to_string(),
+        exec: "zed".to_string(),
+        icon: Some("share/Zed.PNG".to_string()),
+        categories: Vec::new(),
+        file_associations: Vec::new(),
+        protocols: Vec::new(),
+    };
+
+    let assets = expose_gui_app(&layout, &package_dir, "zed", &app)
+        .expect("must expose gui app")
+        .assets;
+    let icon_asset = assets
+        .iter()
+        .find(|asset| asset.key == "icon:dev.zed.zed")
+        .expect("icon ownership key must be present");
+    assert_eq!(icon_asset.rel_path, "icons/zed--dev.zed.Zed.png");
+    let icon_path = gui_asset_path(&layout, &icon_asset.rel_path).expect("must resolve icon");
+    assert_eq!(
+        fs::read(&icon_path).expect("must install icon"),
+        b"png-bytes"
+    );
+
+    if cfg!(target_os = "linux") {
+        let launcher_asset = assets
+            .iter()
+            .find(|asset| asset.key.starts_with("app:"))
+            .expect("launcher must be projected");
+        let launcher = fs::read_to_string(
+            gui_asset_path(&layout, &launcher_asset.rel_path).expect("must resolve launcher"),
+        )
+        .expect("must read launcher");
+        assert!(
+            launcher.contains(&format!("Icon={}\n", icon_path.display())),
+            "launcher must reference the managed icon: {launcher}"
+        );
+    }
+    assert_eq!(
+        managed_gui_icon_path(&layout, "zed", &app).expect("must resolve managed icon"),
+        Some(icon_path.clone())
+    );
+
+    write_gui_exposure_state(&layout, "zed", &assets).expect("must write gui state");
+    write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "zed".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: Vec::new(),
+            exposed_completions: Vec::new(),
+            snapshot_id: None,
+            install_mode: InstallMode::Managed,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 1,
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
+
+    uninstall_package(&layout, "zed").expect("must uninstall");
+    assert!(!icon_path.exists());
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn native_gui_state_round_trip() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let records = vec![
+        GuiNativeRegistrationRecord {
+            key: "app:dev.zed.zed".to_string(),
+            kind: "desktop-entry".to_string(),
+            path: "/tmp/dev.zed.zed.desktop".to_string(),
+        },
+        GuiNativeRegistrationRecord {
+            key: "protocol:zed".to_string(),
+            kind: "protocol-handler".to_string(),
+            path: "HKCU\\Software\\Classes\\zed".to_string(),
+        },
+    ];
 
     write_gui_native_state(&layout, "zed", &records).expect("must write native gui state");
     let loaded = read_gui_native_state(&layout, "zed").expect("must read native gui state");
@@ -1181,6 +2404,32 @@ fn macos_registration_destination_fallback_respects_unmanaged_overwrite_guard()
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(target_os = "linux")]
+#[test]
+fn linux_native_desktop_entry_references_managed_icon() {
+    let app = ArtifactGuiApp {
+        app_id: "dev.zed.Zed".to_string(),
+        display_name: "Zed".to_string(),
+        exec: "zed".to_string(),
+        icon: Some("share/Zed.png".to_string()),
+        categories: Vec::new(),
+        file_associations: Vec::new(),
+        protocols: Vec::new(),
+    };
+    let source_path = Path::new("/opt/crosspack/pkgs/zed/1.0.0/zed");
+    let icon_path = Path::new("/opt/crosspack/share/gui/icons/zed--dev.zed.Zed.png");
+
+    let entry = render_linux_native_desktop_entry(&app, source_path, Some(icon_path));
+    assert!(
+        entry.contains(&format!("Icon={}\n", icon_path.display())),
+        "native entry must reference the managed icon: {entry}"
+    );
+    assert!(!entry.contains("Icon=share/Zed.png"));
+
+    let entry = render_linux_native_desktop_entry(&app, source_path, None);
+    assert!(entry.contains("Icon=share/Zed.png\n"));
+}
+
 #[test]
 fn register_native_gui_returns_warnings_without_error_on_command_failure() {
     let layout = test_layout();
@@ -1205,6 +2454,7 @@ fn register_native_gui_returns_warnings_without_error_on_command_failure() {
         "demo",
         &app,
         &install_root,
+        None,
         &[],
         |_command, _context| Err(anyhow!("simulated command failure")),
     )
@@ -1269,13 +2519,12 @@ fn install_from_artifact_rejects_native_installer_when_escalation_policy_forbids
         &artifact_path,
         ArchiveType::Exe,
         ArtifactInstallOptions {
-            strip_components: 0,
-            artifact_root: None,
             install_mode: InstallMode::Native,
             interaction_policy: InstallInteractionPolicy {
                 allow_prompt_escalation: false,
                 allow_non_prompt_escalation: false,
             },
+            ..ArtifactInstallOptions::default()
         },
     )
     .expect_err("native installer should be blocked when escalation is disallowed");
@@ -1338,151 +2587,225 @@ fn install_from_source_archive_runs_build_and_install_commands() {
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(unix)]
 #[test]
-fn install_from_source_archive_rejects_missing_build_commands() {
+fn install_from_source_archive_runs_commands_with_scrubbed_environment() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let archive_path = layout.prefix().join("demo-src.tar.gz");
-    fs::write(&archive_path, b"not-a-real-archive").expect("must write archive fixture");
+    let source_root = layout.prefix().join("source");
+    fs::create_dir_all(source_root.join("demo-src")).expect("must create source project dir");
+    let archive_path = layout.prefix().join("demo-src.tar");
+    let tar_status = Command::new("tar")
+        .arg("-cf")
+        .arg(&archive_path)
+        .arg("-C")
+        .arg(&source_root)
+        .arg("demo-src")
+        .status()
+        .expect("must execute tar command for test fixture");
+    assert!(tar_status.success(), "tar fixture creation must succeed");
 
-    let err = install_from_source_archive(
+    let install_root = install_from_source_archive_with_env_policy(
         &layout,
         "demo",
         "1.0.0",
         &archive_path,
-        ArchiveType::TarGz,
-        &[],
+        ArchiveType::Tar,
         &["true".to_string()],
+        &[
+            "sh".to_string(),
+            "-c".to_string(),
+            "env > $CROSSPACK_STAGE_DIR/env.txt".to_string(),
+        ],
+        &EnvPolicy::default().with_declared("DEMO_BUILD_MODE", "release"),
     )
-    .expect_err("empty build command set should fail closed");
-    assert!(
-        err.to_string()
-            .contains("source build metadata requires non-empty build_commands"),
-        "unexpected error: {err}"
-    );
+    .expect("source archive install should succeed");
+
+    let env_dump = fs::read_to_string(install_root.join("env.txt")).expect("must read env dump");
+    assert!(env_dump
+        .lines()
+        .any(|line| line == "DEMO_BUILD_MODE=release"));
+    assert!(env_dump.lines().any(|line| line.starts_with("PATH=")));
+    assert!(env_dump
+        .lines()
+        .any(|line| line.starts_with("CROSSPACK_STAGE_DIR=")));
+    assert!(std::env::var_os("CARGO_MANIFEST_DIR").is_some());
+    assert!(!env_dump
+        .lines()
+        .any(|line| line.starts_with("CARGO_MANIFEST_DIR=")));
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
-#[cfg(not(windows))]
+#[cfg(unix)]
 #[test]
-fn install_from_artifact_rejects_msi_on_non_windows_host() {
+fn expose_binary_with_interpreter_wraps_non_executable_script() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.msi");
-    fs::write(&artifact_path, b"dummy msi").expect("must write artifact");
+    let package_dir = layout.package_dir("pydemo", "1.0.0");
+    fs::create_dir_all(package_dir.join("bin")).expect("must create package bin dir");
+    let script = package_dir.join("bin").join("pydemo.py");
+    fs::write(&script, b"print('hello')\n").expect("must write script");
+    let native = package_dir.join("bin").join("native");
+    fs::write(&native, b"#!/bin/sh\necho native\n").expect("must write native script");
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&native, fs::Permissions::from_mode(0o755))
+            .expect("must mark native executable");
+    }
 
-    let err = install_from_artifact(
+    expose_binary_with_interpreter(
         &layout,
-        "demo",
-        "1.0.0",
-        &artifact_path,
-        ArchiveType::Msi,
-        ArtifactInstallOptions {
-            strip_components: 0,
-            artifact_root: None,
-            install_mode: InstallMode::Native,
-            interaction_policy: InstallInteractionPolicy::default(),
-        },
+        &package_dir,
+        "pydemo",
+        "bin/pydemo.py",
+        Some("python3"),
     )
-    .expect_err("msi should be rejected on non-Windows host");
-    assert!(
-        err.to_string()
-            .contains("MSI artifacts are supported only on Windows hosts"),
-        "unexpected error: {err}"
+    .expect("must expose interpreter wrapper");
+    expose_binary_with_interpreter(
+        &layout,
+        &package_dir,
+        "native",
+        "bin/native",
+        Some("python3"),
+    )
+    .expect("must expose executable script");
+
+    let wrapper = bin_path(&layout, "pydemo");
+    assert!(!fs::symlink_metadata(&wrapper)
+        .expect("wrapper must exist")
+        .file_type()
+        .is_symlink());
+    let content = fs::read_to_string(&wrapper).expect("must read wrapper");
+    assert_eq!(
+        content,
+        format!("#!/bin/sh\nexec 'python3' '{}' \"$@\"\n", script.display())
     );
+    assert!(fs::symlink_metadata(bin_path(&layout, "native"))
+        .expect("native entry must exist")
+        .file_type()
+        .is_symlink());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
-#[cfg(not(target_os = "macos"))]
+#[cfg(unix)]
 #[test]
-fn install_from_artifact_rejects_dmg_on_non_macos_host() {
+fn expose_binary_with_env_policy_writes_wrapper_with_clean_environment() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.dmg");
-    fs::write(&artifact_path, b"dummy dmg").expect("must write artifact");
+    let package_dir = layout.package_dir("envdemo", "1.0.0");
+    fs::create_dir_all(package_dir.join("bin")).expect("must create package bin dir");
+    let binary = package_dir.join("bin").join("envdemo");
+    fs::write(&binary, b"#!/bin/sh\nenv\n").expect("must write binary");
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&binary, fs::Permissions::from_mode(0o755))
+            .expect("must mark binary executable");
+    }
 
-    let err = install_from_artifact(
+    expose_binary_with_env_policy(
         &layout,
-        "demo",
-        "1.0.0",
-        &artifact_path,
-        ArchiveType::Dmg,
-        ArtifactInstallOptions {
-            strip_components: 0,
-            artifact_root: None,
-            install_mode: InstallMode::Managed,
-            interaction_policy: InstallInteractionPolicy::default(),
-        },
+        &package_dir,
+        "envdemo",
+        "bin/envdemo",
+        &EnvPolicy::default().with_declared("ENVDEMO_GREETING", "it's quoted"),
     )
-    .expect_err("dmg should be rejected on non-macOS host");
-    assert!(
-        err.to_string()
-            .contains("DMG artifacts are supported only on macOS hosts"),
-        "unexpected error: {err}"
-    );
+    .expect("must expose wrapper");
+
+    let wrapper = bin_path(&layout, "envdemo");
+    assert!(!fs::symlink_metadata(&wrapper)
+        .expect("wrapper must exist")
+        .file_type()
+        .is_symlink());
+    let output = Command::new(&wrapper)
+        .env("ENVDEMO_HOST_ONLY", "leak")
+        .env("PATH", "/usr/bin:/bin")
+        .env("HOME", "/home/envdemo")
+        .env("TMPDIR", "/tmp/envdemo")
+        .output()
+        .expect("must run wrapper");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).expect("env output must be UTF-8");
+    assert!(stdout
+        .lines()
+        .any(|line| line == "ENVDEMO_GREETING=it's quoted"));
+    assert!(stdout.lines().any(|line| line == "PATH=/usr/bin:/bin"));
+    assert!(stdout.lines().any(|line| line == "HOME=/home/envdemo"));
+    assert!(stdout.lines().any(|line| line == "TMPDIR=/tmp/envdemo"));
+    assert!(!stdout.contains("ENVDEMO_HOST_ONLY"));
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
-#[cfg(not(windows))]
+#[cfg(unix)]
 #[test]
-fn install_from_artifact_rejects_exe_on_non_windows_host() {
+fn expose_artifact_wraps_binaries_declaring_env_with_scrubbed_environment() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.exe");
-    fs::write(&artifact_path, b"dummy exe").expect("must write artifact");
+    let package_dir = layout.package_dir("envdemo", "1.0.0");
+    fs::create_dir_all(package_dir.join("bin")).expect("must create package bin dir");
+    fs::write(package_dir.join("bin/envdemo.sh"), b"env\n").expect("must write script");
+    let artifact = crosspack_core::Artifact {
+        target: "x86_64-unknown-linux-gnu".to_string(),
+        url: "https://example.test/envdemo.tar.gz".to_string(),
+        mirrors: Vec::new(),
+        sha256: "0".repeat(64),
+        size: None,
+        signature: None,
+        archive: None,
+        strip_components: None,
+        artifact_root: None,
+        file_manifest: None,
+        files: Vec::new(),
+        binaries: vec![crosspack_core::ArtifactBinary {
+            name: "envdemo".to_string(),
+            path: "bin/envdemo.sh".to_string(),
+            interpreter: Some("/bin/sh".to_string()),
+            env: BTreeMap::from([("ENVDEMO_MODE".to_string(), "wrapped".to_string())]),
+        }],
+        completions: Vec::new(),
+        gui_apps: Vec::new(),
+    };
 
-    let err = install_from_artifact(
-        &layout,
-        "demo",
-        "1.0.0",
-        &artifact_path,
-        ArchiveType::Exe,
-        ArtifactInstallOptions {
-            strip_components: 0,
-            artifact_root: None,
-            install_mode: InstallMode::Native,
-            interaction_policy: InstallInteractionPolicy::default(),
-        },
-    )
-    .expect_err("exe should be rejected on non-Windows host");
-    assert!(
-        err.to_string()
-            .contains("EXE artifacts are supported only on Windows hosts"),
-        "unexpected error: {err}"
-    );
+    expose_artifact(&layout, &package_dir, "envdemo", &artifact).expect("must expose artifact");
+
+    let output = Command::new(bin_path(&layout, "envdemo"))
+        .env("ENVDEMO_HOST_ONLY", "leak")
+        .env("PATH", "/usr/bin:/bin")
+        .output()
+        .expect("must run wrapper");
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).expect("env output must be UTF-8");
+    assert!(stdout.lines().any(|line| line == "ENVDEMO_MODE=wrapped"));
+    assert!(stdout.lines().any(|line| line == "PATH=/usr/bin:/bin"));
+    assert!(!stdout.contains("ENVDEMO_HOST_ONLY"));
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
-#[cfg(not(target_os = "macos"))]
 #[test]
-fn install_from_artifact_rejects_pkg_on_non_macos_host() {
+fn install_from_source_archive_rejects_missing_build_commands() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.pkg");
-    fs::write(&artifact_path, b"dummy pkg").expect("must write artifact");
 
-    let err = install_from_artifact(
+    let archive_path = layout.prefix().join("demo-src.tar.gz");
+    fs::write(&archive_path, b"not-a-real-archive").expect("must write archive fixture");
+
+    let err = install_from_source_archive(
         &layout,
         "demo",
         "1.0.0",
-        &artifact_path,
-        ArchiveType::Pkg,
-        ArtifactInstallOptions {
-            strip_components: 0,
-            artifact_root: None,
-            install_mode: InstallMode::Native,
-            interaction_policy: InstallInteractionPolicy::default(),
-        },
+        &archive_path,
+        ArchiveType::TarGz,
+        &[],
+        &["true".to_string()],
     )
-    .expect_err("pkg should be rejected on non-macOS host");
+    .expect_err("empty build command set should fail closed");
     assert!(
         err.to_string()
-            .contains("PKG artifacts are supported only on macOS hosts"),
+            .contains("source build metadata requires non-empty build_commands"),
         "unexpected error: {err}"
     );
 
@@ -1490,1931 +2813,3949 @@ fn install_from_artifact_rejects_pkg_on_non_macos_host() {
 }
 
 #[test]
-fn installer_dispatch_does_not_support_deb_or_rpm_archive_types() {
-    assert!(
-        ArchiveType::parse("deb").is_none(),
-        "DEB should be unsupported in installer dispatch"
-    );
-    assert!(
-        ArchiveType::parse("rpm").is_none(),
-        "RPM should be unsupported in installer dispatch"
-    );
-    assert!(
-        ArchiveType::infer_from_url("https://example.test/demo.deb").is_none(),
-        "DEB URL inference should be unsupported in installer dispatch"
-    );
-    assert!(
-        ArchiveType::infer_from_url("https://example.test/demo.rpm").is_none(),
-        "RPM URL inference should be unsupported in installer dispatch"
-    );
-}
-
-#[cfg(not(windows))]
-#[test]
-fn install_from_artifact_rejects_msix_on_non_windows_host() {
+fn install_from_artifact_dev_sentinel_sha256_depends_on_checksum_policy() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.msix");
-    fs::write(&artifact_path, b"dummy msix").expect("must write artifact");
+    let artifact_path = layout.prefix().join("demo.bin");
+    fs::write(&artifact_path, b"demo payload").expect("must write artifact");
+    let options = |expected_sha256, checksum_policy| ArtifactInstallOptions {
+        expected_sha256: Some(expected_sha256),
+        checksum_policy,
+        ..ArtifactInstallOptions::default()
+    };
 
-    let err = install_from_artifact(
+    for sentinel in ["", "dev"] {
+        let err = install_from_artifact(
+            &layout,
+            "demo",
+            "1.0.0",
+            &artifact_path,
+            ArchiveType::Bin,
+            options(sentinel, ChecksumPolicy::Production),
+        )
+        .expect_err("production policy must reject the sentinel");
+        assert!(
+            err.to_string()
+                .contains("dev-mode sentinel and is invalid under the production checksum policy"),
+            "unexpected error: {err}"
+        );
+        assert!(!layout.package_dir("demo", "1.0.0").exists());
+    }
+
+    let installed = install_from_artifact_with_result(
         &layout,
         "demo",
         "1.0.0",
         &artifact_path,
-        ArchiveType::Msix,
-        ArtifactInstallOptions {
-            strip_components: 0,
-            artifact_root: None,
-            install_mode: InstallMode::Native,
-            interaction_policy: InstallInteractionPolicy::default(),
-        },
+        ArchiveType::Bin,
+        options("dev", ChecksumPolicy::DevMode),
     )
-    .expect_err("msix should be rejected on non-Windows host");
-    assert!(
-        err.to_string()
-            .contains("MSIX artifacts are supported only on Windows hosts"),
-        "unexpected error: {err}"
-    );
-
-    let _ = fs::remove_dir_all(layout.prefix());
-}
-
-#[cfg(not(windows))]
-#[test]
-fn install_from_artifact_rejects_appx_on_non_windows_host() {
-    let layout = test_layout();
-    layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.appx");
-    fs::write(&artifact_path, b"dummy appx").expect("must write artifact");
+    .expect("dev mode must skip verification for the sentinel");
+    assert!(installed.dest.join("demo.bin").exists());
+    assert_eq!(installed.warnings.len(), 1);
+    assert!(installed.warnings[0].contains("verification SKIPPED for demo@1.0.0"));
 
     let err = install_from_artifact(
         &layout,
         "demo",
         "1.0.0",
         &artifact_path,
-        ArchiveType::Appx,
-        ArtifactInstallOptions {
-            strip_components: 0,
-            artifact_root: None,
-            install_mode: InstallMode::Native,
-            interaction_policy: InstallInteractionPolicy::default(),
-        },
+        ArchiveType::Bin,
+        options(&"0".repeat(64), ChecksumPolicy::DevMode),
     )
-    .expect_err("appx should be rejected on non-Windows host");
-    assert!(
-        err.to_string()
-            .contains("APPX artifacts are supported only on Windows hosts"),
-        "unexpected error: {err}"
-    );
+    .expect_err("dev mode must still verify real digests");
+    assert!(err.to_string().contains("artifact sha256 mismatch"));
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
-#[cfg(windows)]
 #[test]
-fn install_from_artifact_reports_exe_extraction_failure_on_windows_host() {
+fn install_from_artifact_verifies_expected_sha256_before_extraction() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.exe");
-    fs::write(&artifact_path, b"dummy exe").expect("must write artifact");
+    let artifact_path = layout.prefix().join("demo.bin");
+    fs::write(&artifact_path, b"demo payload").expect("must write artifact");
+    let actual_sha256 = crosspack_security::sha256_hex(b"demo payload");
+    let options = |expected_sha256| ArtifactInstallOptions {
+        expected_sha256,
+        ..ArtifactInstallOptions::default()
+    };
 
+    let wrong_sha256 = "0".repeat(64);
     let err = install_from_artifact(
         &layout,
         "demo",
         "1.0.0",
         &artifact_path,
-        ArchiveType::Exe,
-        ArtifactInstallOptions {
-            strip_components: 0,
-            artifact_root: None,
-            install_mode: InstallMode::Native,
-            interaction_policy: InstallInteractionPolicy::default(),
-        },
+        ArchiveType::Bin,
+        options(Some(wrong_sha256.as_str())),
     )
-    .expect_err("exe staging should fail deterministic extraction on Windows host");
+    .expect_err("digest mismatch must abort extraction");
+    let rendered = err.to_string();
     assert!(
-        err.to_string()
-            .contains("failed to stage EXE artifact via deterministic extraction"),
-        "unexpected error: {err}"
+        rendered.contains("artifact sha256 mismatch")
+            && rendered.contains(&format!("expected {wrong_sha256}"))
+            && rendered.contains(&format!("got {actual_sha256}")),
+        "unexpected error: {rendered}"
     );
+    assert!(!layout.package_dir("demo", "1.0.0").exists());
 
-    let _ = fs::remove_dir_all(layout.prefix());
-}
-
-#[cfg(target_os = "macos")]
-#[test]
-fn install_from_artifact_reports_pkg_extraction_failure_on_macos_host() {
-    let layout = test_layout();
-    layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.pkg");
-    fs::write(&artifact_path, b"dummy pkg").expect("must write artifact");
-
-    let err = install_from_artifact(
+    let install_root = install_from_artifact(
         &layout,
         "demo",
         "1.0.0",
         &artifact_path,
-        ArchiveType::Pkg,
-        ArtifactInstallOptions {
-            strip_components: 0,
-            artifact_root: None,
-            install_mode: InstallMode::Native,
-            interaction_policy: InstallInteractionPolicy::default(),
-        },
+        ArchiveType::Bin,
+        options(Some(actual_sha256.as_str())),
     )
-    .expect_err("pkg staging should fail deterministic extraction on macOS host");
-    assert!(
-        err.to_string()
-            .contains("failed to stage PKG artifact via deterministic extraction"),
-        "unexpected error: {err}"
-    );
+    .expect("matching digest must install");
+    assert!(install_root.join("demo.bin").exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
-#[cfg(windows)]
+#[cfg(unix)]
 #[test]
-fn install_from_artifact_reports_msix_extraction_failure_on_windows_host() {
+fn install_from_artifact_tar_fast_path_matches_staged_copy_tree() {
+    fn collect_tree(root: &Path, dir: &Path, out: &mut Vec<(PathBuf, Vec<u8>)>) {
+        let mut entries = fs::read_dir(dir)
+            .expect("must read dir")
+            .map(|entry| entry.expect("must read entry").path())
+            .collect::<Vec<_>>();
+        entries.sort();
+        for path in entries {
+            let rel = path
+                .strip_prefix(root)
+                .expect("must relativize")
+                .to_path_buf();
+            let metadata = fs::symlink_metadata(&path).expect("must stat");
+            if metadata.file_type().is_symlink() {
+                let target = fs::read_link(&path).expect("must read link");
+                out.push((rel, target.into_os_string().into_encoded_bytes()));
+            } else if metadata.is_dir() {
+                out.push((rel, Vec::new()));
+                collect_tree(root, &path, out);
+            } else {
+                out.push((rel, fs::read(&path).expect("must read file")));
+            }
+        }
+    }
+
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.msix");
-    fs::write(&artifact_path, b"dummy msix").expect("must write artifact");
+    let fixture_root = layout.prefix().join("fixture");
+    let payload_dir = fixture_root.join("demo-1.0.0");
+    fs::create_dir_all(payload_dir.join("bin")).expect("must create bin dir");
+    fs::create_dir_all(payload_dir.join("share/doc")).expect("must create doc dir");
+    fs::create_dir_all(payload_dir.join("empty/nested")).expect("must create empty dir");
+    fs::write(payload_dir.join("bin/demo"), b"#!/bin/sh\necho demo\n").expect("must write bin");
+    fs::write(payload_dir.join("share/doc/README"), b"readme").expect("must write doc");
+    std::os::unix::fs::symlink("demo", payload_dir.join("bin/demo-alias"))
+        .expect("must create symlink");
+    let artifact_path = layout.prefix().join("demo.tar.gz");
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&artifact_path)
+        .arg("-C")
+        .arg(&fixture_root)
+        .arg("demo-1.0.0")
+        .status()
+        .expect("must run tar");
+    assert!(status.success());
 
-    let err = install_from_artifact(
+    let options = |artifact_root| ArtifactInstallOptions {
+        strip_components: 1,
+        artifact_root,
+        ..ArtifactInstallOptions::default()
+    };
+    let fast_root = install_from_artifact(
         &layout,
-        "demo",
+        "fast",
         "1.0.0",
         &artifact_path,
-        ArchiveType::Msix,
-        ArtifactInstallOptions {
-            strip_components: 0,
-            artifact_root: None,
-            install_mode: InstallMode::Native,
-            interaction_policy: InstallInteractionPolicy::default(),
-        },
+        ArchiveType::TarGz,
+        options(None),
     )
-    .expect_err("msix staging should fail deterministic extraction on Windows host");
+    .expect("fast path must install");
+    let staged_root = install_from_artifact(
+        &layout,
+        "staged",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::TarGz,
+        options(Some(".")),
+    )
+    .expect("staged copy path must install");
+
+    let mut fast_tree = Vec::new();
+    collect_tree(&fast_root, &fast_root, &mut fast_tree);
+    let mut staged_tree = Vec::new();
+    collect_tree(&staged_root, &staged_root, &mut staged_tree);
+    assert_eq!(fast_tree, staged_tree);
+    assert!(fast_tree
+        .iter()
+        .any(|(rel, _)| rel == Path::new("bin/demo")));
+
+    let siblings = fs::read_dir(fast_root.parent().expect("must have parent"))
+        .expect("must read package parent")
+        .map(|entry| entry.expect("must read entry").file_name())
+        .collect::<Vec<_>>();
+    assert_eq!(siblings, vec![std::ffi::OsString::from("1.0.0")]);
+}
+
+#[cfg(unix)]
+#[test]
+fn install_from_artifact_with_result_reports_installed_file_counts() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let fixture_root = layout.prefix().join("fixture");
+    let payload_dir = fixture_root.join("demo-1.0.0");
+    fs::create_dir_all(payload_dir.join("bin")).expect("must create bin dir");
+    fs::create_dir_all(payload_dir.join("share/doc")).expect("must create doc dir");
+    fs::write(payload_dir.join("bin/demo"), vec![b'x'; 100]).expect("must write bin");
+    fs::write(payload_dir.join("share/doc/README"), vec![b'r'; 20]).expect("must write doc");
+    fs::write(payload_dir.join("share/doc/LICENSE"), vec![b'l'; 3]).expect("must write license");
+    std::os::unix::fs::symlink("demo", payload_dir.join("bin/demo-alias"))
+        .expect("must create symlink");
+    let artifact_path = layout.prefix().join("demo.tar.gz");
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&artifact_path)
+        .arg("-C")
+        .arg(&fixture_root)
+        .arg("demo-1.0.0")
+        .status()
+        .expect("must run tar");
+    assert!(status.success());
+
+    for (name, artifact_root) in [("fast", None), ("staged", Some("."))] {
+        let result = install_from_artifact_with_result(
+            &layout,
+            name,
+            "1.0.0",
+            &artifact_path,
+            ArchiveType::TarGz,
+            ArtifactInstallOptions {
+                strip_components: 1,
+                artifact_root,
+                ..ArtifactInstallOptions::default()
+            },
+        )
+        .expect("must install artifact");
+
+        assert_eq!(result.dest, layout.package_dir(name, "1.0.0"));
+        assert_eq!(result.file_count, 4, "{name}");
+        assert_eq!(result.total_bytes, 100 + 20 + 3 + 4, "{name}");
+        assert_eq!(
+            measure_install_tree(&result.dest).expect("must measure install tree"),
+            (result.file_count, result.total_bytes)
+        );
+    }
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[cfg(unix)]
+#[test]
+fn move_dir_or_copy_falls_back_to_copy_only_across_devices() {
+    let layout = test_layout();
+    let src = layout.prefix().join("staged");
+    fs::create_dir_all(src.join("bin")).expect("must create staged dir");
+    fs::write(src.join("bin").join("demo"), b"demo").expect("must write staged file");
+
+    let dst = layout.prefix().join("pkgs").join("demo");
+    move_dir_or_copy_using(&src, &dst, |_, _| {
+        Err(std::io::Error::from(std::io::ErrorKind::CrossesDevices))
+    })
+    .expect("cross-device rename must fall back to copy");
+    assert_eq!(
+        fs::read(dst.join("bin").join("demo")).expect("must copy staged tree"),
+        b"demo"
+    );
+    assert!(!src.exists());
+
+    fs::create_dir_all(&src).expect("must recreate staged dir");
+    let blocked = layout.prefix().join("pkgs").join("blocked");
+    let err = move_dir_or_copy_using(&src, &blocked, |_, _| {
+        Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+    })
+    .expect_err("permission errors must not be masked by a copy");
     assert!(
-        err.to_string()
-            .contains("failed to stage MSIX artifact via deterministic extraction"),
+        err.to_string().contains("failed to move"),
         "unexpected error: {err}"
     );
+    assert_eq!(
+        err.downcast_ref::<std::io::Error>()
+            .map(std::io::Error::kind),
+        Some(std::io::ErrorKind::PermissionDenied)
+    );
+    assert!(src.exists());
+    assert!(!blocked.exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
-#[cfg(windows)]
 #[test]
-fn install_from_artifact_reports_appx_extraction_failure_on_windows_host() {
+fn install_from_artifact_copies_only_mapped_files() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.appx");
-    fs::write(&artifact_path, b"dummy appx").expect("must write artifact");
+    let fixture_root = layout.prefix().join("fixture");
+    let payload_dir = fixture_root.join("suite-1.0.0");
+    fs::create_dir_all(payload_dir.join("bin")).expect("must create bin dir");
+    fs::create_dir_all(payload_dir.join("share/doc")).expect("must create doc dir");
+    fs::write(payload_dir.join("bin/tool"), b"#!/bin/sh\necho tool\n").expect("must write tool");
+    fs::write(payload_dir.join("bin/other"), b"other").expect("must write other");
+    fs::write(payload_dir.join("bin/third"), b"third").expect("must write third");
+    fs::write(payload_dir.join("share/doc/README"), b"readme").expect("must write doc");
+    let artifact_path = layout.prefix().join("suite.tar.gz");
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&artifact_path)
+        .arg("-C")
+        .arg(&fixture_root)
+        .arg("suite-1.0.0")
+        .status()
+        .expect("must run tar");
+    assert!(status.success());
 
-    let err = install_from_artifact(
+    let options = |file_mappings| ArtifactInstallOptions {
+        strip_components: 1,
+        file_mappings,
+        ..ArtifactInstallOptions::default()
+    };
+    let mappings = [ArtifactFileMapping {
+        from: "bin/tool".to_string(),
+        to: "tool".to_string(),
+    }];
+    let result = install_from_artifact_with_result(
         &layout,
-        "demo",
+        "suite",
         "1.0.0",
         &artifact_path,
-        ArchiveType::Appx,
-        ArtifactInstallOptions {
-            strip_components: 0,
-            artifact_root: None,
-            install_mode: InstallMode::Native,
-            interaction_policy: InstallInteractionPolicy::default(),
-        },
+        ArchiveType::TarGz,
+        options(&mappings),
     )
-    .expect_err("appx staging should fail deterministic extraction on Windows host");
-    assert!(
-        err.to_string()
-            .contains("failed to stage APPX artifact via deterministic extraction"),
-        "unexpected error: {err}"
+    .expect("must install mapped file");
+
+    let installed = fs::read_dir(&result.dest)
+        .expect("must read package dir")
+        .map(|entry| entry.expect("must read entry").file_name())
+        .collect::<Vec<_>>();
+    assert_eq!(installed, vec![std::ffi::OsString::from("tool")]);
+    assert_eq!(
+        fs::read(result.dest.join("tool")).expect("must read tool"),
+        b"#!/bin/sh\necho tool\n"
     );
+    assert_eq!(result.file_count, 1);
+
+    let escaping = [ArtifactFileMapping {
+        from: "../bin/tool".to_string(),
+        to: "tool".to_string(),
+    }];
+    let err = install_from_artifact(
+        &layout,
+        "escape",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::TarGz,
+        options(&escaping),
+    )
+    .expect_err("parent traversal must be rejected");
+    assert!(err.to_string().contains("without '..'"), "{err}");
+    assert!(!layout.package_dir("escape", "1.0.0").exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(unix)]
 #[test]
-fn install_from_artifact_rejects_appimage_with_strip_components() {
+fn install_from_artifact_rejects_archive_with_too_many_entries() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.AppImage");
-    fs::write(&artifact_path, b"dummy appimage").expect("must write artifact");
+    let fixture_root = layout.prefix().join("fixture");
+    let payload_dir = fixture_root.join("demo-1.0.0");
+    fs::create_dir_all(&payload_dir).expect("must create payload dir");
+    for index in 0..32 {
+        fs::write(payload_dir.join(format!("file-{index}")), b"x").expect("must write entry");
+    }
+    let artifact_path = layout.prefix().join("demo.tar.gz");
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&artifact_path)
+        .arg("-C")
+        .arg(&fixture_root)
+        .arg("demo-1.0.0")
+        .status()
+        .expect("must run tar");
+    assert!(status.success());
 
     let err = install_from_artifact(
         &layout,
         "demo",
         "1.0.0",
         &artifact_path,
-        ArchiveType::AppImage,
+        ArchiveType::TarGz,
         ArtifactInstallOptions {
             strip_components: 1,
-            artifact_root: None,
-            install_mode: InstallMode::Managed,
-            interaction_policy: InstallInteractionPolicy::default(),
+            archive_limits: ArchiveLimits {
+                max_entries: 8,
+                ..ArchiveLimits::default()
+            },
+            ..ArtifactInstallOptions::default()
         },
     )
-    .expect_err("appimage strip_components should be rejected");
-    assert!(
-        err.to_string()
-            .contains("strip_components must be 0 for AppImage artifacts"),
-        "unexpected error: {err}"
+    .expect_err("entry count limit must abort extraction");
+    assert_eq!(
+        err.downcast_ref::<ArchiveLimitExceeded>(),
+        Some(&ArchiveLimitExceeded {
+            kind: ArchiveLimitKind::Entries,
+            limit: 8,
+        }),
+        "unexpected error: {err:#}"
+    );
+    assert!(!layout.package_dir("demo", "1.0.0").exists());
+    let leftover_tmp = fs::read_dir(layout.tmp_state_dir())
+        .expect("must read tmp dir")
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("install-"))
+        .count();
+    assert_eq!(
+        leftover_tmp, 0,
+        "partially extracted payload must be removed"
     );
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
-#[cfg(not(target_os = "linux"))]
+#[cfg(unix)]
 #[test]
-fn install_from_artifact_rejects_appimage_on_non_linux_host() {
+fn install_from_artifact_rejects_high_ratio_archive_over_size_limits() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.AppImage");
-    fs::write(&artifact_path, b"dummy appimage").expect("must write artifact");
+    let fixture_root = layout.prefix().join("fixture");
+    let payload_dir = fixture_root.join("demo-1.0.0");
+    fs::create_dir_all(&payload_dir).expect("must create payload dir");
+    fs::write(payload_dir.join("zeros"), vec![0u8; 4 * 1024 * 1024]).expect("must write zeros");
+    let artifact_path = layout.prefix().join("demo.tar.zst");
+    let tar_path = layout.prefix().join("demo.tar");
+    let status = Command::new("tar")
+        .arg("-cf")
+        .arg(&tar_path)
+        .arg("-C")
+        .arg(&fixture_root)
+        .arg("demo-1.0.0")
+        .status()
+        .expect("must run tar");
+    assert!(status.success());
+    let compressed = zstd::stream::encode_all(
+        fs::File::open(&tar_path).expect("must open tar fixture"),
+        19,
+    )
+    .expect("must compress fixture");
+    assert!(compressed.len() < 64 * 1024, "fixture must be high-ratio");
+    fs::write(&artifact_path, compressed).expect("must write zstd fixture");
+
+    for (limits, expected) in [
+        (
+            ArchiveLimits {
+                max_entry_bytes: 1024 * 1024,
+                ..ArchiveLimits::default()
+            },
+            ArchiveLimitExceeded {
+                kind: ArchiveLimitKind::EntryBytes,
+                limit: 1024 * 1024,
+            },
+        ),
+        (
+            ArchiveLimits {
+                max_total_bytes: 2 * 1024 * 1024,
+                ..ArchiveLimits::default()
+            },
+            ArchiveLimitExceeded {
+                kind: ArchiveLimitKind::TotalBytes,
+                limit: 2 * 1024 * 1024,
+            },
+        ),
+    ] {
+        let err = install_from_artifact(
+            &layout,
+            "demo",
+            "1.0.0",
+            &artifact_path,
+            ArchiveType::TarZst,
+            ArtifactInstallOptions {
+                strip_components: 1,
+                archive_limits: limits,
+                ..ArtifactInstallOptions::default()
+            },
+        )
+        .expect_err("size limit must abort extraction");
+        assert_eq!(
+            err.downcast_ref::<ArchiveLimitExceeded>(),
+            Some(&expected),
+            "unexpected error: {err:#}"
+        );
+        assert!(!layout.package_dir("demo", "1.0.0").exists());
+    }
 
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[cfg(unix)]
+#[test]
+fn install_from_artifact_streams_tar_checksum_and_cleans_up_on_mismatch() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let fixture_root = layout.prefix().join("fixture");
+    let payload_dir = fixture_root.join("demo-1.0.0").join("bin");
+    fs::create_dir_all(&payload_dir).expect("must create payload dir");
+    fs::write(payload_dir.join("demo"), vec![b'x'; 256 * 1024]).expect("must write payload");
+
+    let artifact_path = layout.prefix().join("demo.tar.gz");
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&artifact_path)
+        .arg("-C")
+        .arg(&fixture_root)
+        .arg("demo-1.0.0")
+        .status()
+        .expect("must run tar");
+    assert!(status.success());
+    let actual_sha256 =
+        crosspack_security::sha256_hex(&fs::read(&artifact_path).expect("must read artifact"));
+    let options = |expected_sha256| ArtifactInstallOptions {
+        strip_components: 1,
+        expected_sha256,
+        ..ArtifactInstallOptions::default()
+    };
+
+    let wrong_sha256 = "f".repeat(64);
     let err = install_from_artifact(
         &layout,
         "demo",
         "1.0.0",
         &artifact_path,
-        ArchiveType::AppImage,
-        ArtifactInstallOptions {
-            strip_components: 0,
-            artifact_root: None,
-            install_mode: InstallMode::Managed,
-            interaction_policy: InstallInteractionPolicy::default(),
-        },
+        ArchiveType::TarGz,
+        options(Some(wrong_sha256.as_str())),
     )
-    .expect_err("appimage installs should be rejected on non-Linux hosts");
+    .expect_err("digest mismatch must abort streamed extraction");
     assert!(
         err.to_string()
-            .contains("AppImage artifacts are supported only on Linux hosts"),
-        "unexpected error: {err}"
+            .contains(&format!("expected {wrong_sha256}, got {actual_sha256}")),
+        "unexpected error: {err:#}"
+    );
+    assert!(!layout.package_dir("demo", "1.0.0").exists());
+    let leftover_tmp = fs::read_dir(layout.tmp_state_dir())
+        .expect("must read tmp dir")
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("install-"))
+        .count();
+    assert_eq!(
+        leftover_tmp, 0,
+        "partially extracted payload must be removed"
+    );
+
+    let install_root = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::TarGz,
+        options(Some(actual_sha256.as_str())),
+    )
+    .expect("matching digest must install");
+    assert_eq!(
+        fs::read(install_root.join("bin").join("demo"))
+            .expect("must read installed payload")
+            .len(),
+        256 * 1024
     );
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
-#[cfg(target_os = "linux")]
+#[cfg(unix)]
+#[cfg(unix)]
 #[test]
-fn stage_appimage_copies_payload_into_raw_dir() {
+fn copy_with_strip_recreates_hardlinked_entries_as_hardlinks() {
+    use std::os::unix::fs::MetadataExt;
+
     let layout = test_layout();
-    layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.AppImage");
-    fs::write(&artifact_path, b"appimage payload").expect("must write artifact");
-    let raw_dir = layout.prefix().join("raw");
-    fs::create_dir_all(&raw_dir).expect("must create raw dir");
+    let staging = layout.prefix().join("staging");
+    let installed = layout.prefix().join("installed");
+    fs::create_dir_all(staging.join("tool-1.0.0/bin")).expect("must create bin dir");
+    fs::create_dir_all(staging.join("tool-1.0.0/libexec")).expect("must create libexec dir");
+    fs::write(staging.join("tool-1.0.0/bin/tool"), vec![b'x'; 4096]).expect("must write binary");
+    fs::hard_link(
+        staging.join("tool-1.0.0/bin/tool"),
+        staging.join("tool-1.0.0/libexec/tool-real"),
+    )
+    .expect("must hardlink binary");
+    fs::write(staging.join("tool-1.0.0/README"), b"docs").expect("must write readme");
 
-    stage_appimage_payload(&artifact_path, &raw_dir, 0, None).expect("must stage appimage payload");
+    copy_with_strip(
+        &staging,
+        &installed,
+        1,
+        None,
+        InstallPermissionsPolicy::default(),
+    )
+    .expect("must copy staging tree");
 
-    let staged = raw_dir.join("artifact.appimage");
-    assert!(staged.exists(), "staged payload should exist");
+    let first = fs::metadata(installed.join("bin/tool")).expect("must stat binary");
+    let second = fs::metadata(installed.join("libexec/tool-real")).expect("must stat link");
+    assert_eq!(first.ino(), second.ino());
+    assert_eq!(first.nlink(), 2);
     assert_eq!(
-        fs::read(&staged).expect("must read staged payload"),
-        b"appimage payload"
+        fs::metadata(installed.join("README"))
+            .expect("must stat readme")
+            .nlink(),
+        1
     );
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
-#[cfg(target_os = "linux")]
 #[test]
-fn stage_appimage_sets_executable_permissions_on_unix() {
-    use std::os::unix::fs::PermissionsExt;
-
+fn install_from_artifact_copies_only_named_artifact_root_subtree() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.AppImage");
-    fs::write(&artifact_path, b"appimage payload").expect("must write artifact");
-    let raw_dir = layout.prefix().join("raw");
-    fs::create_dir_all(&raw_dir).expect("must create raw dir");
+    let fixture_root = layout.prefix().join("fixture");
+    fs::create_dir_all(fixture_root.join("dist").join("bin")).expect("must create dist");
+    fs::create_dir_all(fixture_root.join("extras")).expect("must create extras");
+    fs::write(fixture_root.join("dist").join("bin").join("demo"), b"demo")
+        .expect("must write dist payload");
+    fs::write(fixture_root.join("extras").join("notes.txt"), b"notes")
+        .expect("must write extras payload");
+
+    let artifact_path = layout.prefix().join("demo.tar");
+    let status = Command::new("tar")
+        .arg("-cf")
+        .arg(&artifact_path)
+        .arg("-C")
+        .arg(&fixture_root)
+        .arg("dist")
+        .arg("extras")
+        .status()
+        .expect("must run tar");
+    assert!(status.success());
+    let options = |artifact_root| ArtifactInstallOptions {
+        artifact_root,
+        ..ArtifactInstallOptions::default()
+    };
 
-    stage_appimage_payload(&artifact_path, &raw_dir, 0, None).expect("must stage appimage payload");
+    let install_root = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::Tar,
+        options(Some("dist")),
+    )
+    .expect("must install dist subtree");
+    assert!(install_root.join("bin").join("demo").exists());
+    assert!(!install_root.join("dist").exists());
+    assert!(!install_root.join("extras").exists());
 
-    let mode = fs::metadata(raw_dir.join("artifact.appimage"))
-        .expect("must stat staged payload")
-        .permissions()
-        .mode()
-        & 0o777;
-    assert_eq!(mode, 0o755);
+    let err = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::Tar,
+        options(Some("../dist")),
+    )
+    .expect_err("escaping artifact_root must be rejected");
+    assert!(err.to_string().contains("must be a relative path"));
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(unix)]
 #[test]
-fn install_from_artifact_rejects_bin_with_strip_components() {
+fn install_from_artifact_preserves_non_utf8_entry_names() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.bin");
-    fs::write(&artifact_path, b"dummy bin").expect("must write artifact");
+    let fixture_root = layout.prefix().join("fixture");
+    let payload_dir = fixture_root.join("demo-1.0.0").join("share");
+    fs::create_dir_all(&payload_dir).expect("must create payload dir");
+    let raw_name = OsStr::from_bytes(b"caf\xe9-\xff.txt");
+    fs::write(payload_dir.join(raw_name), b"latin-1 name").expect("must write payload");
+
+    let artifact_path = layout.prefix().join("demo.tar");
+    let status = Command::new("tar")
+        .arg("-cf")
+        .arg(&artifact_path)
+        .arg("-C")
+        .arg(&fixture_root)
+        .arg("demo-1.0.0")
+        .status()
+        .expect("must run tar");
+    assert!(status.success());
 
-    let err = install_from_artifact(
+    let install_root = install_from_artifact(
         &layout,
         "demo",
         "1.0.0",
         &artifact_path,
-        ArchiveType::Bin,
+        ArchiveType::Tar,
         ArtifactInstallOptions {
             strip_components: 1,
-            artifact_root: None,
-            install_mode: InstallMode::Managed,
-            interaction_policy: InstallInteractionPolicy::default(),
+            ..ArtifactInstallOptions::default()
         },
     )
-    .expect_err("bin strip_components should be rejected");
-    assert!(
-        err.to_string()
-            .contains("strip_components must be 0 for bin artifacts"),
-        "unexpected error: {err}"
+    .expect("non-UTF-8 entry names must install");
+
+    let names = fs::read_dir(install_root.join("share"))
+        .expect("must read installed share dir")
+        .map(|entry| entry.expect("must read entry").file_name())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec![raw_name.to_os_string()]);
+    assert_eq!(
+        fs::read(install_root.join("share").join(raw_name)).expect("must read payload"),
+        b"latin-1 name"
     );
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(not(windows))]
 #[test]
-fn stage_bin_copies_payload_into_raw_dir() {
+fn install_from_artifact_rejects_msi_on_non_windows_host() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.bin");
-    fs::write(&artifact_path, b"bin payload").expect("must write artifact");
-    let raw_dir = layout.prefix().join("raw");
-    fs::create_dir_all(&raw_dir).expect("must create raw dir");
-
-    stage_bin_payload(&artifact_path, &raw_dir, 0, None).expect("must stage bin payload");
+    let artifact_path = layout.prefix().join("demo.msi");
+    fs::write(&artifact_path, b"dummy msi").expect("must write artifact");
 
-    let staged = raw_dir.join("demo.bin");
-    assert!(staged.exists(), "staged payload should exist");
-    assert_eq!(
-        fs::read(&staged).expect("must read staged payload"),
-        b"bin payload"
+    let err = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::Msi,
+        ArtifactInstallOptions {
+            install_mode: InstallMode::Native,
+            ..ArtifactInstallOptions::default()
+        },
+    )
+    .expect_err("msi should be rejected on non-Windows host");
+    assert!(
+        err.to_string()
+            .contains("MSI artifacts are supported only on Windows hosts"),
+        "unexpected error: {err}"
     );
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
-#[cfg(unix)]
+#[cfg(not(target_os = "macos"))]
 #[test]
-fn stage_bin_sets_executable_permissions_on_unix() {
-    use std::os::unix::fs::PermissionsExt;
-
+fn install_from_artifact_rejects_dmg_on_non_macos_host() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-    let artifact_path = layout.prefix().join("demo.bin");
-    fs::write(&artifact_path, b"bin payload").expect("must write artifact");
-    let raw_dir = layout.prefix().join("raw");
-    fs::create_dir_all(&raw_dir).expect("must create raw dir");
-
-    stage_bin_payload(&artifact_path, &raw_dir, 0, None).expect("must stage bin payload");
+    let artifact_path = layout.prefix().join("demo.dmg");
+    fs::write(&artifact_path, b"dummy dmg").expect("must write artifact");
 
-    let mode = fs::metadata(raw_dir.join("demo.bin"))
-        .expect("must stat staged payload")
-        .permissions()
-        .mode()
-        & 0o777;
-    assert_eq!(mode, 0o755);
+    let err = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::Dmg,
+        ArtifactInstallOptions::default(),
+    )
+    .expect_err("dmg should be rejected on non-macOS host");
+    assert!(
+        err.to_string()
+            .contains("DMG artifacts are supported only on macOS hosts"),
+        "unexpected error: {err}"
+    );
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(not(windows))]
 #[test]
-fn stage_msi_builds_admin_extract_command() {
-    let artifact_path = Path::new("/tmp/demo.msi");
-    let raw_dir = Path::new("/tmp/raw");
-    let command = build_msi_admin_extract_command(artifact_path, raw_dir);
+fn install_from_artifact_rejects_exe_on_non_windows_host() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let artifact_path = layout.prefix().join("demo.exe");
+    fs::write(&artifact_path, b"dummy exe").expect("must write artifact");
 
-    assert_eq!(command.get_program(), "msiexec");
-    let args = command
-        .get_args()
-        .map(|arg| arg.to_string_lossy().into_owned())
-        .collect::<Vec<_>>();
-    assert_eq!(
-        args,
-        vec![
-            "/a".to_string(),
-            artifact_path.display().to_string(),
-            "/qn".to_string(),
-            format!("TARGETDIR={}", raw_dir.display())
-        ]
+    let err = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::Exe,
+        ArtifactInstallOptions {
+            install_mode: InstallMode::Native,
+            ..ArtifactInstallOptions::default()
+        },
+    )
+    .expect_err("exe should be rejected on non-Windows host");
+    assert!(
+        err.to_string()
+            .contains("EXE artifacts are supported only on Windows hosts"),
+        "unexpected error: {err}"
     );
+
+    let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(not(target_os = "macos"))]
 #[test]
-fn stage_exe_builds_extract_command_shape() {
-    let artifact_path = Path::new("C:/tmp/demo.exe");
-    let raw_dir = Path::new("C:/tmp/raw");
-    let command = build_exe_extract_command(artifact_path, raw_dir);
+fn install_from_artifact_rejects_pkg_on_non_macos_host() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let artifact_path = layout.prefix().join("demo.pkg");
+    fs::write(&artifact_path, b"dummy pkg").expect("must write artifact");
 
-    assert_eq!(command.get_program(), "7z");
-    let args = command
-        .get_args()
-        .map(|arg| arg.to_string_lossy().into_owned())
-        .collect::<Vec<_>>();
-    assert_eq!(
-        args,
-        vec![
-            "x".to_string(),
-            artifact_path.display().to_string(),
-            format!("-o{}", raw_dir.display()),
-            "-y".to_string(),
-        ]
+    let err = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::Pkg,
+        ArtifactInstallOptions {
+            install_mode: InstallMode::Native,
+            ..ArtifactInstallOptions::default()
+        },
+    )
+    .expect_err("pkg should be rejected on non-macOS host");
+    assert!(
+        err.to_string()
+            .contains("PKG artifacts are supported only on macOS hosts"),
+        "unexpected error: {err}"
     );
+
+    let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn stage_msix_builds_unpack_command_shape() {
-    let artifact_path = Path::new("C:/tmp/demo.msix");
-    let raw_dir = Path::new("C:/tmp/raw-msix");
-    let command = build_msix_unpack_command(artifact_path, raw_dir);
-
-    assert_eq!(command.get_program(), "makeappx");
-    let args = command
-        .get_args()
-        .map(|arg| arg.to_string_lossy().into_owned())
-        .collect::<Vec<_>>();
-    assert_eq!(
-        args,
-        vec![
-            "unpack".to_string(),
-            "/p".to_string(),
-            artifact_path.display().to_string(),
-            "/d".to_string(),
-            raw_dir.display().to_string(),
-            "/o".to_string(),
-        ]
+fn installer_dispatch_does_not_support_deb_or_rpm_archive_types() {
+    assert!(
+        ArchiveType::parse("deb").is_none(),
+        "DEB should be unsupported in installer dispatch"
+    );
+    assert!(
+        ArchiveType::parse("rpm").is_none(),
+        "RPM should be unsupported in installer dispatch"
+    );
+    assert!(
+        ArchiveType::infer_from_url("https://example.test/demo.deb").is_none(),
+        "DEB URL inference should be unsupported in installer dispatch"
+    );
+    assert!(
+        ArchiveType::infer_from_url("https://example.test/demo.rpm").is_none(),
+        "RPM URL inference should be unsupported in installer dispatch"
     );
 }
 
+#[cfg(not(windows))]
 #[test]
-fn stage_appx_builds_unpack_command_shape() {
-    let artifact_path = Path::new("C:/tmp/demo.appx");
-    let raw_dir = Path::new("C:/tmp/raw-appx");
-    let command = build_appx_unpack_command(artifact_path, raw_dir);
+fn install_from_artifact_rejects_msix_on_non_windows_host() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let artifact_path = layout.prefix().join("demo.msix");
+    fs::write(&artifact_path, b"dummy msix").expect("must write artifact");
 
-    assert_eq!(command.get_program(), "makeappx");
-    let args = command
-        .get_args()
-        .map(|arg| arg.to_string_lossy().into_owned())
-        .collect::<Vec<_>>();
-    assert_eq!(
-        args,
-        vec![
-            "unpack".to_string(),
-            "/p".to_string(),
-            artifact_path.display().to_string(),
-            "/d".to_string(),
-            raw_dir.display().to_string(),
-            "/o".to_string(),
-        ]
+    let err = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::Msix,
+        ArtifactInstallOptions {
+            install_mode: InstallMode::Native,
+            ..ArtifactInstallOptions::default()
+        },
+    )
+    .expect_err("msix should be rejected on non-Windows host");
+    assert!(
+        err.to_string()
+            .contains("MSIX artifacts are supported only on Windows hosts"),
+        "unexpected error: {err}"
     );
+
+    let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(not(windows))]
 #[test]
-fn stage_msix_payload_with_runner_invokes_expected_command_context() {
-    let artifact_path = Path::new("C:/tmp/demo.msix");
-    let raw_dir = Path::new("C:/tmp/raw-msix");
-    let mut observed_program = String::new();
-    let mut observed_args = Vec::new();
-    let mut observed_context = String::new();
-
-    stage_msix_payload_with_runner(artifact_path, raw_dir, |command, context| {
-        observed_program = command.get_program().to_string_lossy().into_owned();
-        observed_args = command
-            .get_args()
-            .map(|arg| arg.to_string_lossy().into_owned())
-            .collect::<Vec<_>>();
-        observed_context = context.to_string();
-        Ok(())
-    })
-    .expect("runner should succeed");
+fn install_from_artifact_rejects_appx_on_non_windows_host() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let artifact_path = layout.prefix().join("demo.appx");
+    fs::write(&artifact_path, b"dummy appx").expect("must write artifact");
 
-    assert_eq!(
-        observed_context,
-        "failed to stage MSIX artifact via deterministic extraction"
-    );
-    assert_eq!(observed_program, "makeappx");
-    assert_eq!(
-        observed_args,
-        vec![
-            "unpack".to_string(),
-            "/p".to_string(),
-            artifact_path.display().to_string(),
-            "/d".to_string(),
-            raw_dir.display().to_string(),
-            "/o".to_string(),
-        ]
+    let err = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::Appx,
+        ArtifactInstallOptions {
+            install_mode: InstallMode::Native,
+            ..ArtifactInstallOptions::default()
+        },
+    )
+    .expect_err("appx should be rejected on non-Windows host");
+    assert!(
+        err.to_string()
+            .contains("APPX artifacts are supported only on Windows hosts"),
+        "unexpected error: {err}"
     );
+
+    let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(windows)]
 #[test]
-fn stage_appx_payload_with_runner_invokes_expected_command_context() {
-    let artifact_path = Path::new("C:/tmp/demo.appx");
-    let raw_dir = Path::new("C:/tmp/raw-appx");
-    let mut observed_program = String::new();
-    let mut observed_args = Vec::new();
-    let mut observed_context = String::new();
-
-    stage_appx_payload_with_runner(artifact_path, raw_dir, |command, context| {
-        observed_program = command.get_program().to_string_lossy().into_owned();
-        observed_args = command
-            .get_args()
-            .map(|arg| arg.to_string_lossy().into_owned())
-            .collect::<Vec<_>>();
-        observed_context = context.to_string();
-        Ok(())
-    })
-    .expect("runner should succeed");
+fn install_from_artifact_reports_exe_extraction_failure_on_windows_host() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let artifact_path = layout.prefix().join("demo.exe");
+    fs::write(&artifact_path, b"dummy exe").expect("must write artifact");
 
-    assert_eq!(
-        observed_context,
-        "failed to stage APPX artifact via deterministic extraction"
-    );
-    assert_eq!(observed_program, "makeappx");
-    assert_eq!(
-        observed_args,
-        vec![
-            "unpack".to_string(),
-            "/p".to_string(),
-            artifact_path.display().to_string(),
-            "/d".to_string(),
-            raw_dir.display().to_string(),
-            "/o".to_string(),
-        ]
+    let err = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::Exe,
+        ArtifactInstallOptions {
+            install_mode: InstallMode::Native,
+            ..ArtifactInstallOptions::default()
+        },
+    )
+    .expect_err("exe staging should fail deterministic extraction on Windows host");
+    assert!(
+        err.to_string()
+            .contains("failed to stage EXE artifact via deterministic extraction"),
+        "unexpected error: {err}"
     );
+
+    let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(target_os = "macos")]
 #[test]
-fn stage_exe_uses_extract_tool_not_installer_execution() {
-    let artifact_path = Path::new("C:/tmp/app.exe");
-    let raw_dir = Path::new("C:/tmp/raw");
-    let command = build_exe_extract_command(artifact_path, raw_dir);
+fn install_from_artifact_reports_pkg_extraction_failure_on_macos_host() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let artifact_path = layout.prefix().join("demo.pkg");
+    fs::write(&artifact_path, b"dummy pkg").expect("must write artifact");
 
-    assert_ne!(command.get_program(), artifact_path.as_os_str());
+    let err = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::Pkg,
+        ArtifactInstallOptions {
+            install_mode: InstallMode::Native,
+            ..ArtifactInstallOptions::default()
+        },
+    )
+    .expect_err("pkg staging should fail deterministic extraction on macOS host");
+    assert!(
+        err.to_string()
+            .contains("failed to stage PKG artifact via deterministic extraction"),
+        "unexpected error: {err}"
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(windows)]
 #[test]
-fn stage_exe_payload_with_runner_invokes_expected_command_context() {
-    let artifact_path = Path::new("C:/tmp/demo.exe");
-    let raw_dir = Path::new("C:/tmp/raw");
-    let mut observed_program = String::new();
-    let mut observed_args = Vec::new();
-    let mut observed_context = String::new();
-
-    stage_exe_payload_with_runner(artifact_path, raw_dir, |command, context| {
-        observed_program = command.get_program().to_string_lossy().into_owned();
-        observed_args = command
-            .get_args()
-            .map(|arg| arg.to_string_lossy().into_owned())
-            .collect::<Vec<_>>();
-        observed_context = context.to_string();
-        Ok(())
-    })
-    .expect("runner should succeed");
+fn install_from_artifact_reports_msix_extraction_failure_on_windows_host() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let artifact_path = layout.prefix().join("demo.msix");
+    fs::write(&artifact_path, b"dummy msix").expect("must write artifact");
 
-    assert_eq!(
-        observed_context,
-        "failed to stage EXE artifact via deterministic extraction"
-    );
-    assert_eq!(observed_program, "7z");
-    assert_eq!(
-        observed_args,
-        vec![
-            "x".to_string(),
-            artifact_path.display().to_string(),
-            format!("-o{}", raw_dir.display()),
-            "-y".to_string(),
-        ]
+    let err = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::Msix,
+        ArtifactInstallOptions {
+            install_mode: InstallMode::Native,
+            ..ArtifactInstallOptions::default()
+        },
+    )
+    .expect_err("msix staging should fail deterministic extraction on Windows host");
+    assert!(
+        err.to_string()
+            .contains("failed to stage MSIX artifact via deterministic extraction"),
+        "unexpected error: {err}"
     );
+
+    let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(windows)]
 #[test]
-fn stage_exe_returns_actionable_error_when_extraction_fails() {
-    let artifact_path = Path::new("C:/tmp/demo.exe");
-    let raw_dir = Path::new("C:/tmp/raw");
-    let err = stage_exe_payload_with_runner(artifact_path, raw_dir, |_command, _context| {
-        Err(anyhow!(io::Error::new(
-            io::ErrorKind::NotFound,
-            "simulated missing 7z"
-        )))
-    })
-    .expect_err("missing extraction tool should be surfaced with guidance");
+fn install_from_artifact_reports_appx_extraction_failure_on_windows_host() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let artifact_path = layout.prefix().join("demo.appx");
+    fs::write(&artifact_path, b"dummy appx").expect("must write artifact");
 
-    let message = err.to_string();
-    assert!(
-        message.contains("failed to stage EXE artifact via deterministic extraction"),
-        "unexpected error: {message}"
-    );
+    let err = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::Appx,
+        ArtifactInstallOptions {
+            install_mode: InstallMode::Native,
+            ..ArtifactInstallOptions::default()
+        },
+    )
+    .expect_err("appx staging should fail deterministic extraction on Windows host");
     assert!(
-        message.contains("required extraction tool '7z' was not found on PATH"),
-        "unexpected error: {message}"
+        err.to_string()
+            .contains("failed to stage APPX artifact via deterministic extraction"),
+        "unexpected error: {err}"
     );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn install_from_artifact_rejects_appimage_with_strip_components() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let artifact_path = layout.prefix().join("demo.AppImage");
+    fs::write(&artifact_path, b"dummy appimage").expect("must write artifact");
+
+    let err = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::AppImage,
+        ArtifactInstallOptions {
+            strip_components: 1,
+            ..ArtifactInstallOptions::default()
+        },
+    )
+    .expect_err("appimage strip_components should be rejected");
     assert!(
-        message.contains("install 7-Zip CLI and ensure '7z' is available, then retry"),
-        "unexpected error: {message}"
+        err.to_string()
+            .contains("strip_components must be 0 for AppImage artifacts"),
+        "unexpected error: {err}"
     );
+
+    let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(not(target_os = "linux"))]
 #[test]
-fn stage_pkg_builds_expand_command_shape() {
-    let artifact_path = Path::new("/tmp/demo.pkg");
-    let expanded_dir = Path::new("/tmp/pkg-expanded");
-    let command = build_pkg_expand_command(artifact_path, expanded_dir);
+fn install_from_artifact_rejects_appimage_on_non_linux_host() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let artifact_path = layout.prefix().join("demo.AppImage");
+    fs::write(&artifact_path, b"dummy appimage").expect("must write artifact");
 
-    assert_eq!(command.get_program(), "pkgutil");
-    let args = command
-        .get_args()
-        .map(|arg| arg.to_string_lossy().into_owned())
-        .collect::<Vec<_>>();
-    assert_eq!(
-        args,
-        vec![
-            "--expand-full".to_string(),
-            artifact_path.display().to_string(),
-            expanded_dir.display().to_string(),
-        ]
+    let err = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::AppImage,
+        ArtifactInstallOptions::default(),
+    )
+    .expect_err("appimage installs should be rejected on non-Linux hosts");
+    assert!(
+        err.to_string()
+            .contains("AppImage artifacts are supported only on Linux hosts"),
+        "unexpected error: {err}"
     );
+
+    let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(target_os = "linux")]
 #[test]
-fn stage_pkg_copy_and_cleanup_command_shapes_are_stable() {
-    let expanded_raw_dir = Path::new("/tmp/pkg-expanded/Payload");
-    let raw_dir = Path::new("/tmp/raw");
+fn stage_appimage_copies_payload_into_raw_dir() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let artifact_path = layout.prefix().join("demo.AppImage");
+    fs::write(&artifact_path, b"appimage payload").expect("must write artifact");
+    let raw_dir = layout.prefix().join("raw");
+    fs::create_dir_all(&raw_dir).expect("must create raw dir");
 
-    let copy = build_pkg_copy_command(expanded_raw_dir, raw_dir);
-    assert_eq!(copy.get_program(), "ditto");
-    let copy_args = copy
-        .get_args()
-        .map(|arg| arg.to_string_lossy().into_owned())
-        .collect::<Vec<_>>();
+    stage_appimage_payload(&artifact_path, &raw_dir, 0, None).expect("must stage appimage payload");
+
+    let staged = raw_dir.join("artifact.appimage");
+    assert!(staged.exists(), "staged payload should exist");
     assert_eq!(
-        copy_args,
-        vec![
-            expanded_raw_dir.display().to_string(),
-            raw_dir.display().to_string(),
-        ]
+        fs::read(&staged).expect("must read staged payload"),
+        b"appimage payload"
     );
+
+    let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(target_os = "linux")]
 #[test]
-fn stage_pkg_orchestrates_expand_then_copy_then_cleanup() {
+fn stage_appimage_sets_executable_permissions_on_unix() {
+    use std::os::unix::fs::PermissionsExt;
+
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-
-    let artifact_path = layout.prefix().join("demo.pkg");
-    fs::write(&artifact_path, b"pkg").expect("must create artifact");
+    let artifact_path = layout.prefix().join("demo.AppImage");
+    fs::write(&artifact_path, b"appimage payload").expect("must write artifact");
     let raw_dir = layout.prefix().join("raw");
-    let expanded_dir = layout.prefix().join("pkg-expanded");
-    let mut command_invocations = Vec::new();
+    fs::create_dir_all(&raw_dir).expect("must create raw dir");
+
+    stage_appimage_payload(&artifact_path, &raw_dir, 0, None).expect("must stage appimage payload");
+
+    let mode = fs::metadata(raw_dir.join("artifact.appimage"))
+        .expect("must stat staged payload")
+        .permissions()
+        .mode()
+        & 0o777;
+    assert_eq!(mode, 0o755);
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[cfg(unix)]
+#[test]
+fn extract_tar_zst_supports_long_mode_windows_beyond_default_decoder_limit() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let fixture_root = layout.prefix().join("fixture");
+    fs::create_dir_all(fixture_root.join("tool")).expect("must create fixture dir");
+    fs::write(
+        fixture_root.join("tool").join("payload.txt"),
+        b"long mode payload\n",
+    )
+    .expect("must write fixture payload");
+    let tar_path = layout.prefix().join("fixture.tar");
+    let status = Command::new("tar")
+        .arg("-cf")
+        .arg(&tar_path)
+        .arg("-C")
+        .arg(&fixture_root)
+        .arg("tool")
+        .status()
+        .expect("must run tar");
+    assert!(status.success(), "tar must create fixture archive");
+
+    let archive_path = layout.prefix().join("fixture.tar.zst");
+    let mut encoder = zstd::stream::write::Encoder::new(
+        fs::File::create(&archive_path).expect("must create zstd fixture"),
+        3,
+    )
+    .expect("must create zstd encoder");
+    encoder
+        .long_distance_matching(true)
+        .expect("must enable long distance matching");
+    encoder.window_log(28).expect("must set long window");
+    io::copy(
+        &mut fs::File::open(&tar_path).expect("must open tar fixture"),
+        &mut encoder,
+    )
+    .expect("must compress fixture");
+    encoder.finish().expect("must finish zstd frame");
+
+    let default_decode =
+        zstd::stream::decode_all(fs::File::open(&archive_path).expect("must open zstd fixture"));
+    assert!(
+        default_decode.is_err(),
+        "default decoder window must reject long-mode fixture"
+    );
+
+    let raw_dir = layout.prefix().join("raw");
+    fs::create_dir_all(&raw_dir).expect("must create raw dir");
+    extract_tar_archive(
+        &archive_path,
+        &raw_dir,
+        ArchiveType::TarZst,
+        ArchiveLimits::default(),
+    )
+    .expect("must extract long-mode archive");
+    assert_eq!(
+        fs::read_to_string(raw_dir.join("tool").join("payload.txt"))
+            .expect("must read extracted payload"),
+        "long mode payload\n"
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[cfg(unix)]
+#[test]
+fn extract_tar_bz2_and_tar_lz4_fixtures() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let fixture_root = layout.prefix().join("fixture");
+    fs::create_dir_all(fixture_root.join("tool")).expect("must create fixture dir");
+    fs::write(fixture_root.join("tool").join("payload.txt"), b"payload\n")
+        .expect("must write fixture payload");
+    let tar_path = layout.prefix().join("fixture.tar");
+    let status = Command::new("tar")
+        .arg("-cf")
+        .arg(&tar_path)
+        .arg("-C")
+        .arg(&fixture_root)
+        .arg("tool")
+        .status()
+        .expect("must run tar");
+    assert!(status.success(), "tar must create fixture archive");
+
+    let bz2_path = layout.prefix().join("fixture.tar.bz2");
+    let mut bz2 = bzip2::write::BzEncoder::new(
+        fs::File::create(&bz2_path).expect("must create bzip2 fixture"),
+        bzip2::Compression::default(),
+    );
+    io::copy(
+        &mut fs::File::open(&tar_path).expect("must open tar fixture"),
+        &mut bz2,
+    )
+    .expect("must compress bzip2 fixture");
+    bz2.finish().expect("must finish bzip2 stream");
+
+    let lz4_path = layout.prefix().join("fixture.tar.lz4");
+    let mut lz4 = lz4::EncoderBuilder::new()
+        .build(fs::File::create(&lz4_path).expect("must create lz4 fixture"))
+        .expect("must create lz4 encoder");
+    io::copy(
+        &mut fs::File::open(&tar_path).expect("must open tar fixture"),
+        &mut lz4,
+    )
+    .expect("must compress lz4 fixture");
+    let (_, finished) = lz4.finish();
+    finished.expect("must finish lz4 frame");
+
+    for (archive_path, archive_type) in [
+        (&bz2_path, ArchiveType::TarBz2),
+        (&lz4_path, ArchiveType::TarLz4),
+    ] {
+        assert_eq!(
+            ArchiveType::infer_from_url(&archive_path.to_string_lossy()),
+            Some(archive_type)
+        );
+        let raw_dir = layout
+            .prefix()
+            .join(format!("raw-{}", archive_type.as_str()));
+        fs::create_dir_all(&raw_dir).expect("must create raw dir");
+        extract_tar_archive(
+            archive_path,
+            &raw_dir,
+            archive_type,
+            ArchiveLimits::default(),
+        )
+        .unwrap_or_else(|err| panic!("must extract {}: {err:#}", archive_type.as_str()));
+        assert_eq!(
+            fs::read_to_string(raw_dir.join("tool").join("payload.txt"))
+                .expect("must read extracted payload"),
+            "payload\n"
+        );
+    }
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn install_from_artifact_applies_permissions_policy_to_world_writable_entries() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let payload_root = layout.prefix().join("payload");
+    let payload_dir = payload_root.join("demo-1.0.0").join("share");
+    fs::create_dir_all(&payload_dir).expect("must create payload dir");
+    let payload_file = payload_dir.join("data.txt");
+    fs::write(&payload_file, b"shared").expect("must write payload file");
+    fs::set_permissions(&payload_file, fs::Permissions::from_mode(0o666))
+        .expect("must make payload file world-writable");
+    fs::set_permissions(&payload_dir, fs::Permissions::from_mode(0o777))
+        .expect("must make payload dir world-writable");
+    std::os::unix::fs::symlink("data.txt", payload_dir.join("data-link.txt"))
+        .expect("must create payload symlink");
+
+    let artifact_path = layout.prefix().join("demo.tar.gz");
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&artifact_path)
+        .arg("-C")
+        .arg(&payload_root)
+        .arg("demo-1.0.0")
+        .status()
+        .expect("must run tar");
+    assert!(status.success(), "tar must create test archive");
+
+    let installed = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::TarGz,
+        ArtifactInstallOptions {
+            strip_components: 1,
+            permissions_policy: InstallPermissionsPolicy::shared_prefix(),
+            ..ArtifactInstallOptions::default()
+        },
+    )
+    .expect("must install artifact");
+
+    let file_mode = fs::metadata(installed.join("share").join("data.txt"))
+        .expect("must stat installed file")
+        .permissions()
+        .mode()
+        & 0o777;
+    assert_eq!(file_mode, 0o644);
+    let dir_mode = fs::metadata(installed.join("share"))
+        .expect("must stat installed dir")
+        .permissions()
+        .mode()
+        & 0o777;
+    assert_eq!(dir_mode, 0o755);
+    assert!(
+        fs::symlink_metadata(installed.join("share").join("data-link.txt"))
+            .expect("must stat installed symlink")
+            .file_type()
+            .is_symlink()
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn install_from_artifact_rejects_bin_with_strip_components() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let artifact_path = layout.prefix().join("demo.bin");
+    fs::write(&artifact_path, b"dummy bin").expect("must write artifact");
+
+    let err = install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &artifact_path,
+        ArchiveType::Bin,
+        ArtifactInstallOptions {
+            strip_components: 1,
+            ..ArtifactInstallOptions::default()
+        },
+    )
+    .expect_err("bin strip_components should be rejected");
+    assert!(
+        err.to_string()
+            .contains("strip_components must be 0 for bin artifacts"),
+        "unexpected error: {err}"
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn stage_bin_copies_payload_into_raw_dir() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let artifact_path = layout.prefix().join("demo.bin");
+    fs::write(&artifact_path, b"bin payload").expect("must write artifact");
+    let raw_dir = layout.prefix().join("raw");
+    fs::create_dir_all(&raw_dir).expect("must create raw dir");
+
+    stage_bin_payload(&artifact_path, &raw_dir, 0, None).expect("must stage bin payload");
+
+    let staged = raw_dir.join("demo.bin");
+    assert!(staged.exists(), "staged payload should exist");
+    assert_eq!(
+        fs::read(&staged).expect("must read staged payload"),
+        b"bin payload"
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[cfg(unix)]
+#[test]
+fn stage_bin_sets_executable_permissions_on_unix() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let artifact_path = layout.prefix().join("demo.bin");
+    fs::write(&artifact_path, b"bin payload").expect("must write artifact");
+    let raw_dir = layout.prefix().join("raw");
+    fs::create_dir_all(&raw_dir).expect("must create raw dir");
+
+    stage_bin_payload(&artifact_path, &raw_dir, 0, None).expect("must stage bin payload");
+
+    let mode = fs::metadata(raw_dir.join("demo.bin"))
+        .expect("must stat staged payload")
+        .permissions()
+        .mode()
+        & 0o777;
+    assert_eq!(mode, 0o755);
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn stage_msi_builds_admin_extract_command() {
+    let artifact_path = Path::new("/tmp/demo.msi");
+    let raw_dir = Path::new("/tmp/raw");
+    let command = build_msi_admin_extract_command(artifact_path, raw_dir);
+
+    assert_eq!(command.get_program(), "msiexec");
+    let args = command
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        args,
+        vec![
+            "/a".to_string(),
+            artifact_path.display().to_string(),
+            "/qn".to_string(),
+            format!("TARGETDIR={}", raw_dir.display())
+        ]
+    );
+}
+
+#[test]
+fn stage_exe_builds_extract_command_shape() {
+    let artifact_path = Path::new("C:/tmp/demo.exe");
+    let raw_dir = Path::new("C:/tmp/raw");
+    let command = build_exe_extract_command(artifact_path, raw_dir);
+
+    assert_eq!(command.get_program(), "7z");
+    let args = command
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        args,
+        vec![
+            "x".to_string(),
+            artifact_path.display().to_string(),
+            format!("-o{}", raw_dir.display()),
+            "-y".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn stage_msix_builds_unpack_command_shape() {
+    let artifact_path = Path::new("C:/tmp/demo.msix");
+    let raw_dir = Path::new("C:/tmp/raw-msix");
+    let command = build_msix_unpack_command(artifact_path, raw_dir);
+
+    assert_eq!(command.get_program(), "makeappx");
+    let args = command
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        args,
+        vec![
+            "unpack".to_string(),
+            "/p".to_string(),
+            artifact_path.display().to_string(),
+            "/d".to_string(),
+            raw_dir.display().to_string(),
+            "/o".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn stage_appx_builds_unpack_command_shape() {
+    let artifact_path = Path::new("C:/tmp/demo.appx");
+    let raw_dir = Path::new("C:/tmp/raw-appx");
+    let command = build_appx_unpack_command(artifact_path, raw_dir);
+
+    assert_eq!(command.get_program(), "makeappx");
+    let args = command
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        args,
+        vec![
+            "unpack".to_string(),
+            "/p".to_string(),
+            artifact_path.display().to_string(),
+            "/d".to_string(),
+            raw_dir.display().to_string(),
+            "/o".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn stage_msix_payload_with_runner_invokes_expected_command_context() {
+    let artifact_path = Path::new("C:/tmp/demo.msix");
+    let raw_dir = Path::new("C:/tmp/raw-msix");
+    let mut observed_program = String::new();
+    let mut observed_args = Vec::new();
+    let mut observed_context = String::new();
+
+    stage_msix_payload_with_runner(artifact_path, raw_dir, |command, context| {
+        observed_program = command.get_program().to_string_lossy().into_owned();
+        observed_args = command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        observed_context = context.to_string();
+        Ok(())
+    })
+    .expect("runner should succeed");
+
+    assert_eq!(
+        observed_context,
+        "failed to stage MSIX artifact via deterministic extraction"
+    );
+    assert_eq!(observed_program, "makeappx");
+    assert_eq!(
+        observed_args,
+        vec![
+            "unpack".to_string(),
+            "/p".to_string(),
+            artifact_path.display().to_string(),
+            "/d".to_string(),
+            raw_dir.display().to_string(),
+            "/o".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn stage_appx_payload_with_runner_invokes_expected_command_context() {
+    let artifact_path = Path::new("C:/tmp/demo.appx");
+    let raw_dir = Path::new("C:/tmp/raw-appx");
+    let mut observed_program = String::new();
+    let mut observed_args = Vec::new();
+    let mut observed_context = String::new();
+
+    stage_appx_payload_with_runner(artifact_path, raw_dir, |command, context| {
+        observed_program = command.get_program().to_string_lossy().into_owned();
+        observed_args = command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        observed_context = context.to_string();
+        Ok(())
+    })
+    .expect("runner should succeed");
+
+    assert_eq!(
+        observed_context,
+        "failed to stage APPX artifact via deterministic extraction"
+    );
+    assert_eq!(observed_program, "makeappx");
+    assert_eq!(
+        observed_args,
+        vec![
+            "unpack".to_string(),
+            "/p".to_string(),
+            artifact_path.display().to_string(),
+            "/d".to_string(),
+            raw_dir.display().to_string(),
+            "/o".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn stage_exe_uses_extract_tool_not_installer_execution() {
+    let artifact_path = Path::new("C:/tmp/app.exe");
+    let raw_dir = Path::new("C:/tmp/raw");
+    let command = build_exe_extract_command(artifact_path, raw_dir);
+
+    assert_ne!(command.get_program(), artifact_path.as_os_str());
+}
+
+#[test]
+fn stage_exe_payload_with_runner_invokes_expected_command_context() {
+    let artifact_path = Path::new("C:/tmp/demo.exe");
+    let raw_dir = Path::new("C:/tmp/raw");
+    let mut observed_program = String::new();
+    let mut observed_args = Vec::new();
+    let mut observed_context = String::new();
+
+    stage_exe_payload_with_runner(artifact_path, raw_dir, |command, context| {
+        observed_program = command.get_program().to_string_lossy().into_owned();
+        observed_args = command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        observed_context = context.to_string();
+        Ok(())
+    })
+    .expect("runner should succeed");
+
+    assert_eq!(
+        observed_context,
+        "failed to stage EXE artifact via deterministic extraction"
+    );
+    assert_eq!(observed_program, "7z");
+    assert_eq!(
+        observed_args,
+        vec![
+            "x".to_string(),
+            artifact_path.display().to_string(),
+            format!("-o{}", raw_dir.display()),
+            "-y".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn stage_exe_returns_actionable_error_when_extraction_fails() {
+    let artifact_path = Path::new("C:/tmp/demo.exe");
+    let raw_dir = Path::new("C:/tmp/raw");
+    let err = stage_exe_payload_with_runner(artifact_path, raw_dir, |_command, _context| {
+        Err(anyhow!(io::Error::new(
+            io::ErrorKind::NotFound,
+            "simulated missing 7z"
+        )))
+    })
+    .expect_err("missing extraction tool should be surfaced with guidance");
+
+    let message = err.to_string();
+    assert!(
+        message.contains("failed to stage EXE artifact via deterministic extraction"),
+        "unexpected error: {message}"
+    );
+    assert!(
+        message.contains("required extraction tool '7z' was not found on PATH"),
+        "unexpected error: {message}"
+    );
+    assert!(
+        message.contains("install 7-Zip CLI and ensure '7z' is available, then retry"),
+        "unexpected error: {message}"
+    );
+}
+
+#[test]
+fn stage_pkg_builds_expand_command_shape() {
+    let artifact_path = Path::new("/tmp/demo.pkg");
+    let expanded_dir = Path::new("/tmp/pkg-expanded");
+    let command = build_pkg_expand_command(artifact_path, expanded_dir);
+
+    assert_eq!(command.get_program(), "pkgutil");
+    let args = command
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        args,
+        vec![
+            "--expand-full".to_string(),
+            artifact_path.display().to_string(),
+            expanded_dir.display().to_string(),
+        ]
+    );
+}
+
+#[test]
+fn stage_pkg_copy_and_cleanup_command_shapes_are_stable() {
+    let expanded_raw_dir = Path::new("/tmp/pkg-expanded/Payload");
+    let raw_dir = Path::new("/tmp/raw");
+
+    let copy = build_pkg_copy_command(expanded_raw_dir, raw_dir);
+    assert_eq!(copy.get_program(), "ditto");
+    let copy_args = copy
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        copy_args,
+        vec![
+            expanded_raw_dir.display().to_string(),
+            raw_dir.display().to_string(),
+        ]
+    );
+}
+
+#[test]
+fn stage_pkg_orchestrates_expand_then_copy_then_cleanup() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let artifact_path = layout.prefix().join("demo.pkg");
+    fs::write(&artifact_path, b"pkg").expect("must create artifact");
+    let raw_dir = layout.prefix().join("raw");
+    let expanded_dir = layout.prefix().join("pkg-expanded");
+    let mut command_invocations = Vec::new();
+
+    stage_pkg_payload_with_hooks(&artifact_path, &raw_dir, &expanded_dir, |command, _| {
+        let mut invocation = command.get_program().to_string_lossy().into_owned();
+        for arg in command.get_args() {
+            invocation.push(' ');
+            invocation.push_str(arg.to_string_lossy().as_ref());
+        }
+        command_invocations.push(invocation.clone());
+        if invocation.starts_with("pkgutil --expand-full ") {
+            fs::create_dir_all(expanded_dir.join("Payload"))
+                .expect("must create top-level payload root");
+        }
+        Ok(())
+    })
+    .expect("stage flow should succeed");
+
+    assert_eq!(command_invocations.len(), 2, "expand + copy must run");
+    assert!(command_invocations[0].starts_with("pkgutil --expand-full "));
+    assert!(command_invocations[1].starts_with("ditto "));
+    assert!(
+        !expanded_dir.exists(),
+        "expanded dir should be removed during cleanup"
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn stage_pkg_cleanup_runs_on_expand_failure() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let artifact_path = layout.prefix().join("demo.pkg");
+    fs::write(&artifact_path, b"pkg").expect("must create artifact");
+    let raw_dir = layout.prefix().join("raw");
+    let expanded_dir = layout.prefix().join("pkg-expanded");
+    fs::create_dir_all(&expanded_dir).expect("must seed expanded dir");
+
+    let err = stage_pkg_payload_with_hooks(&artifact_path, &raw_dir, &expanded_dir, |_, _| {
+        Err(anyhow!("simulated expand failure"))
+    })
+    .expect_err("expand failure should propagate");
+
+    assert!(err.to_string().contains("simulated expand failure"));
+    assert!(
+        !expanded_dir.exists(),
+        "expanded dir should be removed during cleanup"
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn stage_pkg_payload_discovery_is_deterministic_for_top_level_and_nested_roots() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let expanded_dir = layout.prefix().join("pkg-expanded");
+    fs::create_dir_all(expanded_dir.join("Payload")).expect("must create top-level payload");
+    fs::create_dir_all(expanded_dir.join("zeta.pkg").join("Payload"))
+        .expect("must create nested payload");
+    fs::create_dir_all(expanded_dir.join("alpha.pkg").join("Payload"))
+        .expect("must create nested payload");
+    fs::create_dir_all(expanded_dir.join("ignored")).expect("must create ignored dir");
+
+    let payload_roots =
+        discover_pkg_payload_roots(&expanded_dir).expect("must discover payload roots");
+
+    assert_eq!(
+        payload_roots,
+        vec![
+            expanded_dir.join("Payload"),
+            expanded_dir.join("alpha.pkg").join("Payload"),
+            expanded_dir.join("zeta.pkg").join("Payload"),
+        ]
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn stage_pkg_payload_discovery_returns_actionable_error_when_missing() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let expanded_dir = layout.prefix().join("pkg-expanded");
+    fs::create_dir_all(&expanded_dir).expect("must create expanded dir");
+
+    let err = discover_pkg_payload_roots(&expanded_dir)
+        .expect_err("missing payload roots must return error");
+    let message = err.to_string();
+    assert!(message.contains("expanded PKG payload not found"));
+    assert!(message.contains(expanded_dir.join("Payload").display().to_string().as_str()));
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn stage_pkg_cleanup_runs_on_copy_failure() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let artifact_path = layout.prefix().join("demo.pkg");
+    fs::write(&artifact_path, b"pkg").expect("must create artifact");
+    let raw_dir = layout.prefix().join("raw");
+    let expanded_dir = layout.prefix().join("pkg-expanded");
+    let mut command_invocations = Vec::new();
+
+    let err =
+        stage_pkg_payload_with_hooks(&artifact_path, &raw_dir, &expanded_dir, |command, _| {
+            let mut invocation = command.get_program().to_string_lossy().into_owned();
+            for arg in command.get_args() {
+                invocation.push(' ');
+                invocation.push_str(arg.to_string_lossy().as_ref());
+            }
+            command_invocations.push(invocation.clone());
+            if invocation.starts_with("pkgutil --expand-full ") {
+                fs::create_dir_all(expanded_dir.join("Payload"))
+                    .expect("must create top-level payload root");
+            }
+            if invocation.starts_with("ditto ") {
+                return Err(anyhow!("simulated copy failure"));
+            }
+            Ok(())
+        })
+        .expect_err("copy failure should propagate");
+
+    assert!(err.to_string().contains("simulated copy failure"));
+    assert_eq!(command_invocations.len(), 2, "expand + copy must run");
+    assert!(
+        !expanded_dir.exists(),
+        "expanded dir should be removed during cleanup"
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn stage_pkg_copies_top_level_then_nested_payloads_in_stable_order() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let artifact_path = layout.prefix().join("demo.pkg");
+    fs::write(&artifact_path, b"pkg").expect("must create artifact");
+    let raw_dir = layout.prefix().join("raw");
+    let expanded_dir = layout.prefix().join("pkg-expanded");
+    let mut copy_sources = Vec::new();
 
     stage_pkg_payload_with_hooks(&artifact_path, &raw_dir, &expanded_dir, |command, _| {
-        let mut invocation = command.get_program().to_string_lossy().into_owned();
-        for arg in command.get_args() {
-            invocation.push(' ');
-            invocation.push_str(arg.to_string_lossy().as_ref());
-        }
-        command_invocations.push(invocation.clone());
-        if invocation.starts_with("pkgutil --expand-full ") {
+        if command.get_program() == "pkgutil" {
             fs::create_dir_all(expanded_dir.join("Payload"))
                 .expect("must create top-level payload root");
+            fs::create_dir_all(expanded_dir.join("zeta.pkg").join("Payload"))
+                .expect("must create nested payload root");
+            fs::create_dir_all(expanded_dir.join("alpha.pkg").join("Payload"))
+                .expect("must create nested payload root");
+            return Ok(());
+        }
+
+        if command.get_program() == "ditto" {
+            let args = command.get_args().collect::<Vec<_>>();
+            let source = args
+                .first()
+                .expect("ditto should have a source arg")
+                .to_string_lossy()
+                .into_owned();
+            copy_sources.push(source);
         }
         Ok(())
     })
-    .expect("stage flow should succeed");
+    .expect("copy flow should succeed");
+
+    assert_eq!(
+        copy_sources.len(),
+        3,
+        "top-level and two nested payload roots should be copied"
+    );
+    assert_eq!(
+        copy_sources,
+        vec![
+            expanded_dir.join("Payload").display().to_string(),
+            expanded_dir
+                .join("alpha.pkg")
+                .join("Payload")
+                .display()
+                .to_string(),
+            expanded_dir
+                .join("zeta.pkg")
+                .join("Payload")
+                .display()
+                .to_string(),
+        ]
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn stage_msix_returns_actionable_error_when_makeappx_is_missing() {
+    let artifact_path = Path::new("C:/tmp/demo.msix");
+    let raw_dir = Path::new("C:/tmp/raw-msix");
+    let err = stage_msix_payload_with_runner(artifact_path, raw_dir, |_command, _context| {
+        Err(anyhow!(io::Error::new(
+            io::ErrorKind::NotFound,
+            "simulated missing makeappx"
+        )))
+    })
+    .expect_err("missing makeappx should be surfaced with guidance");
+
+    let message = err.to_string();
+    assert!(
+        message.contains("failed to stage MSIX artifact via deterministic extraction"),
+        "unexpected error: {message}"
+    );
+    assert!(
+        message.contains("required extraction tool 'makeappx' was not found on PATH"),
+        "unexpected error: {message}"
+    );
+    assert!(
+        message.contains(
+            "install Windows SDK App Certification Kit tools and ensure 'makeappx' is available, then retry"
+        ),
+        "unexpected error: {message}"
+    );
+}
+
+#[test]
+fn stage_appx_returns_actionable_error_when_makeappx_is_missing() {
+    let artifact_path = Path::new("C:/tmp/demo.appx");
+    let raw_dir = Path::new("C:/tmp/raw-appx");
+    let err = stage_appx_payload_with_runner(artifact_path, raw_dir, |_command, _context| {
+        Err(anyhow!(io::Error::new(
+            io::ErrorKind::NotFound,
+            "simulated missing makeappx"
+        )))
+    })
+    .expect_err("missing makeappx should be surfaced with guidance");
+
+    let message = err.to_string();
+    assert!(
+        message.contains("failed to stage APPX artifact via deterministic extraction"),
+        "unexpected error: {message}"
+    );
+    assert!(
+        message.contains("required extraction tool 'makeappx' was not found on PATH"),
+        "unexpected error: {message}"
+    );
+    assert!(
+        message.contains(
+            "install Windows SDK App Certification Kit tools and ensure 'makeappx' is available, then retry"
+        ),
+        "unexpected error: {message}"
+    );
+}
+
+#[test]
+fn stage_dmg_attach_and_detach_command_shapes_are_stable() {
+    let artifact_path = Path::new("/tmp/demo.dmg");
+    let mount_point = Path::new("/tmp/mount-point");
+
+    let attach = build_dmg_attach_command(artifact_path, mount_point);
+    assert_eq!(attach.get_program(), "hdiutil");
+    let attach_args = attach
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        attach_args,
+        vec![
+            "attach".to_string(),
+            artifact_path.display().to_string(),
+            "-readonly".to_string(),
+            "-nobrowse".to_string(),
+            "-mountpoint".to_string(),
+            mount_point.display().to_string(),
+        ]
+    );
+
+    let detach = build_dmg_detach_command(mount_point);
+    assert_eq!(detach.get_program(), "hdiutil");
+    let detach_args = detach
+        .get_args()
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        detach_args,
+        vec!["detach".to_string(), mount_point.display().to_string()]
+    );
+}
+
+#[test]
+fn stage_dmg_detach_runs_on_copy_failure() {
+    let artifact_path = Path::new("/tmp/demo.dmg");
+    let raw_dir = Path::new("/tmp/raw");
+    let mount_point = Path::new("/tmp/mount-point");
+    let mut command_invocations = Vec::new();
+
+    let err = stage_dmg_payload_with_hooks(
+        artifact_path,
+        raw_dir,
+        mount_point,
+        |command, _context| {
+            let mut invocation = command.get_program().to_string_lossy().into_owned();
+            for arg in command.get_args() {
+                invocation.push(' ');
+                invocation.push_str(arg.to_string_lossy().as_ref());
+            }
+            command_invocations.push(invocation);
+            Ok(())
+        },
+        |_mounted, _dst| Err(anyhow!("simulated copy failure")),
+    )
+    .expect_err("copy failure should propagate");
+
+    assert!(err.to_string().contains("simulated copy failure"));
+    assert_eq!(command_invocations.len(), 2, "attach + detach must run");
+    assert!(command_invocations[0].starts_with("hdiutil attach "));
+    assert!(command_invocations[1].starts_with("hdiutil detach "));
+}
+
+#[test]
+#[cfg(unix)]
+fn copy_dmg_payload_skips_root_applications_symlink() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let mount_point = layout.prefix().join("mount-point");
+    let raw_dir = layout.prefix().join("raw");
+    fs::create_dir_all(&mount_point).expect("must create mount point");
+
+    let app_binary = mount_point.join("Demo.app/Contents/MacOS/demo");
+    fs::create_dir_all(app_binary.parent().expect("must have parent"))
+        .expect("must create app bundle dirs");
+    fs::write(&app_binary, b"#!/bin/sh\n").expect("must write app binary");
+
+    let nested_dir = mount_point.join("nested");
+    fs::create_dir_all(&nested_dir).expect("must create nested payload dir");
+
+    std::os::unix::fs::symlink(Path::new("/Applications"), mount_point.join("Applications"))
+        .expect("must create root Applications symlink");
+    std::os::unix::fs::symlink(Path::new("../Demo.app"), nested_dir.join("Applications"))
+        .expect("must create nested Applications symlink");
+
+    copy_dmg_payload(&mount_point, &raw_dir).expect("must copy DMG payload");
+
+    match fs::symlink_metadata(raw_dir.join("Applications")) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Ok(_) => panic!("root Applications symlink should be skipped"),
+        Err(err) => panic!("unexpected root Applications metadata error: {err}"),
+    }
+
+    let copied_binary = raw_dir.join("Demo.app/Contents/MacOS/demo");
+    assert!(copied_binary.exists(), "expected app bundle to be copied");
+
+    let nested_symlink = raw_dir.join("nested/Applications");
+    let nested_metadata =
+        fs::symlink_metadata(&nested_symlink).expect("nested Applications entry should exist");
+    assert!(
+        nested_metadata.file_type().is_symlink(),
+        "nested Applications symlink should be preserved"
+    );
+    assert_eq!(
+        fs::read_link(&nested_symlink).expect("must read nested symlink target"),
+        PathBuf::from("../Demo.app")
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn uninstall_removes_package_dir_and_receipt() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let package_dir = layout.package_dir("demo", "1.0.0");
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
+    let completion_rel_path = "packages/bash/demo--completions--demo.bash".to_string();
+    let completion_path = exposed_completion_path(&layout, &completion_rel_path)
+        .expect("must resolve completion storage path");
+    fs::create_dir_all(
+        completion_path
+            .parent()
+            .expect("completion path must have parent"),
+    )
+    .expect("must create completion parent dir");
+    fs::write(&completion_path, b"# demo completion\n").expect("must create completion file");
+    let gui_rel_path = "launchers/demo--demo.command".to_string();
+    let gui_path = gui_asset_path(&layout, &gui_rel_path).expect("must resolve gui path");
+    fs::create_dir_all(gui_path.parent().expect("gui path must have parent"))
+        .expect("must create gui parent dir");
+    fs::write(&gui_path, b"#!/bin/sh\n").expect("must create gui launcher file");
+    write_gui_exposure_state(
+        &layout,
+        "demo",
+        &[GuiExposureAsset {
+            key: "app:demo".to_string(),
+            rel_path: gui_rel_path,
+        }],
+    )
+    .expect("must write gui state");
+    let native_launcher = layout.prefix().join("native-demo.desktop");
+    fs::write(&native_launcher, b"[Desktop Entry]\n").expect("must write native launcher");
+    write_gui_native_state(
+        &layout,
+        "demo",
+        &[GuiNativeRegistrationRecord {
+            key: "app:demo".to_string(),
+            kind: "desktop-entry".to_string(),
+            path: native_launcher.display().to_string(),
+        }],
+    )
+    .expect("must write native gui state");
+
+    write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: Vec::new(),
+            exposed_completions: vec![completion_rel_path],
+            snapshot_id: None,
+            install_mode: InstallMode::Managed,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 1,
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
+
+    let result = uninstall_package(&layout, "demo").expect("must uninstall");
+    assert_eq!(result.status, UninstallStatus::Uninstalled);
+    assert_eq!(result.version.as_deref(), Some("1.0.0"));
+    assert!(!layout.receipt_path("demo").exists());
+    assert!(!package_dir.exists());
+    assert!(!completion_path.exists());
+    assert!(!gui_path.exists());
+    assert!(!native_launcher.exists());
+    assert!(!layout.gui_state_path("demo").exists());
+    assert!(!layout.gui_native_state_path("demo").exists());
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn uninstall_native_runs_native_uninstall_before_managed_cleanup() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let package_dir = layout.package_dir("demo", "1.0.0");
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
+
+    write_native_sidecar_state(
+        &layout,
+        "demo",
+        &NativeSidecarState {
+            uninstall_actions: vec![NativeUninstallAction {
+                key: "app:demo".to_string(),
+                kind: "desktop-entry".to_string(),
+                path: package_dir.display().to_string(),
+            }],
+        },
+    )
+    .expect("must write native sidecar state");
+
+    write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: Vec::new(),
+            exposed_completions: Vec::new(),
+            snapshot_id: None,
+            install_mode: InstallMode::Native,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 1,
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
+
+    let err = uninstall_package(&layout, "demo")
+        .expect_err("native uninstall action should run before managed cleanup");
+    assert!(
+        err.to_string().contains("native uninstall action failed"),
+        "unexpected error: {err}"
+    );
+    assert!(
+        package_dir.exists(),
+        "managed cleanup should not remove package dir after native action failure"
+    );
+    assert!(
+        layout.receipt_path("demo").exists(),
+        "managed cleanup should not remove receipt after native action failure"
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn uninstall_native_treats_not_found_as_idempotent_success() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let package_dir = layout.package_dir("demo", "1.0.0");
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
+
+    let missing_native_path = layout.prefix().join("already-removed.desktop");
+    write_native_sidecar_state(
+        &layout,
+        "demo",
+        &NativeSidecarState {
+            uninstall_actions: vec![NativeUninstallAction {
+                key: "app:demo".to_string(),
+                kind: "desktop-entry".to_string(),
+                path: missing_native_path.display().to_string(),
+            }],
+        },
+    )
+    .expect("must write native sidecar state");
+
+    write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: Vec::new(),
+            exposed_completions: Vec::new(),
+            snapshot_id: None,
+            install_mode: InstallMode::Native,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 1,
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
+
+    let result = uninstall_package(&layout, "demo")
+        .expect("missing native uninstall action target should be idempotent success");
+    assert_eq!(result.status, UninstallStatus::Uninstalled);
+    assert!(!package_dir.exists());
+    assert!(!layout.receipt_path("demo").exists());
+    assert!(
+        !layout.gui_native_state_path("demo").exists(),
+        "managed cleanup should clear sidecar state"
+    );
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn uninstall_native_removes_bundle_copy_records_recursively() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let package_dir = layout.package_dir("demo", "1.0.0");
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
+
+    let copied_bundle = layout.prefix().join("Applications").join("Demo.app");
+    let copied_binary = copied_bundle.join("Contents").join("MacOS").join("demo");
+    fs::create_dir_all(copied_binary.parent().expect("must have parent"))
+        .expect("must create copied bundle dirs");
+    fs::write(&copied_binary, b"#!/bin/sh\n").expect("must create copied bundle binary");
+
+    write_native_sidecar_state(
+        &layout,
+        "demo",
+        &NativeSidecarState {
+            uninstall_actions: vec![NativeUninstallAction {
+                key: "app:demo".to_string(),
+                kind: "applications-bundle-copy".to_string(),
+                path: copied_bundle.display().to_string(),
+            }],
+        },
+    )
+    .expect("must write native sidecar state");
+
+    write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: Vec::new(),
+            exposed_completions: Vec::new(),
+            snapshot_id: None,
+            install_mode: InstallMode::Native,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 1,
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
+
+    let result = uninstall_package(&layout, "demo")
+        .expect("bundle-copy native uninstall action should be removed recursively");
+    assert_eq!(result.status, UninstallStatus::Uninstalled);
+    assert!(!copied_bundle.exists());
+    assert!(!package_dir.exists());
+    assert!(!layout.receipt_path("demo").exists());
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn uninstall_native_legacy_applications_symlink_kind_preserves_app_bundle_directory() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let package_dir = layout.package_dir("demo", "1.0.0");
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
+
+    let legacy_bundle = layout.prefix().join("Applications").join("LegacyDemo.app");
+    let legacy_bundle_binary = legacy_bundle.join("Contents").join("MacOS").join("demo");
+    fs::create_dir_all(legacy_bundle_binary.parent().expect("must have parent"))
+        .expect("must create legacy bundle dirs");
+    fs::write(&legacy_bundle_binary, b"#!/bin/sh\n").expect("must create legacy bundle binary");
+
+    write_native_sidecar_state(
+        &layout,
+        "demo",
+        &NativeSidecarState {
+            uninstall_actions: vec![NativeUninstallAction {
+                key: "app:demo".to_string(),
+                kind: "applications-symlink".to_string(),
+                path: legacy_bundle.display().to_string(),
+            }],
+        },
+    )
+    .expect("must write native sidecar state");
+
+    write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: Vec::new(),
+            exposed_completions: Vec::new(),
+            snapshot_id: None,
+            install_mode: InstallMode::Native,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 1,
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
+
+    let result = uninstall_package(&layout, "demo")
+        .expect("legacy applications-symlink uninstall action should skip app bundle dirs");
+    assert_eq!(result.status, UninstallStatus::Uninstalled);
+    assert!(legacy_bundle.exists());
+    assert!(!package_dir.exists());
+    assert!(!layout.receipt_path("demo").exists());
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn uninstall_native_applications_symlink_kind_behavior_unchanged() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let package_dir = layout.package_dir("demo", "1.0.0");
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
+
+    let symlink_like_path = layout.prefix().join("Demo.app-link");
+    fs::write(&symlink_like_path, b"simulated symlink file").expect("must write symlink-like path");
+    write_native_sidecar_state(
+        &layout,
+        "demo",
+        &NativeSidecarState {
+            uninstall_actions: vec![NativeUninstallAction {
+                key: "app:demo".to_string(),
+                kind: "applications-symlink".to_string(),
+                path: symlink_like_path.display().to_string(),
+            }],
+        },
+    )
+    .expect("must write native sidecar state");
+
+    write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: Vec::new(),
+            exposed_completions: Vec::new(),
+            snapshot_id: None,
+            install_mode: InstallMode::Native,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 1,
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
+
+    let result = uninstall_package(&layout, "demo")
+        .expect("applications-symlink native uninstall action should still succeed");
+    assert_eq!(result.status, UninstallStatus::Uninstalled);
+    assert!(!symlink_like_path.exists());
+    assert!(!package_dir.exists());
+    assert!(!layout.receipt_path("demo").exists());
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn uninstall_native_stale_cleanup_handles_bundle_copy_and_symlink_kinds() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let package_dir = layout.package_dir("demo", "1.0.0");
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
+
+    let stale_symlink_like_path = layout.prefix().join("stale-demo-link");
+    fs::write(&stale_symlink_like_path, b"simulated symlink")
+        .expect("must create stale symlink-like path");
+    let stale_bundle_copy = layout.prefix().join("stale-applications").join("Demo.app");
+    let stale_bundle_binary = stale_bundle_copy
+        .join("Contents")
+        .join("MacOS")
+        .join("demo");
+    fs::create_dir_all(stale_bundle_binary.parent().expect("must have parent"))
+        .expect("must create stale bundle dirs");
+    fs::write(&stale_bundle_binary, b"#!/bin/sh\n").expect("must create stale bundle binary");
+
+    write_gui_native_state(
+        &layout,
+        "demo",
+        &[
+            GuiNativeRegistrationRecord {
+                key: "app:demo".to_string(),
+                kind: "applications-symlink".to_string(),
+                path: stale_symlink_like_path.display().to_string(),
+            },
+            GuiNativeRegistrationRecord {
+                key: "app:demo-bundle".to_string(),
+                kind: "applications-bundle-copy".to_string(),
+                path: stale_bundle_copy.display().to_string(),
+            },
+        ],
+    )
+    .expect("must write stale native gui state");
+
+    write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: Vec::new(),
+            exposed_completions: Vec::new(),
+            snapshot_id: None,
+            install_mode: InstallMode::Managed,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 1,
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
+
+    let result = uninstall_package(&layout, "demo")
+        .expect("stale native cleanup should handle both bundle-copy and symlink kinds");
+    assert_eq!(result.status, UninstallStatus::Uninstalled);
+    assert!(!stale_symlink_like_path.exists());
+    assert!(!stale_bundle_copy.exists());
+    assert!(!layout.gui_native_state_path("demo").exists());
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn uninstall_native_failure_reports_action_context() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let package_dir = layout.package_dir("demo", "1.0.0");
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
+
+    let action = NativeUninstallAction {
+        key: "protocol:demo".to_string(),
+        kind: "unsupported-kind".to_string(),
+        path: "/tmp/demo-protocol".to_string(),
+    };
+    write_native_sidecar_state(
+        &layout,
+        "demo",
+        &NativeSidecarState {
+            uninstall_actions: vec![action.clone()],
+        },
+    )
+    .expect("must write native sidecar state");
+
+    write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: Vec::new(),
+            exposed_completions: Vec::new(),
+            snapshot_id: None,
+            install_mode: InstallMode::Native,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 1,
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
 
-    assert_eq!(command_invocations.len(), 2, "expand + copy must run");
-    assert!(command_invocations[0].starts_with("pkgutil --expand-full "));
-    assert!(command_invocations[1].starts_with("ditto "));
+    let err = uninstall_package(&layout, "demo")
+        .expect_err("unsupported native uninstall action kind should fail uninstall");
+    let message = err.to_string();
     assert!(
-        !expanded_dir.exists(),
-        "expanded dir should be removed during cleanup"
+        message.contains("native uninstall action failed"),
+        "unexpected error: {message}"
     );
-
-    let _ = fs::remove_dir_all(layout.prefix());
-}
-
-#[test]
-fn stage_pkg_cleanup_runs_on_expand_failure() {
-    let layout = test_layout();
-    layout.ensure_base_dirs().expect("must create dirs");
-
-    let artifact_path = layout.prefix().join("demo.pkg");
-    fs::write(&artifact_path, b"pkg").expect("must create artifact");
-    let raw_dir = layout.prefix().join("raw");
-    let expanded_dir = layout.prefix().join("pkg-expanded");
-    fs::create_dir_all(&expanded_dir).expect("must seed expanded dir");
-
-    let err = stage_pkg_payload_with_hooks(&artifact_path, &raw_dir, &expanded_dir, |_, _| {
-        Err(anyhow!("simulated expand failure"))
-    })
-    .expect_err("expand failure should propagate");
-
-    assert!(err.to_string().contains("simulated expand failure"));
     assert!(
-        !expanded_dir.exists(),
-        "expanded dir should be removed during cleanup"
+        message.contains(&action.key),
+        "error should include action key: {message}"
+    );
+    assert!(
+        message.contains(&action.kind),
+        "error should include action kind: {message}"
+    );
+    assert!(
+        message.contains(&action.path),
+        "error should include action path: {message}"
     );
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn stage_pkg_payload_discovery_is_deterministic_for_top_level_and_nested_roots() {
+fn uninstall_is_idempotent_when_not_installed() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let expanded_dir = layout.prefix().join("pkg-expanded");
-    fs::create_dir_all(expanded_dir.join("Payload")).expect("must create top-level payload");
-    fs::create_dir_all(expanded_dir.join("zeta.pkg").join("Payload"))
-        .expect("must create nested payload");
-    fs::create_dir_all(expanded_dir.join("alpha.pkg").join("Payload"))
-        .expect("must create nested payload");
-    fs::create_dir_all(expanded_dir.join("ignored")).expect("must create ignored dir");
-
-    let payload_roots =
-        discover_pkg_payload_roots(&expanded_dir).expect("must discover payload roots");
-
-    assert_eq!(
-        payload_roots,
-        vec![
-            expanded_dir.join("Payload"),
-            expanded_dir.join("alpha.pkg").join("Payload"),
-            expanded_dir.join("zeta.pkg").join("Payload"),
-        ]
-    );
+    let result = uninstall_package(&layout, "missing").expect("must be ok");
+    assert_eq!(result.status, UninstallStatus::NotInstalled);
+    assert_eq!(result.version, None);
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn stage_pkg_payload_discovery_returns_actionable_error_when_missing() {
+fn uninstall_cleans_stale_receipt_when_package_is_missing() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let expanded_dir = layout.prefix().join("pkg-expanded");
-    fs::create_dir_all(&expanded_dir).expect("must create expanded dir");
+    write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: Vec::new(),
+            exposed_completions: Vec::new(),
+            snapshot_id: None,
+            install_mode: InstallMode::Managed,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 1,
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
 
-    let err = discover_pkg_payload_roots(&expanded_dir)
-        .expect_err("missing payload roots must return error");
-    let message = err.to_string();
-    assert!(message.contains("expanded PKG payload not found"));
-    assert!(message.contains(expanded_dir.join("Payload").display().to_string().as_str()));
+    let result = uninstall_package(&layout, "demo").expect("must uninstall stale state");
+    assert_eq!(result.status, UninstallStatus::RepairedStaleState);
+    assert!(!layout.receipt_path("demo").exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn stage_pkg_cleanup_runs_on_copy_failure() {
+fn uninstall_parse_failure_preserves_files() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let artifact_path = layout.prefix().join("demo.pkg");
-    fs::write(&artifact_path, b"pkg").expect("must create artifact");
-    let raw_dir = layout.prefix().join("raw");
-    let expanded_dir = layout.prefix().join("pkg-expanded");
-    let mut command_invocations = Vec::new();
-
-    let err =
-        stage_pkg_payload_with_hooks(&artifact_path, &raw_dir, &expanded_dir, |command, _| {
-            let mut invocation = command.get_program().to_string_lossy().into_owned();
-            for arg in command.get_args() {
-                invocation.push(' ');
-                invocation.push_str(arg.to_string_lossy().as_ref());
-            }
-            command_invocations.push(invocation.clone());
-            if invocation.starts_with("pkgutil --expand-full ") {
-                fs::create_dir_all(expanded_dir.join("Payload"))
-                    .expect("must create top-level payload root");
-            }
-            if invocation.starts_with("ditto ") {
-                return Err(anyhow!("simulated copy failure"));
-            }
-            Ok(())
-        })
-        .expect_err("copy failure should propagate");
+    let package_dir = layout.package_dir("demo", "1.0.0");
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+    let receipt_path = layout.receipt_path("demo");
+    fs::write(&receipt_path, b"name=demo\nversion=1.0.0\n").expect("must write malformed");
 
-    assert!(err.to_string().contains("simulated copy failure"));
-    assert_eq!(command_invocations.len(), 2, "expand + copy must run");
-    assert!(
-        !expanded_dir.exists(),
-        "expanded dir should be removed during cleanup"
-    );
+    let err = uninstall_package(&layout, "demo").expect_err("must fail on malformed receipt");
+    assert!(err.to_string().contains("failed to parse install receipt"));
+    assert!(receipt_path.exists());
+    assert!(package_dir.exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn stage_pkg_copies_top_level_then_nested_payloads_in_stable_order() {
+fn uninstall_blocks_when_required_by_remaining_root() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let artifact_path = layout.prefix().join("demo.pkg");
-    fs::write(&artifact_path, b"pkg").expect("must create artifact");
-    let raw_dir = layout.prefix().join("raw");
-    let expanded_dir = layout.prefix().join("pkg-expanded");
-    let mut copy_sources = Vec::new();
-
-    stage_pkg_payload_with_hooks(&artifact_path, &raw_dir, &expanded_dir, |command, _| {
-        if command.get_program() == "pkgutil" {
-            fs::create_dir_all(expanded_dir.join("Payload"))
-                .expect("must create top-level payload root");
-            fs::create_dir_all(expanded_dir.join("zeta.pkg").join("Payload"))
-                .expect("must create nested payload root");
-            fs::create_dir_all(expanded_dir.join("alpha.pkg").join("Payload"))
-                .expect("must create nested payload root");
-            return Ok(());
-        }
-
-        if command.get_program() == "ditto" {
-            let args = command.get_args().collect::<Vec<_>>();
-            let source = args
-                .first()
-                .expect("ditto should have a source arg")
-                .to_string_lossy()
-                .into_owned();
-            copy_sources.push(source);
-        }
-        Ok(())
-    })
-    .expect("copy flow should succeed");
-
-    assert_eq!(
-        copy_sources.len(),
-        3,
-        "top-level and two nested payload roots should be copied"
+    write_receipt(
+        &layout,
+        "app",
+        "1.0.0",
+        &["shared@1.0.0"],
+        InstallReason::Root,
+        None,
     );
-    assert_eq!(
-        copy_sources,
-        vec![
-            expanded_dir.join("Payload").display().to_string(),
-            expanded_dir
-                .join("alpha.pkg")
-                .join("Payload")
-                .display()
-                .to_string(),
-            expanded_dir
-                .join("zeta.pkg")
-                .join("Payload")
-                .display()
-                .to_string(),
-        ]
+    write_receipt(
+        &layout,
+        "shared",
+        "1.0.0",
+        &[],
+        InstallReason::Dependency,
+        None,
     );
 
+    let result = uninstall_package(&layout, "shared").expect("must evaluate dependencies");
+    assert_eq!(result.status, UninstallStatus::BlockedByDependents);
+    assert_eq!(result.blocked_by_roots, vec!["app"]);
+    assert!(layout.receipt_path("shared").exists());
+
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(unix)]
 #[test]
-fn stage_msix_returns_actionable_error_when_makeappx_is_missing() {
-    let artifact_path = Path::new("C:/tmp/demo.msix");
-    let raw_dir = Path::new("C:/tmp/raw-msix");
-    let err = stage_msix_payload_with_runner(artifact_path, raw_dir, |_command, _context| {
-        Err(anyhow!(io::Error::new(
-            io::ErrorKind::NotFound,
-            "simulated missing makeappx"
-        )))
-    })
-    .expect_err("missing makeappx should be surfaced with guidance");
-
-    let message = err.to_string();
-    assert!(
-        message.contains("failed to stage MSIX artifact via deterministic extraction"),
-        "unexpected error: {message}"
-    );
-    assert!(
-        message.contains("required extraction tool 'makeappx' was not found on PATH"),
-        "unexpected error: {message}"
-    );
-    assert!(
-        message.contains(
-            "install Windows SDK App Certification Kit tools and ensure 'makeappx' is available, then retry"
-        ),
-        "unexpected error: {message}"
-    );
-}
+fn uninstall_refuses_package_staged_by_foreign_inflight_transaction() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
 
-#[test]
-fn stage_appx_returns_actionable_error_when_makeappx_is_missing() {
-    let artifact_path = Path::new("C:/tmp/demo.appx");
-    let raw_dir = Path::new("C:/tmp/raw-appx");
-    let err = stage_appx_payload_with_runner(artifact_path, raw_dir, |_command, _context| {
-        Err(anyhow!(io::Error::new(
-            io::ErrorKind::NotFound,
-            "simulated missing makeappx"
-        )))
-    })
-    .expect_err("missing makeappx should be surfaced with guidance");
+    write_receipt(&layout, "demo", "1.0.0", &[], InstallReason::Root, None);
+    write_receipt(&layout, "other", "1.0.0", &[], InstallReason::Root, None);
+    fs::create_dir_all(
+        layout
+            .transaction_staging_path("tx-other")
+            .join("rollback")
+            .join("demo"),
+    )
+    .expect("must create staged rollback dir");
+
+    let mut child = Command::new("sleep")
+        .arg("5")
+        .spawn()
+        .expect("must spawn child");
+    fs::write(
+        layout.transaction_active_path(),
+        format!("tx-other\npid={}\n", child.id()),
+    )
+    .expect("must write marker");
+
+    let err = uninstall_package(&layout, "demo").expect_err("staged package must be refused");
+    let other = uninstall_package(&layout, "other");
+    let _ = child.kill();
+    let _ = child.wait();
 
-    let message = err.to_string();
-    assert!(
-        message.contains("failed to stage APPX artifact via deterministic extraction"),
-        "unexpected error: {message}"
-    );
     assert!(
-        message.contains("required extraction tool 'makeappx' was not found on PATH"),
-        "unexpected error: {message}"
+        err.to_string().contains("operation in progress for demo"),
+        "unexpected error: {err}"
     );
-    assert!(
-        message.contains(
-            "install Windows SDK App Certification Kit tools and ensure 'makeappx' is available, then retry"
-        ),
-        "unexpected error: {message}"
+    assert!(layout.receipt_path("demo").exists());
+    assert_eq!(
+        other.expect("unrelated package must uninstall").status,
+        UninstallStatus::Uninstalled
     );
+
+    let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn stage_dmg_attach_and_detach_command_shapes_are_stable() {
-    let artifact_path = Path::new("/tmp/demo.dmg");
-    let mount_point = Path::new("/tmp/mount-point");
+fn uninstall_package_version_removes_inactive_version_and_blocks_required_version() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
 
-    let attach = build_dmg_attach_command(artifact_path, mount_point);
-    assert_eq!(attach.get_program(), "hdiutil");
-    let attach_args = attach
-        .get_args()
-        .map(|arg| arg.to_string_lossy().into_owned())
-        .collect::<Vec<_>>();
-    assert_eq!(
-        attach_args,
-        vec![
-            "attach".to_string(),
-            artifact_path.display().to_string(),
-            "-readonly".to_string(),
-            "-nobrowse".to_string(),
-            "-mountpoint".to_string(),
-            mount_point.display().to_string(),
-        ]
+    write_receipt(
+        &layout,
+        "app",
+        "1.0.0",
+        &["shared@1.1.0"],
+        InstallReason::Root,
+        None,
     );
-
-    let detach = build_dmg_detach_command(mount_point);
-    assert_eq!(detach.get_program(), "hdiutil");
-    let detach_args = detach
-        .get_args()
-        .map(|arg| arg.to_string_lossy().into_owned())
-        .collect::<Vec<_>>();
-    assert_eq!(
-        detach_args,
-        vec!["detach".to_string(), mount_point.display().to_string()]
+    write_receipt(
+        &layout,
+        "shared",
+        "1.1.0",
+        &[],
+        InstallReason::Dependency,
+        None,
     );
-}
+    let old_dir = layout.package_dir("shared", "1.0.0");
+    fs::create_dir_all(&old_dir).expect("must create old package dir");
+    let active_dir = layout.package_dir("shared", "1.1.0");
+    fs::create_dir_all(&active_dir).expect("must create active package dir");
 
-#[test]
-fn stage_dmg_detach_runs_on_copy_failure() {
-    let artifact_path = Path::new("/tmp/demo.dmg");
-    let raw_dir = Path::new("/tmp/raw");
-    let mount_point = Path::new("/tmp/mount-point");
-    let mut command_invocations = Vec::new();
+    let result =
+        uninstall_package_version(&layout, "shared", "1.0.0").expect("must remove old version");
+    assert_eq!(result.status, UninstallStatus::Uninstalled);
+    assert_eq!(result.version.as_deref(), Some("1.0.0"));
+    assert!(!old_dir.exists());
+    assert!(active_dir.exists());
+    assert!(layout.receipt_path("shared").exists());
 
-    let err = stage_dmg_payload_with_hooks(
-        artifact_path,
-        raw_dir,
-        mount_point,
-        |command, _context| {
-            let mut invocation = command.get_program().to_string_lossy().into_owned();
-            for arg in command.get_args() {
-                invocation.push(' ');
-                invocation.push_str(arg.to_string_lossy().as_ref());
-            }
-            command_invocations.push(invocation);
-            Ok(())
-        },
-        |_mounted, _dst| Err(anyhow!("simulated copy failure")),
-    )
-    .expect_err("copy failure should propagate");
+    let result =
+        uninstall_package_version(&layout, "shared", "1.1.0").expect("must evaluate dependencies");
+    assert_eq!(result.status, UninstallStatus::BlockedByDependents);
+    assert_eq!(result.blocked_by_roots, vec!["app"]);
+    assert!(active_dir.exists());
+    assert!(layout.receipt_path("shared").exists());
 
-    assert!(err.to_string().contains("simulated copy failure"));
-    assert_eq!(command_invocations.len(), 2, "attach + detach must run");
-    assert!(command_invocations[0].starts_with("hdiutil attach "));
-    assert!(command_invocations[1].starts_with("hdiutil detach "));
+    let result =
+        uninstall_package_version(&layout, "shared", "0.9.0").expect("must report missing");
+    assert_eq!(result.status, UninstallStatus::NotInstalled);
+
+    let _ = fs::remove_dir_all(layout.prefix());
 }
 
-#[test]
 #[cfg(unix)]
-fn copy_dmg_payload_skips_root_applications_symlink() {
+#[test]
+fn uninstall_package_version_refuses_inactive_version_staged_by_foreign_transaction() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let mount_point = layout.prefix().join("mount-point");
-    let raw_dir = layout.prefix().join("raw");
-    fs::create_dir_all(&mount_point).expect("must create mount point");
-
-    let app_binary = mount_point.join("Demo.app/Contents/MacOS/demo");
-    fs::create_dir_all(app_binary.parent().expect("must have parent"))
-        .expect("must create app bundle dirs");
-    fs::write(&app_binary, b"#!/bin/sh\n").expect("must write app binary");
+    write_receipt(&layout, "demo", "1.1.0", &[], InstallReason::Root, None);
+    let old_dir = layout.package_dir("demo", "1.0.0");
+    fs::create_dir_all(&old_dir).expect("must create old package dir");
+    fs::create_dir_all(
+        layout
+            .transaction_staging_path("tx-other")
+            .join("rollback")
+            .join("demo"),
+    )
+    .expect("must create staged rollback dir");
 
-    let nested_dir = mount_point.join("nested");
-    fs::create_dir_all(&nested_dir).expect("must create nested payload dir");
+    let mut child = Command::new("sleep")
+        .arg("5")
+        .spawn()
+        .expect("must spawn child");
+    fs::write(
+        layout.transaction_active_path(),
+        format!("tx-other\npid={}\n", child.id()),
+    )
+    .expect("must write marker");
 
-    std::os::unix::fs::symlink(Path::new("/Applications"), mount_point.join("Applications"))
-        .expect("must create root Applications symlink");
-    std::os::unix::fs::symlink(Path::new("../Demo.app"), nested_dir.join("Applications"))
-        .expect("must create nested Applications symlink");
+    let err = uninstall_package_version(&layout, "demo", "1.0.0")
+        .expect_err("staged package must be refused");
+    let _ = child.kill();
+    let _ = child.wait();
 
-    copy_dmg_payload(&mount_point, &raw_dir).expect("must copy DMG payload");
+    assert!(
+        err.to_string().contains("operation in progress for demo"),
+        "unexpected error: {err}"
+    );
+    assert!(old_dir.exists());
 
-    match fs::symlink_metadata(raw_dir.join("Applications")) {
-        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
-        Ok(_) => panic!("root Applications symlink should be skipped"),
-        Err(err) => panic!("unexpected root Applications metadata error: {err}"),
-    }
+    let _ = fs::remove_dir_all(layout.prefix());
+}
 
-    let copied_binary = raw_dir.join("Demo.app/Contents/MacOS/demo");
-    assert!(copied_binary.exists(), "expected app bundle to be copied");
+#[test]
+fn uninstall_with_dependency_overrides_allows_planned_root_transition() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
 
-    let nested_symlink = raw_dir.join("nested/Applications");
-    let nested_metadata =
-        fs::symlink_metadata(&nested_symlink).expect("nested Applications entry should exist");
-    assert!(
-        nested_metadata.file_type().is_symlink(),
-        "nested Applications symlink should be preserved"
+    write_receipt(
+        &layout,
+        "app",
+        "1.0.0",
+        &["shared@1.0.0"],
+        InstallReason::Root,
+        None,
     );
-    assert_eq!(
-        fs::read_link(&nested_symlink).expect("must read nested symlink target"),
-        PathBuf::from("../Demo.app")
+    write_receipt(
+        &layout,
+        "shared",
+        "1.0.0",
+        &[],
+        InstallReason::Dependency,
+        None,
     );
 
+    let dependency_overrides =
+        HashMap::from([("app".to_string(), vec!["replacement".to_string()])]);
+    let result =
+        uninstall_package_with_dependency_overrides(&layout, "shared", &dependency_overrides)
+            .expect("planned dependency override should allow uninstall");
+
+    assert_eq!(result.status, UninstallStatus::Uninstalled);
+    assert!(!layout.receipt_path("shared").exists());
+    assert!(layout.receipt_path("app").exists());
+
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn uninstall_removes_package_dir_and_receipt() {
+fn uninstall_with_dependency_overrides_keeps_transitive_edges_for_planned_packages() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let package_dir = layout.package_dir("demo", "1.0.0");
-    fs::create_dir_all(&package_dir).expect("must create package dir");
-    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
-    let completion_rel_path = "packages/bash/demo--completions--demo.bash".to_string();
-    let completion_path = exposed_completion_path(&layout, &completion_rel_path)
-        .expect("must resolve completion storage path");
-    fs::create_dir_all(
-        completion_path
-            .parent()
-            .expect("completion path must have parent"),
-    )
-    .expect("must create completion parent dir");
-    fs::write(&completion_path, b"# demo completion\n").expect("must create completion file");
-    let gui_rel_path = "launchers/demo--demo.command".to_string();
-    let gui_path = gui_asset_path(&layout, &gui_rel_path).expect("must resolve gui path");
-    fs::create_dir_all(gui_path.parent().expect("gui path must have parent"))
-        .expect("must create gui parent dir");
-    fs::write(&gui_path, b"#!/bin/sh\n").expect("must create gui launcher file");
-    write_gui_exposure_state(
+    write_receipt(
         &layout,
-        "demo",
-        &[GuiExposureAsset {
-            key: "app:demo".to_string(),
-            rel_path: gui_rel_path,
-        }],
-    )
-    .expect("must write gui state");
-    let native_launcher = layout.prefix().join("native-demo.desktop");
-    fs::write(&native_launcher, b"[Desktop Entry]\n").expect("must write native launcher");
-    write_gui_native_state(
+        "app",
+        "1.0.0",
+        &["legacy@1.0.0"],
+        InstallReason::Root,
+        None,
+    );
+    write_receipt(
         &layout,
-        "demo",
-        &[GuiNativeRegistrationRecord {
-            key: "app:demo".to_string(),
-            kind: "desktop-entry".to_string(),
-            path: native_launcher.display().to_string(),
-        }],
-    )
-    .expect("must write native gui state");
-
-    write_install_receipt(
+        "legacy",
+        "1.0.0",
+        &["lib@1.0.0"],
+        InstallReason::Dependency,
+        None,
+    );
+    write_receipt(
         &layout,
-        &InstallReceipt {
-            name: "demo".to_string(),
-            version: "1.0.0".to_string(),
-            dependencies: Vec::new(),
-            target: None,
-            artifact_url: None,
-            artifact_sha256: None,
-            cache_path: None,
-            exposed_bins: Vec::new(),
-            exposed_completions: vec![completion_rel_path],
-            snapshot_id: None,
-            install_mode: InstallMode::Managed,
-            install_reason: InstallReason::Root,
-            install_status: "installed".to_string(),
-            installed_at_unix: 1,
-        },
-    )
-    .expect("must write receipt");
+        "lib",
+        "1.0.0",
+        &[],
+        InstallReason::Dependency,
+        None,
+    );
+
+    let dependency_overrides = HashMap::from([
+        ("app".to_string(), vec!["new".to_string()]),
+        ("new".to_string(), vec!["lib".to_string()]),
+    ]);
+    let result =
+        uninstall_package_with_dependency_overrides(&layout, "legacy", &dependency_overrides)
+            .expect("planned transitive overrides should preserve shared dependencies");
 
-    let result = uninstall_package(&layout, "demo").expect("must uninstall");
     assert_eq!(result.status, UninstallStatus::Uninstalled);
-    assert_eq!(result.version.as_deref(), Some("1.0.0"));
-    assert!(!layout.receipt_path("demo").exists());
-    assert!(!package_dir.exists());
-    assert!(!completion_path.exists());
-    assert!(!gui_path.exists());
-    assert!(!native_launcher.exists());
-    assert!(!layout.gui_state_path("demo").exists());
-    assert!(!layout.gui_native_state_path("demo").exists());
+    assert!(
+        result.pruned_dependencies.is_empty(),
+        "shared lib must not be pruned when planned graph still requires it"
+    );
+    assert!(!layout.receipt_path("legacy").exists());
+    assert!(layout.receipt_path("app").exists());
+    assert!(layout.receipt_path("lib").exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn uninstall_native_runs_native_uninstall_before_managed_cleanup() {
+fn uninstall_prunes_orphans_when_root_removed() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let package_dir = layout.package_dir("demo", "1.0.0");
-    fs::create_dir_all(&package_dir).expect("must create package dir");
-    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
-
-    write_native_sidecar_state(
-        &layout,
-        "demo",
-        &NativeSidecarState {
-            uninstall_actions: vec![NativeUninstallAction {
-                key: "app:demo".to_string(),
-                kind: "desktop-entry".to_string(),
-                path: package_dir.display().to_string(),
-            }],
-        },
-    )
-    .expect("must write native sidecar state");
-
-    write_install_receipt(
+    write_receipt(
         &layout,
-        &InstallReceipt {
-            name: "demo".to_string(),
-            version: "1.0.0".to_string(),
-            dependencies: Vec::new(),
-            target: None,
-            artifact_url: None,
-            artifact_sha256: None,
-            cache_path: None,
-            exposed_bins: Vec::new(),
-            exposed_completions: Vec::new(),
-            snapshot_id: None,
-            install_mode: InstallMode::Native,
-            install_reason: InstallReason::Root,
-            install_status: "installed".to_string(),
-            installed_at_unix: 1,
-        },
-    )
-    .expect("must write receipt");
-
-    let err = uninstall_package(&layout, "demo")
-        .expect_err("native uninstall action should run before managed cleanup");
-    assert!(
-        err.to_string().contains("native uninstall action failed"),
-        "unexpected error: {err}"
-    );
-    assert!(
-        package_dir.exists(),
-        "managed cleanup should not remove package dir after native action failure"
+        "app",
+        "1.0.0",
+        &["shared@1.0.0"],
+        InstallReason::Root,
+        None,
     );
-    assert!(
-        layout.receipt_path("demo").exists(),
-        "managed cleanup should not remove receipt after native action failure"
+    write_receipt(
+        &layout,
+        "shared",
+        "1.0.0",
+        &[],
+        InstallReason::Dependency,
+        None,
     );
 
+    let result = uninstall_package(&layout, "app").expect("must uninstall root and orphan");
+    assert_eq!(result.status, UninstallStatus::Uninstalled);
+    assert_eq!(result.pruned_dependencies, vec!["shared"]);
+    assert!(!layout.receipt_path("app").exists());
+    assert!(!layout.receipt_path("shared").exists());
+
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn uninstall_native_treats_not_found_as_idempotent_success() {
+fn uninstall_with_keep_policy_leaves_orphans_installed_and_reports_them() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let package_dir = layout.package_dir("demo", "1.0.0");
-    fs::create_dir_all(&package_dir).expect("must create package dir");
-    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
-
-    let missing_native_path = layout.prefix().join("already-removed.desktop");
-    write_native_sidecar_state(
+    write_receipt(
         &layout,
-        "demo",
-        &NativeSidecarState {
-            uninstall_actions: vec![NativeUninstallAction {
-                key: "app:demo".to_string(),
-                kind: "desktop-entry".to_string(),
-                path: missing_native_path.display().to_string(),
-            }],
-        },
-    )
-    .expect("must write native sidecar state");
+        "app",
+        "1.0.0",
+        &["shared@1.0.0"],
+        InstallReason::Root,
+        None,
+    );
+    write_receipt(
+        &layout,
+        "shared",
+        "1.0.0",
+        &[],
+        InstallReason::Dependency,
+        None,
+    );
 
-    write_install_receipt(
+    let result = uninstall_package_with_autoremove_policy(
         &layout,
-        &InstallReceipt {
-            name: "demo".to_string(),
-            version: "1.0.0".to_string(),
-            dependencies: Vec::new(),
-            target: None,
-            artifact_url: None,
-            artifact_sha256: None,
-            cache_path: None,
-            exposed_bins: Vec::new(),
-            exposed_completions: Vec::new(),
-            snapshot_id: None,
-            install_mode: InstallMode::Native,
-            install_reason: InstallReason::Root,
-            install_status: "installed".to_string(),
-            installed_at_unix: 1,
-        },
+        "app",
+        &HashMap::new(),
+        &HashSet::new(),
+        AutoremovePolicy::Keep,
     )
-    .expect("must write receipt");
-
-    let result = uninstall_package(&layout, "demo")
-        .expect("missing native uninstall action target should be idempotent success");
+    .expect("must uninstall root only");
     assert_eq!(result.status, UninstallStatus::Uninstalled);
-    assert!(!package_dir.exists());
-    assert!(!layout.receipt_path("demo").exists());
-    assert!(
-        !layout.gui_native_state_path("demo").exists(),
-        "managed cleanup should clear sidecar state"
-    );
+    assert!(result.pruned_dependencies.is_empty());
+    assert_eq!(result.orphaned_dependencies, vec!["shared"]);
+    assert!(!layout.receipt_path("app").exists());
+    assert!(layout.receipt_path("shared").exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn uninstall_native_removes_bundle_copy_records_recursively() {
+fn uninstall_keeps_shared_dependency_for_other_root() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let package_dir = layout.package_dir("demo", "1.0.0");
-    fs::create_dir_all(&package_dir).expect("must create package dir");
-    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
+    write_receipt(
+        &layout,
+        "app-a",
+        "1.0.0",
+        &["shared@1.0.0"],
+        InstallReason::Root,
+        None,
+    );
+    write_receipt(
+        &layout,
+        "app-b",
+        "1.0.0",
+        &["shared@1.0.0"],
+        InstallReason::Root,
+        None,
+    );
+    write_receipt(
+        &layout,
+        "shared",
+        "1.0.0",
+        &[],
+        InstallReason::Dependency,
+        None,
+    );
 
-    let copied_bundle = layout.prefix().join("Applications").join("Demo.app");
-    let copied_binary = copied_bundle.join("Contents").join("MacOS").join("demo");
-    fs::create_dir_all(copied_binary.parent().expect("must have parent"))
-        .expect("must create copied bundle dirs");
-    fs::write(&copied_binary, b"#!/bin/sh\n").expect("must create copied bundle binary");
+    let result = uninstall_package(&layout, "app-a").expect("must uninstall app-a only");
+    assert_eq!(result.status, UninstallStatus::Uninstalled);
+    assert!(result.pruned_dependencies.is_empty());
+    assert!(!layout.receipt_path("app-a").exists());
+    assert!(layout.receipt_path("app-b").exists());
+    assert!(layout.receipt_path("shared").exists());
 
-    write_native_sidecar_state(
-        &layout,
-        "demo",
-        &NativeSidecarState {
-            uninstall_actions: vec![NativeUninstallAction {
-                key: "app:demo".to_string(),
-                kind: "applications-bundle-copy".to_string(),
-                path: copied_bundle.display().to_string(),
-            }],
-        },
-    )
-    .expect("must write native sidecar state");
+    let _ = fs::remove_dir_all(layout.prefix());
+}
 
-    write_install_receipt(
+#[test]
+fn uninstall_prunes_unreferenced_cache_paths() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+
+    let cache_path = layout
+        .cache_dir()
+        .join("artifacts")
+        .join("shared")
+        .join("1.0.0")
+        .join("x86_64-unknown-linux-gnu")
+        .join("artifact.tar.zst");
+    if let Some(parent) = cache_path.parent() {
+        fs::create_dir_all(parent).expect("must create cache dir");
+    }
+    fs::write(&cache_path, b"artifact").expect("must create cache file");
+
+    write_receipt(
         &layout,
-        &InstallReceipt {
-            name: "demo".to_string(),
-            version: "1.0.0".to_string(),
-            dependencies: Vec::new(),
-            target: None,
-            artifact_url: None,
-            artifact_sha256: None,
-            cache_path: None,
-            exposed_bins: Vec::new(),
-            exposed_completions: Vec::new(),
-            snapshot_id: None,
-            install_mode: InstallMode::Native,
-            install_reason: InstallReason::Root,
-            install_status: "installed".to_string(),
-            installed_at_unix: 1,
-        },
-    )
-    .expect("must write receipt");
+        "app",
+        "1.0.0",
+        &["shared@1.0.0"],
+        InstallReason::Root,
+        None,
+    );
+    write_receipt(
+        &layout,
+        "shared",
+        "1.0.0",
+        &[],
+        InstallReason::Dependency,
+        Some(cache_path.to_string_lossy().to_string()),
+    );
 
-    let result = uninstall_package(&layout, "demo")
-        .expect("bundle-copy native uninstall action should be removed recursively");
-    assert_eq!(result.status, UninstallStatus::Uninstalled);
-    assert!(!copied_bundle.exists());
-    assert!(!package_dir.exists());
-    assert!(!layout.receipt_path("demo").exists());
+    let result = uninstall_package(&layout, "app").expect("must uninstall root and orphan");
+    assert_eq!(result.pruned_dependencies, vec!["shared"]);
+    assert!(!cache_path.exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn uninstall_native_legacy_applications_symlink_kind_preserves_app_bundle_directory() {
+fn uninstall_keeps_cache_when_still_referenced() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let package_dir = layout.package_dir("demo", "1.0.0");
-    fs::create_dir_all(&package_dir).expect("must create package dir");
-    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
-
-    let legacy_bundle = layout.prefix().join("Applications").join("LegacyDemo.app");
-    let legacy_bundle_binary = legacy_bundle.join("Contents").join("MacOS").join("demo");
-    fs::create_dir_all(legacy_bundle_binary.parent().expect("must have parent"))
-        .expect("must create legacy bundle dirs");
-    fs::write(&legacy_bundle_binary, b"#!/bin/sh\n").expect("must create legacy bundle binary");
+    let cache_path = layout
+        .cache_dir()
+        .join("artifacts")
+        .join("shared")
+        .join("1.0.0")
+        .join("x86_64-unknown-linux-gnu")
+        .join("artifact.tar.zst");
+    if let Some(parent) = cache_path.parent() {
+        fs::create_dir_all(parent).expect("must create cache dir");
+    }
+    fs::write(&cache_path, b"artifact").expect("must create cache file");
 
-    write_native_sidecar_state(
+    write_receipt(
         &layout,
-        "demo",
-        &NativeSidecarState {
-            uninstall_actions: vec![NativeUninstallAction {
-                key: "app:demo".to_string(),
-                kind: "applications-symlink".to_string(),
-                path: legacy_bundle.display().to_string(),
-            }],
-        },
-    )
-    .expect("must write native sidecar state");
-
-    write_install_receipt(
+        "app-a",
+        "1.0.0",
+        &["shared@1.0.0"],
+        InstallReason::Root,
+        None,
+    );
+    write_receipt(
         &layout,
-        &InstallReceipt {
-            name: "demo".to_string(),
-            version: "1.0.0".to_string(),
-            dependencies: Vec::new(),
-            target: None,
-            artifact_url: None,
-            artifact_sha256: None,
-            cache_path: None,
-            exposed_bins: Vec::new(),
-            exposed_completions: Vec::new(),
-            snapshot_id: None,
-            install_mode: InstallMode::Native,
-            install_reason: InstallReason::Root,
-            install_status: "installed".to_string(),
-            installed_at_unix: 1,
-        },
-    )
-    .expect("must write receipt");
+        "app-b",
+        "1.0.0",
+        &["shared@1.0.0"],
+        InstallReason::Root,
+        None,
+    );
+    write_receipt(
+        &layout,
+        "shared",
+        "1.0.0",
+        &[],
+        InstallReason::Dependency,
+        Some(cache_path.to_string_lossy().to_string()),
+    );
 
-    let result = uninstall_package(&layout, "demo")
-        .expect("legacy applications-symlink uninstall action should skip app bundle dirs");
-    assert_eq!(result.status, UninstallStatus::Uninstalled);
-    assert!(legacy_bundle.exists());
-    assert!(!package_dir.exists());
-    assert!(!layout.receipt_path("demo").exists());
+    uninstall_package(&layout, "app-a").expect("must uninstall only app-a");
+    assert!(cache_path.exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn uninstall_native_applications_symlink_kind_behavior_unchanged() {
+fn uninstall_resolves_and_prunes_prefix_relative_cache_paths() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let package_dir = layout.package_dir("demo", "1.0.0");
-    fs::create_dir_all(&package_dir).expect("must create package dir");
-    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
+    let cache_path = layout.artifact_cache_path(
+        "shared",
+        "1.0.0",
+        "x86_64-unknown-linux-gnu",
+        ArchiveType::TarZst,
+    );
+    fs::create_dir_all(cache_path.parent().expect("cache parent")).expect("must create cache dir");
+    fs::write(&cache_path, b"artifact").expect("must create cache file");
+    let relative = layout
+        .portable_cache_path(&cache_path)
+        .expect("cache path must be inside the prefix");
+    assert_eq!(
+        relative,
+        "cache/artifacts/shared/1.0.0/x86_64-unknown-linux-gnu/artifact.tar.zst"
+    );
+    assert_eq!(layout.resolve_cache_path(&relative), cache_path);
+    assert_eq!(
+        layout.resolve_cache_path(cache_path.to_string_lossy().as_ref()),
+        cache_path
+    );
 
-    let symlink_like_path = layout.prefix().join("Demo.app-link");
-    fs::write(&symlink_like_path, b"simulated symlink file").expect("must write symlink-like path");
-    write_native_sidecar_state(
-        &layout,
-        "demo",
-        &NativeSidecarState {
-            uninstall_actions: vec![NativeUninstallAction {
-                key: "app:demo".to_string(),
-                kind: "applications-symlink".to_string(),
-                path: symlink_like_path.display().to_string(),
-            }],
-        },
-    )
-    .expect("must write native sidecar state");
+    let escaping = layout.prefix().join("outside-cache-file");
+    fs::write(&escaping, b"artifact").expect("must create outside cache file");
 
-    write_install_receipt(
+    write_receipt(
         &layout,
-        &InstallReceipt {
-            name: "demo".to_string(),
-            version: "1.0.0".to_string(),
-            dependencies: Vec::new(),
-            target: None,
-            artifact_url: None,
-            artifact_sha256: None,
-            cache_path: None,
-            exposed_bins: Vec::new(),
-            exposed_completions: Vec::new(),
-            snapshot_id: None,
-            install_mode: InstallMode::Native,
-            install_reason: InstallReason::Root,
-            install_status: "installed".to_string(),
-            installed_at_unix: 1,
-        },
-    )
-    .expect("must write receipt");
+        "app",
+        "1.0.0",
+        &["shared@1.0.0", "other@1.0.0"],
+        InstallReason::Root,
+        None,
+    );
+    write_receipt(
+        &layout,
+        "keeper",
+        "1.0.0",
+        &[],
+        InstallReason::Root,
+        Some(cache_path.to_string_lossy().to_string()),
+    );
+    write_receipt(
+        &layout,
+        "shared",
+        "1.0.0",
+        &[],
+        InstallReason::Dependency,
+        Some(relative.clone()),
+    );
+    write_receipt(
+        &layout,
+        "other",
+        "1.0.0",
+        &[],
+        InstallReason::Dependency,
+        Some("cache/artifacts/../../outside-cache-file".to_string()),
+    );
 
-    let result = uninstall_package(&layout, "demo")
-        .expect("applications-symlink native uninstall action should still succeed");
-    assert_eq!(result.status, UninstallStatus::Uninstalled);
-    assert!(!symlink_like_path.exists());
-    assert!(!package_dir.exists());
-    assert!(!layout.receipt_path("demo").exists());
+    let issues = verify_prefix_integrity(&layout).expect("must verify prefix");
+    assert!(!issues.iter().any(
+        |issue| matches!(issue, IntegrityIssue::MissingCache { name, .. } if name == "shared")
+    ));
+
+    uninstall_package(&layout, "app").expect("must uninstall app");
+    assert!(
+        cache_path.exists(),
+        "absolute reference keeps the shared cache file"
+    );
+    assert!(
+        escaping.exists(),
+        "escaping relative path must never be pruned"
+    );
+
+    uninstall_package(&layout, "keeper").expect("must uninstall keeper");
+    assert!(!cache_path.exists());
+
+    write_receipt(
+        &layout,
+        "late",
+        "1.0.0",
+        &[],
+        InstallReason::Root,
+        Some(relative),
+    );
+    fs::write(&cache_path, b"artifact").expect("must recreate cache file");
+    uninstall_package(&layout, "late").expect("must uninstall late");
+    assert!(!cache_path.exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn uninstall_native_stale_cleanup_handles_bundle_copy_and_symlink_kinds() {
+fn uninstall_skips_pruning_cache_path_outside_artifacts_dir() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let package_dir = layout.package_dir("demo", "1.0.0");
-    fs::create_dir_all(&package_dir).expect("must create package dir");
-    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
-
-    let stale_symlink_like_path = layout.prefix().join("stale-demo-link");
-    fs::write(&stale_symlink_like_path, b"simulated symlink")
-        .expect("must create stale symlink-like path");
-    let stale_bundle_copy = layout.prefix().join("stale-applications").join("Demo.app");
-    let stale_bundle_binary = stale_bundle_copy
-        .join("Contents")
-        .join("MacOS")
-        .join("demo");
-    fs::create_dir_all(stale_bundle_binary.parent().expect("must have parent"))
-        .expect("must create stale bundle dirs");
-    fs::write(&stale_bundle_binary, b"#!/bin/sh\n").expect("must create stale bundle binary");
-
-    write_gui_native_state(
-        &layout,
-        "demo",
-        &[
-            GuiNativeRegistrationRecord {
-                key: "app:demo".to_string(),
-                kind: "applications-symlink".to_string(),
-                path: stale_symlink_like_path.display().to_string(),
-            },
-            GuiNativeRegistrationRecord {
-                key: "app:demo-bundle".to_string(),
-                kind: "applications-bundle-copy".to_string(),
-                path: stale_bundle_copy.display().to_string(),
-            },
-        ],
-    )
-    .expect("must write stale native gui state");
+    let outside_cache_path = layout.prefix().join("outside-cache-file");
+    fs::write(&outside_cache_path, b"artifact").expect("must create outside cache file");
 
-    write_install_receipt(
+    write_receipt(
         &layout,
-        &InstallReceipt {
-            name: "demo".to_string(),
-            version: "1.0.0".to_string(),
-            dependencies: Vec::new(),
-            target: None,
-            artifact_url: None,
-            artifact_sha256: None,
-            cache_path: None,
-            exposed_bins: Vec::new(),
-            exposed_completions: Vec::new(),
-            snapshot_id: None,
-            install_mode: InstallMode::Managed,
-            install_reason: InstallReason::Root,
-            install_status: "installed".to_string(),
-            installed_at_unix: 1,
-        },
-    )
-    .expect("must write receipt");
+        "app",
+        "1.0.0",
+        &["shared@1.0.0"],
+        InstallReason::Root,
+        None,
+    );
+    write_receipt(
+        &layout,
+        "shared",
+        "1.0.0",
+        &[],
+        InstallReason::Dependency,
+        Some(outside_cache_path.to_string_lossy().to_string()),
+    );
 
-    let result = uninstall_package(&layout, "demo")
-        .expect("stale native cleanup should handle both bundle-copy and symlink kinds");
-    assert_eq!(result.status, UninstallStatus::Uninstalled);
-    assert!(!stale_symlink_like_path.exists());
-    assert!(!stale_bundle_copy.exists());
-    assert!(!layout.gui_native_state_path("demo").exists());
+    let result = uninstall_package(&layout, "app").expect("must ignore unsafe cache prune");
+    assert_eq!(result.pruned_dependencies, vec!["shared"]);
+    assert!(outside_cache_path.exists());
+    assert!(!layout.receipt_path("app").exists());
+    assert!(!layout.receipt_path("shared").exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(unix)]
 #[test]
-fn uninstall_native_failure_reports_action_context() {
+fn verify_installed_detects_tampered_file_against_cached_artifact() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
+    let fixture_root = layout.prefix().join("fixture");
+    let payload_dir = fixture_root.join("demo-1.0.0");
+    fs::create_dir_all(payload_dir.join("bin")).expect("must create bin dir");
+    fs::write(payload_dir.join("bin/demo"), b"#!/bin/sh\necho demo\n").expect("must write bin");
+    fs::write(payload_dir.join("README"), b"readme").expect("must write readme");
+    let cache_path = layout.artifact_cache_path(
+        "demo",
+        "1.0.0",
+        "x86_64-unknown-linux-gnu",
+        ArchiveType::TarGz,
+    );
+    fs::create_dir_all(cache_path.parent().expect("cache parent")).expect("must create cache dir");
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&cache_path)
+        .arg("-C")
+        .arg(&fixture_root)
+        .arg("demo-1.0.0")
+        .status()
+        .expect("must run tar");
+    assert!(status.success());
 
-    let package_dir = layout.package_dir("demo", "1.0.0");
-    fs::create_dir_all(&package_dir).expect("must create package dir");
-    fs::write(package_dir.join("demo.txt"), b"hello").expect("must create package file");
-
-    let action = NativeUninstallAction {
-        key: "protocol:demo".to_string(),
-        kind: "unsupported-kind".to_string(),
-        path: "/tmp/demo-protocol".to_string(),
-    };
-    write_native_sidecar_state(
+    install_from_artifact(
         &layout,
         "demo",
-        &NativeSidecarState {
-            uninstall_actions: vec![action.clone()],
+        "1.0.0",
+        &cache_path,
+        ArchiveType::TarGz,
+        ArtifactInstallOptions {
+            strip_components: 1,
+            ..ArtifactInstallOptions::default()
         },
     )
-    .expect("must write native sidecar state");
-
-    write_install_receipt(
+    .expect("must install artifact");
+    write_receipt(
         &layout,
-        &InstallReceipt {
-            name: "demo".to_string(),
-            version: "1.0.0".to_string(),
-            dependencies: Vec::new(),
-            target: None,
-            artifact_url: None,
-            artifact_sha256: None,
-            cache_path: None,
-            exposed_bins: Vec::new(),
-            exposed_completions: Vec::new(),
-            snapshot_id: None,
-            install_mode: InstallMode::Native,
-            install_reason: InstallReason::Root,
-            install_status: "installed".to_string(),
-            installed_at_unix: 1,
-        },
-    )
-    .expect("must write receipt");
+        "demo",
+        "1.0.0",
+        &[],
+        InstallReason::Root,
+        Some(cache_path.to_string_lossy().to_string()),
+    );
 
-    let err = uninstall_package(&layout, "demo")
-        .expect_err("unsupported native uninstall action kind should fail uninstall");
-    let message = err.to_string();
-    assert!(
-        message.contains("native uninstall action failed"),
-        "unexpected error: {message}"
+    let results = verify_installed(&layout).expect("must verify installed packages");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].status, VerifyStatus::Verified);
+
+    let install_root = layout.package_dir("demo", "1.0.0");
+    fs::write(install_root.join("bin/demo"), b"#!/bin/sh\necho tampered\n")
+        .expect("must tamper with installed file");
+    fs::remove_file(install_root.join("README")).expect("must remove readme");
+
+    let results = verify_installed(&layout).expect("must verify installed packages");
+    assert_eq!(
+        results[0].status,
+        VerifyStatus::Drifted {
+            modified: vec!["bin/demo".to_string()],
+            missing: vec!["README".to_string()],
+            extra: Vec::new(),
+        }
     );
-    assert!(
-        message.contains(&action.key),
-        "error should include action key: {message}"
+    assert!(fs::read_dir(layout.tmp_state_dir())
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(true));
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[cfg(unix)]
+#[test]
+fn verify_installed_reports_disjoint_tree_as_drift() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let payload_dir = layout.prefix().join("fixture");
+    fs::create_dir_all(payload_dir.join("lib")).expect("must create lib dir");
+    fs::create_dir_all(payload_dir.join("doc")).expect("must create doc dir");
+    fs::write(payload_dir.join("lib/demo.so"), b"lib").expect("must write lib");
+    fs::write(payload_dir.join("doc/README"), b"readme").expect("must write doc");
+    let cache_path = layout.artifact_cache_path(
+        "demo",
+        "1.0.0",
+        "x86_64-unknown-linux-gnu",
+        ArchiveType::TarGz,
     );
-    assert!(
-        message.contains(&action.kind),
-        "error should include action kind: {message}"
+    fs::create_dir_all(cache_path.parent().expect("cache parent")).expect("must create cache dir");
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&cache_path)
+        .arg("-C")
+        .arg(&payload_dir)
+        .arg("lib")
+        .arg("doc")
+        .status()
+        .expect("must run tar");
+    assert!(status.success());
+
+    let install_root = layout.package_dir("demo", "1.0.0");
+    fs::create_dir_all(install_root.join("bin")).expect("must create bin dir");
+    fs::write(install_root.join("bin/demo"), b"unrelated").expect("must write bin");
+    write_receipt(
+        &layout,
+        "demo",
+        "1.0.0",
+        &[],
+        InstallReason::Root,
+        Some(cache_path.to_string_lossy().to_string()),
     );
-    assert!(
-        message.contains(&action.path),
-        "error should include action path: {message}"
+
+    let results = verify_installed(&layout).expect("must verify installed packages");
+    assert_eq!(
+        results[0].status,
+        VerifyStatus::Drifted {
+            modified: Vec::new(),
+            missing: vec!["doc/README".to_string(), "lib/demo.so".to_string()],
+            extra: vec!["bin/demo".to_string()],
+        }
     );
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn uninstall_is_idempotent_when_not_installed() {
+fn verify_installed_checks_recorded_digests_not_the_in_tree_manifest() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
+    let install_root = layout.package_dir("tool", "1.0.0");
+    fs::create_dir_all(install_root.join("bin")).expect("must create package dirs");
+    fs::write(install_root.join("bin/tool"), b"tool binary").expect("must write binary");
+    let manifest_path = install_root.join("crosspack-files.sha256");
+    fs::write(
+        &manifest_path,
+        format!(
+            "{}  bin/tool\n",
+            crosspack_security::sha256_hex(b"tool binary")
+        ),
+    )
+    .expect("must write file manifest");
+    write_receipt(&layout, "tool", "1.0.0", &[], InstallReason::Root, None);
+    record_installed_file_digests(&layout, "tool", &install_root, "crosspack-files.sha256")
+        .expect("must record file digests");
+    assert!(layout.file_digests_state_path("tool").exists());
 
-    let result = uninstall_package(&layout, "missing").expect("must be ok");
-    assert_eq!(result.status, UninstallStatus::NotInstalled);
-    assert_eq!(result.version, None);
+    let results = verify_installed(&layout).expect("must verify installed packages");
+    assert_eq!(results[0].status, VerifyStatus::Verified);
+
+    fs::write(install_root.join("bin/tool"), b"tampered").expect("must tamper binary");
+    fs::write(
+        &manifest_path,
+        format!(
+            "{}  bin/tool\n",
+            crosspack_security::sha256_hex(b"tampered")
+        ),
+    )
+    .expect("must tamper manifest");
+    let results = verify_installed(&layout).expect("must verify installed packages");
+    assert_eq!(
+        results[0].status,
+        VerifyStatus::Drifted {
+            modified: vec!["bin/tool".to_string(), "crosspack-files.sha256".to_string()],
+            missing: Vec::new(),
+            extra: Vec::new(),
+        }
+    );
+
+    uninstall_package(&layout, "tool").expect("must uninstall");
+    assert!(!layout.file_digests_state_path("tool").exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(unix)]
 #[test]
-fn uninstall_cleans_stale_receipt_when_package_is_missing() {
+fn verify_installed_reports_cache_checksum_mismatch_before_comparing() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-
+    let cache_path = layout.artifacts_cache_dir().join("demo.tar.gz");
+    fs::create_dir_all(layout.artifacts_cache_dir()).expect("must create cache dir");
+    fs::write(&cache_path, b"replaced cache blob").expect("must write cache");
+    fs::create_dir_all(layout.package_dir("demo", "1.0.0")).expect("must create package dir");
+    let expected = crosspack_security::sha256_hex(b"original archive");
     write_install_receipt(
         &layout,
         &InstallReceipt {
             name: "demo".to_string(),
             version: "1.0.0".to_string(),
-            dependencies: Vec::new(),
-            target: None,
-            artifact_url: None,
-            artifact_sha256: None,
-            cache_path: None,
-            exposed_bins: Vec::new(),
-            exposed_completions: Vec::new(),
-            snapshot_id: None,
-            install_mode: InstallMode::Managed,
-            install_reason: InstallReason::Root,
+            artifact_sha256: Some(expected.clone()),
+            cache_path: Some(cache_path.display().to_string()),
             install_status: "installed".to_string(),
-            installed_at_unix: 1,
+            ..InstallReceipt::default()
         },
     )
     .expect("must write receipt");
 
-    let result = uninstall_package(&layout, "demo").expect("must uninstall stale state");
-    assert_eq!(result.status, UninstallStatus::RepairedStaleState);
-    assert!(!layout.receipt_path("demo").exists());
-
-    let _ = fs::remove_dir_all(layout.prefix());
-}
-
-#[test]
-fn uninstall_parse_failure_preserves_files() {
-    let layout = test_layout();
-    layout.ensure_base_dirs().expect("must create dirs");
-
-    let package_dir = layout.package_dir("demo", "1.0.0");
-    fs::create_dir_all(&package_dir).expect("must create package dir");
-    let receipt_path = layout.receipt_path("demo");
-    fs::write(&receipt_path, b"name=demo\nversion=1.0.0\n").expect("must write malformed");
-
-    let err = uninstall_package(&layout, "demo").expect_err("must fail on malformed receipt");
-    assert!(err.to_string().contains("failed to parse install receipt"));
-    assert!(receipt_path.exists());
-    assert!(package_dir.exists());
+    let results = verify_installed(&layout).expect("must verify installed packages");
+    assert_eq!(
+        results[0].status,
+        VerifyStatus::CacheChecksumMismatch {
+            cache_path: cache_path.display().to_string(),
+            expected,
+            actual: crosspack_security::sha256_hex(b"replaced cache blob"),
+        }
+    );
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(unix)]
 #[test]
-fn uninstall_blocks_when_required_by_remaining_root() {
+fn verify_installed_compares_symlinks_by_target_and_reports_failures_per_package() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-
+    let payload_dir = layout.prefix().join("fixture");
+    fs::create_dir_all(payload_dir.join("lib")).expect("must create lib dir");
+    fs::write(payload_dir.join("lib/demo.so"), b"lib").expect("must write lib");
+    std::os::unix::fs::symlink("lib", payload_dir.join("lib-link")).expect("must link dir");
+    std::os::unix::fs::symlink("missing", payload_dir.join("dangling"))
+        .expect("must link dangling");
+    let cache_path = layout.artifacts_cache_dir().join("demo.tar.gz");
+    fs::create_dir_all(layout.artifacts_cache_dir()).expect("must create cache dir");
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&cache_path)
+        .arg("-C")
+        .arg(&payload_dir)
+        .arg(".")
+        .status()
+        .expect("must run tar");
+    assert!(status.success());
+    install_from_artifact(
+        &layout,
+        "demo",
+        "1.0.0",
+        &cache_path,
+        ArchiveType::TarGz,
+        ArtifactInstallOptions::default(),
+    )
+    .expect("must install artifact");
     write_receipt(
         &layout,
-        "app",
+        "demo",
         "1.0.0",
-        &["shared@1.0.0"],
+        &[],
         InstallReason::Root,
-        None,
+        Some(cache_path.display().to_string()),
     );
+
+    let broken_cache = layout.artifacts_cache_dir().join("broken.tar.gz");
+    fs::write(&broken_cache, b"not a tarball").expect("must write broken cache");
     write_receipt(
         &layout,
-        "shared",
+        "broken",
         "1.0.0",
         &[],
-        InstallReason::Dependency,
-        None,
+        InstallReason::Root,
+        Some(broken_cache.display().to_string()),
     );
+    fs::write(layout.package_dir("broken", "1.0.0").join("file"), b"x")
+        .expect("must write broken package file");
 
-    let result = uninstall_package(&layout, "shared").expect("must evaluate dependencies");
-    assert_eq!(result.status, UninstallStatus::BlockedByDependents);
-    assert_eq!(result.blocked_by_roots, vec!["app"]);
-    assert!(layout.receipt_path("shared").exists());
+    let results = verify_installed(&layout).expect("must verify installed packages");
+    assert!(
+        matches!(results[0].status, VerifyStatus::Failed { .. }),
+        "unexpected status: {:?}",
+        results[0].status
+    );
+    assert_eq!(results[1].name, "demo");
+    assert_eq!(results[1].status, VerifyStatus::Verified);
+
+    let install_root = layout.package_dir("demo", "1.0.0");
+    fs::remove_file(install_root.join("dangling")).expect("must remove link");
+    std::os::unix::fs::symlink("elsewhere", install_root.join("dangling")).expect("must relink");
+    let results = verify_installed(&layout).expect("must verify installed packages");
+    assert_eq!(
+        results[1].status,
+        VerifyStatus::Drifted {
+            modified: vec!["dangling".to_string()],
+            missing: Vec::new(),
+            extra: Vec::new(),
+        }
+    );
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
+#[cfg(unix)]
 #[test]
-fn uninstall_with_dependency_overrides_allows_planned_root_transition() {
+fn verify_installed_with_artifact_options_stages_cache_like_install() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-
-    write_receipt(
+    let payload_dir = layout.prefix().join("fixture");
+    fs::create_dir_all(payload_dir.join("dist/tool-1.0.0/bin")).expect("must create dist");
+    fs::create_dir_all(payload_dir.join("docs")).expect("must create docs");
+    fs::write(payload_dir.join("dist/tool-1.0.0/bin/tool"), b"tool").expect("must write tool");
+    fs::write(payload_dir.join("docs/README"), b"readme").expect("must write readme");
+    let cache_path = layout.artifacts_cache_dir().join("tool.tar.gz");
+    fs::create_dir_all(layout.artifacts_cache_dir()).expect("must create cache dir");
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&cache_path)
+        .arg("-C")
+        .arg(&payload_dir)
+        .arg("dist")
+        .arg("docs")
+        .status()
+        .expect("must run tar");
+    assert!(status.success());
+    let options = ArtifactInstallOptions {
+        strip_components: 1,
+        artifact_root: Some("dist"),
+        ..ArtifactInstallOptions::default()
+    };
+    install_from_artifact(
         &layout,
-        "app",
+        "tool",
         "1.0.0",
-        &["shared@1.0.0"],
-        InstallReason::Root,
-        None,
-    );
+        &cache_path,
+        ArchiveType::TarGz,
+        options,
+    )
+    .expect("must install artifact");
     write_receipt(
         &layout,
-        "shared",
+        "tool",
         "1.0.0",
         &[],
-        InstallReason::Dependency,
-        None,
+        InstallReason::Root,
+        Some(cache_path.display().to_string()),
+    );
+
+    let artifact_options = BTreeMap::from([("tool".to_string(), options)]);
+    let results = verify_installed_with_artifact_options(&layout, &artifact_options)
+        .expect("must verify installed packages");
+    assert_eq!(results[0].status, VerifyStatus::Verified);
+
+    fs::write(
+        layout.package_dir("tool", "1.0.0").join("bin/tool"),
+        b"tampered",
+    )
+    .expect("must tamper tool");
+    let results = verify_installed_with_artifact_options(&layout, &artifact_options)
+        .expect("must verify installed packages");
+    assert_eq!(
+        results[0].status,
+        VerifyStatus::Drifted {
+            modified: vec!["bin/tool".to_string()],
+            missing: Vec::new(),
+            extra: Vec::new(),
+        }
     );
 
-    let dependency_overrides =
-        HashMap::from([("app".to_string(), vec!["replacement".to_string()])]);
-    let result =
-        uninstall_package_with_dependency_overrides(&layout, "shared", &dependency_overrides)
-            .expect("planned dependency override should allow uninstall");
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
+#[test]
+fn package_files_lists_payload_and_exposed_shim() {
+    let layout = test_layout();
+    layout.ensure_base_dirs().expect("must create dirs");
+    let package_dir = layout.package_dir("demo", "1.0.0");
+    fs::create_dir_all(package_dir.join("bin")).expect("must create bin dir");
+    fs::create_dir_all(package_dir.join("share")).expect("must create share dir");
+    fs::write(package_dir.join("bin").join("demo"), b"#!/bin/sh\n").expect("must write bin");
+    fs::write(package_dir.join("share").join("README"), b"docs").expect("must write readme");
+    expose_binary(&layout, &package_dir, "demo", "bin/demo").expect("must expose binary");
+    write_install_receipt(
+        &layout,
+        &InstallReceipt {
+            name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: vec!["demo".to_string()],
+            exposed_completions: Vec::new(),
+            snapshot_id: None,
+            install_mode: InstallMode::Managed,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 1,
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
+
+    let files = package_files(&layout, "demo").expect("must list package files");
+    let shim = bin_path(&layout, "demo")
+        .strip_prefix(layout.prefix())
+        .expect("shim must live under prefix")
+        .to_path_buf();
+    for expected in [
+        Path::new("pkgs/demo/1.0.0/bin/demo").to_path_buf(),
+        Path::new("pkgs/demo/1.0.0/share/README").to_path_buf(),
+        shim,
+    ] {
+        assert!(
+            files.contains(&expected),
+            "missing {}: {files:?}",
+            expected.display()
+        );
+    }
+    assert_eq!(files.len(), 3);
 
-    assert_eq!(result.status, UninstallStatus::Uninstalled);
-    assert!(!layout.receipt_path("shared").exists());
-    assert!(layout.receipt_path("app").exists());
+    let err = package_files(&layout, "missing").expect_err("unknown package must fail");
+    assert!(err
+        .to_string()
+        .contains("package 'missing' is not installed"));
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn uninstall_with_dependency_overrides_keeps_transitive_edges_for_planned_packages() {
+fn owner_of_file_maps_package_payload_and_shim_to_owner() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
-
-    write_receipt(
+    let package_dir = layout.package_dir("demo", "1.0.0");
+    fs::create_dir_all(package_dir.join("bin")).expect("must create bin dir");
+    fs::write(package_dir.join("bin").join("demo"), b"#!/bin/sh\n").expect("must write bin");
+    expose_binary(&layout, &package_dir, "demo", "bin/demo").expect("must expose binary");
+    write_install_receipt(
         &layout,
-        "app",
-        "1.0.0",
-        &["legacy@1.0.0"],
-        InstallReason::Root,
-        None,
+        &InstallReceipt {
+            name: "demo".to_string(),
+            version: "1.0.0".to_string(),
+            dependencies: Vec::new(),
+            target: None,
+            artifact_url: None,
+            artifact_sha256: None,
+            cache_path: None,
+            exposed_bins: vec!["demo".to_string()],
+            exposed_completions: Vec::new(),
+            snapshot_id: None,
+            install_mode: InstallMode::Managed,
+            install_reason: InstallReason::Root,
+            install_status: "installed".to_string(),
+            installed_at_unix: 1,
+            ..InstallReceipt::default()
+        },
+    )
+    .expect("must write receipt");
+
+    let payload = package_dir.join("bin").join("demo");
+    assert_eq!(
+        owner_of_file(&layout, &payload).expect("must look up payload owner"),
+        Some("demo".to_string())
     );
-    write_receipt(
-        &layout,
-        "legacy",
-        "1.0.0",
-        &["lib@1.0.0"],
-        InstallReason::Dependency,
-        None,
+    assert_eq!(
+        owner_of_file(&layout, &bin_path(&layout, "demo")).expect("must look up shim owner"),
+        Some("demo".to_string())
     );
-    write_receipt(
-        &layout,
-        "lib",
-        "1.0.0",
-        &[],
-        InstallReason::Dependency,
-        None,
+    assert_eq!(
+        owner_of_file(&layout, &bin_path(&layout, "stray")).expect("must look up stray path"),
+        None
     );
-
-    let dependency_overrides = HashMap::from([
-        ("app".to_string(), vec!["new".to_string()]),
-        ("new".to_string(), vec!["lib".to_string()]),
-    ]);
-    let result =
-        uninstall_package_with_dependency_overrides(&layout, "legacy", &dependency_overrides)
-            .expect("planned transitive overrides should preserve shared dependencies");
-
-    assert_eq!(result.status, UninstallStatus::Uninstalled);
-    assert!(
-        result.pruned_dependencies.is_empty(),
-        "shared lib must not be pruned when planned graph still requires it"
+    assert_eq!(
+        owner_of_file(&layout, Path::new("/definitely/outside/prefix"))
+            .expect("must look up outside path"),
+        None
     );
-    assert!(!layout.receipt_path("legacy").exists());
-    assert!(layout.receipt_path("app").exists());
-    assert!(layout.receipt_path("lib").exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn uninstall_prunes_orphans_when_root_removed() {
+fn repair_prefix_integrity_removes_orphaned_shims_and_stale_receipts() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
+    write_receipt(&layout, "kept", "1.0.0", &[], InstallReason::Root, None);
+    write_receipt(&layout, "stale", "2.0.0", &[], InstallReason::Root, None);
+    fs::remove_dir_all(layout.package_dir("stale", "2.0.0")).expect("must remove package dir");
     write_receipt(
         &layout,
-        "app",
+        "uncached",
         "1.0.0",
-        &["shared@1.0.0"],
+        &[],
         InstallReason::Root,
-        None,
+        Some(
+            layout
+                .artifacts_cache_dir()
+                .join("missing.tar.gz")
+                .display()
+                .to_string(),
+        ),
     );
-    write_receipt(
-        &layout,
-        "shared",
-        "1.0.0",
-        &[],
-        InstallReason::Dependency,
-        None,
+
+    let orphan_bin = bin_path(&layout, "orphan");
+    fs::write(
+        &orphan_bin,
+        format!(
+            "#!/bin/sh\nexec '{}' \"$@\"\n",
+            layout
+                .package_dir("gone", "1.0.0")
+                .join("bin/orphan")
+                .display()
+        ),
+    )
+    .expect("must write orphan shim");
+    let user_bin = bin_path(&layout, "user-script");
+    fs::write(&user_bin, b"#!/bin/sh\necho mine\n").expect("must write user script");
+    let orphan_completion = layout
+        .completions_dir()
+        .join("packages")
+        .join("bash")
+        .join("gone--gone.bash");
+    fs::create_dir_all(orphan_completion.parent().expect("completion parent"))
+        .expect("must create completion dir");
+    fs::write(&orphan_completion, b"complete").expect("must write orphan completion");
+    let orphan_gui = layout.gui_handlers_dir().join("gone--app.meta");
+    fs::create_dir_all(layout.gui_handlers_dir()).expect("must create gui dir");
+    fs::write(&orphan_gui, b"meta").expect("must write orphan gui asset");
+
+    let issues = verify_prefix_integrity(&layout).expect("must verify prefix");
+    assert_eq!(
+        issues,
+        vec![
+            IntegrityIssue::StaleReceipt {
+                name: "stale".to_string(),
+                version: "2.0.0".to_string(),
+            },
+            IntegrityIssue::MissingCache {
+                name: "uncached".to_string(),
+                cache_path: layout
+                    .artifacts_cache_dir()
+                    .join("missing.tar.gz")
+                    .display()
+                    .to_string(),
+            },
+            IntegrityIssue::OrphanedBinary {
+                name: "orphan".to_string(),
+            },
+            IntegrityIssue::OrphanedCompletion {
+                rel_path: "packages/bash/gone--gone.bash".to_string(),
+            },
+            IntegrityIssue::OrphanedGuiAsset {
+                rel_path: "handlers/gone--app.meta".to_string(),
+            },
+        ]
     );
 
-    let result = uninstall_package(&layout, "app").expect("must uninstall root and orphan");
-    assert_eq!(result.status, UninstallStatus::Uninstalled);
-    assert_eq!(result.pruned_dependencies, vec!["shared"]);
-    assert!(!layout.receipt_path("app").exists());
-    assert!(!layout.receipt_path("shared").exists());
+    let outcomes = repair_prefix_integrity(&layout, &issues).expect("must repair prefix");
+    assert_eq!(
+        outcomes
+            .iter()
+            .map(|outcome| outcome.action)
+            .collect::<Vec<_>>(),
+        vec![
+            RepairAction::Removed,
+            RepairAction::NeedsRedownload,
+            RepairAction::Removed,
+            RepairAction::Removed,
+            RepairAction::Removed,
+        ]
+    );
+    assert!(!layout.receipt_path("stale").exists());
+    assert!(layout.receipt_path("kept").exists());
+    assert!(!orphan_bin.exists());
+    assert!(user_bin.exists());
+    assert!(!orphan_completion.exists());
+    assert!(!orphan_gui.exists());
+
+    let rerun = repair_prefix_integrity(&layout, &issues).expect("repair must be idempotent");
+    assert_eq!(
+        rerun
+            .iter()
+            .map(|outcome| outcome.action)
+            .collect::<Vec<_>>(),
+        vec![
+            RepairAction::AlreadyResolved,
+            RepairAction::NeedsRedownload,
+            RepairAction::AlreadyResolved,
+            RepairAction::AlreadyResolved,
+            RepairAction::AlreadyResolved,
+        ]
+    );
+    assert_eq!(
+        verify_prefix_integrity(&layout).expect("must verify repaired prefix"),
+        vec![IntegrityIssue::MissingCache {
+            name: "uncached".to_string(),
+            cache_path: layout
+                .artifacts_cache_dir()
+                .join("missing.tar.gz")
+                .display()
+                .to_string(),
+        }]
+    );
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn uninstall_keeps_shared_dependency_for_other_root() {
+fn verify_prefix_integrity_reports_receipt_version_missing_from_package_dir() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    write_receipt(
-        &layout,
-        "app-a",
-        "1.0.0",
-        &["shared@1.0.0"],
-        InstallReason::Root,
-        None,
-    );
-    write_receipt(
-        &layout,
-        "app-b",
-        "1.0.0",
-        &["shared@1.0.0"],
-        InstallReason::Root,
-        None,
-    );
-    write_receipt(
-        &layout,
-        "shared",
-        "1.0.0",
-        &[],
-        InstallReason::Dependency,
-        None,
+    write_receipt(&layout, "tool", "1.0.0", &[], InstallReason::Root, None);
+    fs::rename(
+        layout.package_dir("tool", "1.0.0"),
+        layout.package_dir("tool", "1.1.0"),
+    )
+    .expect("must move package dir");
+    fs::create_dir_all(layout.pkgs_dir().join("tool").join(".1.2.0.partial-1-1"))
+        .expect("must create staging dir");
+
+    let issues = verify_prefix_integrity(&layout).expect("must verify prefix");
+    assert_eq!(
+        issues,
+        vec![IntegrityIssue::VersionMismatch {
+            name: "tool".to_string(),
+            recorded: "1.0.0".to_string(),
+            found: vec!["1.1.0".to_string()],
+        }]
     );
 
-    let result = uninstall_package(&layout, "app-a").expect("must uninstall app-a only");
-    assert_eq!(result.status, UninstallStatus::Uninstalled);
-    assert!(result.pruned_dependencies.is_empty());
-    assert!(!layout.receipt_path("app-a").exists());
-    assert!(layout.receipt_path("app-b").exists());
-    assert!(layout.receipt_path("shared").exists());
+    let outcomes = repair_prefix_integrity(&layout, &issues).expect("must repair prefix");
+    assert_eq!(outcomes[0].action, RepairAction::NeedsRedownload);
+    assert!(layout.receipt_path("tool").exists());
+    assert!(layout.package_dir("tool", "1.1.0").exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn uninstall_prunes_unreferenced_cache_paths() {
+fn verify_installed_file_manifest_detects_tampered_and_extra_files() {
     let layout = test_layout();
-    layout.ensure_base_dirs().expect("must create dirs");
+    let install_root = layout.package_dir("tool", "1.0.0");
+    fs::create_dir_all(install_root.join("bin")).expect("must create package dirs");
+    fs::write(install_root.join("bin/tool"), b"tool binary").expect("must write binary");
+    fs::write(install_root.join("README"), b"readme").expect("must write readme");
+    fs::write(
+        install_root.join("crosspack-files.sha256"),
+        format!(
+            "{}  bin/tool\n{}  README\n",
+            crosspack_security::sha256_hex(b"tool binary"),
+            crosspack_security::sha256_hex(b"readme")
+        ),
+    )
+    .expect("must write file manifest");
 
-    let cache_path = layout
-        .cache_dir()
-        .join("artifacts")
-        .join("shared")
-        .join("1.0.0")
-        .join("x86_64-unknown-linux-gnu")
-        .join("artifact.tar.zst");
-    if let Some(parent) = cache_path.parent() {
-        fs::create_dir_all(parent).expect("must create cache dir");
-    }
-    fs::write(&cache_path, b"artifact").expect("must create cache file");
+    verify_installed_file_manifest(&install_root, "crosspack-files.sha256")
+        .expect("clean install must match file manifest");
 
-    write_receipt(
-        &layout,
-        "app",
-        "1.0.0",
-        &["shared@1.0.0"],
-        InstallReason::Root,
-        None,
-    );
-    write_receipt(
-        &layout,
-        "shared",
-        "1.0.0",
-        &[],
-        InstallReason::Dependency,
-        Some(cache_path.to_string_lossy().to_string()),
-    );
+    fs::write(install_root.join("bin/tool"), b"tampered").expect("must tamper binary");
+    let err = verify_installed_file_manifest(&install_root, "crosspack-files.sha256")
+        .expect_err("tampered file must fail verification");
+    assert!(err.to_string().contains("mismatched: bin/tool"));
 
-    let result = uninstall_package(&layout, "app").expect("must uninstall root and orphan");
-    assert_eq!(result.pruned_dependencies, vec!["shared"]);
-    assert!(!cache_path.exists());
+    fs::write(install_root.join("bin/tool"), b"tool binary").expect("must restore binary");
+    fs::write(install_root.join("bin/extra"), b"extra").expect("must write extra file");
+    fs::remove_file(install_root.join("README")).expect("must remove readme");
+    let err = verify_installed_file_manifest(&install_root, "crosspack-files.sha256")
+        .expect_err("extra and missing files must fail verification");
+    assert!(err.to_string().contains("missing: README"));
+    assert!(err.to_string().contains("extra: bin/extra"));
+
+    let err = verify_installed_file_manifest(&install_root, "../outside.sha256")
+        .expect_err("escaping manifest path must be rejected");
+    assert!(err.to_string().contains("must be relative"));
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn uninstall_keeps_cache_when_still_referenced() {
+fn enforce_cache_quota_evicts_oldest_unreferenced_blobs_first() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let cache_path = layout
-        .cache_dir()
-        .join("artifacts")
-        .join("shared")
-        .join("1.0.0")
-        .join("x86_64-unknown-linux-gnu")
-        .join("artifact.tar.zst");
-    if let Some(parent) = cache_path.parent() {
-        fs::create_dir_all(parent).expect("must create cache dir");
-    }
-    fs::write(&cache_path, b"artifact").expect("must create cache file");
-
-    write_receipt(
-        &layout,
-        "app-a",
-        "1.0.0",
-        &["shared@1.0.0"],
-        InstallReason::Root,
-        None,
-    );
-    write_receipt(
-        &layout,
-        "app-b",
-        "1.0.0",
-        &["shared@1.0.0"],
-        InstallReason::Root,
-        None,
-    );
+    let referenced = write_cache_blob(&layout, "npm/referenced.tar.gz", 100, 10);
+    let oldest = write_cache_blob(&layout, "npm/oldest.tar.gz", 100, 20);
+    let middle = write_cache_blob(&layout, "other/middle.tar.gz", 100, 30);
+    let newest = write_cache_blob(&layout, "other/newest.tar.gz", 100, 40);
     write_receipt(
         &layout,
-        "shared",
+        "kept",
         "1.0.0",
         &[],
-        InstallReason::Dependency,
-        Some(cache_path.to_string_lossy().to_string()),
+        InstallReason::Root,
+        Some(referenced.display().to_string()),
     );
 
-    uninstall_package(&layout, "app-a").expect("must uninstall only app-a");
-    assert!(cache_path.exists());
+    let evicted = enforce_cache_quota(&layout, 250).expect("must enforce cache quota");
+    assert_eq!(evicted, vec![oldest.clone(), middle.clone()]);
+    assert!(referenced.exists());
+    assert!(!oldest.exists());
+    assert!(!middle.exists());
+    assert!(newest.exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
 #[test]
-fn uninstall_skips_pruning_cache_path_outside_artifacts_dir() {
+fn enforce_cache_quota_keeps_referenced_blobs_when_still_over_quota() {
     let layout = test_layout();
     layout.ensure_base_dirs().expect("must create dirs");
 
-    let outside_cache_path = layout.prefix().join("outside-cache-file");
-    fs::write(&outside_cache_path, b"artifact").expect("must create outside cache file");
-
-    write_receipt(
-        &layout,
-        "app",
-        "1.0.0",
-        &["shared@1.0.0"],
-        InstallReason::Root,
-        None,
-    );
+    let referenced = write_cache_blob(&layout, "referenced.tar.gz", 500, 10);
+    let unreferenced = write_cache_blob(&layout, "unreferenced.tar.gz", 100, 20);
     write_receipt(
         &layout,
-        "shared",
+        "kept",
         "1.0.0",
         &[],
-        InstallReason::Dependency,
-        Some(outside_cache_path.to_string_lossy().to_string()),
+        InstallReason::Root,
+        Some(referenced.display().to_string()),
     );
 
-    let result = uninstall_package(&layout, "app").expect("must ignore unsafe cache prune");
-    assert_eq!(result.pruned_dependencies, vec!["shared"]);
-    assert!(outside_cache_path.exists());
-    assert!(!layout.receipt_path("app").exists());
-    assert!(!layout.receipt_path("shared").exists());
+    let evicted = enforce_cache_quota(&layout, 0).expect("must enforce cache quota");
+    assert_eq!(evicted, vec![unreferenced.clone()]);
+    assert!(referenced.exists());
+    assert!(!unreferenced.exists());
 
     let _ = fs::remove_dir_all(layout.prefix());
 }
 
+fn write_cache_blob(
+    layout: &PrefixLayout,
+    rel_path: &str,
+    size: usize,
+    mtime_unix: u64,
+) -> PathBuf {
+    let path = layout.artifacts_cache_dir().join(rel_path);
+    fs::create_dir_all(path.parent().expect("cache blob parent")).expect("must create cache dir");
+    fs::write(&path, vec![0u8; size]).expect("must write cache blob");
+    fs::File::options()
+        .write(true)
+        .open(&path)
+        .expect("must open cache blob")
+        .set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(mtime_unix))
+        .expect("must set cache blob mtime");
+    path
+}
+
 fn write_receipt(
     layout: &PrefixLayout,
     name: &str,
@@ -3430,7 +6771,11 @@ fn write_receipt(
         &InstallReceipt {
             name: name.to_string(),
             version: version.to_string(),
-            dependencies: dependencies.iter().map(|v| (*v).to_string()).collect(),
+            dependencies: dependencies
+                .iter()
+                .filter_map(|entry| entry.split_once('@'))
+                .map(|(name, version)| ResolvedDependency::new(name, version))
+                .collect(),
             target: None,
             artifact_url: None,
             artifact_sha256: None,
@@ -3442,6 +6787,7 @@ fn write_receipt(
             install_reason,
             install_status: "installed".to_string(),
             installed_at_unix: 1,
+            ..InstallReceipt::default()
         },
     )
     .expect("must write receipt");
@@ -3461,6 +6807,37 @@ fn build_test_layout_path(nanos: u128) -> PathBuf {
     path
 }
 
+#[test]
+fn prefix_layout_validate_rejects_unsafe_prefixes() {
+    let err = PrefixLayout::new("")
+        .ensure_base_dirs()
+        .expect_err("empty prefix must be rejected");
+    assert!(err.to_string().contains("empty install prefix"));
+
+    let root = if cfg!(windows) { "C:\\" } else { "/" };
+    let err = PrefixLayout::new(root)
+        .validate()
+        .expect_err("filesystem root must be rejected");
+    assert!(err.to_string().contains("filesystem root"));
+
+    let err = PrefixLayout::new("relative/prefix")
+        .validate()
+        .expect_err("relative prefix must be rejected by default");
+    assert!(err.to_string().contains("must be an absolute path"));
+    PrefixLayout::new("relative/prefix")
+        .allowing_relative_prefix()
+        .validate()
+        .expect("relative prefix must be accepted when explicitly allowed");
+
+    let layout = test_layout();
+    layout.validate().expect("temp prefix must be accepted");
+    layout
+        .ensure_base_dirs()
+        .expect("temp prefix must be created");
+
+    let _ = fs::remove_dir_all(layout.prefix());
+}
+
 #[test]
 fn build_test_layout_path_disambiguates_same_timestamp_calls() {
     let first = build_test_layout_path(42);
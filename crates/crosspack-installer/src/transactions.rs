@@ -1,13 +1,27 @@
 use anyhow::{anyhow, Context, Result};
+use crosspack_core::{Clock, SystemClock};
 use std::collections::HashMap;
 use std::fs;
 use std::io::{self, Write};
 use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::process::Command;
+#[cfg(unix)]
+use std::process::Stdio;
+use std::time::UNIX_EPOCH;
 
-use crate::{PrefixLayout, TransactionJournalEntry, TransactionMetadata};
+use crate::{ActiveTransactionMarker, PrefixLayout, TransactionJournalEntry, TransactionMetadata};
+
+const TRANSACTION_METADATA_VERSION: u32 = 1;
 
 pub fn set_active_transaction(layout: &PrefixLayout, txid: &str) -> Result<PathBuf> {
+    set_active_transaction_with_clock(layout, txid, &SystemClock)
+}
+
+pub fn set_active_transaction_with_clock(
+    layout: &PrefixLayout,
+    txid: &str,
+    clock: &dyn Clock,
+) -> Result<PathBuf> {
     let path = layout.transaction_active_path();
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)
@@ -37,13 +51,20 @@ pub fn set_active_transaction(layout: &PrefixLayout, txid: &str) -> Result<PathB
         }
     };
 
-    file.write_all(format!("{txid}\n").as_bytes())
-        .with_context(|| {
-            format!(
-                "failed to write active transaction file: {}",
-                path.display()
-            )
-        })?;
+    let pid = std::process::id();
+    let mut marker = format!(
+        "{txid}\npid={pid}\nstarted_at_unix={}\n",
+        current_unix_timestamp_with_clock(clock)?
+    );
+    if let Some(ticks) = process_start_ticks(pid) {
+        marker.push_str(&format!("process_start_ticks={ticks}\n"));
+    }
+    file.write_all(marker.as_bytes()).with_context(|| {
+        format!(
+            "failed to write active transaction file: {}",
+            path.display()
+        )
+    })?;
     file.flush().with_context(|| {
         format!(
             "failed to flush active transaction file: {}",
@@ -55,6 +76,12 @@ pub fn set_active_transaction(layout: &PrefixLayout, txid: &str) -> Result<PathB
 }
 
 pub fn read_active_transaction(layout: &PrefixLayout) -> Result<Option<String>> {
+    Ok(read_active_transaction_marker(layout)?.map(|marker| marker.txid))
+}
+
+pub fn read_active_transaction_marker(
+    layout: &PrefixLayout,
+) -> Result<Option<ActiveTransactionMarker>> {
     let path = layout.transaction_active_path();
     let raw = match fs::read_to_string(&path) {
         Ok(raw) => raw,
@@ -66,12 +93,135 @@ pub fn read_active_transaction(layout: &PrefixLayout) -> Result<Option<String>>
         }
     };
 
-    let txid = raw.trim();
-    if txid.is_empty() {
+    let mut lines = raw.lines().map(str::trim).filter(|line| !line.is_empty());
+    let Some(txid) = lines.next() else {
         return Ok(None);
+    };
+
+    let mut marker = ActiveTransactionMarker {
+        txid: txid.to_string(),
+        pid: None,
+        started_at_unix: None,
+        process_start_ticks: None,
+    };
+    for line in lines {
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| anyhow!("invalid active transaction marker line: {line}"))?;
+        match key {
+            "pid" => {
+                marker.pid = Some(
+                    value
+                        .parse()
+                        .with_context(|| format!("invalid active transaction pid: {value}"))?,
+                )
+            }
+            "started_at_unix" => {
+                marker.started_at_unix =
+                    Some(value.parse().with_context(|| {
+                        format!("invalid active transaction start time: {value}")
+                    })?)
+            }
+            "process_start_ticks" => {
+                marker.process_start_ticks = Some(value.parse().with_context(|| {
+                    format!("invalid active transaction process start time: {value}")
+                })?)
+            }
+            _ => {}
+        }
     }
 
-    Ok(Some(txid.to_string()))
+    Ok(Some(marker))
+}
+
+pub fn reclaim_stale_transaction(layout: &PrefixLayout) -> Result<Option<String>> {
+    let Some(marker) = read_active_transaction_marker(layout)? else {
+        return Ok(None);
+    };
+    let Some(pid) = marker.pid else {
+        return Ok(None);
+    };
+    if process_is_alive(pid)? && !owner_pid_was_reused(pid, marker.process_start_ticks) {
+        return Ok(None);
+    }
+
+    if let Some(metadata) = read_transaction_metadata(layout, &marker.txid)? {
+        if matches!(metadata.status.as_str(), "planning" | "applying") {
+            update_transaction_status(layout, &marker.txid, "failed")?;
+        }
+    }
+    clear_active_transaction(layout)?;
+    Ok(Some(marker.txid))
+}
+
+fn owner_pid_was_reused(pid: u32, recorded_start_ticks: Option<u64>) -> bool {
+    match (recorded_start_ticks, process_start_ticks(pid)) {
+        (Some(recorded), Some(current)) => recorded != current,
+        _ => false,
+    }
+}
+
+/// Start time of `pid` in clock ticks since boot, read from field 22 of
+/// `/proc/<pid>/stat`. Returns `None` where procfs is unavailable.
+pub fn process_start_ticks(pid: u32) -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+        parse_proc_stat_start_ticks(&stat)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+pub(crate) fn parse_proc_stat_start_ticks(stat: &str) -> Option<u64> {
+    // The command name (field 2) is parenthesized and may contain spaces, so
+    // count fields from the last ')' which closes it; starttime is field 22.
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(19)?.parse().ok()
+}
+
+pub fn process_is_alive(pid: u32) -> Result<bool> {
+    #[cfg(unix)]
+    {
+        let status = Command::new("kill")
+            .arg("-0")
+            .arg(pid.to_string())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .with_context(|| format!("failed executing owner liveness probe for pid={pid}"))?;
+        Ok(status.success())
+    }
+
+    #[cfg(windows)]
+    {
+        let output = Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {pid}"), "/FO", "CSV", "/NH"])
+            .output()
+            .with_context(|| format!("failed executing owner liveness probe for pid={pid}"))?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "owner liveness probe failed for pid={pid}: status={} stderr='{}'",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        Ok(stdout.contains(&format!(",\"{pid}\""))
+            && !stdout.to_ascii_lowercase().contains("no tasks are running"))
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = pid;
+        Ok(true)
+    }
 }
 
 pub fn clear_active_transaction(layout: &PrefixLayout) -> Result<()> {
@@ -177,7 +327,12 @@ pub fn append_transaction_journal_entry(
 }
 
 pub fn current_unix_timestamp() -> Result<u64> {
-    Ok(SystemTime::now()
+    current_unix_timestamp_with_clock(&SystemClock)
+}
+
+pub fn current_unix_timestamp_with_clock(clock: &dyn Clock) -> Result<u64> {
+    Ok(clock
+        .now()
         .duration_since(UNIX_EPOCH)
         .context("system time is before unix epoch")?
         .as_secs())
@@ -187,7 +342,7 @@ fn serialize_transaction_metadata(metadata: &TransactionMetadata) -> String {
     let snapshot_id = metadata
         .snapshot_id
         .as_ref()
-        .map(|value| format!("\n  \"snapshot_id\": \"{}\"", escape_json(value)))
+        .map(|value| format!(",\n  \"snapshot_id\": \"{}\"", escape_json(value)))
         .unwrap_or_default();
 
     format!(
@@ -217,31 +372,43 @@ fn parse_transaction_metadata(raw: &str) -> Result<TransactionMetadata> {
     let mut string_fields = HashMap::new();
     let mut number_fields = HashMap::new();
 
-    for line in raw.lines().map(str::trim).filter(|line| !line.is_empty()) {
-        if line == "{" || line == "}" {
-            continue;
-        }
-
-        let normalized = line.strip_suffix(',').unwrap_or(line);
-        let (raw_key, raw_value) = normalized
-            .split_once(':')
-            .ok_or_else(|| anyhow!("invalid transaction metadata line: {line}"))?;
-
-        let key = raw_key.trim().trim_matches('"').to_string();
-        let value = raw_value.trim();
-        if value.starts_with('"') || value.ends_with('"') {
-            if !(value.starts_with('"') && value.ends_with('"') && value.len() >= 2) {
-                return Err(anyhow!(
-                    "invalid quoted transaction metadata value for field: {key}"
-                ));
+    let mut cursor = JsonCursor::new(raw);
+    cursor.expect(b'{')?;
+    if !cursor.consume(b'}') {
+        loop {
+            let key = cursor.string()?;
+            cursor.expect(b':')?;
+            let quoted = cursor.peek() == Some(b'"');
+            match cursor.peek() {
+                Some(b'"') => {
+                    let value = cursor.string().with_context(|| {
+                        format!("invalid quoted transaction metadata value for field: {key}")
+                    })?;
+                    string_fields.insert(key.clone(), value);
+                }
+                // Later metadata versions may add nested fields; skip them unread.
+                Some(b'{' | b'[') => cursor.skip_nested()?,
+                _ => {
+                    number_fields.insert(key.clone(), cursor.scalar()?.to_string());
+                }
+            }
+            if cursor.consume(b'}') {
+                break;
+            }
+            // Older writers omitted the comma before a trailing `snapshot_id` line.
+            if !cursor.consume(b',') && (quoted || cursor.peek() != Some(b'"')) {
+                if quoted {
+                    return Err(anyhow!(
+                        "invalid quoted transaction metadata value for field: {key}"
+                    ));
+                }
+                cursor.expect(b',')?;
             }
-
-            let inner = &value[1..value.len() - 1];
-            string_fields.insert(key, unescape_json(inner)?);
-        } else {
-            number_fields.insert(key, value.to_string());
         }
     }
+    if cursor.peek().is_some() {
+        return Err(anyhow!("trailing data after transaction metadata object"));
+    }
 
     let parse_number = |field: &str| -> Result<u64> {
         number_fields
@@ -251,8 +418,15 @@ fn parse_transaction_metadata(raw: &str) -> Result<TransactionMetadata> {
             .with_context(|| format!("invalid numeric transaction metadata field: {field}"))
     };
 
+    let version = parse_number("version")?;
+    if version == 0 || version > u64::from(TRANSACTION_METADATA_VERSION) {
+        return Err(anyhow!(
+            "unsupported transaction metadata version {version} (supported: {TRANSACTION_METADATA_VERSION})"
+        ));
+    }
+
     Ok(TransactionMetadata {
-        version: parse_number("version")? as u32,
+        version: version as u32,
         txid: string_fields
             .get("txid")
             .with_context(|| "missing transaction metadata field: txid")?
@@ -270,6 +444,117 @@ fn parse_transaction_metadata(raw: &str) -> Result<TransactionMetadata> {
     })
 }
 
+struct JsonCursor<'a> {
+    raw: &'a str,
+    pos: usize,
+}
+
+impl<'a> JsonCursor<'a> {
+    fn new(raw: &'a str) -> Self {
+        Self { raw, pos: 0 }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        let rest = &self.raw.as_bytes()[self.pos..];
+        let skipped = rest
+            .iter()
+            .take_while(|byte| byte.is_ascii_whitespace())
+            .count();
+        self.pos += skipped;
+        rest.get(skipped).copied()
+    }
+
+    fn consume(&mut self, byte: u8) -> bool {
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<()> {
+        if self.consume(byte) {
+            return Ok(());
+        }
+        Err(anyhow!(
+            "invalid transaction metadata: expected '{}' at byte {}",
+            byte as char,
+            self.pos
+        ))
+    }
+
+    fn raw_string(&mut self) -> Result<&'a str> {
+        self.expect(b'"')?;
+        let bytes = self.raw.as_bytes();
+        let start = self.pos;
+        while self.pos < bytes.len() {
+            match bytes[self.pos] {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Ok(&self.raw[start..self.pos - 1]);
+                }
+                _ => self.pos += 1,
+            }
+        }
+        Err(anyhow!("invalid transaction metadata: unterminated string"))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let raw = self.raw_string()?;
+        unescape_json(raw)
+    }
+
+    fn scalar(&mut self) -> Result<&'a str> {
+        self.peek();
+        let start = self.pos;
+        let bytes = self.raw.as_bytes();
+        while self.pos < bytes.len()
+            && !matches!(bytes[self.pos], b',' | b'}' | b']')
+            && !bytes[self.pos].is_ascii_whitespace()
+        {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Err(anyhow!(
+                "invalid transaction metadata: expected value at byte {start}"
+            ));
+        }
+        Ok(&self.raw[start..self.pos])
+    }
+
+    fn skip_nested(&mut self) -> Result<()> {
+        let mut depth = 0usize;
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.raw_string()?;
+                }
+                Some(b'{' | b'[') => {
+                    depth += 1;
+                    self.pos += 1;
+                }
+                Some(b'}' | b']') => {
+                    depth = depth
+                        .checked_sub(1)
+                        .ok_or_else(|| anyhow!("invalid transaction metadata: unbalanced value"))?;
+                    self.pos += 1;
+                    if depth == 0 {
+                        return Ok(());
+                    }
+                }
+                Some(_) => self.pos += 1,
+                None => {
+                    return Err(anyhow!(
+                        "invalid transaction metadata: unterminated nested value"
+                    ))
+                }
+            }
+        }
+    }
+}
+
 fn escape_json(value: &str) -> String {
     value
         .replace('\\', "\\\\")
@@ -1,10 +1,16 @@
 use anyhow::{anyhow, Result};
+use crosspack_core::ArtifactFileMapping;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::process::Command;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
 pub struct InstallReceipt {
     pub name: String,
     pub version: String,
-    pub dependencies: Vec<String>,
+    pub dependencies: Vec<ResolvedDependency>,
     pub target: Option<String>,
     pub artifact_url: Option<String>,
     pub artifact_sha256: Option<String>,
@@ -16,14 +22,37 @@ pub struct InstallReceipt {
     pub install_reason: InstallReason,
     pub install_status: String,
     pub installed_at_unix: u64,
+    pub file_count: Option<u64>,
+    pub installed_bytes: Option<u64>,
+    pub deprecated: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct ResolvedDependency {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct GuiExposureAsset {
     pub key: String,
     pub rel_path: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GuiExposureOutcome {
+    pub assets: Vec<GuiExposureAsset>,
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExposureResult {
+    pub exposed_bins: Vec<String>,
+    pub exposed_completions: Vec<String>,
+    pub gui_assets: Vec<GuiExposureAsset>,
+    pub gui_changed: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GuiNativeRegistrationRecord {
     pub key: String,
@@ -73,6 +102,14 @@ pub struct TransactionMetadata {
     pub snapshot_id: Option<String>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveTransactionMarker {
+    pub txid: String,
+    pub pid: Option<u32>,
+    pub started_at_unix: Option<u64>,
+    pub process_start_ticks: Option<u64>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct TransactionJournalEntry {
     pub seq: u64,
@@ -81,14 +118,36 @@ pub struct TransactionJournalEntry {
     pub path: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum InstallReason {
+    #[default]
     Root,
     Dependency,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReceiptSignaturePolicy {
+    #[default]
+    Ignore,
+    Warn,
+    Enforce,
+}
+
+/// How `expected_sha256` is enforced. `DevMode` lets the empty or `"dev"` sentinel
+/// skip verification during local packaging; the CLI always installs with
+/// `Production`, which rejects the sentinel outright.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChecksumPolicy {
+    #[default]
+    Production,
+    DevMode,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum InstallMode {
+    #[default]
     Managed,
     Native,
 }
@@ -109,11 +168,161 @@ impl Default for InstallInteractionPolicy {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveLimits {
+    pub max_total_bytes: u64,
+    pub max_entries: u64,
+    pub max_entry_bytes: u64,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_total_bytes: 16 * 1024 * 1024 * 1024,
+            max_entries: 1_000_000,
+            max_entry_bytes: 8 * 1024 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveLimitKind {
+    TotalBytes,
+    Entries,
+    EntryBytes,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArchiveLimitExceeded {
+    pub kind: ArchiveLimitKind,
+    pub limit: u64,
+}
+
+impl fmt::Display for ArchiveLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match self.kind {
+            ArchiveLimitKind::TotalBytes => "total extracted bytes",
+            ArchiveLimitKind::Entries => "entry count",
+            ArchiveLimitKind::EntryBytes => "entry size",
+        };
+        write!(f, "archive limit exceeded: {what} exceeds {}", self.limit)
+    }
+}
+
+impl std::error::Error for ArchiveLimitExceeded {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InstallPermissionsPolicy {
+    pub file_mode_mask: u32,
+    pub directory_mode: Option<u32>,
+}
+
+impl InstallPermissionsPolicy {
+    pub fn shared_prefix() -> Self {
+        Self {
+            file_mode_mask: 0o022,
+            directory_mode: Some(0o755),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvPolicy {
+    pub inherited: Vec<String>,
+    pub declared: BTreeMap<String, String>,
+}
+
+// The default inherits only what build tools commonly need to run: the search
+// path, the user's home and temp dirs, and locale/terminal settings.
+impl Default for EnvPolicy {
+    fn default() -> Self {
+        let inherited: &[&str] = if cfg!(windows) {
+            &[
+                "PATH",
+                "PATHEXT",
+                "SYSTEMROOT",
+                "SYSTEMDRIVE",
+                "WINDIR",
+                "COMSPEC",
+                "TEMP",
+                "TMP",
+                "USERPROFILE",
+                "USERNAME",
+            ]
+        } else {
+            &[
+                "PATH", "HOME", "USER", "LOGNAME", "TMPDIR", "LANG", "LC_ALL", "TERM",
+            ]
+        };
+        Self {
+            inherited: inherited.iter().map(|name| (*name).to_string()).collect(),
+            declared: BTreeMap::new(),
+        }
+    }
+}
+
+impl EnvPolicy {
+    pub fn with_declared(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.declared.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn with_inherited(mut self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        if !self.inherited.contains(&name) {
+            self.inherited.push(name);
+        }
+        self
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for name in self.inherited.iter().chain(self.declared.keys()) {
+            if !is_valid_env_var_name(name) {
+                return Err(anyhow!("invalid environment variable name: '{name}'"));
+            }
+        }
+        for (name, value) in &self.declared {
+            if value.contains('\0') || value.contains('\n') || value.contains('\r') {
+                return Err(anyhow!(
+                    "environment variable '{name}' value must not contain NUL or newline characters"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn apply_to_command(&self, command: &mut Command) -> Result<()> {
+        self.validate()?;
+        command.env_clear();
+        for name in &self.inherited {
+            if let Some(value) = std::env::var_os(name) {
+                command.env(name, value);
+            }
+        }
+        command.envs(&self.declared);
+        Ok(())
+    }
+}
+
+fn is_valid_env_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    (first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct ArtifactInstallOptions<'a> {
     pub strip_components: u32,
     pub artifact_root: Option<&'a str>,
+    pub file_mappings: &'a [ArtifactFileMapping],
     pub install_mode: InstallMode,
     pub interaction_policy: InstallInteractionPolicy,
+    pub permissions_policy: InstallPermissionsPolicy,
+    pub expected_sha256: Option<&'a str>,
+    pub checksum_policy: ChecksumPolicy,
+    pub archive_limits: ArchiveLimits,
 }
 
 impl InstallMode {
@@ -137,6 +346,32 @@ impl InstallMode {
     }
 }
 
+impl ResolvedDependency {
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+        }
+    }
+
+    pub(crate) fn receipt_token(&self) -> String {
+        format!("{}@{}", self.name, self.version)
+    }
+
+    pub(crate) fn parse_receipt_token(value: &str) -> Result<Self> {
+        match value.split_once('@') {
+            Some((name, version)) if !name.is_empty() && !version.is_empty() => {
+                Ok(Self::new(name, version))
+            }
+            _ => Err(anyhow!("invalid resolved_dependency: {value}")),
+        }
+    }
+
+    pub(crate) fn parse_legacy_receipt_token(value: &str) -> Option<Self> {
+        Self::parse_receipt_token(value).ok()
+    }
+}
+
 impl InstallReason {
     pub(crate) fn as_str(&self) -> &'static str {
         match self {
@@ -168,9 +403,17 @@ pub struct UninstallResult {
     pub version: Option<String>,
     pub status: UninstallStatus,
     pub pruned_dependencies: Vec<String>,
+    pub orphaned_dependencies: Vec<String>,
     pub blocked_by_roots: Vec<String>,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AutoremovePolicy {
+    #[default]
+    Prune,
+    Keep,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NativeServiceAction {
     Status,
@@ -185,3 +428,115 @@ pub struct NativeServiceOutcome {
     pub applied: bool,
     pub reason_code: String,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityIssue {
+    OrphanedBinary {
+        name: String,
+    },
+    OrphanedCompletion {
+        rel_path: String,
+    },
+    OrphanedGuiAsset {
+        rel_path: String,
+    },
+    StaleReceipt {
+        name: String,
+        version: String,
+    },
+    /// The recorded version dir is gone but `pkgs/<name>` holds other versions.
+    VersionMismatch {
+        name: String,
+        recorded: String,
+        found: Vec<String>,
+    },
+    MissingCache {
+        name: String,
+        cache_path: String,
+    },
+    /// The package tree no longer matches the size recorded in its receipt.
+    SizeDrift {
+        name: String,
+        version: String,
+        recorded_file_count: u64,
+        recorded_bytes: u64,
+        actual_file_count: u64,
+        actual_bytes: u64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepairAction {
+    Removed,
+    AlreadyResolved,
+    NeedsRedownload,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairOutcome {
+    pub issue: IntegrityIssue,
+    pub action: RepairAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallArtifactResult {
+    pub dest: PathBuf,
+    /// Regular files and symlinks installed, counted as `measure_install_tree` does.
+    pub file_count: u64,
+    pub total_bytes: u64,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReexposeReport {
+    pub packages: Vec<ReexposeResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReexposeResult {
+    pub name: String,
+    pub version: String,
+    /// Prefix-relative paths whose exposed entry differed after re-creation.
+    pub changed: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl ReexposeReport {
+    pub fn has_errors(&self) -> bool {
+        self.packages
+            .iter()
+            .any(|package| !package.errors.is_empty())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyStatus {
+    Verified,
+    Drifted {
+        modified: Vec<String>,
+        missing: Vec<String>,
+        extra: Vec<String>,
+    },
+    MissingPackageDir,
+    MissingCache {
+        cache_path: String,
+    },
+    CacheChecksumMismatch {
+        cache_path: String,
+        expected: String,
+        actual: String,
+    },
+    Skipped {
+        reason: String,
+    },
+    Failed {
+        reason: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyResult {
+    pub name: String,
+    pub version: String,
+    pub status: VerifyStatus,
+}
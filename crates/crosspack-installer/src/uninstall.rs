@@ -1,20 +1,27 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
-use std::path::{Component, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 use crate::exposure::{
-    clear_gui_exposure_state, read_gui_exposure_state, remove_exposed_binary,
+    clear_binary_aliases, clear_gui_exposure_state, read_gui_exposure_state, remove_exposed_binary,
     remove_exposed_completion, remove_exposed_gui_asset,
 };
 use crate::fs_utils::remove_file_if_exists;
+use crate::integrity::clear_installed_file_digests;
 use crate::native::{
     clear_native_sidecar_state, remove_package_native_gui_registrations_best_effort,
     run_package_native_uninstall_actions,
 };
-use crate::receipts::{clear_declared_services_state, read_install_receipts};
+use crate::receipts::{
+    clear_declared_services_state, read_install_receipts, remove_install_receipt_signature,
+};
+use crate::transactions::{
+    process_is_alive, read_active_transaction_marker, read_transaction_metadata,
+};
 use crate::{
-    InstallMode, InstallReason, InstallReceipt, PrefixLayout, UninstallResult, UninstallStatus,
+    AutoremovePolicy, InstallMode, InstallReason, InstallReceipt, PrefixLayout, UninstallResult,
+    UninstallStatus,
 };
 
 pub fn uninstall_package(layout: &PrefixLayout, name: &str) -> Result<UninstallResult> {
@@ -40,6 +47,23 @@ pub fn uninstall_package_with_dependency_overrides_and_ignored_roots(
     dependency_overrides: &HashMap<String, Vec<String>>,
     ignored_root_names: &HashSet<String>,
 ) -> Result<UninstallResult> {
+    uninstall_package_with_autoremove_policy(
+        layout,
+        name,
+        dependency_overrides,
+        ignored_root_names,
+        AutoremovePolicy::default(),
+    )
+}
+
+pub fn uninstall_package_with_autoremove_policy(
+    layout: &PrefixLayout,
+    name: &str,
+    dependency_overrides: &HashMap<String, Vec<String>>,
+    ignored_root_names: &HashSet<String>,
+    autoremove_policy: AutoremovePolicy,
+) -> Result<UninstallResult> {
+    ensure_no_inflight_operation(layout, name)?;
     let receipts = read_install_receipts(layout)?;
     let Some(target_receipt) = receipts
         .iter()
@@ -51,6 +75,7 @@ pub fn uninstall_package_with_dependency_overrides_and_ignored_roots(
             version: None,
             status: UninstallStatus::NotInstalled,
             pruned_dependencies: Vec::new(),
+            orphaned_dependencies: Vec::new(),
             blocked_by_roots: Vec::new(),
         });
     };
@@ -79,6 +104,7 @@ pub fn uninstall_package_with_dependency_overrides_and_ignored_roots(
             version: Some(target_receipt.version),
             status: UninstallStatus::BlockedByDependents,
             pruned_dependencies: Vec::new(),
+            orphaned_dependencies: Vec::new(),
             blocked_by_roots,
         });
     }
@@ -91,6 +117,10 @@ pub fn uninstall_package_with_dependency_overrides_and_ignored_roots(
         .cloned()
         .collect::<Vec<_>>();
     pruned_dependencies.sort();
+    let orphaned_dependencies = match autoremove_policy {
+        AutoremovePolicy::Prune => Vec::new(),
+        AutoremovePolicy::Keep => std::mem::take(&mut pruned_dependencies),
+    };
 
     let mut removal_names = Vec::with_capacity(pruned_dependencies.len() + 1);
     removal_names.push(name.to_string());
@@ -114,13 +144,14 @@ pub fn uninstall_package_with_dependency_overrides_and_ignored_roots(
         }
     }
 
-    let referenced_cache_paths: HashSet<String> = receipt_map
+    let referenced_cache_paths: HashSet<PathBuf> = receipt_map
         .iter()
         .filter(|(receipt_name, _)| !removal_names_set.contains(receipt_name.as_str()))
-        .filter_map(|(_, receipt)| receipt.cache_path.clone())
+        .filter_map(|(_, receipt)| receipt.cache_path.as_deref())
+        .map(|cache_path| layout.resolve_cache_path(cache_path))
         .collect();
     for cache_path in removed_cache_paths {
-        if referenced_cache_paths.contains(&cache_path) {
+        if referenced_cache_paths.contains(&layout.resolve_cache_path(&cache_path)) {
             continue;
         }
         if let Some(cache_path) = safe_cache_prune_path(layout, &cache_path) {
@@ -134,6 +165,47 @@ pub fn uninstall_package_with_dependency_overrides_and_ignored_roots(
         version: Some(target_receipt.version),
         status: target_status,
         pruned_dependencies,
+        orphaned_dependencies,
+        blocked_by_roots: Vec::new(),
+    })
+}
+
+pub fn uninstall_package_version(
+    layout: &PrefixLayout,
+    name: &str,
+    version: &str,
+) -> Result<UninstallResult> {
+    ensure_no_inflight_operation(layout, name)?;
+    let receipts = read_install_receipts(layout)?;
+    let active_version = receipts
+        .iter()
+        .find(|receipt| receipt.name == name)
+        .map(|receipt| receipt.version.clone());
+    if active_version.as_deref() == Some(version) {
+        return uninstall_package(layout, name);
+    }
+
+    let package_dir = layout.package_dir(name, version);
+    if !package_dir.exists() {
+        return Ok(UninstallResult {
+            name: name.to_string(),
+            version: None,
+            status: UninstallStatus::NotInstalled,
+            pruned_dependencies: Vec::new(),
+            orphaned_dependencies: Vec::new(),
+            blocked_by_roots: Vec::new(),
+        });
+    }
+
+    fs::remove_dir_all(&package_dir)
+        .with_context(|| format!("failed to remove package dir: {}", package_dir.display()))?;
+
+    Ok(UninstallResult {
+        name: name.to_string(),
+        version: Some(version.to_string()),
+        status: UninstallStatus::Uninstalled,
+        pruned_dependencies: Vec::new(),
+        orphaned_dependencies: Vec::new(),
         blocked_by_roots: Vec::new(),
     })
 }
@@ -205,8 +277,44 @@ fn collect_remaining_roots(
     remaining_roots
 }
 
-fn safe_cache_prune_path(layout: &PrefixLayout, cache_path: &str) -> Option<PathBuf> {
-    let path = PathBuf::from(cache_path);
+// Another live process whose active transaction has captured rollback state for
+// `name` is still mutating that package, so removing it underneath would race.
+fn ensure_no_inflight_operation(layout: &PrefixLayout, name: &str) -> Result<()> {
+    let Some(marker) = read_active_transaction_marker(layout)? else {
+        return Ok(());
+    };
+    if let Some(pid) = marker.pid {
+        if pid == std::process::id() || !process_is_alive(pid)? {
+            return Ok(());
+        }
+    }
+    if let Some(metadata) = read_transaction_metadata(layout, &marker.txid)? {
+        if matches!(
+            metadata.status.as_str(),
+            "committed" | "rolled_back" | "failed"
+        ) {
+            return Ok(());
+        }
+    }
+
+    let staged = layout
+        .transaction_staging_path(&marker.txid)
+        .join("rollback")
+        .join(name);
+    if staged.exists() {
+        return Err(anyhow!(
+            "operation in progress for {name} (transaction {})",
+            marker.txid
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn safe_cache_prune_path(
+    layout: &PrefixLayout,
+    cache_path: impl AsRef<Path>,
+) -> Option<PathBuf> {
+    let path = layout.resolve_cache_path(cache_path);
     if !path.is_absolute() {
         return None;
     }
@@ -225,7 +333,7 @@ fn safe_cache_prune_path(layout: &PrefixLayout, cache_path: &str) -> Option<Path
     Some(path)
 }
 
-fn remove_receipt_artifacts(
+pub(crate) fn remove_receipt_artifacts(
     layout: &PrefixLayout,
     receipt: &InstallReceipt,
 ) -> Result<UninstallStatus> {
@@ -253,6 +361,8 @@ fn remove_receipt_artifacts(
         remove_exposed_gui_asset(layout, asset)?;
     }
     clear_gui_exposure_state(layout, &receipt.name)?;
+    clear_binary_aliases(layout, &receipt.name)?;
+    clear_installed_file_digests(layout, &receipt.name)?;
     if receipt.install_mode != InstallMode::Native {
         let _native_gui_warnings =
             remove_package_native_gui_registrations_best_effort(layout, &receipt.name)?;
@@ -265,6 +375,7 @@ fn remove_receipt_artifacts(
             receipt_path.display()
         )
     })?;
+    remove_install_receipt_signature(layout, &receipt.name)?;
     clear_declared_services_state(layout, &receipt.name)?;
 
     Ok(if package_existed {
@@ -281,7 +392,7 @@ fn dependency_map(receipts: &HashMap<String, InstallReceipt>) -> HashMap<String,
             let deps = receipt
                 .dependencies
                 .iter()
-                .filter_map(|entry| parse_dependency_name(entry))
+                .map(|dependency| dependency.name.as_str())
                 .filter(|dep| receipts.contains_key(*dep))
                 .map(ToOwned::to_owned)
                 .collect::<BTreeSet<_>>();
@@ -303,10 +414,6 @@ fn apply_dependency_overrides(
     }
 }
 
-fn parse_dependency_name(entry: &str) -> Option<&str> {
-    entry.split_once('@').map(|(name, _)| name)
-}
-
 fn reachable_packages(
     roots: &[String],
     dependencies: &HashMap<String, BTreeSet<String>>,
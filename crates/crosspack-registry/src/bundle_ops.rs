@@ -0,0 +1,88 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{Context, Result};
+
+const BUNDLE_EXTENSIONS: [&str; 4] = [".tar", ".tar.gz", ".tgz", ".tar.zst"];
+
+pub(crate) fn is_registry_bundle_path(path: &Path) -> bool {
+    if !path.is_file() {
+        return false;
+    }
+    let file_name = path
+        .file_name()
+        .map(|value| value.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    BUNDLE_EXTENSIONS
+        .iter()
+        .any(|extension| file_name.ends_with(extension))
+}
+
+pub(crate) fn read_bundle_member(
+    bundle_path: &Path,
+    member: &str,
+    source_name: &str,
+) -> Result<Vec<u8>> {
+    for candidate in [member.to_string(), format!("./{member}")] {
+        let output = Command::new("tar")
+            .arg("-xOf")
+            .arg(bundle_path)
+            .arg(&candidate)
+            .output()
+            .with_context(|| {
+                format!(
+                    "source-sync-failed: source '{}' failed launching tar for {}",
+                    source_name,
+                    bundle_path.display()
+                )
+            })?;
+        if output.status.success() {
+            return Ok(output.stdout);
+        }
+    }
+
+    anyhow::bail!(
+        "source-metadata-invalid: source '{}' bundle {} is missing {}",
+        source_name,
+        bundle_path.display(),
+        member
+    )
+}
+
+pub(crate) fn extract_bundle(
+    bundle_path: &Path,
+    staged_root: &Path,
+    source_name: &str,
+) -> Result<()> {
+    fs::create_dir_all(staged_root).with_context(|| {
+        format!(
+            "source-sync-failed: source '{}' failed creating staging dir {}",
+            source_name,
+            staged_root.display()
+        )
+    })?;
+
+    let output = Command::new("tar")
+        .arg("-xf")
+        .arg(bundle_path)
+        .arg("-C")
+        .arg(staged_root)
+        .output()
+        .with_context(|| {
+            format!(
+                "source-sync-failed: source '{}' failed launching tar for {}",
+                source_name,
+                bundle_path.display()
+            )
+        })?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "source-sync-failed: source '{}' failed extracting bundle {}: {}",
+            source_name,
+            bundle_path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
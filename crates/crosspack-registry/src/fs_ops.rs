@@ -1,9 +1,9 @@
 use std::collections::VecDeque;
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::{Context, Result};
+use crosspack_core::{Clock, SystemClock};
 use crosspack_security::sha256_hex;
 
 pub(crate) fn copy_source_to_temp(
@@ -81,6 +81,30 @@ pub(crate) fn copy_dir_recursive(source_root: &Path, destination_root: &Path) ->
     Ok(())
 }
 
+pub(crate) fn move_dir_with_copy_fallback_using<R>(
+    from: &Path,
+    to: &Path,
+    rename: R,
+) -> std::io::Result<()>
+where
+    R: Fn(&Path, &Path) -> std::io::Result<()>,
+{
+    match rename(from, to) {
+        Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {
+            if let Err(copy_err) = copy_dir_recursive(from, to) {
+                let _ = fs::remove_dir_all(to);
+                return Err(std::io::Error::other(format!(
+                    "cross-device copy from {} to {} failed: {copy_err:#}",
+                    from.display(),
+                    to.display()
+                )));
+            }
+            fs::remove_dir_all(from)
+        }
+        result => result,
+    }
+}
+
 pub(crate) fn validate_staged_registry_layout(staged_root: &Path, source_name: &str) -> Result<()> {
     let registry_pub = staged_root.join("registry.pub");
     if !registry_pub.is_file() {
@@ -112,8 +136,51 @@ pub(crate) fn validate_staged_registry_layout(staged_root: &Path, source_name: &
     Ok(())
 }
 
-pub(crate) fn count_manifest_files(releases_root: &Path) -> Result<u64> {
+const STRICT_REGISTRY_TOP_LEVEL_ENTRIES: &[&str] = &[
+    ".git",
+    "keys",
+    "packages",
+    "registry.pub",
+    "releases",
+    "snapshot.json",
+];
+
+pub(crate) fn validate_strict_registry_entries(
+    staged_root: &Path,
+    source_name: &str,
+    extra_allowed: &[&str],
+) -> Result<()> {
+    let mut unexpected = Vec::new();
+    for entry in fs::read_dir(staged_root).with_context(|| {
+        format!(
+            "failed reading snapshot directory {}",
+            staged_root.display()
+        )
+    })? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        if !STRICT_REGISTRY_TOP_LEVEL_ENTRIES.contains(&name.as_str())
+            && !extra_allowed.contains(&name.as_str())
+        {
+            unexpected.push(name);
+        }
+    }
+
+    if !unexpected.is_empty() {
+        unexpected.sort();
+        anyhow::bail!(
+            "source-metadata-invalid: source '{}' snapshot has unexpected top-level entries: {}",
+            source_name,
+            unexpected.join(", ")
+        );
+    }
+    Ok(())
+}
+
+// Returns the number of release manifests and their combined size; signatures,
+// keys, and VCS metadata outside `releases/*.toml` are not counted.
+pub(crate) fn count_manifest_files(releases_root: &Path) -> Result<(u64, u64)> {
     let mut count = 0_u64;
+    let mut total_bytes = 0_u64;
     let mut queue: VecDeque<PathBuf> = VecDeque::new();
     queue.push_back(releases_root.to_path_buf());
 
@@ -125,16 +192,19 @@ pub(crate) fn count_manifest_files(releases_root: &Path) -> Result<u64> {
             let path = entry.path();
             let file_type = entry.file_type()?;
             if file_type.is_dir() {
-                queue.push_back(path);
+                if entry.file_name() != ".git" {
+                    queue.push_back(path);
+                }
             } else if file_type.is_file()
                 && path.extension().and_then(|value| value.to_str()) == Some("toml")
             {
                 count += 1;
+                total_bytes += entry.metadata()?.len();
             }
         }
     }
 
-    Ok(count)
+    Ok((count, total_bytes))
 }
 
 pub(crate) fn compute_filesystem_snapshot_id(staged_root: &Path) -> Result<String> {
@@ -199,16 +269,6 @@ pub(crate) fn normalize_path_for_snapshot(path: &Path) -> String {
         .join("/")
 }
 
-pub(crate) fn current_unix_timestamp() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs()
-}
-
 pub(crate) fn unique_suffix() -> u128 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_nanos()
+    SystemClock.unix_nanos()
 }
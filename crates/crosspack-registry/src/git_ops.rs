@@ -1,10 +1,31 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use anyhow::{Context, Result};
 
-pub(crate) fn base_git_command() -> Command {
-    let mut command = Command::new("git");
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitConfig {
+    pub program: PathBuf,
+    pub extra_env: Vec<(String, String)>,
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            program: PathBuf::from("git"),
+            extra_env: Vec::new(),
+        }
+    }
+}
+
+pub(crate) fn base_git_command(config: &GitConfig) -> Command {
+    let mut command = Command::new(&config.program);
+    command.envs(
+        config
+            .extra_env
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str())),
+    );
     command
         .arg("-c")
         .arg("core.autocrlf=false")
@@ -16,9 +37,19 @@ pub(crate) fn base_git_command() -> Command {
     command
 }
 
-pub(crate) fn run_git_clone(location: &str, destination: &Path, source_name: &str) -> Result<()> {
-    let output = base_git_command()
-        .arg("clone")
+pub(crate) fn run_git_clone(
+    config: &GitConfig,
+    location: &str,
+    destination: &Path,
+    source_name: &str,
+    sparse: bool,
+) -> Result<()> {
+    let mut command = base_git_command(config);
+    command.arg("clone");
+    if sparse {
+        command.arg("--sparse");
+    }
+    let output = command
         .arg("--")
         .arg(location)
         .arg(destination)
@@ -39,8 +70,13 @@ pub(crate) fn run_git_clone(location: &str, destination: &Path, source_name: &st
     Ok(())
 }
 
-pub(crate) fn run_git_command(repo_root: &Path, args: &[&str], source_name: &str) -> Result<()> {
-    let output = base_git_command()
+pub(crate) fn run_git_command(
+    config: &GitConfig,
+    repo_root: &Path,
+    args: &[&str],
+    source_name: &str,
+) -> Result<()> {
+    let output = base_git_command(config)
         .args(args)
         .current_dir(repo_root)
         .output()
@@ -62,8 +98,12 @@ pub(crate) fn run_git_command(repo_root: &Path, args: &[&str], source_name: &str
     Ok(())
 }
 
-pub(crate) fn git_head_snapshot_id(repo_root: &Path, source_name: &str) -> Result<String> {
-    let output = base_git_command()
+pub(crate) fn git_head_snapshot_id(
+    config: &GitConfig,
+    repo_root: &Path,
+    source_name: &str,
+) -> Result<String> {
+    let output = base_git_command(config)
         .arg("rev-parse")
         .arg("HEAD")
         .current_dir(repo_root)
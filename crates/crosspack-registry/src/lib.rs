@@ -1,40 +1,51 @@
+mod bundle_ops;
 mod fs_ops;
 mod git_ops;
 mod registry_index;
+mod security_policy;
 mod snapshot_state;
 mod source_state;
 mod source_store;
 mod source_sync;
 mod source_types;
 
-pub use registry_index::{ConfiguredRegistryIndex, RegistryIndex};
-pub use source_store::RegistrySourceStore;
+pub use git_ops::GitConfig;
+pub use registry_index::{
+    ConfiguredRegistryIndex, InventoryPolicy, RegistryIndex, SearchHit, DEFAULT_MAX_MANIFEST_BYTES,
+};
+pub use security_policy::SecurityPolicy;
+pub use source_store::{DuplicateSourcePolicy, RegistrySourceStore, SnapshotLayoutPolicy};
 pub use source_types::{
     RegistrySourceCommunity, RegistrySourceKind, RegistrySourceRecord, RegistrySourceSnapshotState,
-    RegistrySourceWithSnapshotState, RegistrySourceWithSnapshotStatus, SourceUpdateResult,
-    SourceUpdateStatus,
+    RegistrySourceWithSnapshotState, RegistrySourceWithSnapshotStatus, SourceStatus,
+    SourceUpdateResult, SourceUpdateStatus,
 };
 
+pub(crate) use bundle_ops::{extract_bundle, is_registry_bundle_path, read_bundle_member};
 pub(crate) use fs_ops::{
     compute_filesystem_snapshot_id, copy_source_to_temp, count_manifest_files,
-    current_unix_timestamp, unique_suffix, validate_staged_registry_layout,
+    move_dir_with_copy_fallback_using, unique_suffix, validate_staged_registry_layout,
+    validate_strict_registry_entries,
 };
 pub(crate) use git_ops::{git_head_snapshot_id, run_git_clone, run_git_command};
 pub(crate) use snapshot_state::{
-    read_snapshot_id, read_snapshot_state, source_has_ready_snapshot, write_snapshot_file,
+    read_snapshot_id, read_snapshot_state, read_snapshot_state_with_summary,
+    source_has_ready_snapshot, write_snapshot_file,
 };
 pub(crate) use source_state::{
-    parse_source_state_file, select_update_sources, sort_sources,
-    validate_community_recipe_catalog_path, validate_source_fingerprint, validate_source_name,
-    RegistrySourceStateFile,
+    find_same_location_source, parse_source_state_file, select_update_sources, sort_sources,
+    source_content_root, validate_community_recipe_catalog_path, validate_source_location,
+    validate_source_name, validate_source_record, RegistrySourceStateFile,
 };
-pub(crate) use source_sync::update_source;
 pub(crate) use source_sync::verify_community_recipe_catalog_policy;
+pub(crate) use source_sync::{discover_source_fingerprint, update_source};
 
 #[cfg(test)]
 pub(crate) use git_ops::derive_snapshot_id_from_full_git_sha;
 #[cfg(test)]
-pub(crate) use source_sync::combine_replace_restore_errors;
+pub(crate) use source_sync::{
+    combine_replace_restore_errors, finalize_staged_source_update_with_rename,
+};
 
 #[cfg(test)]
 mod tests;
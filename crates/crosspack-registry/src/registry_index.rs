@@ -1,26 +1,54 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::fs;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use crosspack_core::PackageManifest;
-use crosspack_security::verify_ed25519_signature_hex;
+use crosspack_security::{sha256_hex, verify_ed25519_signature_hex};
+use semver::Version;
 use toml::value::Table;
 use toml::Value;
 
 use crate::{
-    parse_source_state_file, sort_sources, source_has_ready_snapshot,
-    verify_community_recipe_catalog_policy, RegistrySourceRecord, RegistrySourceStateFile,
+    find_same_location_source, parse_source_state_file, sort_sources, source_content_root,
+    source_has_ready_snapshot, verify_community_recipe_catalog_policy, RegistrySourceRecord,
+    RegistrySourceStateFile, SecurityPolicy,
 };
 
 #[derive(Debug, Clone)]
 pub struct RegistryIndex {
     root: PathBuf,
+    namespace_separator: Option<char>,
+    security_policy: SecurityPolicy,
+    expected_fingerprint: Option<String>,
+    max_manifest_bytes: u64,
+    index_dirs: Vec<PathBuf>,
 }
 
+pub const DEFAULT_MAX_MANIFEST_BYTES: u64 = 256 * 1024;
+
 #[derive(Debug, Clone)]
 pub struct ConfiguredRegistryIndex {
+    state_root: PathBuf,
+    security_policy: SecurityPolicy,
     sources: Vec<ConfiguredSnapshotSource>,
+    redundant_sources: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    pub name: String,
+    pub latest_version: String,
+    pub source: String,
+    pub installed: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InventoryPolicy {
+    #[default]
+    Strict,
+    SkipInvalid,
 }
 
 #[derive(Debug, Clone)]
@@ -31,7 +59,64 @@ struct ConfiguredSnapshotSource {
 
 impl RegistryIndex {
     pub fn open(root: impl Into<PathBuf>) -> Self {
-        Self { root: root.into() }
+        Self::open_with_security_policy(root, SecurityPolicy::default())
+    }
+
+    pub fn open_with_security_policy(
+        root: impl Into<PathBuf>,
+        security_policy: SecurityPolicy,
+    ) -> Self {
+        Self {
+            root: root.into(),
+            namespace_separator: None,
+            security_policy,
+            expected_fingerprint: None,
+            max_manifest_bytes: DEFAULT_MAX_MANIFEST_BYTES,
+            index_dirs: Vec::new(),
+        }
+    }
+
+    pub fn open_with_fingerprint(
+        root: impl Into<PathBuf>,
+        expected_fingerprint: &str,
+    ) -> Result<Self> {
+        let mut index = Self::open(root);
+        index.expected_fingerprint = Some(expected_fingerprint.to_string());
+        index.read_trusted_key()?;
+        Ok(index)
+    }
+
+    pub fn with_namespace_separator(mut self, separator: char) -> Self {
+        self.namespace_separator = Some(separator);
+        self
+    }
+
+    pub fn with_max_manifest_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_manifest_bytes = max_bytes;
+        self
+    }
+
+    /// Reads `packages/` and `releases/` from these subdirectories of the root
+    /// instead of the root itself. Earlier directories take precedence: a version
+    /// present in several of them is taken from the first. `registry.pub` and
+    /// `keys/` stay at the root and verify every directory.
+    pub fn with_index_dirs<I, P>(mut self, dirs: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.index_dirs = dirs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    fn index_roots(&self) -> Vec<PathBuf> {
+        if self.index_dirs.is_empty() {
+            return vec![self.root.clone()];
+        }
+        self.index_dirs
+            .iter()
+            .map(|dir| self.root.join(dir))
+            .collect()
     }
 
     pub fn root(&self) -> &Path {
@@ -39,21 +124,82 @@ impl RegistryIndex {
     }
 
     pub fn search_names(&self, needle: &str) -> Result<Vec<String>> {
-        let releases_root = self.root.join("releases");
+        let mut names = Vec::new();
+        for name in self.all_package_names()? {
+            if name.contains(needle) {
+                let manifests = self.package_versions(&name)?;
+                if !manifests.is_empty() {
+                    names.push(name);
+                }
+            }
+        }
+
+        Ok(names)
+    }
+
+    pub fn inventory(&self) -> Result<Vec<(String, Version)>> {
+        self.inventory_with_policy(InventoryPolicy::Strict)
+    }
+
+    pub fn inventory_with_policy(&self, policy: InventoryPolicy) -> Result<Vec<(String, Version)>> {
+        let mut inventory = Vec::new();
+        for name in self.all_package_names()? {
+            let manifests = match self.package_versions(&name) {
+                Ok(manifests) => manifests,
+                Err(_) if policy == InventoryPolicy::SkipInvalid => continue,
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("failed to inventory package '{name}'"));
+                }
+            };
+            inventory.extend(
+                manifests
+                    .into_iter()
+                    .map(|manifest| (name.clone(), manifest.version)),
+            );
+        }
+        inventory.sort();
+        Ok(inventory)
+    }
+
+    pub fn all_package_names(&self) -> Result<Vec<String>> {
+        let mut names = BTreeSet::new();
+        for index_root in self.index_roots() {
+            names.extend(self.package_names_in(&index_root)?);
+        }
+        Ok(names.into_iter().collect())
+    }
+
+    fn package_names_in(&self, index_root: &Path) -> Result<Vec<String>> {
+        let releases_root = index_root.join("releases");
         if !releases_root.exists() {
             return Ok(Vec::new());
         }
 
         let mut names = Vec::new();
-        for entry in fs::read_dir(releases_root).context("failed to read registry releases")? {
+        for entry in fs::read_dir(&releases_root).context("failed to read registry releases")? {
             let entry = entry?;
-            if entry.file_type()?.is_dir() {
-                let name = entry.file_name().to_string_lossy().to_string();
-                if name.contains(needle) {
-                    let manifests = self.package_versions(&name)?;
-                    if !manifests.is_empty() {
-                        names.push(name);
-                    }
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(separator) = self.namespace_separator else {
+                names.push(name);
+                continue;
+            };
+            if Self::is_package_release_dir(index_root, &entry.path(), &name)? {
+                names.push(name);
+                continue;
+            }
+
+            for scoped_entry in fs::read_dir(entry.path())
+                .with_context(|| format!("failed to read registry namespace: {name}"))?
+            {
+                let scoped_entry = scoped_entry?;
+                if scoped_entry.file_type()?.is_dir() {
+                    let scoped_name = scoped_entry.file_name().to_string_lossy().to_string();
+                    names.push(format!("{name}{separator}{scoped_name}"));
                 }
             }
         }
@@ -62,36 +208,175 @@ impl RegistryIndex {
         Ok(names)
     }
 
+    fn is_package_release_dir(index_root: &Path, release_dir: &Path, name: &str) -> Result<bool> {
+        if index_root
+            .join("packages")
+            .join(format!("{name}.toml"))
+            .exists()
+        {
+            return Ok(true);
+        }
+
+        for entry in fs::read_dir(release_dir)
+            .with_context(|| format!("failed to read release directory: {name}"))?
+        {
+            let entry = entry?;
+            if entry.file_type()?.is_file()
+                && entry.path().extension().and_then(|v| v.to_str()) == Some("toml")
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn package_paths(&self, index_root: &Path, package: &str) -> Result<(PathBuf, PathBuf)> {
+        let segments: Vec<&str> = match self.namespace_separator {
+            Some(separator) => package.split(separator).collect(),
+            None => vec![package],
+        };
+        if segments.len() > 2
+            || segments
+                .iter()
+                .any(|segment| segment.is_empty() || *segment == "." || *segment == "..")
+        {
+            anyhow::bail!("invalid package name for registry index: '{package}'");
+        }
+
+        let mut release_dir = index_root.join("releases");
+        let mut package_template_path = index_root.join("packages");
+        for (index, segment) in segments.iter().enumerate() {
+            release_dir.push(segment);
+            if index + 1 == segments.len() {
+                package_template_path.push(format!("{segment}.toml"));
+            } else {
+                package_template_path.push(segment);
+            }
+        }
+        Ok((release_dir, package_template_path))
+    }
+
+    pub(crate) fn contains_package(&self, package: &str) -> Result<bool> {
+        for index_root in self.index_roots() {
+            let (release_dir, package_template_path) = self.package_paths(&index_root, package)?;
+            if release_dir.exists() || package_template_path.exists() {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
     pub fn package_versions(&self, package: &str) -> Result<Vec<PackageManifest>> {
-        let release_dir = self.root.join("releases").join(package);
-        let package_template_path = self.root.join("packages").join(format!("{package}.toml"));
+        if !self.contains_package(package)? {
+            return Ok(Vec::new());
+        }
+
+        let trusted_public_key_hex = self.read_trusted_key()?;
+        self.package_versions_with_trusted_key(package, trusted_public_key_hex.as_deref())
+    }
+
+    pub fn package_versions_for_target(
+        &self,
+        package: &str,
+        target: &str,
+    ) -> Result<Vec<PackageManifest>> {
+        let mut manifests = self.package_versions(package)?;
+        manifests.retain(|manifest| manifest.artifact_for_target(target).is_some());
+        Ok(manifests)
+    }
+
+    pub(crate) fn read_trusted_key(&self) -> Result<Option<String>> {
+        let trusted_key_path = self.root.join("registry.pub");
+        match fs::read(&trusted_key_path) {
+            Ok(raw) => {
+                if let Some(expected) = &self.expected_fingerprint {
+                    let actual = sha256_hex(&raw);
+                    if !actual.eq_ignore_ascii_case(expected) {
+                        self.security_policy.record_signature_rejection(
+                            &trusted_key_path,
+                            "trusted-key-fingerprint-mismatch",
+                        );
+                        anyhow::bail!(
+                            "trusted registry key fingerprint mismatch for {}: expected {}, got {}",
+                            trusted_key_path.display(),
+                            expected,
+                            actual
+                        );
+                    }
+                }
+                let content = String::from_utf8(raw).with_context(|| {
+                    format!(
+                        "trusted registry key is not UTF-8: {}",
+                        trusted_key_path.display()
+                    )
+                })?;
+                Ok(Some(content.trim().to_string()))
+            }
+            Err(err)
+                if err.kind() == std::io::ErrorKind::NotFound
+                    && self.expected_fingerprint.is_none()
+                    && !self.security_policy.require_signatures() =>
+            {
+                Ok(None)
+            }
+            Err(err) => {
+                self.security_policy
+                    .record_signature_rejection(&trusted_key_path, "trusted-key-unreadable");
+                Err(err).with_context(|| {
+                    format!(
+                        "failed to read trusted registry key: {}",
+                        trusted_key_path.display()
+                    )
+                })
+            }
+        }
+    }
+
+    pub(crate) fn package_versions_with_trusted_key(
+        &self,
+        package: &str,
+        trusted_public_key_hex: Option<&str>,
+    ) -> Result<Vec<PackageManifest>> {
+        let mut manifests: Vec<PackageManifest> = Vec::new();
+        for index_root in self.index_roots() {
+            for manifest in
+                self.package_versions_in(&index_root, package, trusted_public_key_hex)?
+            {
+                if !manifests
+                    .iter()
+                    .any(|existing| existing.version == manifest.version)
+                {
+                    manifests.push(manifest);
+                }
+            }
+        }
+
+        manifests.sort_by(|a, b| b.version.cmp(&a.version));
+        Ok(manifests)
+    }
+
+    fn package_versions_in(
+        &self,
+        index_root: &Path,
+        package: &str,
+        trusted_public_key_hex: Option<&str>,
+    ) -> Result<Vec<PackageManifest>> {
+        let (release_dir, package_template_path) = self.package_paths(index_root, package)?;
         let has_release_dir = release_dir.exists();
         let has_package_template = package_template_path.exists();
         if !has_release_dir && !has_package_template {
             return Ok(Vec::new());
         }
 
-        let trusted_key_path = self.root.join("registry.pub");
-        let trusted_public_key_hex = fs::read_to_string(&trusted_key_path).with_context(|| {
-            format!(
-                "failed to read trusted registry key: {}",
-                trusted_key_path.display()
-            )
-        })?;
-        let trusted_public_key_hex = trusted_public_key_hex.trim();
-        let key_identifier: String = trusted_public_key_hex.chars().take(16).collect();
-
-        let package_template_bytes = fs::read(&package_template_path).with_context(|| {
-            format!(
-                "failed reading package template: {}",
-                package_template_path.display()
-            )
-        })?;
-        verify_signed_toml_document(
+        let package_template_bytes = read_bounded_metadata(
+            &package_template_path,
+            self.max_manifest_bytes,
+            "package template",
+        )?;
+        self.verify_document(
             &package_template_path,
             &package_template_bytes,
             trusted_public_key_hex,
-            &key_identifier,
         )?;
         let package_template = parse_toml_table(
             &package_template_bytes,
@@ -121,14 +406,9 @@ impl RegistryIndex {
                 continue;
             }
 
-            let release_bytes = fs::read(&path)
-                .with_context(|| format!("failed reading release file: {}", path.display()))?;
-            verify_signed_toml_document(
-                &path,
-                &release_bytes,
-                trusted_public_key_hex,
-                &key_identifier,
-            )?;
+            let release_bytes =
+                read_bounded_metadata(&path, self.max_manifest_bytes, "release file")?;
+            self.verify_document(&path, &release_bytes, trusted_public_key_hex)?;
             let release_document = parse_toml_table(&release_bytes, &path, "release metadata")?;
             let merged_document = merge_manifest_documents(&package_template, &release_document);
             let merged_manifest =
@@ -146,32 +426,146 @@ impl RegistryIndex {
                     path.display()
                 )
             })?;
+            // A provider may be filed under the capability it provides, but any
+            // other name means the release sits in the wrong package directory.
+            if manifest.name != package && !manifest.provides.iter().any(|name| name == package) {
+                anyhow::bail!(
+                    "manifest name mismatch: release {} declares name '{}' but is stored under package '{}'",
+                    path.display(),
+                    manifest.name,
+                    package
+                );
+            }
             manifests.push(manifest);
         }
 
-        manifests.sort_by(|a, b| b.version.cmp(&a.version));
         Ok(manifests)
     }
+
+    fn verify_document(
+        &self,
+        document_path: &Path,
+        document_bytes: &[u8],
+        trusted_public_key_hex: Option<&str>,
+    ) -> Result<()> {
+        let Some(trusted_public_key_hex) = trusted_public_key_hex else {
+            return Ok(());
+        };
+        if !self.security_policy.require_signatures()
+            && !document_path.with_extension("toml.sig").exists()
+        {
+            return Ok(());
+        }
+
+        let default_key_identifier: String = trusted_public_key_hex.chars().take(16).collect();
+        verify_signed_toml_document(
+            document_path,
+            document_bytes,
+            self.max_manifest_bytes,
+            |key_id| match key_id {
+                Some(key_id) => Ok((
+                    self.read_keyring_key(key_id, trusted_public_key_hex)?,
+                    key_id.to_string(),
+                )),
+                None => Ok((
+                    trusted_public_key_hex.to_string(),
+                    default_key_identifier.clone(),
+                )),
+            },
+        )
+        .inspect_err(|_| {
+            self.security_policy
+                .record_signature_rejection(document_path, "signature-verification-failed");
+        })
+    }
+
+    // Keyring keys are only trusted when `keys/<id>.pub.sig` is a valid signature
+    // over the key file by the fingerprint-pinned `registry.pub`.
+    fn read_keyring_key(&self, key_id: &str, registry_public_key_hex: &str) -> Result<String> {
+        if key_id.is_empty()
+            || key_id.len() > 64
+            || !key_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            anyhow::bail!("invalid metadata signature key id: '{key_id}'");
+        }
+        let key_path = self.root.join("keys").join(format!("{key_id}.pub"));
+        let content = fs::read(&key_path).with_context(|| {
+            format!(
+                "metadata signature references key id '{key_id}' not present in registry keyring: {}",
+                key_path.display()
+            )
+        })?;
+        let signature_path = self.root.join("keys").join(format!("{key_id}.pub.sig"));
+        let signature_hex = fs::read_to_string(&signature_path).with_context(|| {
+            format!(
+                "registry keyring key '{key_id}' is not signed by registry.pub: missing {}",
+                signature_path.display()
+            )
+        })?;
+        let endorsed =
+            verify_ed25519_signature_hex(&content, registry_public_key_hex, signature_hex.trim())
+                .unwrap_or(false);
+        if !endorsed {
+            anyhow::bail!(
+                "registry keyring key '{key_id}' has an invalid registry.pub signature: {}",
+                signature_path.display()
+            );
+        }
+        let content = String::from_utf8(content).with_context(|| {
+            format!("registry keyring key is not UTF-8: {}", key_path.display())
+        })?;
+        Ok(content.trim().to_string())
+    }
+}
+
+// Checks the size before reading so an oversized file in a synced snapshot is
+// rejected without being buffered, and caps the read in case it grows meanwhile.
+fn read_bounded_metadata(path: &Path, max_bytes: u64, kind: &str) -> Result<Vec<u8>> {
+    let file = fs::File::open(path)
+        .with_context(|| format!("failed reading {kind}: {}", path.display()))?;
+    let len = file
+        .metadata()
+        .with_context(|| format!("failed reading {kind}: {}", path.display()))?
+        .len();
+    let mut bytes = Vec::with_capacity(len.min(max_bytes) as usize);
+    if len <= max_bytes {
+        file.take(max_bytes + 1)
+            .read_to_end(&mut bytes)
+            .with_context(|| format!("failed reading {kind}: {}", path.display()))?;
+    }
+    if len > max_bytes || bytes.len() as u64 > max_bytes {
+        anyhow::bail!(
+            "{kind} exceeds maximum manifest size of {max_bytes} bytes: {}",
+            path.display()
+        );
+    }
+    Ok(bytes)
 }
 
 fn verify_signed_toml_document(
     document_path: &Path,
     document_bytes: &[u8],
-    trusted_public_key_hex: &str,
-    key_identifier: &str,
+    max_bytes: u64,
+    resolve_key: impl Fn(Option<&str>) -> Result<(String, String)>,
 ) -> Result<()> {
     let signature_path = document_path.with_extension("toml.sig");
-    let signature_hex = fs::read_to_string(&signature_path).with_context(|| {
+    let sidecar_bytes = read_bounded_metadata(&signature_path, max_bytes, "metadata signature")?;
+    let sidecar = String::from_utf8(sidecar_bytes).with_context(|| {
         format!(
-            "failed reading metadata signature for trusted key {}: {}",
-            key_identifier,
+            "metadata signature is not UTF-8: {}",
             signature_path.display()
         )
     })?;
-    let signature_hex = signature_hex.trim();
+    let (key_id, signature_hex) = match sidecar.trim().split_once(':') {
+        Some((key_id, signature_hex)) => (Some(key_id.trim()), signature_hex.trim()),
+        None => (None, sidecar.trim()),
+    };
+    let (trusted_public_key_hex, key_identifier) = resolve_key(key_id)?;
 
     let signature_is_valid =
-        verify_ed25519_signature_hex(document_bytes, trusted_public_key_hex, signature_hex)
+        verify_ed25519_signature_hex(document_bytes, &trusted_public_key_hex, signature_hex)
             .with_context(|| {
                 format!(
                     "failed verifying metadata signature for trusted key {}: {}",
@@ -267,6 +661,13 @@ fn merge_artifacts(base: &[Value], overlay: &[Value]) -> Vec<Value> {
 
 impl ConfiguredRegistryIndex {
     pub fn open(state_root: impl Into<PathBuf>) -> Result<Self> {
+        Self::open_with_security_policy(state_root, SecurityPolicy::default())
+    }
+
+    pub fn open_with_security_policy(
+        state_root: impl Into<PathBuf>,
+        security_policy: SecurityPolicy,
+    ) -> Result<Self> {
         let state_root = state_root.into();
         let sources_path = state_root.join("sources.toml");
         let (state, has_sources_file) = match fs::read_to_string(&sources_path) {
@@ -300,13 +701,23 @@ impl ConfiguredRegistryIndex {
         let enabled_count = enabled_sources.len();
         sort_sources(&mut enabled_sources);
 
+        let redundant_sources = enabled_sources
+            .iter()
+            .enumerate()
+            .filter_map(|(position, source)| {
+                find_same_location_source(&enabled_sources[..position], source)
+                    .map(|original| (source.name.clone(), original.name.clone()))
+            })
+            .collect::<Vec<_>>();
+
         let mut configured = Vec::new();
         for source in enabled_sources {
             let cache_root = state_root.join("cache").join(&source.name);
             if !source_has_ready_snapshot(&cache_root)? {
                 continue;
             }
-            verify_community_recipe_catalog_policy(&cache_root, &source).with_context(|| {
+            let content_root = source_content_root(&cache_root, &source);
+            verify_community_recipe_catalog_policy(&content_root, &source).with_context(|| {
                 format!(
                     "failed validating community recipe metadata for configured source '{}'",
                     source.name
@@ -314,25 +725,48 @@ impl ConfiguredRegistryIndex {
             })?;
             configured.push(ConfiguredSnapshotSource {
                 name: source.name,
-                index: RegistryIndex::open(cache_root),
+                index: RegistryIndex::open_with_security_policy(
+                    content_root,
+                    security_policy.clone(),
+                ),
             });
         }
 
         if !configured.is_empty() {
             return Ok(Self {
+                state_root,
+                security_policy,
                 sources: configured,
+                redundant_sources,
             });
         }
 
         if !has_sources_file || enabled_count == 0 {
             return Ok(Self {
+                state_root,
+                security_policy,
                 sources: Vec::new(),
+                redundant_sources: Vec::new(),
             });
         }
 
         anyhow::bail!("no ready snapshot exists for enabled sources")
     }
 
+    /// Re-reads `sources.toml` and each source's snapshot state under the same
+    /// state root and security policy. On error the current sources are kept.
+    pub fn reload(&mut self) -> Result<()> {
+        let reloaded =
+            Self::open_with_security_policy(self.state_root.clone(), self.security_policy.clone())?;
+        self.sources = reloaded.sources;
+        self.redundant_sources = reloaded.redundant_sources;
+        Ok(())
+    }
+
+    pub fn redundant_sources(&self) -> &[(String, String)] {
+        &self.redundant_sources
+    }
+
     pub fn search_names(&self, needle: &str) -> Result<Vec<String>> {
         let mut deduped = HashSet::new();
         for source in &self.sources {
@@ -346,6 +780,90 @@ impl ConfiguredRegistryIndex {
         Ok(names)
     }
 
+    pub fn search_names_lenient(
+        &self,
+        needle: &str,
+    ) -> (Vec<String>, Vec<(String, anyhow::Error)>) {
+        let mut deduped = HashSet::new();
+        let mut errors = Vec::new();
+        for source in &self.sources {
+            match source.index.search_names(needle) {
+                Ok(names) => deduped.extend(names),
+                Err(err) => errors.push((source.name.clone(), err)),
+            }
+        }
+
+        let mut names: Vec<String> = deduped.into_iter().collect();
+        names.sort();
+        (names, errors)
+    }
+
+    pub fn inventory(&self) -> Result<Vec<(String, Version)>> {
+        self.inventory_with_policy(InventoryPolicy::Strict)
+    }
+
+    pub fn inventory_with_policy(&self, policy: InventoryPolicy) -> Result<Vec<(String, Version)>> {
+        let mut inventory = BTreeSet::new();
+        for source in &self.sources {
+            let entries = source
+                .index
+                .inventory_with_policy(policy)
+                .with_context(|| format!("failed to inventory source '{}'", source.name))?;
+            inventory.extend(entries);
+        }
+        Ok(inventory.into_iter().collect())
+    }
+
+    pub fn search_detailed(
+        &self,
+        needle: &str,
+        installed: &BTreeSet<String>,
+    ) -> Result<Vec<SearchHit>> {
+        let mut hits = Vec::new();
+        for name in self.search_names(needle)? {
+            let Some((source, manifests)) = self.package_versions_with_source(&name)? else {
+                continue;
+            };
+            let Some(latest) = manifests.first() else {
+                continue;
+            };
+            hits.push(SearchHit {
+                installed: installed.contains(&name),
+                latest_version: latest.version.to_string(),
+                name,
+                source,
+            });
+        }
+        Ok(hits)
+    }
+
+    // Only the latest version of each package, taken from its precedence source,
+    // decides whether it is listed as a provider.
+    pub fn providers_of(&self, capability: &str) -> Result<Vec<(String, Version)>> {
+        let mut names = BTreeSet::new();
+        for source in &self.sources {
+            names.extend(source.index.all_package_names()?);
+        }
+
+        let mut providers = Vec::new();
+        for name in names {
+            let Some((_, manifests)) = self.package_versions_with_source(&name)? else {
+                continue;
+            };
+            let Some(latest) = manifests.into_iter().next() else {
+                continue;
+            };
+            if latest
+                .provides
+                .iter()
+                .any(|provided| provided == capability)
+            {
+                providers.push((name, latest.version));
+            }
+        }
+        Ok(providers)
+    }
+
     pub fn package_versions(&self, package: &str) -> Result<Vec<PackageManifest>> {
         if let Some((_, manifests)) = self.package_versions_with_source(package)? {
             return Ok(manifests);
@@ -353,6 +871,29 @@ impl ConfiguredRegistryIndex {
         Ok(Vec::new())
     }
 
+    // Filters after source selection, so a lower-priority source never supplies
+    // a version just because the winning source lacks the target.
+    pub fn package_versions_for_target(
+        &self,
+        package: &str,
+        target: &str,
+    ) -> Result<Vec<PackageManifest>> {
+        let mut manifests = self.package_versions(package)?;
+        manifests.retain(|manifest| manifest.artifact_for_target(target).is_some());
+        Ok(manifests)
+    }
+
+    // Cheap provenance pre-check: only looks for the package's release directory or
+    // template in priority order, without reading or verifying any manifest.
+    pub fn resolving_source(&self, package: &str) -> Result<Option<String>> {
+        for source in &self.sources {
+            if source.index.contains_package(package)? {
+                return Ok(Some(source.name.clone()));
+            }
+        }
+        Ok(None)
+    }
+
     pub fn package_versions_with_source(
         &self,
         package: &str,
@@ -0,0 +1,101 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use crosspack_core::{Clock, SystemClock};
+
+#[derive(Debug, Clone)]
+pub struct SecurityPolicy {
+    require_signatures: bool,
+    audit_log_path: Option<PathBuf>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for SecurityPolicy {
+    fn default() -> Self {
+        Self {
+            require_signatures: true,
+            audit_log_path: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+// The clock only stamps audit entries, so it takes no part in policy equality.
+impl PartialEq for SecurityPolicy {
+    fn eq(&self, other: &Self) -> bool {
+        self.require_signatures == other.require_signatures
+            && self.audit_log_path == other.audit_log_path
+    }
+}
+
+impl Eq for SecurityPolicy {}
+
+impl SecurityPolicy {
+    pub fn with_audit_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.audit_log_path = Some(path.into());
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn require_signatures(&self) -> bool {
+        self.require_signatures
+    }
+
+    pub fn audit_log_path(&self) -> Option<&Path> {
+        self.audit_log_path.as_deref()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn allow_unsigned_for_tests() -> Self {
+        Self {
+            require_signatures: false,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn record_signature_rejection(&self, document_path: &Path, reason: &str) {
+        let _ = self.append_audit_event("signature_verification_rejected", document_path, reason);
+    }
+
+    fn append_audit_event(&self, event: &str, document_path: &Path, reason: &str) -> Result<()> {
+        let Some(log_path) = &self.audit_log_path else {
+            return Ok(());
+        };
+        if let Some(parent) = log_path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("failed creating security audit dir: {}", parent.display())
+            })?;
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_path)
+            .with_context(|| {
+                format!(
+                    "failed opening security audit log for append: {}",
+                    log_path.display()
+                )
+            })?;
+        writeln!(
+            file,
+            "timestamp_unix={} level=error event={event} require_signatures={} reason={reason} path={}",
+            self.clock.unix_timestamp(),
+            self.require_signatures,
+            document_path.display()
+        )
+        .with_context(|| {
+            format!(
+                "failed writing security audit log entry: {}",
+                log_path.display()
+            )
+        })
+    }
+}
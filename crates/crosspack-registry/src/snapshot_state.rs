@@ -4,9 +4,7 @@ use std::path::Path;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::{
-    current_unix_timestamp, RegistrySourceSnapshotState, RegistrySourceWithSnapshotStatus,
-};
+use crate::{RegistrySourceSnapshotState, RegistrySourceWithSnapshotStatus};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct SourceSnapshotFile {
@@ -15,6 +13,8 @@ struct SourceSnapshotFile {
     snapshot_id: String,
     updated_at_unix: u64,
     manifest_count: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    total_bytes: Option<u64>,
     status: String,
 }
 
@@ -23,14 +23,17 @@ pub(crate) fn write_snapshot_file(
     source_name: &str,
     snapshot_id: &str,
     manifest_count: u64,
+    total_bytes: u64,
+    updated_at_unix: u64,
 ) -> Result<()> {
     let snapshot_path = cache_root.join("snapshot.json");
     let snapshot = SourceSnapshotFile {
         version: 1,
         source: source_name.to_string(),
         snapshot_id: snapshot_id.to_string(),
-        updated_at_unix: current_unix_timestamp(),
+        updated_at_unix,
         manifest_count,
+        total_bytes: Some(total_bytes),
         status: "ready".to_string(),
     };
     let content = serde_json::to_string_pretty(&snapshot).with_context(|| {
@@ -56,40 +59,60 @@ pub(crate) fn read_snapshot_id(path: &Path) -> Option<String> {
 }
 
 pub(crate) fn read_snapshot_state(cache_root: &Path) -> RegistrySourceSnapshotState {
+    read_snapshot_state_with_summary(cache_root).0
+}
+
+// The summary is (updated_at_unix, manifest_count), present only for ready snapshots.
+pub(crate) fn read_snapshot_state_with_summary(
+    cache_root: &Path,
+) -> (RegistrySourceSnapshotState, Option<(u64, u64)>) {
     let snapshot_path = cache_root.join("snapshot.json");
     let content = match fs::read_to_string(&snapshot_path) {
         Ok(content) => content,
         Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
-            return RegistrySourceSnapshotState::None;
+            return (RegistrySourceSnapshotState::None, None);
         }
         Err(_) => {
-            return RegistrySourceSnapshotState::Error {
-                status: RegistrySourceWithSnapshotStatus::Unreadable,
-                reason_code: "snapshot-unreadable".to_string(),
-            };
+            return (
+                RegistrySourceSnapshotState::Error {
+                    status: RegistrySourceWithSnapshotStatus::Unreadable,
+                    reason_code: "snapshot-unreadable".to_string(),
+                },
+                None,
+            );
         }
     };
 
     let snapshot = match serde_json::from_str::<SourceSnapshotFile>(&content) {
         Ok(snapshot) => snapshot,
         Err(_) => {
-            return RegistrySourceSnapshotState::Error {
-                status: RegistrySourceWithSnapshotStatus::Unreadable,
-                reason_code: "snapshot-unreadable".to_string(),
-            };
+            return (
+                RegistrySourceSnapshotState::Error {
+                    status: RegistrySourceWithSnapshotStatus::Unreadable,
+                    reason_code: "snapshot-unreadable".to_string(),
+                },
+                None,
+            );
         }
     };
 
     if snapshot.status == "ready" {
-        return RegistrySourceSnapshotState::Ready {
-            snapshot_id: snapshot.snapshot_id,
-        };
+        return (
+            RegistrySourceSnapshotState::Ready {
+                snapshot_id: snapshot.snapshot_id,
+                total_bytes: snapshot.total_bytes,
+            },
+            Some((snapshot.updated_at_unix, snapshot.manifest_count)),
+        );
     }
 
-    RegistrySourceSnapshotState::Error {
-        status: RegistrySourceWithSnapshotStatus::Invalid,
-        reason_code: "snapshot-invalid".to_string(),
-    }
+    (
+        RegistrySourceSnapshotState::Error {
+            status: RegistrySourceWithSnapshotStatus::Invalid,
+            reason_code: "snapshot-invalid".to_string(),
+        },
+        None,
+    )
 }
 
 pub(crate) fn source_has_ready_snapshot(cache_root: &Path) -> Result<bool> {
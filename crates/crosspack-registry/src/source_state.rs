@@ -1,10 +1,10 @@
 use std::collections::HashSet;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
-use crate::RegistrySourceRecord;
+use crate::{RegistrySourceKind, RegistrySourceRecord};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct RegistrySourceStateFile {
@@ -73,6 +73,19 @@ pub(crate) fn sort_sources(sources: &mut [RegistrySourceRecord]) {
     });
 }
 
+pub(crate) fn find_same_location_source<'a>(
+    sources: &'a [RegistrySourceRecord],
+    candidate: &RegistrySourceRecord,
+) -> Option<&'a RegistrySourceRecord> {
+    sources.iter().find(|existing| {
+        existing.name != candidate.name
+            && existing.location == candidate.location
+            && existing
+                .fingerprint_sha256
+                .eq_ignore_ascii_case(&candidate.fingerprint_sha256)
+    })
+}
+
 pub(crate) fn validate_source_name(name: &str) -> Result<()> {
     if name.is_empty() || name.len() > 64 {
         anyhow::bail!("invalid source name: must not be empty");
@@ -93,6 +106,63 @@ pub(crate) fn validate_source_name(name: &str) -> Result<()> {
     Ok(())
 }
 
+pub(crate) fn validate_source_git_ref(git_ref: &str) -> Result<()> {
+    let is_valid = !git_ref.is_empty()
+        && !git_ref.starts_with('-')
+        && !git_ref.contains("..")
+        && !git_ref.chars().any(|ch| {
+            ch.is_whitespace() || ch.is_control() || matches!(ch, '~' | '^' | ':' | '\\')
+        });
+    if !is_valid {
+        anyhow::bail!("invalid source git ref: '{git_ref}'");
+    }
+
+    Ok(())
+}
+
+pub(crate) fn validate_source_location(kind: &RegistrySourceKind, location: &str) -> Result<()> {
+    let has_url_scheme = location
+        .split_once("://")
+        .is_some_and(|(scheme, _)| is_url_scheme(scheme));
+    let is_absolute_path = Path::new(location).is_absolute() || location.starts_with('/');
+    match kind {
+        RegistrySourceKind::Filesystem => {
+            if has_url_scheme || location.starts_with("file:") {
+                anyhow::bail!(
+                    "invalid filesystem source location '{location}': URL schemes are not allowed"
+                );
+            }
+            if !is_absolute_path {
+                anyhow::bail!(
+                    "invalid filesystem source location '{location}': must be an absolute path"
+                );
+            }
+        }
+        RegistrySourceKind::Git => {
+            if !has_url_scheme && !is_scp_like_git_location(location) && !is_absolute_path {
+                anyhow::bail!(
+                    "invalid git source location '{location}': expected a URL or absolute path"
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn is_url_scheme(scheme: &str) -> bool {
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|ch| ch.is_ascii_alphabetic())
+        && chars.all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'))
+}
+
+fn is_scp_like_git_location(location: &str) -> bool {
+    let Some((host, path)) = location.split_once(':') else {
+        return false;
+    };
+    host.contains('@') && !host.contains('/') && !path.is_empty()
+}
+
 pub(crate) fn validate_source_fingerprint(fingerprint: &str) -> Result<()> {
     if fingerprint.len() != 64 || !fingerprint.chars().all(|ch| ch.is_ascii_hexdigit()) {
         anyhow::bail!("invalid source fingerprint: '{fingerprint}'");
@@ -101,14 +171,60 @@ pub(crate) fn validate_source_fingerprint(fingerprint: &str) -> Result<()> {
     Ok(())
 }
 
+pub(crate) fn validate_source_record(source: &RegistrySourceRecord) -> Result<()> {
+    validate_source_name(&source.name)?;
+    validate_source_fingerprint(&source.fingerprint_sha256)?;
+    if let Some(community) = &source.community {
+        validate_community_recipe_catalog_path(&community.recipe_catalog_path)?;
+    }
+    if let Some(git_ref) = &source.git_ref {
+        if source.kind != RegistrySourceKind::Git {
+            anyhow::bail!(
+                "source '{}' sets git_ref but is not a git source",
+                source.name
+            );
+        }
+        validate_source_git_ref(git_ref)?;
+    }
+    if let Some(subdir) = &source.subdir {
+        if source.kind != RegistrySourceKind::Git {
+            anyhow::bail!(
+                "source '{}' sets subdir but is not a git source",
+                source.name
+            );
+        }
+        validate_source_subdir(subdir)?;
+    }
+
+    Ok(())
+}
+
+pub(crate) fn validate_source_subdir(subdir: &str) -> Result<()> {
+    let is_valid = !subdir.is_empty()
+        && !subdir.starts_with('-')
+        && Path::new(subdir)
+            .components()
+            .all(|component| matches!(component, std::path::Component::Normal(_)));
+    if !is_valid {
+        anyhow::bail!(
+            "invalid source subdir '{subdir}': must be a relative path without '.' or '..'"
+        );
+    }
+
+    Ok(())
+}
+
+pub(crate) fn source_content_root(checkout_root: &Path, source: &RegistrySourceRecord) -> PathBuf {
+    match &source.subdir {
+        Some(subdir) => checkout_root.join(subdir),
+        None => checkout_root.to_path_buf(),
+    }
+}
+
 pub(crate) fn validate_loaded_sources(sources: &[RegistrySourceRecord]) -> Result<()> {
     let mut seen_names: HashSet<&str> = HashSet::with_capacity(sources.len());
     for source in sources {
-        validate_source_name(&source.name)?;
-        validate_source_fingerprint(&source.fingerprint_sha256)?;
-        if let Some(community) = &source.community {
-            validate_community_recipe_catalog_path(&community.recipe_catalog_path)?;
-        }
+        validate_source_record(source)?;
 
         if !seen_names.insert(source.name.as_str()) {
             anyhow::bail!(
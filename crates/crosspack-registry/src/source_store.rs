@@ -1,33 +1,77 @@
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use anyhow::{Context, Result};
+use crosspack_core::{Clock, SystemClock};
 
 use crate::{
-    parse_source_state_file, read_snapshot_state, select_update_sources, sort_sources,
-    update_source, validate_community_recipe_catalog_path, validate_source_fingerprint,
-    validate_source_name, RegistrySourceRecord, RegistrySourceStateFile,
-    RegistrySourceWithSnapshotState, SourceUpdateResult, SourceUpdateStatus,
+    discover_source_fingerprint, find_same_location_source, parse_source_state_file,
+    read_snapshot_state, read_snapshot_state_with_summary, select_update_sources, sort_sources,
+    update_source, validate_source_location, validate_source_name, validate_source_record,
+    GitConfig, RegistrySourceRecord, RegistrySourceStateFile, RegistrySourceWithSnapshotState,
+    SourceStatus, SourceUpdateResult, SourceUpdateStatus,
 };
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateSourcePolicy {
+    #[default]
+    Warn,
+    Reject,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SnapshotLayoutPolicy {
+    #[default]
+    Lenient,
+    Strict,
+}
+
 #[derive(Debug, Clone)]
 pub struct RegistrySourceStore {
     pub(crate) state_root: PathBuf,
+    pub(crate) git: GitConfig,
+    pub(crate) layout_policy: SnapshotLayoutPolicy,
+    pub(crate) clock: Arc<dyn Clock>,
 }
 
 impl RegistrySourceStore {
     pub fn new(state_root: impl Into<PathBuf>) -> Self {
         Self {
             state_root: state_root.into(),
+            git: GitConfig::default(),
+            layout_policy: SnapshotLayoutPolicy::default(),
+            clock: Arc::new(SystemClock),
         }
     }
 
+    pub fn with_git_config(mut self, git: GitConfig) -> Self {
+        self.git = git;
+        self
+    }
+
+    pub fn with_snapshot_layout_policy(mut self, policy: SnapshotLayoutPolicy) -> Self {
+        self.layout_policy = policy;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
     pub fn add_source(&self, source: RegistrySourceRecord) -> Result<()> {
-        validate_source_name(&source.name)?;
-        validate_source_fingerprint(&source.fingerprint_sha256)?;
-        if let Some(community) = &source.community {
-            validate_community_recipe_catalog_path(&community.recipe_catalog_path)?;
-        }
+        self.add_source_with_duplicate_policy(source, DuplicateSourcePolicy::Warn)
+            .map(|_| ())
+    }
+
+    pub fn add_source_with_duplicate_policy(
+        &self,
+        source: RegistrySourceRecord,
+        policy: DuplicateSourcePolicy,
+    ) -> Result<Vec<String>> {
+        validate_source_record(&source)?;
+        validate_source_location(&source.kind, &source.location)?;
 
         let mut state = self.load_state()?;
         if state
@@ -38,9 +82,28 @@ impl RegistrySourceStore {
             anyhow::bail!("source '{}' already exists", source.name);
         }
 
+        let mut warnings = Vec::new();
+        if let Some(existing) = find_same_location_source(&state.sources, &source) {
+            let message = format!(
+                "source '{}' duplicates source '{}' (same location and fingerprint)",
+                source.name, existing.name
+            );
+            match policy {
+                DuplicateSourcePolicy::Warn => warnings.push(message),
+                DuplicateSourcePolicy::Reject => anyhow::bail!("{message}"),
+            }
+        }
+
         state.sources.push(source);
         sort_sources(&mut state.sources);
-        self.save_state(&state)
+        self.save_state(&state)?;
+        Ok(warnings)
+    }
+
+    pub fn discover_source_fingerprint(&self, source: &RegistrySourceRecord) -> Result<String> {
+        validate_source_name(&source.name)?;
+        validate_source_location(&source.kind, &source.location)?;
+        discover_source_fingerprint(self, source)
     }
 
     pub fn list_sources(&self) -> Result<Vec<RegistrySourceRecord>> {
@@ -62,6 +125,60 @@ impl RegistrySourceStore {
         Ok(listed)
     }
 
+    pub fn source_statuses(&self) -> Result<Vec<SourceStatus>> {
+        let mut state = self.load_state()?;
+        sort_sources(&mut state.sources);
+
+        let now = self.clock.unix_timestamp();
+        let mut statuses = Vec::with_capacity(state.sources.len());
+        for source in state.sources {
+            let cache_root = self.state_root.join("cache").join(&source.name);
+            let (snapshot, summary) = read_snapshot_state_with_summary(&cache_root);
+            let updated_at_unix = summary.map(|(updated_at_unix, _)| updated_at_unix);
+            statuses.push(SourceStatus {
+                name: source.name,
+                enabled: source.enabled,
+                priority: source.priority,
+                snapshot,
+                updated_at_unix,
+                age_secs: updated_at_unix.map(|updated_at| now.saturating_sub(updated_at)),
+                manifest_count: summary.map(|(_, manifest_count)| manifest_count),
+            });
+        }
+        Ok(statuses)
+    }
+
+    pub fn export_sources(&self) -> Result<String> {
+        let state = self.load_state()?;
+        toml::to_string(&state).context("failed serializing exported sources")
+    }
+
+    pub fn import_sources(&self, content: &str, merge: bool) -> Result<()> {
+        let imported =
+            parse_source_state_file(content).context("failed parsing imported sources")?;
+
+        let mut state = self.load_state()?;
+        for source in imported.sources {
+            validate_source_location(&source.kind, &source.location)
+                .with_context(|| format!("failed validating imported source '{}'", source.name))?;
+            match state
+                .sources
+                .iter_mut()
+                .find(|existing| existing.name == source.name)
+            {
+                Some(existing) if merge => *existing = source,
+                Some(_) => anyhow::bail!(
+                    "source '{}' already exists: re-run import with merge to replace it",
+                    source.name
+                ),
+                None => state.sources.push(source),
+            }
+        }
+
+        sort_sources(&mut state.sources);
+        self.save_state(&state)
+    }
+
     pub fn remove_source(&self, name: &str) -> Result<()> {
         let mut state = self.load_state()?;
         let before = state.sources.len();
@@ -88,12 +205,20 @@ impl RegistrySourceStore {
     }
 
     pub fn update_sources(&self, target_names: &[String]) -> Result<Vec<SourceUpdateResult>> {
+        self.update_sources_with_revalidate(target_names, false)
+    }
+
+    pub fn update_sources_with_revalidate(
+        &self,
+        target_names: &[String],
+        revalidate: bool,
+    ) -> Result<Vec<SourceUpdateResult>> {
         let state = self.load_state()?;
         let selected = select_update_sources(&state.sources, target_names)?;
 
         let mut results = Vec::with_capacity(selected.len());
         for source in selected {
-            match update_source(self, &source) {
+            match update_source(self, &source, revalidate) {
                 Ok((status, snapshot_id)) => results.push(SourceUpdateResult {
                     name: source.name,
                     status,
@@ -112,6 +237,50 @@ impl RegistrySourceStore {
         Ok(results)
     }
 
+    // Source updates stage into `tmp-<name>-<nanos>` under the state root and back
+    // up the previous cache as `cache/.<name>-backup-<nanos>`; the nanosecond
+    // suffix dates each leftover, so live caches and foreign entries never match.
+    pub fn sweep_stale_temp(&self, older_than_secs: u64) -> Result<Vec<PathBuf>> {
+        let cutoff_nanos = self
+            .clock
+            .unix_nanos()
+            .saturating_sub(u128::from(older_than_secs) * 1_000_000_000);
+        let mut swept = Vec::new();
+        for (dir, is_backup_dir) in [
+            (self.state_root.clone(), false),
+            (self.state_root.join("cache"), true),
+        ] {
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => {
+                    return Err(err)
+                        .with_context(|| format!("failed reading dir: {}", dir.display()));
+                }
+            };
+            for entry in entries {
+                let entry =
+                    entry.with_context(|| format!("failed reading dir: {}", dir.display()))?;
+                let file_name = entry.file_name();
+                let Some(created_nanos) =
+                    temp_dir_suffix(&file_name.to_string_lossy(), is_backup_dir)
+                else {
+                    continue;
+                };
+                if created_nanos > cutoff_nanos || !entry.path().is_dir() {
+                    continue;
+                }
+                let path = entry.path();
+                fs::remove_dir_all(&path).with_context(|| {
+                    format!("failed removing stale temp dir: {}", path.display())
+                })?;
+                swept.push(path);
+            }
+        }
+        swept.sort();
+        Ok(swept)
+    }
+
     fn sources_file_path(&self) -> PathBuf {
         self.state_root.join("sources.toml")
     }
@@ -147,3 +316,16 @@ impl RegistrySourceStore {
             .with_context(|| format!("failed writing source state: {}", path.display()))
     }
 }
+
+fn temp_dir_suffix(file_name: &str, is_backup_dir: bool) -> Option<u128> {
+    let stem = if is_backup_dir {
+        file_name.strip_prefix('.')?.rsplit_once("-backup-")
+    } else {
+        file_name.strip_prefix("tmp-")?.rsplit_once('-')
+    };
+    let (name, suffix) = stem?;
+    if name.is_empty() {
+        return None;
+    }
+    suffix.parse().ok()
+}
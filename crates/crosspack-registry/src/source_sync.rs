@@ -1,16 +1,20 @@
 use std::collections::BTreeSet;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 use anyhow::{Context, Result};
 use crosspack_security::{sha256_hex, verify_ed25519_signature_hex};
 use serde::Deserialize;
 
 use crate::{
-    compute_filesystem_snapshot_id, copy_source_to_temp, count_manifest_files,
-    git_head_snapshot_id, read_snapshot_id, run_git_clone, run_git_command, unique_suffix,
-    validate_community_recipe_catalog_path, validate_staged_registry_layout, write_snapshot_file,
-    RegistryIndex, RegistrySourceKind, RegistrySourceRecord, RegistrySourceStore,
+    compute_filesystem_snapshot_id, copy_source_to_temp, count_manifest_files, extract_bundle,
+    git_head_snapshot_id, is_registry_bundle_path, move_dir_with_copy_fallback_using,
+    read_bundle_member, read_snapshot_id, run_git_clone, run_git_command, source_content_root,
+    unique_suffix, validate_community_recipe_catalog_path, validate_staged_registry_layout,
+    validate_strict_registry_entries, write_snapshot_file, GitConfig, RegistryIndex,
+    RegistrySourceKind, RegistrySourceRecord, RegistrySourceStore, SnapshotLayoutPolicy,
     SourceUpdateStatus,
 };
 
@@ -47,23 +51,81 @@ fn is_package_token(value: &str) -> bool {
 pub(crate) fn update_source(
     store: &RegistrySourceStore,
     source: &RegistrySourceRecord,
+    revalidate: bool,
 ) -> Result<(SourceUpdateStatus, String)> {
     match source.kind {
-        RegistrySourceKind::Filesystem => update_filesystem_source(store, source),
-        RegistrySourceKind::Git => update_git_source(store, source),
+        RegistrySourceKind::Filesystem => update_filesystem_source(store, source, revalidate),
+        RegistrySourceKind::Git => update_git_source(store, source, revalidate),
     }
 }
 
+// Fetches the source once into a throwaway directory so its trust root can be
+// fingerprinted before the source is ever recorded.
+pub(crate) fn discover_source_fingerprint(
+    store: &RegistrySourceStore,
+    source: &RegistrySourceRecord,
+) -> Result<String> {
+    let source_path = PathBuf::from(&source.location);
+    if source.kind == RegistrySourceKind::Filesystem && is_registry_bundle_path(&source_path) {
+        let registry_pub_raw = read_bundle_member(&source_path, "registry.pub", &source.name)?;
+        return Ok(sha256_hex(&registry_pub_raw));
+    }
+
+    let staged_root =
+        store
+            .state_root
+            .join(format!("tmp-discover-{}-{}", source.name, unique_suffix()));
+    let result = match source.kind {
+        RegistrySourceKind::Filesystem => {
+            copy_source_to_temp(&source_path, &staged_root, &source.name)
+        }
+        RegistrySourceKind::Git => run_git_clone(
+            &store.git,
+            &source.location,
+            &staged_root,
+            &source.name,
+            source.subdir.is_some(),
+        )
+        .and_then(|_| apply_git_sparse_checkout(&store.git, &staged_root, source))
+        .and_then(|_| {
+            if source.git_ref.is_some() {
+                fetch_git_source_ref(&store.git, &staged_root, source)
+            } else {
+                Ok(())
+            }
+        }),
+    }
+    .and_then(|_| {
+        let registry_pub_path = source_content_root(&staged_root, source).join("registry.pub");
+        fs::read(&registry_pub_path).with_context(|| {
+            format!(
+                "source-snapshot-missing: source '{}' missing registry.pub in {}",
+                source.name,
+                registry_pub_path.display()
+            )
+        })
+    });
+    let _ = fs::remove_dir_all(&staged_root);
+    Ok(sha256_hex(&result?))
+}
+
 fn update_filesystem_source(
     store: &RegistrySourceStore,
     source: &RegistrySourceRecord,
+    revalidate: bool,
 ) -> Result<(SourceUpdateStatus, String)> {
     let staged_root = store
         .state_root
         .join(format!("tmp-{}-{}", source.name, unique_suffix()));
 
     let source_path = PathBuf::from(&source.location);
-    if let Err(err) = copy_source_to_temp(&source_path, &staged_root, &source.name) {
+    let prepare_result = if is_registry_bundle_path(&source_path) {
+        verify_bundle_fingerprint(&source_path, source)
+            .and_then(|_| extract_bundle(&source_path, &staged_root, &source.name))
+    } else {
+        copy_source_to_temp(&source_path, &staged_root, &source.name)
+    };
+    if let Err(err) = prepare_result {
         let _ = fs::remove_dir_all(&staged_root);
         return Err(err);
     }
@@ -76,12 +138,34 @@ fn update_filesystem_source(
         }
     };
 
-    finalize_staged_source_update(store, source, staged_root, snapshot_id)
+    finalize_staged_source_update(store, source, staged_root, snapshot_id, revalidate)
+}
+
+fn verify_bundle_fingerprint(bundle_path: &Path, source: &RegistrySourceRecord) -> Result<()> {
+    let registry_pub_raw = read_bundle_member(bundle_path, "registry.pub", &source.name)?;
+    verify_registry_pub_fingerprint(&registry_pub_raw, source)
+}
+
+fn verify_registry_pub_fingerprint(
+    registry_pub_raw: &[u8],
+    source: &RegistrySourceRecord,
+) -> Result<()> {
+    let actual_fingerprint = sha256_hex(registry_pub_raw);
+    if !actual_fingerprint.eq_ignore_ascii_case(&source.fingerprint_sha256) {
+        anyhow::bail!(
+            "source-key-fingerprint-mismatch: source '{}' expected {}, got {}",
+            source.name,
+            source.fingerprint_sha256,
+            actual_fingerprint
+        );
+    }
+    Ok(())
 }
 
 fn update_git_source(
     store: &RegistrySourceStore,
     source: &RegistrySourceRecord,
+    revalidate: bool,
 ) -> Result<(SourceUpdateStatus, String)> {
     let staged_root = store
         .state_root
@@ -89,21 +173,25 @@ fn update_git_source(
     let destination = store.state_root.join("cache").join(&source.name);
 
     let prepare_result = if destination.exists() {
-        copy_source_to_temp(&destination, &staged_root, &source.name).and_then(|_| {
-            run_git_command(
-                &staged_root,
-                &["fetch", "--prune", "--", source.location.as_str()],
-                &source.name,
-            )?;
-            run_git_command(
-                &staged_root,
-                &["reset", "--hard", "FETCH_HEAD"],
-                &source.name,
-            )?;
-            Ok(())
-        })
+        copy_source_to_temp(&destination, &staged_root, &source.name)
+            .and_then(|_| apply_git_sparse_checkout(&store.git, &staged_root, source))
+            .and_then(|_| fetch_git_source_ref(&store.git, &staged_root, source))
     } else {
-        run_git_clone(&source.location, &staged_root, &source.name)
+        run_git_clone(
+            &store.git,
+            &source.location,
+            &staged_root,
+            &source.name,
+            source.subdir.is_some(),
+        )
+        .and_then(|_| apply_git_sparse_checkout(&store.git, &staged_root, source))
+        .and_then(|_| {
+            if source.git_ref.is_some() {
+                fetch_git_source_ref(&store.git, &staged_root, source)
+            } else {
+                Ok(())
+            }
+        })
     };
 
     if let Err(err) = prepare_result {
@@ -111,7 +199,7 @@ fn update_git_source(
         return Err(err);
     }
 
-    let snapshot_id = match git_head_snapshot_id(&staged_root, &source.name) {
+    let snapshot_id = match git_head_snapshot_id(&store.git, &staged_root, &source.name) {
         Ok(snapshot_id) => snapshot_id,
         Err(err) => {
             let _ = fs::remove_dir_all(&staged_root);
@@ -119,7 +207,41 @@ fn update_git_source(
         }
     };
 
-    finalize_staged_source_update(store, source, staged_root, snapshot_id)
+    finalize_staged_source_update(store, source, staged_root, snapshot_id, revalidate)
+}
+
+fn apply_git_sparse_checkout(
+    git: &GitConfig,
+    staged_root: &Path,
+    source: &RegistrySourceRecord,
+) -> Result<()> {
+    let Some(subdir) = &source.subdir else {
+        return Ok(());
+    };
+    run_git_command(
+        git,
+        staged_root,
+        &["sparse-checkout", "set", "--cone", "--", subdir.as_str()],
+        &source.name,
+    )
+}
+
+fn fetch_git_source_ref(
+    git: &GitConfig,
+    staged_root: &Path,
+    source: &RegistrySourceRecord,
+) -> Result<()> {
+    let mut fetch_args = vec!["fetch", "--prune", "--", source.location.as_str()];
+    if let Some(git_ref) = &source.git_ref {
+        fetch_args.push(git_ref.as_str());
+    }
+    run_git_command(git, staged_root, &fetch_args, &source.name)?;
+    run_git_command(
+        git,
+        staged_root,
+        &["reset", "--hard", "FETCH_HEAD"],
+        &source.name,
+    )
 }
 
 fn finalize_staged_source_update(
@@ -127,11 +249,66 @@ fn finalize_staged_source_update(
     source: &RegistrySourceRecord,
     staged_root: PathBuf,
     snapshot_id: String,
+    revalidate: bool,
 ) -> Result<(SourceUpdateStatus, String)> {
-    let pipeline_result = (|| -> Result<(String, u64, Option<String>)> {
-        validate_staged_registry_layout(&staged_root, &source.name)?;
+    if !revalidate && cached_snapshot_is_current(store, source, &snapshot_id) {
+        let _ = fs::remove_dir_all(&staged_root);
+        return Ok((SourceUpdateStatus::UpToDate, snapshot_id));
+    }
+
+    finalize_staged_source_update_with_rename(
+        store,
+        source,
+        staged_root,
+        snapshot_id,
+        |from, to| fs::rename(from, to),
+    )
+}
 
-        let registry_pub_path = staged_root.join("registry.pub");
+// An unchanged snapshot is only left alone when the cached trust root still
+// matches the configured fingerprint; anything else goes through full validation.
+fn cached_snapshot_is_current(
+    store: &RegistrySourceStore,
+    source: &RegistrySourceRecord,
+    snapshot_id: &str,
+) -> bool {
+    let cache_root = store.state_root.join("cache").join(&source.name);
+    if read_snapshot_id(&cache_root.join("snapshot.json")).as_deref() != Some(snapshot_id) {
+        return false;
+    }
+    fs::read(source_content_root(&cache_root, source).join("registry.pub"))
+        .is_ok_and(|raw| verify_registry_pub_fingerprint(&raw, source).is_ok())
+}
+
+pub(crate) fn finalize_staged_source_update_with_rename<R>(
+    store: &RegistrySourceStore,
+    source: &RegistrySourceRecord,
+    staged_root: PathBuf,
+    snapshot_id: String,
+    rename: R,
+) -> Result<(SourceUpdateStatus, String)>
+where
+    R: Fn(&Path, &Path) -> std::io::Result<()>,
+{
+    let move_dir = |from: &Path, to: &Path| move_dir_with_copy_fallback_using(from, to, &rename);
+    let pipeline_result = (|| -> Result<(String, u64, u64, Option<String>)> {
+        let content_root = source_content_root(&staged_root, source);
+        validate_staged_registry_layout(&content_root, &source.name)?;
+        if store.layout_policy == SnapshotLayoutPolicy::Strict {
+            let community_root = source.community.as_ref().and_then(|community| {
+                Path::new(&community.recipe_catalog_path)
+                    .components()
+                    .next()
+                    .and_then(|component| component.as_os_str().to_str())
+            });
+            validate_strict_registry_entries(
+                &content_root,
+                &source.name,
+                community_root.as_slice(),
+            )?;
+        }
+
+        let registry_pub_path = content_root.join("registry.pub");
         let registry_pub_raw = fs::read(&registry_pub_path).with_context(|| {
             format!(
                 "source-sync-failed: source '{}' failed reading {}",
@@ -139,20 +316,12 @@ fn finalize_staged_source_update(
                 registry_pub_path.display()
             )
         })?;
-        let actual_fingerprint = sha256_hex(&registry_pub_raw);
-        if !actual_fingerprint.eq_ignore_ascii_case(&source.fingerprint_sha256) {
-            anyhow::bail!(
-                "source-key-fingerprint-mismatch: source '{}' expected {}, got {}",
-                source.name,
-                source.fingerprint_sha256,
-                actual_fingerprint
-            );
-        }
+        verify_registry_pub_fingerprint(&registry_pub_raw, source)?;
 
-        verify_metadata_signature_policy(&staged_root, &source.name)?;
-        verify_community_recipe_catalog_policy(&staged_root, source)?;
+        verify_metadata_signature_policy(&content_root, &source.name)?;
+        verify_community_recipe_catalog_policy(&content_root, source)?;
 
-        let manifest_count = count_manifest_files(&staged_root.join("releases"))?;
+        let (manifest_count, total_bytes) = count_manifest_files(&content_root.join("releases"))?;
         let existing_snapshot_id = read_snapshot_id(
             &store
                 .state_root
@@ -160,7 +329,12 @@ fn finalize_staged_source_update(
                 .join(&source.name)
                 .join("snapshot.json"),
         );
-        Ok((snapshot_id, manifest_count, existing_snapshot_id))
+        Ok((
+            snapshot_id,
+            manifest_count,
+            total_bytes,
+            existing_snapshot_id,
+        ))
     })();
 
     if let Err(err) = pipeline_result {
@@ -168,7 +342,7 @@ fn finalize_staged_source_update(
         return Err(err);
     }
 
-    let (snapshot_id, manifest_count, existing_snapshot_id) = pipeline_result?;
+    let (snapshot_id, manifest_count, total_bytes, existing_snapshot_id) = pipeline_result?;
     let cache_root = store.state_root.join("cache");
     fs::create_dir_all(&cache_root).with_context(|| {
         format!(
@@ -182,7 +356,7 @@ fn finalize_staged_source_update(
     let had_existing = destination.exists();
 
     if had_existing {
-        fs::rename(&destination, &backup).with_context(|| {
+        move_dir(&destination, &backup).with_context(|| {
             format!(
                 "source-sync-failed: source '{}' failed backing up cache {}",
                 source.name,
@@ -191,7 +365,7 @@ fn finalize_staged_source_update(
         })?;
     }
 
-    if let Err(err) = fs::rename(&staged_root, &destination).with_context(|| {
+    if let Err(err) = move_dir(&staged_root, &destination).with_context(|| {
         format!(
             "source-sync-failed: source '{}' failed replacing cache {}",
             source.name,
@@ -199,7 +373,7 @@ fn finalize_staged_source_update(
         )
     }) {
         if had_existing {
-            if let Err(restore_err) = fs::rename(&backup, &destination) {
+            if let Err(restore_err) = move_dir(&backup, &destination) {
                 return Err(combine_replace_restore_errors(
                     &source.name,
                     &destination,
@@ -212,11 +386,17 @@ fn finalize_staged_source_update(
         return Err(err);
     }
 
-    if let Err(err) = write_snapshot_file(&destination, &source.name, &snapshot_id, manifest_count)
-    {
+    if let Err(err) = write_snapshot_file(
+        &destination,
+        &source.name,
+        &snapshot_id,
+        manifest_count,
+        total_bytes,
+        store.clock.unix_timestamp(),
+    ) {
         let _ = fs::remove_dir_all(&destination);
         if had_existing {
-            if let Err(restore_err) = fs::rename(&backup, &destination) {
+            if let Err(restore_err) = move_dir(&backup, &destination) {
                 return Err(combine_replace_restore_errors(
                     &source.name,
                     &destination,
@@ -234,7 +414,7 @@ fn finalize_staged_source_update(
     }
 
     let status = if existing_snapshot_id.as_deref() == Some(snapshot_id.as_str()) {
-        SourceUpdateStatus::UpToDate
+        SourceUpdateStatus::NoChange
     } else {
         SourceUpdateStatus::Updated
     };
@@ -369,20 +549,64 @@ pub(crate) fn verify_metadata_signature_policy(
     source_name: &str,
 ) -> Result<()> {
     let index = RegistryIndex::open(staged_root);
-    let package_names = collect_metadata_packages(staged_root, source_name)?;
+    let package_names = collect_metadata_packages(staged_root, source_name)?
+        .into_iter()
+        .collect::<Vec<_>>();
+    let trusted_public_key_hex = index.read_trusted_key().with_context(|| {
+        format!(
+            "source-metadata-invalid: source '{}' failed reading trusted key",
+            source_name
+        )
+    })?;
+
+    let worker_count = std::thread::available_parallelism()
+        .map(usize::from)
+        .unwrap_or(1)
+        .clamp(1, MAX_SIGNATURE_VERIFICATION_WORKERS)
+        .min(package_names.len().max(1));
+    let next_package = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let failures = Mutex::new(Vec::new());
+    std::thread::scope(|scope| {
+        for _ in 0..worker_count {
+            scope.spawn(|| {
+                while !failed.load(Ordering::Relaxed) {
+                    let position = next_package.fetch_add(1, Ordering::Relaxed);
+                    let Some(package) = package_names.get(position) else {
+                        break;
+                    };
+                    if let Err(err) = index.package_versions_with_trusted_key(
+                        package,
+                        trusted_public_key_hex.as_deref(),
+                    ) {
+                        failed.store(true, Ordering::Relaxed);
+                        failures
+                            .lock()
+                            .unwrap_or_else(|poisoned| poisoned.into_inner())
+                            .push((position, err));
+                    }
+                }
+            });
+        }
+    });
 
-    for package in package_names {
-        index.package_versions(&package).with_context(|| {
+    let failures = failures
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some((position, err)) = failures.into_iter().min_by_key(|(position, _)| *position) {
+        return Err(err).with_context(|| {
             format!(
                 "source-metadata-invalid: source '{}' package '{}' failed metadata signature validation",
-                source_name, package
+                source_name, package_names[position]
             )
-        })?;
+        });
     }
 
     Ok(())
 }
 
+const MAX_SIGNATURE_VERIFICATION_WORKERS: usize = 8;
+
 fn collect_metadata_packages(staged_root: &Path, source_name: &str) -> Result<BTreeSet<String>> {
     let mut packages = BTreeSet::new();
     let releases_root = staged_root.join("releases");
@@ -3,7 +3,9 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum RegistrySourceKind {
+    #[serde(alias = "vcs")]
     Git,
+    #[serde(alias = "fs", alias = "local")]
     Filesystem,
 }
 
@@ -19,6 +21,10 @@ pub struct RegistrySourceRecord {
     pub priority: u32,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub community: Option<RegistrySourceCommunity>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_ref: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subdir: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -30,6 +36,7 @@ pub struct RegistrySourceCommunity {
 pub enum SourceUpdateStatus {
     Updated,
     UpToDate,
+    NoChange,
     Failed,
 }
 
@@ -47,11 +54,23 @@ pub struct RegistrySourceWithSnapshotState {
     pub snapshot: RegistrySourceSnapshotState,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceStatus {
+    pub name: String,
+    pub enabled: bool,
+    pub priority: u32,
+    pub snapshot: RegistrySourceSnapshotState,
+    pub updated_at_unix: Option<u64>,
+    pub age_secs: Option<u64>,
+    pub manifest_count: Option<u64>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RegistrySourceSnapshotState {
     None,
     Ready {
         snapshot_id: String,
+        total_bytes: Option<u64>,
     },
     Error {
         status: RegistrySourceWithSnapshotStatus,
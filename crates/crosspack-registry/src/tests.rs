@@ -1,9 +1,12 @@
 use super::*;
+use crosspack_core::FakeClock;
 use ed25519_dalek::{Signer, SigningKey};
+use semver::Version;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 #[test]
@@ -152,13 +155,99 @@ fn source_store_list_sources_with_snapshot_state_returns_ready_snapshot() {
     assert_eq!(
         listed[0].snapshot,
         RegistrySourceSnapshotState::Ready {
-            snapshot_id: "git:0123456789abcdef".to_string()
+            snapshot_id: "git:0123456789abcdef".to_string(),
+            total_bytes: None,
         }
     );
 
     let _ = fs::remove_dir_all(&root);
 }
 
+#[test]
+fn source_store_source_statuses_report_age_and_manifest_count_for_ready_sources() {
+    let root = test_registry_root();
+    let store = RegistrySourceStore::new(&root);
+    store
+        .add_source(source_record("official", 1))
+        .expect("must add source");
+    store
+        .add_source(source_record("unsynced", 2))
+        .expect("must add source");
+
+    let cache_root = root.join("cache").join("official");
+    fs::create_dir_all(&cache_root).expect("must create cache root");
+    fs::write(
+        cache_root.join("snapshot.json"),
+        r#"{
+  "version": 1,
+  "source": "official",
+  "snapshot_id": "git:0123456789abcdef",
+  "updated_at_unix": 1,
+  "manifest_count": 42,
+  "status": "ready"
+}"#,
+    )
+    .expect("must write snapshot file");
+
+    let statuses = store
+        .source_statuses()
+        .expect("must report source statuses");
+    assert_eq!(statuses.len(), 2);
+
+    let official = &statuses[0];
+    assert_eq!(official.name, "official");
+    assert!(official.enabled);
+    assert_eq!(official.priority, 1);
+    assert!(matches!(
+        official.snapshot,
+        RegistrySourceSnapshotState::Ready { .. }
+    ));
+    assert_eq!(official.updated_at_unix, Some(1));
+    assert!(official.age_secs.expect("ready source must report age") > 0);
+    assert_eq!(official.manifest_count, Some(42));
+
+    let unsynced = &statuses[1];
+    assert_eq!(unsynced.name, "unsynced");
+    assert_eq!(unsynced.snapshot, RegistrySourceSnapshotState::None);
+    assert_eq!(unsynced.updated_at_unix, None);
+    assert_eq!(unsynced.age_secs, None);
+    assert_eq!(unsynced.manifest_count, None);
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn source_store_sweep_stale_temp_removes_only_stale_leftovers() {
+    let root = test_registry_root();
+    let store = RegistrySourceStore::new(&root);
+    let now_nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("clock must be after epoch")
+        .as_nanos();
+
+    let stale_tmp = root.join("tmp-official-1");
+    let stale_backup = root.join("cache").join(".official-backup-1");
+    let fresh_tmp = root.join(format!("tmp-official-{now_nanos}"));
+    let active_cache = root.join("cache").join("official");
+    for dir in [&stale_tmp, &stale_backup, &fresh_tmp, &active_cache] {
+        fs::create_dir_all(dir).expect("must create dir");
+    }
+    fs::write(active_cache.join("snapshot.json"), "{}").expect("must write snapshot file");
+
+    let swept = store
+        .sweep_stale_temp(3600)
+        .expect("must sweep stale temp dirs");
+    let mut expected = vec![stale_tmp.clone(), stale_backup.clone()];
+    expected.sort();
+    assert_eq!(swept, expected);
+    assert!(!stale_tmp.exists());
+    assert!(!stale_backup.exists());
+    assert!(fresh_tmp.exists());
+    assert!(active_cache.join("snapshot.json").exists());
+
+    let _ = fs::remove_dir_all(&root);
+}
+
 #[test]
 fn source_store_list_sources_with_snapshot_state_maps_invalid_snapshot_to_error_reason_code() {
     let root = test_registry_root();
@@ -340,6 +429,41 @@ fn source_store_loads_unversioned_state_file_for_back_compat() {
     let _ = fs::remove_dir_all(&root);
 }
 
+#[test]
+fn source_store_loads_kind_aliases_and_rewrites_canonical_kinds() {
+    for (alias, expected, canonical) in [
+        ("fs", RegistrySourceKind::Filesystem, "filesystem"),
+        ("local", RegistrySourceKind::Filesystem, "filesystem"),
+        ("vcs", RegistrySourceKind::Git, "git"),
+    ] {
+        let root = test_registry_root();
+        fs::create_dir_all(&root).expect("must create state root");
+        fs::write(
+            root.join("sources.toml"),
+            format!(
+                "[[sources]]\nname = \"legacy\"\nkind = \"{alias}\"\nlocation = \"/srv/legacy-registry\"\nfingerprint_sha256 = \"0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef\"\npriority = 5\n"
+            ),
+        )
+        .expect("must write legacy state file");
+
+        let store = RegistrySourceStore::new(&root);
+        let listed = store.list_sources().expect("must list sources");
+        assert_eq!(listed[0].kind, expected, "alias '{alias}'");
+
+        store
+            .add_source(source_record("official", 1))
+            .expect("must add source");
+        let rewritten = fs::read_to_string(root.join("sources.toml")).expect("must read state");
+        assert!(
+            rewritten.contains(&format!("kind = \"{canonical}\"")),
+            "alias '{alias}' must re-serialize canonically: {rewritten}"
+        );
+        assert!(!rewritten.contains(&format!("kind = \"{alias}\"")));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
+
 #[test]
 fn source_store_rejects_negative_priority_in_legacy_state() {
     let root = test_registry_root();
@@ -553,6 +677,52 @@ fn source_store_rejects_invalid_community_recipe_catalog_path() {
     let _ = fs::remove_dir_all(&root);
 }
 
+#[test]
+fn configured_index_reload_picks_up_newly_synced_source() {
+    let state_root = test_registry_root();
+    fs::create_dir_all(&state_root).expect("must create state root");
+    let mut index =
+        ConfiguredRegistryIndex::open(&state_root).expect("must open empty configured index");
+    assert!(index
+        .search_names("rip")
+        .expect("must search empty index")
+        .is_empty());
+
+    let source_root = filesystem_source_fixture();
+    let registry_pub = fs::read(source_root.join("registry.pub")).expect("must read registry pub");
+    let store = RegistrySourceStore::new(&state_root);
+    store
+        .add_source(filesystem_source_record(
+            "local",
+            source_root
+                .to_str()
+                .expect("filesystem source path must be valid UTF-8"),
+            sha256_hex_bytes(&registry_pub),
+            0,
+        ))
+        .expect("must add source");
+    let results = store.update_sources(&[]).expect("must sync source");
+    assert_eq!(results[0].status, SourceUpdateStatus::Updated);
+
+    index.reload().expect("must reload configured index");
+    assert_eq!(
+        index
+            .search_names("rip")
+            .expect("must search reloaded index"),
+        vec!["ripgrep"]
+    );
+    assert_eq!(
+        index
+            .resolving_source("ripgrep")
+            .expect("must resolve source")
+            .as_deref(),
+        Some("local")
+    );
+
+    let _ = fs::remove_dir_all(&state_root);
+    let _ = fs::remove_dir_all(&source_root);
+}
+
 #[test]
 fn configured_index_precedence_remains_deterministic_with_community_metadata() {
     let state_root = test_registry_root();
@@ -653,6 +823,108 @@ fn update_filesystem_source_writes_ready_snapshot() {
     assert_eq!(snapshot["manifest_count"], 1);
     assert_eq!(snapshot["snapshot_id"], results[0].snapshot_id.as_str());
 
+    let total_bytes = snapshot["total_bytes"]
+        .as_u64()
+        .expect("snapshot must record total_bytes");
+    let manifest_bytes = fs::metadata(source_root.join("releases/ripgrep/14.1.0.toml"))
+        .expect("fixture manifest must exist")
+        .len();
+    assert_eq!(
+        total_bytes, manifest_bytes,
+        "total_bytes must count release manifests only"
+    );
+    let listed = store
+        .list_sources_with_snapshot_state()
+        .expect("must list source states");
+    assert_eq!(
+        listed[0].snapshot,
+        RegistrySourceSnapshotState::Ready {
+            snapshot_id: results[0].snapshot_id.clone(),
+            total_bytes: Some(total_bytes),
+        }
+    );
+
+    let _ = fs::remove_dir_all(&source_root);
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn discover_source_fingerprint_reports_registry_pub_digest_without_persisting() {
+    let root = test_registry_root();
+    let source_root = filesystem_source_fixture();
+    let store = RegistrySourceStore::new(&root);
+
+    let registry_pub = fs::read(source_root.join("registry.pub")).expect("must read registry pub");
+    let mut source = filesystem_source_record(
+        "local",
+        source_root
+            .to_str()
+            .expect("filesystem source path must be valid UTF-8"),
+        String::new(),
+        0,
+    );
+    let fingerprint = store
+        .discover_source_fingerprint(&source)
+        .expect("must discover fingerprint");
+    assert_eq!(fingerprint, sha256_hex_bytes(&registry_pub));
+    assert!(
+        store.list_sources().expect("must list sources").is_empty(),
+        "discovery must not record the source before confirmation"
+    );
+
+    source.fingerprint_sha256 = fingerprint;
+    let warnings = store
+        .add_source_with_duplicate_policy(source.clone(), DuplicateSourcePolicy::Warn)
+        .expect("must add confirmed source");
+    assert!(warnings.is_empty());
+    let sources = store.list_sources().expect("must list sources");
+    assert_eq!(sources, vec![source]);
+    let leftover_temp = fs::read_dir(&root)
+        .expect("must read state root")
+        .filter_map(|entry| entry.ok())
+        .any(|entry| entry.file_name().to_string_lossy().starts_with("tmp-"));
+    assert!(!leftover_temp, "discovery must clean up its temp checkout");
+
+    let results = store.update_sources(&[]).expect("must update source");
+    assert_eq!(results[0].status, SourceUpdateStatus::Updated);
+
+    let _ = fs::remove_dir_all(&source_root);
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn update_filesystem_source_stamps_snapshot_with_injected_clock() {
+    let root = test_registry_root();
+    let source_root = filesystem_source_fixture();
+    let clock = Arc::new(FakeClock::at_unix(1_700_000_000));
+    let store = RegistrySourceStore::new(&root).with_clock(clock.clone());
+
+    let registry_pub = fs::read(source_root.join("registry.pub")).expect("must read registry pub");
+    store
+        .add_source(filesystem_source_record(
+            "local",
+            source_root
+                .to_str()
+                .expect("filesystem source path must be valid UTF-8"),
+            sha256_hex_bytes(&registry_pub),
+            0,
+        ))
+        .expect("must add source");
+    store.update_sources(&[]).expect("must update source");
+
+    let snapshot_content = fs::read_to_string(root.join("cache/local/snapshot.json"))
+        .expect("must write snapshot.json");
+    let snapshot: serde_json::Value =
+        serde_json::from_str(&snapshot_content).expect("must parse snapshot.json");
+    assert_eq!(snapshot["updated_at_unix"], 1_700_000_000);
+
+    clock.advance(std::time::Duration::from_secs(90));
+    let statuses = store
+        .source_statuses()
+        .expect("must report source statuses");
+    assert_eq!(statuses[0].updated_at_unix, Some(1_700_000_000));
+    assert_eq!(statuses[0].age_secs, Some(90));
+
     let _ = fs::remove_dir_all(&source_root);
     let _ = fs::remove_dir_all(&root);
 }
@@ -690,6 +962,79 @@ fn update_filesystem_source_fails_on_fingerprint_mismatch() {
     let _ = fs::remove_dir_all(&root);
 }
 
+#[test]
+fn update_filesystem_bundle_source_rejects_wrong_fingerprint_before_staging() {
+    let root = test_registry_root();
+    let source_root = filesystem_source_fixture();
+    let bundle_path = source_root.with_extension("tar.gz");
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(&bundle_path)
+        .arg("-C")
+        .arg(&source_root)
+        .arg(".")
+        .status()
+        .expect("must run tar");
+    assert!(status.success(), "tar must create registry bundle");
+    let store = RegistrySourceStore::new(&root);
+
+    store
+        .add_source(filesystem_source_record(
+            "bundle",
+            bundle_path
+                .to_str()
+                .expect("bundle path must be valid UTF-8"),
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff".to_string(),
+            0,
+        ))
+        .expect("must add source");
+
+    let results = store
+        .update_sources(&[])
+        .expect("update API must report per-source failure");
+    assert_eq!(results[0].status, SourceUpdateStatus::Failed);
+    assert!(results[0]
+        .error
+        .as_deref()
+        .expect("must include error message")
+        .contains("source-key-fingerprint-mismatch"));
+    let staged_entries = fs::read_dir(&root)
+        .expect("must read state root")
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("tmp-"))
+        .count();
+    assert_eq!(staged_entries, 0);
+    assert!(!root.join("cache").join("bundle").exists());
+
+    let registry_pub = fs::read(source_root.join("registry.pub")).expect("must read registry pub");
+    store.remove_source("bundle").expect("must remove source");
+    store
+        .add_source(filesystem_source_record(
+            "bundle",
+            bundle_path
+                .to_str()
+                .expect("bundle path must be valid UTF-8"),
+            sha256_hex_bytes(&registry_pub),
+            0,
+        ))
+        .expect("must re-add source");
+    let results = store
+        .update_sources(&[])
+        .expect("must update bundle source");
+    assert_eq!(results[0].status, SourceUpdateStatus::Updated);
+    assert!(root
+        .join("cache")
+        .join("bundle")
+        .join("releases")
+        .join("ripgrep")
+        .join("14.1.0.toml")
+        .exists());
+
+    let _ = fs::remove_file(&bundle_path);
+    let _ = fs::remove_dir_all(&source_root);
+    let _ = fs::remove_dir_all(&root);
+}
+
 #[test]
 fn update_filesystem_source_accepts_uppercase_configured_fingerprint() {
     let root = test_registry_root();
@@ -806,6 +1151,77 @@ fn update_filesystem_source_fails_when_releases_root_is_missing() {
     let _ = fs::remove_dir_all(&root);
 }
 
+#[test]
+fn update_filesystem_source_strict_layout_accepts_clean_snapshot() {
+    let root = test_registry_root();
+    let source_root = filesystem_source_fixture();
+    let store =
+        RegistrySourceStore::new(&root).with_snapshot_layout_policy(SnapshotLayoutPolicy::Strict);
+
+    let registry_pub = fs::read(source_root.join("registry.pub")).expect("must read registry pub");
+    store
+        .add_source(filesystem_source_record(
+            "local",
+            source_root
+                .to_str()
+                .expect("filesystem source path must be valid UTF-8"),
+            sha256_hex_bytes(&registry_pub),
+            0,
+        ))
+        .expect("must add source");
+
+    let results = store.update_sources(&[]).expect("must update source");
+    assert_eq!(results[0].status, SourceUpdateStatus::Updated);
+
+    let _ = fs::remove_dir_all(&source_root);
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn update_filesystem_source_strict_layout_rejects_unexpected_top_level_entry() {
+    let source_root = filesystem_source_fixture();
+    fs::write(source_root.join("evil.sh"), "#!/bin/sh\n").expect("must write stray file");
+    let registry_pub = fs::read(source_root.join("registry.pub")).expect("must read registry pub");
+    let source = filesystem_source_record(
+        "local",
+        source_root
+            .to_str()
+            .expect("filesystem source path must be valid UTF-8"),
+        sha256_hex_bytes(&registry_pub),
+        0,
+    );
+
+    let strict_root = test_registry_root();
+    let strict_store = RegistrySourceStore::new(&strict_root)
+        .with_snapshot_layout_policy(SnapshotLayoutPolicy::Strict);
+    strict_store
+        .add_source(source.clone())
+        .expect("must add source");
+    let strict = strict_store
+        .update_sources(&[])
+        .expect("must return update results");
+    assert_eq!(strict[0].status, SourceUpdateStatus::Failed);
+    let error = strict[0].error.as_deref().expect("must record error");
+    assert!(error.contains("source-metadata-invalid"), "{error}");
+    assert!(
+        error.contains("unexpected top-level entries: evil.sh"),
+        "{error}"
+    );
+    assert!(!strict_root.join("cache").join("local").exists());
+
+    let lenient_root = test_registry_root();
+    let lenient_store = RegistrySourceStore::new(&lenient_root);
+    lenient_store.add_source(source).expect("must add source");
+    let lenient = lenient_store
+        .update_sources(&[])
+        .expect("must return update results");
+    assert_eq!(lenient[0].status, SourceUpdateStatus::Updated);
+
+    let _ = fs::remove_dir_all(&source_root);
+    let _ = fs::remove_dir_all(&strict_root);
+    let _ = fs::remove_dir_all(&lenient_root);
+}
+
 #[test]
 fn update_filesystem_source_fails_when_orphaned_package_template_exists() {
     let root = test_registry_root();
@@ -894,25 +1310,81 @@ fn update_filesystem_source_fails_when_orphaned_package_template_signature_is_mi
 }
 
 #[test]
-fn update_filesystem_source_fails_when_community_recipe_catalog_signature_is_missing() {
+fn update_filesystem_source_fails_when_one_of_many_packages_has_bad_signature() {
     let root = test_registry_root();
     let source_root = filesystem_source_fixture();
     let store = RegistrySourceStore::new(&root);
 
+    let signing_key = signing_key();
+    for index in 0..32 {
+        write_signed_manifest(
+            &source_root
+                .join("releases")
+                .join(format!("tool-{index:02}")),
+            &signing_key,
+            "1.0.0",
+        );
+    }
+    let tampered = source_root
+        .join("releases")
+        .join("tool-17")
+        .join("1.0.0.toml");
+    let mut tampered_content = fs::read_to_string(&tampered).expect("must read release manifest");
+    tampered_content.push_str("\n# tampered\n");
+    fs::write(&tampered, tampered_content).expect("must tamper release manifest");
+
     let registry_pub = fs::read(source_root.join("registry.pub")).expect("must read registry pub");
-    let mut source = filesystem_source_record(
-        "local",
-        source_root
-            .to_str()
-            .expect("filesystem source path must be valid UTF-8"),
-        sha256_hex_bytes(&registry_pub),
-        0,
-    );
-    source.community = Some(RegistrySourceCommunity {
-        recipe_catalog_path: "community/recipes.toml".to_string(),
-    });
+    store
+        .add_source(filesystem_source_record(
+            "local",
+            source_root
+                .to_str()
+                .expect("filesystem source path must be valid UTF-8"),
+            sha256_hex_bytes(&registry_pub),
+            0,
+        ))
+        .expect("must add source");
 
-    fs::create_dir_all(source_root.join("community")).expect("must create community dir");
+    let results = store
+        .update_sources(&[])
+        .expect("update API must report per-source failure");
+    assert_eq!(results[0].status, SourceUpdateStatus::Failed);
+    let rendered = results[0]
+        .error
+        .as_deref()
+        .expect("must include error message");
+    assert!(
+        rendered.contains("source-metadata-invalid")
+            && rendered.contains("package 'tool-17'")
+            && rendered.contains("invalid metadata signature"),
+        "expected signature failure for tampered package, got: {rendered}"
+    );
+    assert!(!root.join("cache").join("local").exists());
+
+    let _ = fs::remove_dir_all(&source_root);
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn update_filesystem_source_fails_when_community_recipe_catalog_signature_is_missing() {
+    let root = test_registry_root();
+    let source_root = filesystem_source_fixture();
+    let store = RegistrySourceStore::new(&root);
+
+    let registry_pub = fs::read(source_root.join("registry.pub")).expect("must read registry pub");
+    let mut source = filesystem_source_record(
+        "local",
+        source_root
+            .to_str()
+            .expect("filesystem source path must be valid UTF-8"),
+        sha256_hex_bytes(&registry_pub),
+        0,
+    );
+    source.community = Some(RegistrySourceCommunity {
+        recipe_catalog_path: "community/recipes.toml".to_string(),
+    });
+
+    fs::create_dir_all(source_root.join("community")).expect("must create community dir");
     fs::write(
         source_root.join("community").join("recipes.toml"),
         "version = 1\n[[recipes]]\npackage = \"ripgrep\"\n",
@@ -1067,6 +1539,55 @@ fn update_git_source_clones_and_records_snapshot_id() {
     let _ = fs::remove_dir_all(&root);
 }
 
+#[cfg(unix)]
+#[test]
+fn update_git_source_spawns_configured_git_program_with_extra_env() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let root = test_registry_root();
+    fs::create_dir_all(&root).expect("must create registry root");
+    let marker = root.join("fake-git-marker");
+    let fake_git = root.join("fake-git");
+    fs::write(
+        &fake_git,
+        "#!/bin/sh\necho \"$CROSSPACK_FAKE_GIT_TAG $1 $2\" > \"$CROSSPACK_FAKE_GIT_MARKER\"\nexit 1\n",
+    )
+    .expect("must write fake git");
+    fs::set_permissions(&fake_git, fs::Permissions::from_mode(0o755))
+        .expect("must mark fake git executable");
+
+    let store = RegistrySourceStore::new(&root).with_git_config(GitConfig {
+        program: fake_git.clone(),
+        extra_env: vec![
+            (
+                "CROSSPACK_FAKE_GIT_MARKER".to_string(),
+                marker.display().to_string(),
+            ),
+            ("CROSSPACK_FAKE_GIT_TAG".to_string(), "pinned".to_string()),
+        ],
+    });
+    store
+        .add_source(git_source_record(
+            "origin",
+            "https://example.com/origin.git",
+            "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef".to_string(),
+            0,
+        ))
+        .expect("must add source");
+
+    let results = store
+        .update_sources(&[])
+        .expect("update must report results");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].status, SourceUpdateStatus::Failed);
+    assert_eq!(
+        fs::read_to_string(&marker).expect("fake git must write marker"),
+        "pinned -c core.autocrlf=false\n"
+    );
+
+    let _ = fs::remove_dir_all(&root);
+}
+
 #[test]
 fn update_git_source_fetches_new_commit() {
     let root = test_registry_root();
@@ -1120,6 +1641,376 @@ fn update_git_source_fetches_new_commit() {
     let _ = fs::remove_dir_all(&root);
 }
 
+#[test]
+fn update_git_source_follows_configured_git_ref() {
+    let root = test_registry_root();
+    let source_root = git_source_fixture();
+    let store = RegistrySourceStore::new(&root);
+
+    git_run(&source_root, &["checkout", "-b", "stable"]);
+    write_signed_manifest(
+        &source_root.join("releases").join("ripgrep"),
+        &signing_key(),
+        "15.0.0",
+    );
+    git_commit_all(&source_root, "publish ripgrep 15.0.0 on stable");
+    let stable_snapshot_id = git_head_short(&source_root);
+    git_run(&source_root, &["checkout", "-"]);
+
+    let registry_pub = fs::read(source_root.join("registry.pub")).expect("must read registry pub");
+    let source_location = git_fixture_location(&source_root);
+    let mut source = git_source_record(
+        "origin",
+        &source_location,
+        sha256_hex_bytes(&registry_pub),
+        0,
+    );
+    source.git_ref = Some("refs/heads/stable".to_string());
+    store.add_source(source).expect("must add source");
+
+    let first = store.update_sources(&[]).expect("must update source");
+    assert_eq!(first[0].status, SourceUpdateStatus::Updated);
+    assert_eq!(first[0].snapshot_id, stable_snapshot_id);
+    let cached_release = root
+        .join("cache")
+        .join("origin")
+        .join("releases")
+        .join("ripgrep")
+        .join("15.0.0.toml");
+    assert!(cached_release.exists());
+
+    git_run(&source_root, &["checkout", "stable"]);
+    write_signed_manifest(
+        &source_root.join("releases").join("ripgrep"),
+        &signing_key(),
+        "15.1.0",
+    );
+    git_commit_all(&source_root, "publish ripgrep 15.1.0 on stable");
+    let next_stable_snapshot_id = git_head_short(&source_root);
+    git_run(&source_root, &["checkout", "-"]);
+
+    let second = store.update_sources(&[]).expect("must update source");
+    assert_eq!(second[0].status, SourceUpdateStatus::Updated);
+    assert_eq!(second[0].snapshot_id, next_stable_snapshot_id);
+    assert!(cached_release.with_file_name("15.1.0.toml").exists());
+
+    let _ = fs::remove_dir_all(&source_root);
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn update_git_source_sparse_checks_out_configured_subdir() {
+    let root = test_registry_root();
+    let registry_root = filesystem_source_fixture();
+    let source_root = test_registry_root();
+    fs::create_dir_all(&source_root).expect("must create monorepo root");
+    fs::write(source_root.join("README.md"), "monorepo\n").expect("must write readme");
+    fs::create_dir_all(source_root.join("app")).expect("must create unrelated dir");
+    fs::write(source_root.join("app").join("main.rs"), "fn main() {}\n")
+        .expect("must write unrelated file");
+    fs::rename(&registry_root, source_root.join("packaging")).expect("must move registry");
+    git_run(&source_root, &["init"]);
+    git_commit_all(&source_root, "initial monorepo snapshot");
+    let expected_snapshot_id = git_head_short(&source_root);
+
+    let registry_pub = fs::read(source_root.join("packaging").join("registry.pub"))
+        .expect("must read registry pub");
+    let mut source = git_source_record(
+        "origin",
+        &git_fixture_location(&source_root),
+        sha256_hex_bytes(&registry_pub),
+        0,
+    );
+    source.subdir = Some("packaging".to_string());
+    let store = RegistrySourceStore::new(&root);
+    store.add_source(source).expect("must add source");
+
+    let results = store.update_sources(&[]).expect("must update source");
+    assert_eq!(results[0].status, SourceUpdateStatus::Updated);
+    assert_eq!(results[0].snapshot_id, expected_snapshot_id);
+
+    let cache_root = root.join("cache").join("origin");
+    assert!(cache_root.join("packaging").join("registry.pub").is_file());
+    assert!(
+        !cache_root.join("app").exists(),
+        "unrelated dirs must stay sparse"
+    );
+
+    let index = ConfiguredRegistryIndex::open(&root).expect("must open configured index");
+    let manifests = index
+        .package_versions("ripgrep")
+        .expect("must read package versions");
+    assert!(!manifests.is_empty());
+
+    let _ = fs::remove_dir_all(&source_root);
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn export_and_import_sources_round_trip_with_conflict_and_merge() {
+    let exporter_root = test_registry_root();
+    let exporter = RegistrySourceStore::new(&exporter_root);
+    exporter
+        .add_source(source_record("official", 10))
+        .expect("must add official source");
+    let mut beta = source_record("beta", 20);
+    beta.git_ref = Some("refs/heads/beta".to_string());
+    exporter.add_source(beta).expect("must add beta source");
+    exporter
+        .add_source(filesystem_source_record(
+            "local",
+            "/srv/registry",
+            "fedcba9876543210fedcba9876543210fedcba9876543210fedcba9876543210".to_string(),
+            5,
+        ))
+        .expect("must add local source");
+
+    let exported = exporter.export_sources().expect("must export sources");
+    assert!(!exported.contains("snapshot"));
+
+    let importer_root = test_registry_root();
+    let importer = RegistrySourceStore::new(&importer_root);
+    importer
+        .import_sources(&exported, false)
+        .expect("must import into empty store");
+    assert_eq!(
+        importer.list_sources().expect("must list imported sources"),
+        exporter.list_sources().expect("must list exported sources")
+    );
+
+    let mut changed = source_record("official", 1);
+    changed.location = "https://mirror.example.com/official.git".to_string();
+    let changed_root = test_registry_root();
+    let changed_store = RegistrySourceStore::new(&changed_root);
+    changed_store
+        .add_source(changed.clone())
+        .expect("must add changed source");
+    let changed_export = changed_store.export_sources().expect("must export changed");
+
+    let err = importer
+        .import_sources(&changed_export, false)
+        .expect_err("conflicting import must fail without merge");
+    assert!(err.to_string().contains("source 'official' already exists"));
+    let official = importer
+        .list_sources()
+        .expect("must list sources")
+        .into_iter()
+        .find(|source| source.name == "official")
+        .expect("official source must remain");
+    assert_eq!(official.location, "https://example.com/official.git");
+
+    importer
+        .import_sources(&changed_export, true)
+        .expect("merge import must replace conflicting source");
+    let sources = importer.list_sources().expect("must list merged sources");
+    assert_eq!(sources.len(), 3);
+    assert_eq!(sources[0], changed);
+
+    let _ = fs::remove_dir_all(&exporter_root);
+    let _ = fs::remove_dir_all(&importer_root);
+    let _ = fs::remove_dir_all(&changed_root);
+}
+
+#[test]
+fn import_sources_rejects_invalid_records() {
+    let root = test_registry_root();
+    let store = RegistrySourceStore::new(&root);
+    let content = r#"
+version = 1
+
+[[sources]]
+name = "Bad Name"
+kind = "git"
+location = "https://example.com/bad.git"
+fingerprint_sha256 = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef"
+priority = 1
+"#;
+
+    let err = store
+        .import_sources(content, true)
+        .expect_err("invalid source name must be rejected");
+    assert!(format!("{err:#}").contains("invalid source name"));
+    assert!(store.list_sources().expect("must list sources").is_empty());
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn add_source_rejects_location_that_does_not_match_kind() {
+    let state_root = test_registry_root();
+    let store = RegistrySourceStore::new(&state_root);
+    let fingerprint = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    let cases = [
+        (
+            filesystem_source_record("web", "http://example.com/registry", fingerprint.into(), 0),
+            "URL schemes are not allowed",
+        ),
+        (
+            filesystem_source_record("fileurl", "file:///srv/registry", fingerprint.into(), 0),
+            "URL schemes are not allowed",
+        ),
+        (
+            filesystem_source_record("relative", "registry/local", fingerprint.into(), 0),
+            "must be an absolute path",
+        ),
+        (
+            git_source_record("bare", "registry.git", fingerprint.into(), 0),
+            "expected a URL or absolute path",
+        ),
+    ];
+    for (source, expected) in cases {
+        let name = source.name.clone();
+        let err = store
+            .add_source(source)
+            .expect_err("mismatched location must be rejected");
+        assert!(
+            err.to_string().contains(expected),
+            "unexpected error for '{name}': {err:#}"
+        );
+    }
+
+    for source in [
+        git_source_record("https", "https://example.com/r.git", fingerprint.into(), 0),
+        git_source_record("scp", "git@example.com:org/r.git", fingerprint.into(), 1),
+        git_source_record("local-git", "/srv/registry.git", fingerprint.into(), 2),
+        filesystem_source_record("local-fs", "/srv/registry", fingerprint.into(), 3),
+    ] {
+        store
+            .add_source(source)
+            .expect("matching location must be accepted");
+    }
+    assert_eq!(store.list_sources().expect("must list sources").len(), 4);
+
+    let _ = fs::remove_dir_all(&state_root);
+}
+
+#[test]
+fn add_source_flags_duplicate_location_and_fingerprint() {
+    let state_root = test_registry_root();
+    let store = RegistrySourceStore::new(&state_root);
+    let fingerprint = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+
+    let first = store
+        .add_source_with_duplicate_policy(
+            git_source_record(
+                "official",
+                "https://example.com/r.git",
+                fingerprint.into(),
+                0,
+            ),
+            DuplicateSourcePolicy::Warn,
+        )
+        .expect("first source must be added");
+    assert!(first.is_empty());
+
+    let warnings = store
+        .add_source_with_duplicate_policy(
+            git_source_record("mirror", "https://example.com/r.git", fingerprint.into(), 1),
+            DuplicateSourcePolicy::Warn,
+        )
+        .expect("duplicate location must be accepted with a warning");
+    assert_eq!(
+        warnings,
+        vec![
+            "source 'mirror' duplicates source 'official' (same location and fingerprint)"
+                .to_string()
+        ]
+    );
+
+    let err = store
+        .add_source_with_duplicate_policy(
+            git_source_record("again", "https://example.com/r.git", fingerprint.into(), 2),
+            DuplicateSourcePolicy::Reject,
+        )
+        .expect_err("strict policy must reject duplicate location");
+    assert!(err.to_string().contains("duplicates source 'official'"));
+    assert_eq!(store.list_sources().expect("must list sources").len(), 2);
+
+    let signing_key = SigningKey::from_bytes(&[17u8; 32]);
+    write_ready_snapshot_cache(&state_root, "official", &signing_key, &["14.0.0"]);
+    write_ready_snapshot_cache(&state_root, "mirror", &signing_key, &["14.0.0"]);
+    let index = ConfiguredRegistryIndex::open(&state_root).expect("must open configured index");
+    assert_eq!(
+        index.redundant_sources(),
+        &[("mirror".to_string(), "official".to_string())]
+    );
+
+    let _ = fs::remove_dir_all(&state_root);
+}
+
+#[test]
+fn add_source_rejects_git_ref_on_filesystem_source() {
+    let root = test_registry_root();
+    let store = RegistrySourceStore::new(&root);
+    let mut source = filesystem_source_record(
+        "local",
+        "/tmp/registry",
+        "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef".to_string(),
+        0,
+    );
+    source.git_ref = Some("refs/heads/stable".to_string());
+
+    let err = store
+        .add_source(source)
+        .expect_err("git_ref must be rejected for filesystem sources");
+    assert!(err
+        .to_string()
+        .contains("sets git_ref but is not a git source"));
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn update_with_revalidate_reports_no_change_for_identical_snapshot() {
+    let root = test_registry_root();
+    let source_root = filesystem_source_fixture();
+    let store = RegistrySourceStore::new(&root);
+
+    let registry_pub = fs::read(source_root.join("registry.pub")).expect("must read registry pub");
+    store
+        .add_source(filesystem_source_record(
+            "local",
+            source_root
+                .to_str()
+                .expect("filesystem source path must be valid UTF-8"),
+            sha256_hex_bytes(&registry_pub),
+            0,
+        ))
+        .expect("must add source");
+    let first = store.update_sources(&[]).expect("must update source");
+    assert_eq!(first[0].status, SourceUpdateStatus::Updated);
+
+    let cached_signature = root
+        .join("cache")
+        .join("local")
+        .join("releases")
+        .join("ripgrep")
+        .join("14.1.0.toml.sig");
+    fs::remove_file(&cached_signature).expect("must tamper with cached signature");
+
+    let unchanged = store.update_sources(&[]).expect("must update source");
+    assert_eq!(unchanged[0].status, SourceUpdateStatus::UpToDate);
+    assert_eq!(unchanged[0].snapshot_id, first[0].snapshot_id);
+    assert!(
+        !cached_signature.exists(),
+        "unchanged snapshot must not be revalidated or replaced"
+    );
+
+    let revalidated = store
+        .update_sources_with_revalidate(&[], true)
+        .expect("must revalidate source");
+    assert_eq!(revalidated[0].status, SourceUpdateStatus::NoChange);
+    assert_eq!(revalidated[0].snapshot_id, first[0].snapshot_id);
+    assert!(
+        cached_signature.exists(),
+        "revalidation must rebuild the cache from the verified snapshot"
+    );
+
+    let _ = fs::remove_dir_all(&source_root);
+    let _ = fs::remove_dir_all(&root);
+}
+
 #[test]
 fn update_git_source_returns_up_to_date_when_revision_unchanged() {
     let root = test_registry_root();
@@ -1187,6 +2078,86 @@ fn rollback_replace_error_includes_restore_failure_context() {
     assert!(rendered.contains("denied"));
 }
 
+#[test]
+fn finalize_source_update_falls_back_to_copy_on_cross_device_rename() {
+    let root = test_registry_root();
+    let source_root = filesystem_source_fixture();
+    let store = RegistrySourceStore::new(&root);
+
+    let registry_pub = fs::read(source_root.join("registry.pub")).expect("must read registry pub");
+    let source = filesystem_source_record(
+        "local",
+        source_root
+            .to_str()
+            .expect("filesystem source path must be valid UTF-8"),
+        sha256_hex_bytes(&registry_pub),
+        0,
+    );
+    store.add_source(source.clone()).expect("must add source");
+    let first = store.update_sources(&[]).expect("must seed cache");
+    assert_eq!(first[0].status, SourceUpdateStatus::Updated);
+
+    let staging_root = test_registry_root();
+    let cross_device_rename = |from: &Path, to: &Path| {
+        if from.starts_with(&staging_root) {
+            return Err(std::io::Error::from(std::io::ErrorKind::CrossesDevices));
+        }
+        fs::rename(from, to)
+    };
+
+    fs::write(source_root.join("NOTES"), "second snapshot").expect("must mark fixture");
+    let staged = staging_root.join("local-staged");
+    copy_source_to_temp(&source_root, &staged, "local").expect("must stage source");
+    let (status, snapshot_id) = finalize_staged_source_update_with_rename(
+        &store,
+        &source,
+        staged.clone(),
+        "fs:second".to_string(),
+        cross_device_rename,
+    )
+    .expect("cross-device rename must fall back to copy");
+    assert_eq!(status, SourceUpdateStatus::Updated);
+    assert_eq!(snapshot_id, "fs:second");
+    assert!(!staged.exists());
+    let cache_root = root.join("cache").join("local");
+    assert_eq!(
+        fs::read_to_string(cache_root.join("NOTES")).expect("must copy staged tree"),
+        "second snapshot"
+    );
+
+    fs::write(source_root.join("NOTES"), "third snapshot").expect("must mark fixture");
+    let staged = staging_root.join("local-staged-again");
+    copy_source_to_temp(&source_root, &staged, "local").expect("must stage source");
+    let err = finalize_staged_source_update_with_rename(
+        &store,
+        &source,
+        staged,
+        "fs:third".to_string(),
+        |from: &Path, to: &Path| {
+            if from.starts_with(&staging_root) {
+                return Err(std::io::Error::from(std::io::ErrorKind::PermissionDenied));
+            }
+            fs::rename(from, to)
+        },
+    )
+    .expect_err("non cross-device rename failure must abort");
+    assert!(err.to_string().contains("failed replacing cache"));
+    assert_eq!(
+        fs::read_to_string(cache_root.join("NOTES")).expect("must restore previous cache"),
+        "second snapshot"
+    );
+    let leftover_backups = fs::read_dir(root.join("cache"))
+        .expect("must read cache root")
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().contains("-backup-"))
+        .count();
+    assert_eq!(leftover_backups, 0);
+
+    let _ = fs::remove_dir_all(&staging_root);
+    let _ = fs::remove_dir_all(&source_root);
+    let _ = fs::remove_dir_all(&root);
+}
+
 #[test]
 fn update_unknown_source_returns_source_not_found() {
     let root = test_registry_root();
@@ -1231,6 +2202,80 @@ fn search_names_fails_when_registry_public_key_is_missing() {
     let _ = fs::remove_dir_all(&root);
 }
 
+#[test]
+fn open_with_fingerprint_accepts_matching_registry_key() {
+    let root = filesystem_source_fixture();
+    let registry_pub = fs::read(root.join("registry.pub")).expect("must read registry pub");
+
+    let index = RegistryIndex::open_with_fingerprint(&root, &sha256_hex_bytes(&registry_pub))
+        .expect("matching fingerprint must open");
+    let versions = index
+        .package_versions("ripgrep")
+        .expect("must read verified versions");
+    assert_eq!(versions.len(), 1);
+
+    let swapped_key = SigningKey::from_bytes(&[42u8; 32]);
+    fs::write(root.join("registry.pub"), public_key_hex(&swapped_key))
+        .expect("must swap registry key");
+    let err = index
+        .package_versions("ripgrep")
+        .expect_err("swapped key must be rejected");
+    assert!(err.to_string().contains("fingerprint mismatch"));
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn open_with_fingerprint_rejects_mismatched_registry_key() {
+    let root = filesystem_source_fixture();
+
+    let err = RegistryIndex::open_with_fingerprint(&root, &"0".repeat(64))
+        .expect_err("mismatched fingerprint must fail");
+    let message = err.to_string();
+    assert!(message.contains("trusted registry key fingerprint mismatch"));
+    assert!(message.contains(&format!("expected {}", "0".repeat(64))));
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn inventory_lists_signed_versions_and_excludes_unsigned_package() {
+    let root = test_registry_root();
+    let signing_key = SigningKey::from_bytes(&[41u8; 32]);
+    fs::create_dir_all(&root).expect("must create registry root");
+    fs::write(root.join("registry.pub"), public_key_hex(&signing_key))
+        .expect("must write registry key");
+
+    let ripgrep_dir = root.join("releases").join("ripgrep");
+    fs::create_dir_all(&ripgrep_dir).expect("must create ripgrep releases");
+    write_signed_manifest(&ripgrep_dir, &signing_key, "14.0.0");
+    write_signed_manifest(&ripgrep_dir, &signing_key, "14.1.0");
+
+    let fd_dir = root.join("releases").join("fd");
+    fs::create_dir_all(&fd_dir).expect("must create fd releases");
+    write_signed_manifest(&fd_dir, &signing_key, "10.0.0");
+    fs::remove_file(fd_dir.join("10.0.0.toml.sig")).expect("must remove fd signature");
+
+    let index = RegistryIndex::open(&root);
+    let err = index
+        .inventory()
+        .expect_err("strict inventory must fail on unsigned package");
+    assert!(err.to_string().contains("failed to inventory package 'fd'"));
+
+    let inventory = index
+        .inventory_with_policy(InventoryPolicy::SkipInvalid)
+        .expect("lenient inventory must skip unsigned package");
+    assert_eq!(
+        inventory,
+        vec![
+            ("ripgrep".to_string(), Version::new(14, 0, 0)),
+            ("ripgrep".to_string(), Version::new(14, 1, 0)),
+        ]
+    );
+
+    let _ = fs::remove_dir_all(&root);
+}
+
 #[test]
 fn configured_index_package_versions_prefers_higher_priority_source() {
     let state_root = test_registry_root();
@@ -1262,6 +2307,46 @@ fn configured_index_package_versions_prefers_higher_priority_source() {
     let _ = fs::remove_dir_all(&state_root);
 }
 
+#[test]
+fn configured_index_resolving_source_reports_higher_priority_source() {
+    let state_root = test_registry_root();
+    let store = RegistrySourceStore::new(&state_root);
+
+    store
+        .add_source(source_record("fallback", 10))
+        .expect("must add fallback source");
+    store
+        .add_source(source_record("preferred", 0))
+        .expect("must add preferred source");
+
+    let fallback_key = SigningKey::from_bytes(&[11u8; 32]);
+    let preferred_key = SigningKey::from_bytes(&[13u8; 32]);
+    write_ready_snapshot_cache(&state_root, "fallback", &fallback_key, &["14.0.0"]);
+    write_ready_snapshot_cache(&state_root, "preferred", &preferred_key, &["14.1.0"]);
+    fs::write(
+        state_root.join("cache/preferred/releases/ripgrep/14.1.0.toml.sig"),
+        "not-a-signature",
+    )
+    .expect("must corrupt preferred signature");
+
+    let index = ConfiguredRegistryIndex::open(&state_root).expect("must open configured index");
+    assert_eq!(
+        index
+            .resolving_source("ripgrep")
+            .expect("must check sources without verifying manifests"),
+        Some("preferred".to_string())
+    );
+    assert!(index.package_versions("ripgrep").is_err());
+    assert_eq!(
+        index
+            .resolving_source("missing")
+            .expect("must check sources for missing package"),
+        None
+    );
+
+    let _ = fs::remove_dir_all(&state_root);
+}
+
 #[test]
 fn configured_index_package_versions_uses_name_tiebreaker() {
     let state_root = test_registry_root();
@@ -1293,6 +2378,101 @@ fn configured_index_package_versions_uses_name_tiebreaker() {
     let _ = fs::remove_dir_all(&state_root);
 }
 
+#[test]
+fn configured_index_search_detailed_reports_precedence_source_latest_version() {
+    let state_root = test_registry_root();
+    let store = RegistrySourceStore::new(&state_root);
+
+    store
+        .add_source(source_record("fallback", 10))
+        .expect("must add fallback source");
+    store
+        .add_source(source_record("preferred", 0))
+        .expect("must add preferred source");
+
+    let fallback_key = SigningKey::from_bytes(&[47u8; 32]);
+    let preferred_key = SigningKey::from_bytes(&[53u8; 32]);
+    write_ready_snapshot_cache(
+        &state_root,
+        "fallback",
+        &fallback_key,
+        &["14.0.0", "15.0.0"],
+    );
+    write_ready_snapshot_cache(
+        &state_root,
+        "preferred",
+        &preferred_key,
+        &["14.0.0", "14.1.0"],
+    );
+
+    let index = ConfiguredRegistryIndex::open(&state_root).expect("must open configured index");
+    let installed = std::collections::BTreeSet::from(["ripgrep".to_string()]);
+    let hits = index
+        .search_detailed("rip", &installed)
+        .expect("must search configured sources");
+    assert_eq!(
+        hits,
+        vec![SearchHit {
+            name: "ripgrep".to_string(),
+            latest_version: "14.1.0".to_string(),
+            source: "preferred".to_string(),
+            installed: true,
+        }]
+    );
+
+    let hits = index
+        .search_detailed("rip", &std::collections::BTreeSet::new())
+        .expect("must search configured sources");
+    assert!(!hits[0].installed);
+
+    let _ = fs::remove_dir_all(&state_root);
+}
+
+#[test]
+fn configured_index_providers_of_lists_latest_versions_declaring_capability() {
+    let state_root = test_registry_root();
+    let store = RegistrySourceStore::new(&state_root);
+    store
+        .add_source(source_record("core", 0))
+        .expect("must add source");
+
+    let signing_key = SigningKey::from_bytes(&[61u8; 32]);
+    write_ready_snapshot_cache(&state_root, "core", &signing_key, &["14.1.0"]);
+    let cache_root = state_root.join("cache").join("core");
+    for (name, version) in [("gcc", "13.2.0"), ("clang", "18.1.0")] {
+        write_signed_package_template(
+            &cache_root,
+            &signing_key,
+            name,
+            &format!("name = \"{name}\"\nprovides = [\"compiler\"]\n"),
+        );
+        write_signed_release_manifest(
+            &cache_root.join("releases").join(name),
+            &signing_key,
+            version,
+            &release_toml(version),
+        );
+    }
+
+    let index = ConfiguredRegistryIndex::open(&state_root).expect("must open configured index");
+    let providers = index
+        .providers_of("compiler")
+        .expect("must list capability providers");
+    assert_eq!(
+        providers,
+        vec![
+            ("clang".to_string(), Version::new(18, 1, 0)),
+            ("gcc".to_string(), Version::new(13, 2, 0)),
+        ]
+    );
+    assert!(index
+        .providers_of("linker")
+        .expect("must list capability providers")
+        .is_empty());
+
+    let _ = fs::remove_dir_all(&state_root);
+}
+
 #[test]
 fn configured_index_search_names_deduplicates_across_sources() {
     let state_root = test_registry_root();
@@ -1311,10 +2491,50 @@ fn configured_index_search_names_deduplicates_across_sources() {
     write_ready_snapshot_cache(&state_root, "two", &second_key, &["14.0.0"]);
 
     let index = ConfiguredRegistryIndex::open(&state_root).expect("must open configured index");
-    let names = index
+    let names = index
+        .search_names("rip")
+        .expect("must deduplicate package names");
+    assert_eq!(names, vec!["ripgrep"]);
+
+    let _ = fs::remove_dir_all(&state_root);
+}
+
+#[test]
+fn configured_index_search_names_lenient_reports_corrupt_source() {
+    let state_root = test_registry_root();
+    let store = RegistrySourceStore::new(&state_root);
+
+    store
+        .add_source(source_record("healthy", 0))
+        .expect("must add healthy source");
+    store
+        .add_source(source_record("corrupt", 1))
+        .expect("must add corrupt source");
+
+    let healthy_key = SigningKey::from_bytes(&[31u8; 32]);
+    let corrupt_key = SigningKey::from_bytes(&[37u8; 32]);
+    write_ready_snapshot_cache(&state_root, "healthy", &healthy_key, &["14.1.0"]);
+    write_ready_snapshot_cache(&state_root, "corrupt", &corrupt_key, &["14.0.0"]);
+    fs::write(
+        state_root
+            .join("cache")
+            .join("corrupt")
+            .join("releases")
+            .join("ripgrep")
+            .join("14.0.0.toml"),
+        "name = \"ripgrep\"\nversion = \"14.0.0\"\n# tampered\n",
+    )
+    .expect("must corrupt cached manifest");
+
+    let index = ConfiguredRegistryIndex::open(&state_root).expect("must open configured index");
+    index
         .search_names("rip")
-        .expect("must deduplicate package names");
+        .expect_err("strict search must fail on corrupt source");
+
+    let (names, errors) = index.search_names_lenient("rip");
     assert_eq!(names, vec!["ripgrep"]);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, "corrupt");
 
     let _ = fs::remove_dir_all(&state_root);
 }
@@ -1632,6 +2852,272 @@ fn search_names_returns_matching_package_with_valid_signed_manifests() {
     let _ = fs::remove_dir_all(&root);
 }
 
+#[test]
+fn package_versions_prefers_overlay_index_dir_over_base() {
+    let root = test_registry_root();
+    let signing_key = signing_key();
+    fs::create_dir_all(&root).expect("must create registry root");
+    fs::write(root.join("registry.pub"), public_key_hex(&signing_key))
+        .expect("must write registry public key");
+
+    for (index_dir, versions) in [
+        ("index", ["14.0.0", "14.1.0"].as_slice()),
+        ("index-overlay", ["14.1.0", "14.2.0"].as_slice()),
+    ] {
+        let index_root = root.join(index_dir);
+        write_signed_package_template(
+            &index_root,
+            &signing_key,
+            "ripgrep",
+            &package_template_toml("ripgrep"),
+        );
+        for version in versions {
+            write_signed_release_manifest(
+                &index_root.join("releases").join("ripgrep"),
+                &signing_key,
+                version,
+                &format!("version = \"{version}\"\ndescription = \"{index_dir}\"\n"),
+            );
+        }
+    }
+    write_signed_manifest(
+        &root.join("index").join("releases").join("fd"),
+        &signing_key,
+        "10.0.0",
+    );
+
+    let index = RegistryIndex::open(&root).with_index_dirs(["index-overlay", "index"]);
+    let manifests = index
+        .package_versions("ripgrep")
+        .expect("must merge index dirs");
+    let versions = manifests
+        .iter()
+        .map(|manifest| {
+            (
+                manifest.version.to_string(),
+                manifest.description.clone().unwrap_or_default(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        versions,
+        vec![
+            ("14.2.0".to_string(), "index-overlay".to_string()),
+            ("14.1.0".to_string(), "index-overlay".to_string()),
+            ("14.0.0".to_string(), "index".to_string()),
+        ]
+    );
+    assert_eq!(
+        index.all_package_names().expect("must list packages"),
+        vec!["fd", "ripgrep"]
+    );
+    assert!(RegistryIndex::open(&root)
+        .package_versions("ripgrep")
+        .expect("root without index dirs has no releases")
+        .is_empty());
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn package_versions_rejects_oversized_release_before_parsing() {
+    let root = test_registry_root();
+    fs::create_dir_all(&root).expect("must create registry root");
+    let signing_key = signing_key();
+    fs::write(root.join("registry.pub"), public_key_hex(&signing_key))
+        .expect("must write registry public key");
+    let release_dir = root.join("releases").join("ripgrep");
+    write_signed_manifest(&release_dir, &signing_key, "14.1.0");
+    let oversized_path = release_dir.join("15.0.0.toml");
+    fs::write(&oversized_path, vec![b'#'; 4096]).expect("must write oversized release");
+
+    let index = RegistryIndex::open(&root).with_max_manifest_bytes(1024);
+    let err = index
+        .package_versions("ripgrep")
+        .expect_err("oversized release must be rejected");
+    let message = err.to_string();
+    assert!(
+        message.contains("release file exceeds maximum manifest size of 1024 bytes"),
+        "{message}"
+    );
+    assert!(message.contains("15.0.0.toml"), "{message}");
+
+    fs::remove_file(&oversized_path).expect("must remove oversized release");
+    assert_eq!(
+        index
+            .package_versions("ripgrep")
+            .expect("releases within the limit must load")
+            .len(),
+        1
+    );
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn package_versions_rejects_manifest_named_for_another_package() {
+    let root = test_registry_root();
+    fs::create_dir_all(&root).expect("must create registry root");
+    let signing_key = signing_key();
+    fs::write(root.join("registry.pub"), public_key_hex(&signing_key))
+        .expect("must write registry public key");
+
+    write_signed_package_template(&root, &signing_key, "ripgrep", &package_template_toml("fd"));
+    write_signed_release_manifest(
+        &root.join("releases").join("ripgrep"),
+        &signing_key,
+        "14.1.0",
+        &release_toml("14.1.0"),
+    );
+    write_signed_package_template(
+        &root,
+        &signing_key,
+        "rg",
+        "name = \"ripgrep\"\nprovides = [\"rg\"]\n",
+    );
+    write_signed_release_manifest(
+        &root.join("releases").join("rg"),
+        &signing_key,
+        "14.1.0",
+        &release_toml("14.1.0"),
+    );
+
+    let index = RegistryIndex::open(&root);
+    let err = index
+        .package_versions("ripgrep")
+        .expect_err("mismatched manifest name must be rejected");
+    let message = err.to_string();
+    assert!(message.contains("manifest name mismatch"), "{message}");
+    assert!(message.contains("declares name 'fd'"), "{message}");
+    assert!(
+        message.contains("stored under package 'ripgrep'"),
+        "{message}"
+    );
+
+    let providers = index
+        .package_versions("rg")
+        .expect("provider filed under its capability must load");
+    assert_eq!(providers.len(), 1);
+    assert_eq!(providers[0].name, "ripgrep");
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn namespaced_package_names_resolve_and_search_with_separator() {
+    let root = test_registry_root();
+    fs::create_dir_all(&root).expect("must create registry root");
+    let signing_key = signing_key();
+    fs::write(root.join("registry.pub"), public_key_hex(&signing_key))
+        .expect("must write registry public key");
+
+    fs::create_dir_all(root.join("packages").join("myorg")).expect("must create namespace dir");
+    write_signed_toml_file(
+        &root.join("packages").join("myorg").join("tool.toml"),
+        &signing_key,
+        &package_template_toml("myorg/tool"),
+    );
+    write_signed_release_manifest(
+        &root.join("releases").join("myorg").join("tool"),
+        &signing_key,
+        "1.2.0",
+        &release_toml("1.2.0"),
+    );
+    write_signed_manifest(
+        &root.join("releases").join("ripgrep"),
+        &signing_key,
+        "14.1.0",
+    );
+
+    let index = RegistryIndex::open(&root).with_namespace_separator('/');
+    assert_eq!(
+        index.all_package_names().expect("must list package names"),
+        vec!["myorg/tool", "ripgrep"]
+    );
+    assert_eq!(
+        index
+            .search_names("tool")
+            .expect("must search namespaced names"),
+        vec!["myorg/tool"]
+    );
+    assert_eq!(
+        index
+            .search_names("rip")
+            .expect("must search unscoped names"),
+        vec!["ripgrep"]
+    );
+
+    let manifests = index
+        .package_versions("myorg/tool")
+        .expect("must load namespaced package");
+    assert_eq!(manifests.len(), 1);
+    assert_eq!(manifests[0].name, "myorg/tool");
+    assert_eq!(manifests[0].version.to_string(), "1.2.0");
+    assert_eq!(
+        index
+            .package_versions("ripgrep")
+            .expect("must load unscoped package")
+            .len(),
+        1
+    );
+
+    let err = index
+        .package_versions("myorg/../tool")
+        .expect_err("must reject traversal segments");
+    assert!(err.to_string().contains("invalid package name"));
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn default_security_policy_rejects_unsigned_manifests_and_records_audit_event() {
+    let root = test_registry_root();
+    let package_dir = root.join("releases").join("ripgrep");
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+    let signing_key = signing_key();
+    fs::write(root.join("registry.pub"), public_key_hex(&signing_key))
+        .expect("must write registry public key");
+    fs::create_dir_all(root.join("packages")).expect("must create packages dir");
+    fs::write(
+        root.join("packages").join("ripgrep.toml"),
+        package_template_toml("ripgrep"),
+    )
+    .expect("must write unsigned package template");
+    fs::write(package_dir.join("14.1.0.toml"), release_toml("14.1.0"))
+        .expect("must write unsigned release");
+
+    let policy = SecurityPolicy::default();
+    assert!(policy.require_signatures());
+    let audit_log = root.join("audit").join("security-audit.log");
+    let index = RegistryIndex::open_with_security_policy(
+        &root,
+        policy
+            .with_audit_log(&audit_log)
+            .with_clock(Arc::new(FakeClock::at_unix(1_700_000_000))),
+    );
+    let err = index
+        .package_versions("ripgrep")
+        .expect_err("unsigned manifests must be rejected");
+    assert!(err
+        .to_string()
+        .contains("failed reading metadata signature"));
+
+    let audit = fs::read_to_string(&audit_log).expect("must write audit log");
+    assert!(audit.starts_with("timestamp_unix=1700000000 "));
+    assert!(audit.contains("event=signature_verification_rejected"));
+    assert!(audit.contains("require_signatures=true"));
+    assert!(audit.contains("ripgrep.toml"));
+
+    let relaxed =
+        RegistryIndex::open_with_security_policy(&root, SecurityPolicy::allow_unsigned_for_tests());
+    let manifests = relaxed
+        .package_versions("ripgrep")
+        .expect("test-only policy must allow unsigned manifests");
+    assert_eq!(manifests.len(), 1);
+
+    let _ = fs::remove_dir_all(&root);
+}
+
 #[test]
 fn package_versions_fails_when_registry_public_key_is_missing() {
     let root = test_registry_root();
@@ -1824,6 +3310,48 @@ fn package_versions_succeeds_with_valid_signatures_and_descending_sort() {
     let _ = fs::remove_dir_all(&root);
 }
 
+#[test]
+fn package_versions_for_target_skips_versions_without_matching_artifact() {
+    let root = test_registry_root();
+    let package_dir = root.join("releases").join("tool");
+    let signing_key = signing_key();
+    fs::create_dir_all(&root).expect("must create registry root");
+    fs::write(root.join("registry.pub"), public_key_hex(&signing_key))
+        .expect("must write registry public key");
+    write_signed_package_template(&root, &signing_key, "tool", &package_template_toml("tool"));
+    for (version, target) in [
+        ("1.0.0", "x86_64-unknown-linux-gnu"),
+        ("2.0.0", "aarch64-apple-darwin"),
+    ] {
+        write_signed_release_manifest(
+            &package_dir,
+            &signing_key,
+            version,
+            &format!(
+                "version = \"{version}\"\n\n[[artifacts]]\ntarget = \"{target}\"\nurl = \"https://example.invalid/tool-{version}.tar.gz\"\nsha256 = \"abc\"\n"
+            ),
+        );
+    }
+
+    let index = RegistryIndex::open(&root);
+    let linux = index
+        .package_versions_for_target("tool", "x86_64-unknown-linux-gnu")
+        .expect("must load manifests");
+    assert_eq!(
+        linux
+            .iter()
+            .map(|manifest| manifest.version.to_string())
+            .collect::<Vec<_>>(),
+        vec!["1.0.0"]
+    );
+    assert!(index
+        .package_versions_for_target("tool", "x86_64-pc-windows-msvc")
+        .expect("must load manifests")
+        .is_empty());
+
+    let _ = fs::remove_dir_all(&root);
+}
+
 #[test]
 fn package_versions_merges_release_artifacts_with_package_template_fields() {
     let root = test_registry_root();
@@ -1884,6 +3412,144 @@ sha256 = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"
     let _ = fs::remove_dir_all(&root);
 }
 
+#[test]
+fn package_versions_verifies_release_signed_by_keyring_key_id() {
+    let root = test_registry_root();
+    let package_dir = root.join("releases").join("ripgrep");
+    let signing_key = signing_key();
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+    fs::write(root.join("registry.pub"), public_key_hex(&signing_key))
+        .expect("must write registry public key");
+    write_signed_package_template(
+        &root,
+        &signing_key,
+        "ripgrep",
+        &package_template_toml("ripgrep"),
+    );
+
+    let package_key = SigningKey::from_bytes(&[31u8; 32]);
+    write_keyring_key(&root, &signing_key, "ripgrep-2026", &package_key);
+    write_key_id_signed_release(&package_dir, &package_key, "ripgrep-2026", "14.1.0");
+
+    let manifests = RegistryIndex::open(&root)
+        .package_versions("ripgrep")
+        .expect("keyring-signed release must verify");
+    assert_eq!(manifests.len(), 1);
+    assert_eq!(manifests[0].version.to_string(), "14.1.0");
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn package_versions_rejects_keyring_key_not_signed_by_registry_pub() {
+    let root = test_registry_root();
+    let package_dir = root.join("releases").join("ripgrep");
+    let signing_key = signing_key();
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+    fs::write(root.join("registry.pub"), public_key_hex(&signing_key))
+        .expect("must write registry public key");
+    write_signed_package_template(
+        &root,
+        &signing_key,
+        "ripgrep",
+        &package_template_toml("ripgrep"),
+    );
+
+    let attacker_key = SigningKey::from_bytes(&[47u8; 32]);
+    fs::create_dir_all(root.join("keys")).expect("must create keyring dir");
+    fs::write(
+        root.join("keys").join("intruder.pub"),
+        public_key_hex(&attacker_key),
+    )
+    .expect("must write unsigned keyring key");
+    write_key_id_signed_release(&package_dir, &attacker_key, "intruder", "14.1.0");
+
+    let err = RegistryIndex::open(&root)
+        .package_versions("ripgrep")
+        .expect_err("unsigned keyring key must be rejected");
+    assert!(
+        format!("{err:#}")
+            .contains("registry keyring key 'intruder' is not signed by registry.pub"),
+        "unexpected error: {err:#}"
+    );
+
+    // A key endorsed by some other key is no better than an unsigned one.
+    write_keyring_key(&root, &attacker_key, "intruder", &attacker_key);
+    let err = RegistryIndex::open(&root)
+        .package_versions("ripgrep")
+        .expect_err("self-signed keyring key must be rejected");
+    assert!(
+        format!("{err:#}")
+            .contains("registry keyring key 'intruder' has an invalid registry.pub signature"),
+        "unexpected error: {err:#}"
+    );
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn package_versions_fails_when_signature_references_unknown_key_id() {
+    let root = test_registry_root();
+    let package_dir = root.join("releases").join("ripgrep");
+    let signing_key = signing_key();
+    fs::create_dir_all(&package_dir).expect("must create package dir");
+    fs::write(root.join("registry.pub"), public_key_hex(&signing_key))
+        .expect("must write registry public key");
+    write_signed_package_template(
+        &root,
+        &signing_key,
+        "ripgrep",
+        &package_template_toml("ripgrep"),
+    );
+    write_key_id_signed_release(&package_dir, &signing_key, "unlisted", "14.1.0");
+
+    let err = RegistryIndex::open(&root)
+        .package_versions("ripgrep")
+        .expect_err("unknown key id must fail");
+    assert!(
+        err.to_string()
+            .contains("key id 'unlisted' not present in registry keyring"),
+        "unexpected error: {err:#}"
+    );
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+fn write_keyring_key(
+    registry_root: &Path,
+    registry_key: &SigningKey,
+    key_id: &str,
+    keyring_key: &SigningKey,
+) {
+    let keys_dir = registry_root.join("keys");
+    fs::create_dir_all(&keys_dir).expect("must create keyring dir");
+    let key_hex = public_key_hex(keyring_key);
+    fs::write(keys_dir.join(format!("{key_id}.pub")), &key_hex).expect("must write keyring key");
+    let signature = registry_key.sign(key_hex.as_bytes());
+    fs::write(
+        keys_dir.join(format!("{key_id}.pub.sig")),
+        hex::encode(signature.to_bytes()),
+    )
+    .expect("must write keyring key signature");
+}
+
+fn write_key_id_signed_release(
+    package_dir: &Path,
+    signing_key: &SigningKey,
+    key_id: &str,
+    version: &str,
+) {
+    let content = release_toml(version);
+    let manifest_path = package_dir.join(format!("{version}.toml"));
+    fs::write(&manifest_path, content.as_bytes()).expect("must write manifest");
+    let signature = signing_key.sign(content.as_bytes());
+    fs::write(
+        manifest_path.with_extension("toml.sig"),
+        format!("{key_id}:{}", hex::encode(signature.to_bytes())),
+    )
+    .expect("must write signature sidecar");
+}
+
 fn write_signed_manifest(package_dir: &std::path::Path, signing_key: &SigningKey, version: &str) {
     let package_name = package_dir
         .file_name()
@@ -2031,6 +3697,8 @@ fn source_record(name: &str, priority: u32) -> RegistrySourceRecord {
         enabled: true,
         priority,
         community: None,
+        git_ref: None,
+        subdir: None,
     }
 }
 
@@ -2048,6 +3716,8 @@ fn git_source_record(
         enabled: true,
         priority,
         community: None,
+        git_ref: None,
+        subdir: None,
     }
 }
 
@@ -2065,6 +3735,8 @@ fn filesystem_source_record(
         enabled: true,
         priority,
         community: None,
+        git_ref: None,
+        subdir: None,
     }
 }
 
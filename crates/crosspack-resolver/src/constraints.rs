@@ -1,7 +1,8 @@
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
 
 use crosspack_core::PackageManifest;
-use semver::VersionReq;
+use semver::{Comparator, Op, Version, VersionReq};
 
 pub(crate) fn selected_satisfies_constraints(
     selected: &BTreeMap<String, PackageManifest>,
@@ -46,13 +47,128 @@ pub(crate) fn selected_satisfies_constraints(
 }
 
 fn manifests_conflict(left: &PackageManifest, right: &PackageManifest) -> bool {
-    left.conflicts
-        .get(&right.name)
-        .map(|req| req.matches(&right.version))
-        .unwrap_or(false)
-        || right
-            .conflicts
-            .get(&left.name)
-            .map(|req| req.matches(&left.version))
-            .unwrap_or(false)
+    declares_conflict_with(left, right) || declares_conflict_with(right, left)
+}
+
+// A conflict key names either the other package or a capability it provides;
+// either way the requirement is checked against the other package's version.
+fn declares_conflict_with(manifest: &PackageManifest, other: &PackageManifest) -> bool {
+    manifest.conflicts.iter().any(|(name, req)| {
+        (*name == other.name || other.provides.contains(name)) && req.matches(&other.version)
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bound {
+    version: Version,
+    inclusive: bool,
+}
+
+pub fn normalize_version_req(req: &VersionReq) -> String {
+    let mut lower: Option<Bound> = None;
+    let mut upper: Option<Bound> = None;
+    for comparator in &req.comparators {
+        let Some((comparator_lower, comparator_upper)) = comparator_bounds(comparator) else {
+            return req.to_string();
+        };
+        if let Some(bound) = comparator_lower {
+            lower = Some(match lower {
+                Some(current) if !tighter_lower(&bound, &current) => current,
+                _ => bound,
+            });
+        }
+        if let Some(bound) = comparator_upper {
+            upper = Some(match upper {
+                Some(current) if !tighter_upper(&bound, &current) => current,
+                _ => bound,
+            });
+        }
+    }
+
+    match (lower, upper) {
+        (None, None) => "*".to_string(),
+        (Some(lower), Some(upper))
+            if lower.inclusive && upper.inclusive && lower.version == upper.version =>
+        {
+            format!("={}", lower.version)
+        }
+        (lower, upper) => {
+            let mut parts = Vec::new();
+            if let Some(lower) = lower {
+                let op = if lower.inclusive { ">=" } else { ">" };
+                parts.push(format!("{op}{}", lower.version));
+            }
+            if let Some(upper) = upper {
+                let op = if upper.inclusive { "<=" } else { "<" };
+                parts.push(format!("{op}{}", upper.version));
+            }
+            parts.join(", ")
+        }
+    }
+}
+
+fn tighter_lower(candidate: &Bound, current: &Bound) -> bool {
+    match candidate.version.cmp(&current.version) {
+        Ordering::Greater => true,
+        Ordering::Less => false,
+        Ordering::Equal => !candidate.inclusive && current.inclusive,
+    }
+}
+
+fn tighter_upper(candidate: &Bound, current: &Bound) -> bool {
+    match candidate.version.cmp(&current.version) {
+        Ordering::Less => true,
+        Ordering::Greater => false,
+        Ordering::Equal => !candidate.inclusive && current.inclusive,
+    }
+}
+
+fn comparator_bounds(comparator: &Comparator) -> Option<(Option<Bound>, Option<Bound>)> {
+    let major = comparator.major;
+    let minor = comparator.minor;
+    let patch = comparator.patch;
+    let floor = Version {
+        major,
+        minor: minor.unwrap_or(0),
+        patch: patch.unwrap_or(0),
+        pre: comparator.pre.clone(),
+        build: Default::default(),
+    };
+    let bound = |version: Version, inclusive: bool| Some(Bound { version, inclusive });
+    let at_least = |version: Version| bound(version, true);
+    let below = |version: Version| bound(version, false);
+    let next_partial = || match minor {
+        None => Version::new(major + 1, 0, 0),
+        Some(minor) => Version::new(major, minor + 1, 0),
+    };
+
+    let bounds = match comparator.op {
+        Op::Exact | Op::Wildcard => match (minor, patch) {
+            (Some(_), Some(_)) => (at_least(floor.clone()), bound(floor, true)),
+            _ => (at_least(floor), below(next_partial())),
+        },
+        Op::Greater => match patch {
+            Some(_) => (bound(floor, false), None),
+            None => (at_least(next_partial()), None),
+        },
+        Op::GreaterEq => (at_least(floor), None),
+        Op::Less => (None, below(floor)),
+        Op::LessEq => match patch {
+            Some(_) => (None, bound(floor, true)),
+            None => (None, below(next_partial())),
+        },
+        Op::Tilde => (at_least(floor), below(next_partial())),
+        Op::Caret => {
+            let next = match (minor, patch) {
+                _ if major > 0 => Version::new(major + 1, 0, 0),
+                (None, _) => Version::new(1, 0, 0),
+                (Some(0), None) => Version::new(0, 1, 0),
+                (Some(0), Some(patch)) => Version::new(0, 0, patch + 1),
+                (Some(minor), _) => Version::new(0, minor + 1, 0),
+            };
+            (at_least(floor), below(next))
+        }
+        _ => return None,
+    };
+    Some(bounds)
 }
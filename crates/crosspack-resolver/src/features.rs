@@ -0,0 +1,107 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crosspack_core::PackageManifest;
+use semver::VersionReq;
+
+use crate::types::RootRequirement;
+
+pub(crate) type FeatureSelection = BTreeMap<String, BTreeSet<String>>;
+
+pub(crate) fn root_feature_selection(roots: &[RootRequirement]) -> FeatureSelection {
+    let mut selection = FeatureSelection::new();
+    for root in roots {
+        selection
+            .entry(root.name.clone())
+            .or_default()
+            .extend(root.features.iter().cloned());
+    }
+    selection
+}
+
+pub(crate) fn expand_enabled_features(
+    manifest: &PackageManifest,
+    requested: Option<&BTreeSet<String>>,
+) -> BTreeSet<String> {
+    let mut enabled = BTreeSet::new();
+    let mut pending = requested
+        .map(|features| features.iter().cloned().collect::<Vec<_>>())
+        .unwrap_or_default();
+    while let Some(feature) = pending.pop() {
+        if !enabled.insert(feature.clone()) {
+            continue;
+        }
+        if let Some(entries) = manifest.features.get(&feature) {
+            pending.extend(
+                entries
+                    .iter()
+                    .filter(|entry| !entry.contains('/') && manifest.features.contains_key(*entry))
+                    .cloned(),
+            );
+        }
+    }
+    enabled
+}
+
+pub(crate) fn active_dependencies(
+    manifest: &PackageManifest,
+    requested: Option<&BTreeSet<String>>,
+) -> BTreeMap<String, VersionReq> {
+    let mut active = manifest.dependencies.clone();
+    for feature in expand_enabled_features(manifest, requested) {
+        let Some(entries) = manifest.features.get(&feature) else {
+            continue;
+        };
+        for entry in entries {
+            let dependency = entry.split_once('/').map_or(entry.as_str(), |(dep, _)| dep);
+            if let Some(req) = manifest.optional_dependencies.get(dependency) {
+                active.insert(dependency.to_string(), req.clone());
+            }
+        }
+    }
+    active
+}
+
+pub(crate) fn propagate_features(
+    roots: &[RootRequirement],
+    selected: &BTreeMap<String, PackageManifest>,
+) -> FeatureSelection {
+    let mut selection = root_feature_selection(roots);
+    loop {
+        let mut changed = false;
+        for (name, manifest) in selected {
+            let enabled = expand_enabled_features(manifest, selection.get(name));
+            let active = active_dependencies(manifest, selection.get(name));
+
+            let mut requested_downstream: Vec<(String, String)> = Vec::new();
+            for (dependency, features) in &manifest.dependency_features {
+                if active.contains_key(dependency) {
+                    for feature in features {
+                        requested_downstream.push((dependency.clone(), feature.clone()));
+                    }
+                }
+            }
+            for feature in &enabled {
+                let Some(entries) = manifest.features.get(feature) else {
+                    continue;
+                };
+                for entry in entries {
+                    if let Some((dependency, dependency_feature)) = entry.split_once('/') {
+                        requested_downstream
+                            .push((dependency.to_string(), dependency_feature.to_string()));
+                    }
+                }
+            }
+
+            for (dependency, feature) in requested_downstream {
+                if selection.entry(dependency).or_default().insert(feature) {
+                    changed = true;
+                }
+            }
+        }
+
+        if !changed {
+            selection.retain(|_, features| !features.is_empty());
+            return selection;
+        }
+    }
+}
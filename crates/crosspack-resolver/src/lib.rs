@@ -1,13 +1,21 @@
 mod constraints;
+mod features;
 mod order;
 mod resolve;
 mod search;
 mod types;
 
+pub use constraints::normalize_version_req;
 pub use resolve::{
-    resolve_dependency_graph, resolve_dependency_graph_with_installed, select_highest_compatible,
+    explain_unsatisfiable, resolvable_versions, resolve_dependency_graph,
+    resolve_dependency_graph_with_bans, resolve_dependency_graph_with_cache_limit,
+    resolve_dependency_graph_with_fixed, resolve_dependency_graph_with_installed,
+    select_highest_compatible,
+};
+pub use types::{
+    Advisory, AdvisoryKind, GraphDiff, ResolvedGraph, RootRequirement, UnsatExplanation,
+    VersionChange,
 };
-pub use types::{ResolvedGraph, RootRequirement};
 
 #[cfg(test)]
 mod tests;
@@ -3,7 +3,12 @@ use std::collections::{BTreeMap, BTreeSet, HashSet};
 use anyhow::{anyhow, Result};
 use crosspack_core::PackageManifest;
 
-pub(crate) fn topo_order(selected: &BTreeMap<String, PackageManifest>) -> Result<Vec<String>> {
+use crate::features::{active_dependencies, FeatureSelection};
+
+pub(crate) fn topo_order(
+    selected: &BTreeMap<String, PackageManifest>,
+    features: &FeatureSelection,
+) -> Result<Vec<String>> {
     let mut deps: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
     let mut reverse: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
     let mut in_degree: BTreeMap<String, usize> = BTreeMap::new();
@@ -15,8 +20,8 @@ pub(crate) fn topo_order(selected: &BTreeMap<String, PackageManifest>) -> Result
     }
 
     for (name, manifest) in selected {
-        for dep_name in manifest.dependencies.keys() {
-            if !selected.contains_key(dep_name) {
+        for dep_name in active_dependencies(manifest, features.get(name)).into_keys() {
+            if !selected.contains_key(&dep_name) {
                 continue;
             }
             deps.entry(name.clone())
@@ -69,3 +74,34 @@ pub(crate) fn topo_order(selected: &BTreeMap<String, PackageManifest>) -> Result
 
     Ok(ordered)
 }
+
+// Level of a package = 1 + the deepest level among its active dependencies, so
+// everything a package needs sits in an earlier level. Each level keeps the
+// relative order of `install_order`.
+pub(crate) fn install_levels(
+    selected: &BTreeMap<String, PackageManifest>,
+    features: &FeatureSelection,
+    install_order: &[String],
+) -> Vec<Vec<String>> {
+    let mut level_of: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut levels: Vec<Vec<String>> = Vec::new();
+    for name in install_order {
+        let level = selected
+            .get(name)
+            .map(|manifest| {
+                active_dependencies(manifest, features.get(name))
+                    .keys()
+                    .filter_map(|dep_name| level_of.get(dep_name.as_str()))
+                    .map(|dep_level| dep_level + 1)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .unwrap_or(0);
+        level_of.insert(name.as_str(), level);
+        if levels.len() <= level {
+            levels.resize_with(level + 1, Vec::new);
+        }
+        levels[level].push(name.clone());
+    }
+    levels
+}
@@ -2,11 +2,15 @@ use std::collections::{BTreeMap, HashMap};
 
 use anyhow::{anyhow, Result};
 use crosspack_core::PackageManifest;
-use semver::VersionReq;
+use semver::{Version, VersionReq};
 
+use crate::constraints::selected_satisfies_constraints;
+use crate::features::{
+    active_dependencies, expand_enabled_features, propagate_features, root_feature_selection,
+};
 use crate::order::topo_order;
-use crate::search::search;
-use crate::types::{ResolvedGraph, RootRequirement};
+use crate::search::{search, VersionsCache};
+use crate::types::{Advisory, AdvisoryKind, ResolvedGraph, RootRequirement, UnsatExplanation};
 
 pub fn select_highest_compatible<'a>(
     candidates: &'a [PackageManifest],
@@ -29,15 +33,194 @@ where
     resolve_dependency_graph_with_installed(roots, pins, &BTreeMap::new(), load_versions)
 }
 
+pub fn resolve_dependency_graph_with_bans<F>(
+    roots: &[RootRequirement],
+    pins: &BTreeMap<String, VersionReq>,
+    bans: &BTreeMap<String, Vec<Version>>,
+    load_versions: F,
+) -> Result<ResolvedGraph>
+where
+    F: FnMut(&str) -> Result<Vec<PackageManifest>>,
+{
+    resolve_graph(
+        roots,
+        pins,
+        bans,
+        &BTreeMap::new(),
+        &BTreeMap::new(),
+        None,
+        load_versions,
+    )
+}
+
+pub fn resolve_dependency_graph_with_fixed<F>(
+    roots: &[RootRequirement],
+    pins: &BTreeMap<String, VersionReq>,
+    fixed: &BTreeMap<String, PackageManifest>,
+    load_versions: F,
+) -> Result<ResolvedGraph>
+where
+    F: FnMut(&str) -> Result<Vec<PackageManifest>>,
+{
+    resolve_graph(
+        roots,
+        pins,
+        &BTreeMap::new(),
+        &BTreeMap::new(),
+        fixed,
+        None,
+        load_versions,
+    )
+}
+
 pub fn resolve_dependency_graph_with_installed<F>(
     roots: &[RootRequirement],
     pins: &BTreeMap<String, VersionReq>,
     installed: &BTreeMap<String, PackageManifest>,
-    mut load_versions: F,
+    load_versions: F,
 ) -> Result<ResolvedGraph>
 where
     F: FnMut(&str) -> Result<Vec<PackageManifest>>,
 {
+    resolve_graph(
+        roots,
+        pins,
+        &BTreeMap::new(),
+        installed,
+        &BTreeMap::new(),
+        None,
+        load_versions,
+    )
+}
+
+/// Like `resolve_dependency_graph`, but keeps at most `max_cached_packages`
+/// packages' version lists in memory, reloading evicted ones through
+/// `load_versions` when the search revisits them. The result is unchanged.
+pub fn resolve_dependency_graph_with_cache_limit<F>(
+    roots: &[RootRequirement],
+    pins: &BTreeMap<String, VersionReq>,
+    max_cached_packages: usize,
+    load_versions: F,
+) -> Result<ResolvedGraph>
+where
+    F: FnMut(&str) -> Result<Vec<PackageManifest>>,
+{
+    resolve_graph(
+        roots,
+        pins,
+        &BTreeMap::new(),
+        &BTreeMap::new(),
+        &BTreeMap::new(),
+        Some(max_cached_packages),
+        load_versions,
+    )
+}
+
+// Resolves once, then probes every candidate of each selected package against
+// the other selections held fixed. Versions are listed highest first.
+pub fn resolvable_versions<F>(
+    roots: &[RootRequirement],
+    pins: &BTreeMap<String, VersionReq>,
+    mut load_versions: F,
+) -> Result<BTreeMap<String, Vec<Version>>>
+where
+    F: FnMut(&str) -> Result<Vec<PackageManifest>>,
+{
+    let mut versions_cache: HashMap<String, Vec<PackageManifest>> = HashMap::new();
+    let mut load_cached = |name: &str| -> Result<Vec<PackageManifest>> {
+        if let Some(cached) = versions_cache.get(name) {
+            return Ok(cached.clone());
+        }
+        let loaded = load_versions(name)?;
+        versions_cache.insert(name.to_string(), loaded.clone());
+        Ok(loaded)
+    };
+    let graph = resolve_dependency_graph(roots, pins, &mut load_cached)?;
+
+    let mut resolvable = BTreeMap::new();
+    for name in graph.manifests.keys() {
+        let mut versions = Vec::new();
+        for candidate in load_cached(name)? {
+            let mut probe = graph.manifests.clone();
+            let mut candidate = candidate;
+            candidate.dependencies = active_dependencies(&candidate, graph.features.get(name));
+            probe.insert(name.clone(), candidate.clone());
+            if probe_is_consistent(roots, pins, &probe) && !versions.contains(&candidate.version) {
+                versions.push(candidate.version);
+            }
+        }
+        versions.sort_by(|a, b| b.cmp(a));
+        resolvable.insert(name.clone(), versions);
+    }
+    Ok(resolvable)
+}
+
+// Shrinks a failing root set one element at a time: a root or pin is dropped
+// whenever the remainder still fails, which leaves a core where every remaining
+// requirement is needed for the failure.
+pub fn explain_unsatisfiable<F>(
+    roots: &[RootRequirement],
+    pins: &BTreeMap<String, VersionReq>,
+    mut load_versions: F,
+) -> Result<UnsatExplanation>
+where
+    F: FnMut(&str) -> Result<Vec<PackageManifest>>,
+{
+    let mut versions_cache: HashMap<String, Vec<PackageManifest>> = HashMap::new();
+    let mut attempt = |roots: &[RootRequirement], pins: &BTreeMap<String, VersionReq>| {
+        resolve_dependency_graph(roots, pins, |name: &str| {
+            if let Some(cached) = versions_cache.get(name) {
+                return Ok(cached.clone());
+            }
+            let loaded = load_versions(name)?;
+            versions_cache.insert(name.to_string(), loaded.clone());
+            Ok(loaded)
+        })
+        .err()
+    };
+
+    let Some(mut reason) = attempt(roots, pins) else {
+        return Err(anyhow!(
+            "roots resolve without conflict; nothing to explain"
+        ));
+    };
+
+    let mut core = roots.to_vec();
+    let mut index = 0;
+    while index < core.len() {
+        let mut candidate = core.clone();
+        candidate.remove(index);
+        match attempt(&candidate, pins) {
+            Some(err) => {
+                core = candidate;
+                reason = err;
+            }
+            None => index += 1,
+        }
+    }
+
+    let mut core_pins = pins.clone();
+    for name in pins.keys() {
+        let mut candidate = core_pins.clone();
+        candidate.remove(name);
+        if let Some(err) = attempt(&core, &candidate) {
+            core_pins = candidate;
+            reason = err;
+        }
+    }
+
+    Ok(UnsatExplanation {
+        roots: core,
+        pins: core_pins,
+        reason: format!("{reason:#}"),
+    })
+}
+
+fn probe_is_consistent(
+    roots: &[RootRequirement],
+    pins: &BTreeMap<String, VersionReq>,
+    probe: &BTreeMap<String, PackageManifest>,
+) -> bool {
     let mut constraints: BTreeMap<String, Vec<VersionReq>> = BTreeMap::new();
     for root in roots {
         constraints
@@ -45,24 +228,119 @@ where
             .or_default()
             .push(root.requirement.clone());
     }
+    for manifest in probe.values() {
+        for (dependency, requirement) in &manifest.dependencies {
+            if !probe.contains_key(dependency) {
+                return false;
+            }
+            constraints
+                .entry(dependency.clone())
+                .or_default()
+                .push(requirement.clone());
+        }
+    }
+    selected_satisfies_constraints(probe, &constraints, pins, &BTreeMap::new())
+}
 
-    let mut versions_cache: HashMap<String, Vec<PackageManifest>> = HashMap::new();
-    let mut selected: BTreeMap<String, PackageManifest> = BTreeMap::new();
+fn resolve_graph<F>(
+    roots: &[RootRequirement],
+    pins: &BTreeMap<String, VersionReq>,
+    bans: &BTreeMap<String, Vec<Version>>,
+    installed: &BTreeMap<String, PackageManifest>,
+    fixed: &BTreeMap<String, PackageManifest>,
+    versions_cache_limit: Option<usize>,
+    mut load_versions: F,
+) -> Result<ResolvedGraph>
+where
+    F: FnMut(&str) -> Result<Vec<PackageManifest>>,
+{
+    let mut constraints: BTreeMap<String, Vec<VersionReq>> = BTreeMap::new();
+    for root in roots {
+        constraints
+            .entry(root.name.clone())
+            .or_default()
+            .push(root.requirement.clone());
+    }
 
-    if !search(
-        &mut constraints,
-        pins,
-        installed,
-        &mut selected,
-        &mut versions_cache,
-        &mut load_versions,
-    )? {
-        return Err(anyhow!("no compatible dependency graph found"));
+    // Fixed packages are seeded as their only candidate so the registry is never
+    // consulted for them; their dependencies still resolve through `load_versions`.
+    let mut versions_cache = VersionsCache::new(versions_cache_limit);
+    for (name, manifest) in fixed {
+        versions_cache.insert_fixed(name, manifest.clone());
     }
+    let mut acceptable_cache = HashMap::new();
+    let mut features = root_feature_selection(roots);
+    loop {
+        let mut search_constraints = constraints.clone();
+        let mut selected: BTreeMap<String, PackageManifest> = BTreeMap::new();
+        if !search(
+            &mut search_constraints,
+            pins,
+            bans,
+            installed,
+            &features,
+            &mut selected,
+            &mut versions_cache,
+            &mut acceptable_cache,
+            &mut load_versions,
+        )? {
+            return Err(anyhow!("no compatible dependency graph found"));
+        }
 
-    let install_order = topo_order(&selected)?;
-    Ok(ResolvedGraph {
-        manifests: selected,
-        install_order,
-    })
+        // Features only accumulate across rounds: replacing the set could toggle
+        // between selections that request each other's features forever.
+        let mut grew = false;
+        for (name, requested) in propagate_features(roots, &selected) {
+            let enabled = features.entry(name).or_default();
+            for feature in requested {
+                grew |= enabled.insert(feature);
+            }
+        }
+        if grew {
+            continue;
+        }
+
+        for (name, requested) in &features {
+            let Some(manifest) = selected.get(name) else {
+                continue;
+            };
+            if let Some(unknown) = requested
+                .iter()
+                .find(|feature| !manifest.features.contains_key(*feature))
+            {
+                return Err(anyhow!(
+                    "package '{name}' does not declare feature '{unknown}'"
+                ));
+            }
+        }
+
+        let install_order = topo_order(&selected, &features)?;
+        let mut enabled_features = BTreeMap::new();
+        for (name, manifest) in &mut selected {
+            let enabled = expand_enabled_features(manifest, features.get(name));
+            manifest.dependencies = active_dependencies(manifest, features.get(name));
+            if !enabled.is_empty() {
+                enabled_features.insert(name.clone(), enabled);
+            }
+        }
+        let advisories = selected
+            .iter()
+            .filter_map(|(name, manifest)| {
+                let message = manifest.deprecated.clone()?;
+                Some(Advisory {
+                    package: name.clone(),
+                    version: manifest.version.clone(),
+                    kind: AdvisoryKind::Deprecated { message },
+                })
+            })
+            .collect();
+        let graph = ResolvedGraph {
+            manifests: selected,
+            install_order,
+            features: enabled_features,
+            advisories,
+        };
+        graph.assert_unified()?;
+        return Ok(graph);
+    }
 }
@@ -2,16 +2,21 @@ use std::collections::{BTreeMap, HashMap};
 
 use anyhow::{anyhow, Result};
 use crosspack_core::PackageManifest;
-use semver::VersionReq;
+use semver::{Version, VersionReq};
 
-use crate::constraints::selected_satisfies_constraints;
+use crate::constraints::{normalize_version_req, selected_satisfies_constraints};
+use crate::features::{active_dependencies, FeatureSelection};
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn search<F>(
     constraints: &mut BTreeMap<String, Vec<VersionReq>>,
     pins: &BTreeMap<String, VersionReq>,
+    bans: &BTreeMap<String, Vec<Version>>,
     installed: &BTreeMap<String, PackageManifest>,
+    features: &FeatureSelection,
     selected: &mut BTreeMap<String, PackageManifest>,
-    versions_cache: &mut HashMap<String, Vec<PackageManifest>>,
+    versions_cache: &mut VersionsCache,
+    acceptable_cache: &mut HashMap<String, AcceptableVersions>,
     load_versions: &mut F,
 ) -> Result<bool>
 where
@@ -22,17 +27,24 @@ where
         .find(|name| !selected.contains_key(*name))
         .cloned()
     {
-        let candidates =
-            matching_candidates(&next, constraints, pins, versions_cache, load_versions)?;
+        let candidates = matching_candidates(
+            &next,
+            constraints,
+            pins,
+            bans,
+            versions_cache,
+            acceptable_cache,
+            load_versions,
+        )?;
 
         for candidate in candidates {
             selected.insert(next.clone(), candidate.clone());
 
             let mut added_constraints: Vec<(String, usize)> = Vec::new();
-            for (dep_name, dep_req) in &candidate.dependencies {
+            for (dep_name, dep_req) in active_dependencies(&candidate, features.get(&next)) {
                 let list = constraints.entry(dep_name.clone()).or_default();
-                list.push(dep_req.clone());
-                added_constraints.push((dep_name.clone(), list.len()));
+                list.push(dep_req);
+                added_constraints.push((dep_name, list.len()));
             }
 
             let consistent = selected_satisfies_constraints(selected, constraints, pins, installed);
@@ -40,9 +52,12 @@ where
                 && search(
                     constraints,
                     pins,
+                    bans,
                     installed,
+                    features,
                     selected,
                     versions_cache,
+                    acceptable_cache,
                     load_versions,
                 )?
             {
@@ -69,24 +84,153 @@ where
     ))
 }
 
+// Loaded version lists per package. With a capacity, the least recently used
+// package is evicted once the limit is reached and reloaded on its next lookup;
+// fixed packages cannot be reloaded, so they are never evicted or counted.
+pub(crate) struct VersionsCache {
+    entries: HashMap<String, CachedVersions>,
+    recency: BTreeMap<u64, String>,
+    capacity: Option<usize>,
+    clock: u64,
+}
+
+struct CachedVersions {
+    versions: Vec<PackageManifest>,
+    last_used: Option<u64>,
+}
+
+impl VersionsCache {
+    pub(crate) fn new(capacity: Option<usize>) -> Self {
+        Self {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            capacity: capacity.map(|capacity| capacity.max(1)),
+            clock: 0,
+        }
+    }
+
+    pub(crate) fn insert_fixed(&mut self, name: &str, manifest: PackageManifest) {
+        self.entries.insert(
+            name.to_string(),
+            CachedVersions {
+                versions: vec![manifest],
+                last_used: None,
+            },
+        );
+    }
+
+    /// Returns the versions for `name` and whether they were (re)loaded by this call.
+    fn get_or_load<F>(
+        &mut self,
+        name: &str,
+        load_versions: &mut F,
+    ) -> Result<(&[PackageManifest], bool)>
+    where
+        F: FnMut(&str) -> Result<Vec<PackageManifest>>,
+    {
+        self.clock += 1;
+        let tick = self.clock;
+        if self.entries.contains_key(name) {
+            let entry = self
+                .entries
+                .get_mut(name)
+                .ok_or_else(|| anyhow!("internal resolver cache error for package '{name}'"))?;
+            if let Some(last_used) = entry.last_used.as_mut() {
+                self.recency.remove(last_used);
+                *last_used = tick;
+                self.recency.insert(tick, name.to_string());
+            }
+            return Ok((&entry.versions, false));
+        }
+
+        let versions = load_versions(name)?;
+        if let Some(capacity) = self.capacity {
+            while self.recency.len() >= capacity {
+                let Some((_, evicted)) = self.recency.pop_first() else {
+                    break;
+                };
+                self.entries.remove(&evicted);
+            }
+        }
+        self.recency.insert(tick, name.to_string());
+        let entry = self
+            .entries
+            .entry(name.to_string())
+            .or_insert(CachedVersions {
+                versions,
+                last_used: Some(tick),
+            });
+        Ok((&entry.versions, true))
+    }
+}
+
+// Acceptance masks over one package's loaded versions, layered per constraint.
+// Backtracking pushes and truncates constraint lists like a stack, so a revisit
+// reuses the layers for the shared prefix and only evaluates the new requirements
+// against versions that are still accepted.
+#[derive(Default)]
+pub(crate) struct AcceptableVersions {
+    reqs: Vec<VersionReq>,
+    masks: Vec<Vec<bool>>,
+}
+
+impl AcceptableVersions {
+    pub(crate) fn refresh(
+        &mut self,
+        versions: &[PackageManifest],
+        reqs: &[VersionReq],
+        pin: Option<&VersionReq>,
+    ) -> &[bool] {
+        if self.masks.is_empty() {
+            self.masks.push(
+                versions
+                    .iter()
+                    .map(|manifest| pin.is_none_or(|pin| pin.matches(&manifest.version)))
+                    .collect(),
+            );
+        }
+
+        let shared = self
+            .reqs
+            .iter()
+            .zip(reqs)
+            .take_while(|(cached, current)| cached == current)
+            .count();
+        self.reqs.truncate(shared);
+        self.masks.truncate(shared + 1);
+
+        for req in &reqs[shared..] {
+            let previous = &self.masks[self.masks.len() - 1];
+            let next = previous
+                .iter()
+                .zip(versions)
+                .map(|(accepted, manifest)| *accepted && req.matches(&manifest.version))
+                .collect();
+            self.reqs.push(req.clone());
+            self.masks.push(next);
+        }
+
+        &self.masks[self.masks.len() - 1]
+    }
+}
+
 fn matching_candidates<F>(
     name: &str,
     constraints: &BTreeMap<String, Vec<VersionReq>>,
     pins: &BTreeMap<String, VersionReq>,
-    versions_cache: &mut HashMap<String, Vec<PackageManifest>>,
+    bans: &BTreeMap<String, Vec<Version>>,
+    versions_cache: &mut VersionsCache,
+    acceptable_cache: &mut HashMap<String, AcceptableVersions>,
     load_versions: &mut F,
 ) -> Result<Vec<PackageManifest>>
 where
     F: FnMut(&str) -> Result<Vec<PackageManifest>>,
 {
-    if !versions_cache.contains_key(name) {
-        let versions = load_versions(name)?;
-        versions_cache.insert(name.to_string(), versions);
+    let (versions, loaded) = versions_cache.get_or_load(name, load_versions)?;
+    if loaded {
+        // Masks index into the previous load's list, which may have been evicted.
+        acceptable_cache.remove(name);
     }
-
-    let versions = versions_cache
-        .get(name)
-        .ok_or_else(|| anyhow!("internal resolver cache error for package '{name}'"))?;
     if versions.is_empty() {
         return Err(anyhow!(
             "package '{name}' was not found in the registry index"
@@ -96,19 +240,15 @@ where
     let package_reqs = constraints.get(name).cloned().unwrap_or_default();
     let pin_req = pins.get(name);
 
+    let accepted = acceptable_cache
+        .entry(name.to_string())
+        .or_default()
+        .refresh(versions, &package_reqs, pin_req);
     let matched: Vec<PackageManifest> = versions
         .iter()
-        .filter(|manifest| {
-            package_reqs
-                .iter()
-                .all(|req| req.matches(&manifest.version))
-        })
-        .filter(|manifest| {
-            pin_req
-                .map(|pin| pin.matches(&manifest.version))
-                .unwrap_or(true)
-        })
-        .cloned()
+        .zip(accepted)
+        .filter(|(_, accepted)| **accepted)
+        .map(|(manifest, _)| manifest.clone())
         .collect();
 
     let has_direct_match = matched.iter().any(|manifest| manifest.name == name);
@@ -132,11 +272,12 @@ where
         } else {
             package_reqs
                 .iter()
-                .map(ToString::to_string)
+                .map(normalize_version_req)
                 .collect::<Vec<_>>()
                 .join(" && ")
         };
         if let Some(pin) = pin_req {
+            let pin = normalize_version_req(pin);
             return Err(anyhow!(
                 "no matching version for '{name}' with constraints [{req_desc}] and pin {pin}"
             ));
@@ -146,5 +287,19 @@ where
         ));
     }
 
+    if let Some(banned) = bans.get(name).filter(|banned| !banned.is_empty()) {
+        selected.retain(|manifest| !banned.contains(&manifest.version));
+        if selected.is_empty() {
+            let banned = banned
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(anyhow!(
+                "all candidates for '{name}' were banned (banned versions: {banned})"
+            ));
+        }
+    }
+
     Ok(selected)
 }
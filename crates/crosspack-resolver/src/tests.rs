@@ -1,7 +1,7 @@
 use std::collections::BTreeMap;
 
 use crosspack_core::PackageManifest;
-use semver::VersionReq;
+use semver::{Version, VersionReq};
 
 use super::*;
 
@@ -86,6 +86,7 @@ sha256 = "zlib"
     let roots = vec![RootRequirement {
         name: "app".to_string(),
         requirement: VersionReq::STAR,
+        features: Vec::new(),
     }];
     let graph = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| {
         Ok(available.get(name).cloned().unwrap_or_default())
@@ -142,6 +143,7 @@ sha256 = "b"
     let roots = vec![RootRequirement {
         name: "app".to_string(),
         requirement: VersionReq::STAR,
+        features: Vec::new(),
     }];
     let mut pins = BTreeMap::new();
     pins.insert("lib".to_string(), VersionReq::parse("<1.3.0").expect("pin"));
@@ -183,6 +185,7 @@ sha256 = "app"
     let roots = vec![RootRequirement {
         name: "app".to_string(),
         requirement: VersionReq::STAR,
+        features: Vec::new(),
     }];
     let err = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| {
         Ok(available.get(name).cloned().unwrap_or_default())
@@ -227,6 +230,7 @@ sha256 = "lib"
     let roots = vec![RootRequirement {
         name: "app".to_string(),
         requirement: VersionReq::STAR,
+        features: Vec::new(),
     }];
     let mut pins = BTreeMap::new();
     pins.insert("lib".to_string(), VersionReq::parse("<2.0.0").expect("pin"));
@@ -236,6 +240,255 @@ sha256 = "lib"
     })
     .expect_err("must fail");
     assert!(err.to_string().contains("pin"));
+    assert!(err
+        .to_string()
+        .contains("with constraints [>=2.0.0, <3.0.0] and pin <2.0.0"));
+}
+
+#[test]
+fn bans_skip_banned_versions_and_report_when_all_are_banned() {
+    let tool = |version: &str| {
+        manifest(&format!(
+            r#"
+name = "tool"
+version = "{version}"
+[[artifacts]]
+target = "x86_64-unknown-linux-gnu"
+url = "https://example.test/tool-{version}.tar.zst"
+sha256 = "tool"
+"#
+        ))
+    };
+    let available = vec![tool("1.2.0"), tool("1.3.0")];
+    let roots = vec![RootRequirement {
+        name: "tool".to_string(),
+        requirement: VersionReq::parse("^1").expect("req"),
+        features: Vec::new(),
+    }];
+    let ban = |versions: &[&str]| {
+        BTreeMap::from([(
+            "tool".to_string(),
+            versions
+                .iter()
+                .map(|version| Version::parse(version).expect("version"))
+                .collect::<Vec<_>>(),
+        )])
+    };
+
+    let graph =
+        resolve_dependency_graph_with_bans(&roots, &BTreeMap::new(), &ban(&["1.3.0"]), |_| {
+            Ok(available.clone())
+        })
+        .expect("must fall back to prior version");
+    assert_eq!(graph.manifests["tool"].version.to_string(), "1.2.0");
+
+    let err = resolve_dependency_graph_with_bans(
+        &roots,
+        &BTreeMap::new(),
+        &ban(&["1.2.0", "1.3.0"]),
+        |_| Ok(available.clone()),
+    )
+    .expect_err("banning every candidate must fail");
+    assert_eq!(
+        err.to_string(),
+        "all candidates for 'tool' were banned (banned versions: 1.2.0, 1.3.0)"
+    );
+}
+
+#[test]
+fn fixed_packages_bypass_loader_but_resolve_their_dependencies() {
+    let mut available = BTreeMap::new();
+    available.insert(
+        "app".to_string(),
+        vec![manifest(
+            r#"
+name = "app"
+version = "1.0.0"
+[dependencies]
+lib = "^1"
+[[artifacts]]
+target = "x86_64-unknown-linux-gnu"
+url = "https://example.test/app-1.0.0.tar.zst"
+sha256 = "app"
+"#,
+        )],
+    );
+    available.insert(
+        "zlib".to_string(),
+        vec![manifest(
+            r#"
+name = "zlib"
+version = "2.1.0"
+[[artifacts]]
+target = "x86_64-unknown-linux-gnu"
+url = "https://example.test/zlib-2.1.0.tar.zst"
+sha256 = "zlib"
+"#,
+        )],
+    );
+    let fixed = BTreeMap::from([(
+        "lib".to_string(),
+        manifest(
+            r#"
+name = "lib"
+version = "1.0.5"
+[dependencies]
+zlib = "^2"
+[[artifacts]]
+target = "x86_64-unknown-linux-gnu"
+url = "https://vendor.test/lib-1.0.5.tar.zst"
+sha256 = "lib"
+"#,
+        ),
+    )]);
+
+    let roots = vec![RootRequirement {
+        name: "app".to_string(),
+        requirement: VersionReq::STAR,
+        features: Vec::new(),
+    }];
+    let mut loaded = Vec::new();
+    let graph = resolve_dependency_graph_with_fixed(&roots, &BTreeMap::new(), &fixed, |name| {
+        loaded.push(name.to_string());
+        Ok(available.get(name).cloned().unwrap_or_default())
+    })
+    .expect("must resolve graph");
+
+    assert_eq!(graph.install_order, vec!["zlib", "lib", "app"]);
+    assert_eq!(graph.manifests["lib"].version.to_string(), "1.0.5");
+    assert!(
+        !loaded.iter().any(|name| name == "lib"),
+        "fixed package must not be loaded: {loaded:?}"
+    );
+}
+
+#[test]
+fn resolvable_versions_reports_every_version_compatible_with_the_graph() {
+    let mut available = BTreeMap::new();
+    available.insert(
+        "app".to_string(),
+        vec![
+            manifest(
+                r#"
+name = "app"
+version = "1.0.0"
+[dependencies]
+zlib = ">=1.2, <2"
+"#,
+            ),
+            manifest(
+                r#"
+name = "app"
+version = "0.9.0"
+[dependencies]
+zlib = "<1.3"
+"#,
+            ),
+        ],
+    );
+    available.insert(
+        "zlib".to_string(),
+        ["1.1.0", "1.2.0", "1.3.0", "2.0.0"]
+            .iter()
+            .map(|version| manifest(&format!("name = \"zlib\"\nversion = \"{version}\"\n")))
+            .collect(),
+    );
+
+    let roots = vec![RootRequirement {
+        name: "app".to_string(),
+        requirement: VersionReq::STAR,
+        features: Vec::new(),
+    }];
+    let resolvable = resolvable_versions(&roots, &BTreeMap::new(), |name| {
+        Ok(available.get(name).cloned().unwrap_or_default())
+    })
+    .expect("must compute resolvable versions");
+
+    assert_eq!(resolvable["app"], vec![Version::new(1, 0, 0)]);
+    assert_eq!(
+        resolvable["zlib"],
+        vec![Version::new(1, 3, 0), Version::new(1, 2, 0)]
+    );
+}
+
+#[test]
+fn acceptable_version_masks_match_naive_filter_across_many_versions() {
+    let versions = (0..10)
+        .flat_map(|major| (0..30).map(move |minor| (major, minor)))
+        .map(|(major, minor)| {
+            manifest(&format!(
+                "name = \"lib\"\nversion = \"{major}.{minor}.0\"\n"
+            ))
+        })
+        .collect::<Vec<_>>();
+    let req = |raw: &str| VersionReq::parse(raw).expect("req");
+    let pin = req("<9");
+    let steps = [
+        vec![req(">=2")],
+        vec![req(">=2"), req("<7")],
+        vec![req(">=2"), req("<7"), req("~5.4")],
+        vec![req(">=2"), req("<7")],
+        vec![req(">=2"), req("<7"), req("^3.10")],
+        vec![req(">=1, <4")],
+        vec![],
+        vec![req("=6.12.0")],
+    ];
+
+    let mut acceptable = crate::search::AcceptableVersions::default();
+    for reqs in &steps {
+        let naive = versions
+            .iter()
+            .map(|manifest| {
+                pin.matches(&manifest.version)
+                    && reqs.iter().all(|req| req.matches(&manifest.version))
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(acceptable.refresh(&versions, reqs, Some(&pin)), naive);
+    }
+
+    let app = manifest(
+        r#"
+name = "app"
+version = "1.0.0"
+[dependencies]
+lib = "<5"
+"#,
+    );
+    let roots = vec![
+        RootRequirement {
+            name: "lib".to_string(),
+            requirement: req(">=2"),
+            features: Vec::new(),
+        },
+        RootRequirement {
+            name: "app".to_string(),
+            requirement: VersionReq::STAR,
+            features: Vec::new(),
+        },
+    ];
+    let graph = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| match name {
+        "lib" => Ok(versions.clone()),
+        "app" => Ok(vec![app.clone()]),
+        _ => Ok(Vec::new()),
+    })
+    .expect("must resolve");
+    assert_eq!(graph.manifests["lib"].version, Version::new(4, 29, 0));
+}
+
+#[test]
+fn normalize_version_req_renders_equivalent_ranges_identically() {
+    let normalized = |raw: &str| normalize_version_req(&VersionReq::parse(raw).expect("req"));
+
+    assert_eq!(normalized("^1.2"), ">=1.2.0, <2.0.0");
+    assert_eq!(normalized(">=1.2.0, <2.0.0"), normalized("^1.2"));
+    assert_eq!(normalized(">=1.2, <2"), normalized("^1.2"));
+    assert_eq!(normalized("~1.2"), ">=1.2.0, <1.3.0");
+    assert_eq!(normalized("1.2.*"), normalized("~1.2"));
+    assert_eq!(normalized("^0.2.3"), ">=0.2.3, <0.3.0");
+    assert_eq!(normalized("=1.2.3"), "=1.2.3");
+    assert_eq!(normalized(">=1.2.3, <=1.2.3"), "=1.2.3");
+    assert_eq!(normalized("*"), "*");
+    assert_eq!(normalized(">1.0.0"), ">1.0.0");
 }
 
 #[test]
@@ -275,6 +528,7 @@ sha256 = "b"
     let roots = vec![RootRequirement {
         name: "a".to_string(),
         requirement: VersionReq::STAR,
+        features: Vec::new(),
     }];
     let err = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| {
         Ok(available.get(name).cloned().unwrap_or_default())
@@ -283,6 +537,209 @@ sha256 = "b"
     assert!(err.to_string().contains("cycle"));
 }
 
+#[test]
+fn deprecated_package_resolves_and_surfaces_advisory() {
+    let available = BTreeMap::from([
+        (
+            "app".to_string(),
+            vec![manifest(
+                r#"
+name = "app"
+version = "1.0.0"
+[dependencies]
+oldlib = "^1"
+"#,
+            )],
+        ),
+        (
+            "oldlib".to_string(),
+            vec![manifest(
+                r#"
+name = "oldlib"
+version = "1.4.0"
+deprecated = "use newlib instead"
+"#,
+            )],
+        ),
+    ]);
+
+    let roots = vec![RootRequirement {
+        name: "app".to_string(),
+        requirement: VersionReq::parse("^1").expect("req should parse"),
+        features: Vec::new(),
+    }];
+    let graph = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| {
+        Ok(available.get(name).cloned().unwrap_or_default())
+    })
+    .expect("deprecated packages must still resolve");
+
+    assert_eq!(graph.install_order, vec!["oldlib", "app"]);
+    assert_eq!(
+        graph.advisories,
+        vec![Advisory {
+            package: "oldlib".to_string(),
+            version: Version::parse("1.4.0").expect("version should parse"),
+            kind: AdvisoryKind::Deprecated {
+                message: "use newlib instead".to_string(),
+            },
+        }]
+    );
+    assert_eq!(
+        graph.advisories[0].to_string(),
+        "oldlib 1.4.0 is deprecated: use newlib instead"
+    );
+}
+
+#[test]
+fn duplicate_root_requirements_are_intersected() {
+    let available = ["1.0.0", "1.2.0", "1.4.0", "1.6.0", "2.0.0"]
+        .iter()
+        .map(|version| manifest(&format!("name = \"tool\"\nversion = \"{version}\"\n")))
+        .collect::<Vec<_>>();
+    let root = |requirement: &str| RootRequirement {
+        name: "tool".to_string(),
+        requirement: VersionReq::parse(requirement).expect("req should parse"),
+        features: Vec::new(),
+    };
+    let roots = vec![root("^1"), root("<1.5")];
+
+    let graph = resolve_dependency_graph(&roots, &BTreeMap::new(), |_| Ok(available.clone()))
+        .expect("intersected root requirements must resolve");
+
+    let selected = &graph.manifests["tool"].version;
+    assert_eq!(
+        selected,
+        &Version::parse("1.4.0").expect("version should parse")
+    );
+    assert!(roots.iter().all(|root| root.requirement.matches(selected)));
+    assert_eq!(graph.install_order, vec!["tool"]);
+}
+
+#[test]
+fn explain_unsatisfiable_names_only_the_conflicting_roots() {
+    let mut available = BTreeMap::new();
+    for (name, version, dependency) in [
+        ("editor", "1.0.0", Some("libgit = \"^1\"")),
+        ("viewer", "1.0.0", Some("libgit = \"^2\"")),
+        ("ripgrep", "14.1.0", None),
+        ("libgit", "1.4.0", None),
+        ("libgit", "2.0.0", None),
+    ] {
+        let dependencies = dependency
+            .map(|dependency| format!("[dependencies]\n{dependency}\n"))
+            .unwrap_or_default();
+        available
+            .entry(name.to_string())
+            .or_insert_with(Vec::new)
+            .push(manifest(&format!(
+                "name = \"{name}\"\nversion = \"{version}\"\n{dependencies}"
+            )));
+    }
+    let root = |name: &str| RootRequirement {
+        name: name.to_string(),
+        requirement: VersionReq::STAR,
+        features: Vec::new(),
+    };
+    let roots = vec![root("ripgrep"), root("editor"), root("viewer")];
+    let load = |name: &str| Ok(available.get(name).cloned().unwrap_or_default());
+
+    let explanation =
+        explain_unsatisfiable(&roots, &BTreeMap::new(), load).expect("must explain conflict");
+    let names = explanation
+        .roots
+        .iter()
+        .map(|root| root.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["editor", "viewer"]);
+    assert!(explanation.pins.is_empty());
+    assert!(
+        explanation
+            .to_string()
+            .starts_with("editor *, viewer * cannot be satisfied together"),
+        "unexpected explanation: {explanation}"
+    );
+
+    let err = explain_unsatisfiable(&roots[..2], &BTreeMap::new(), load)
+        .expect_err("resolvable roots have nothing to explain");
+    assert!(err.to_string().contains("nothing to explain"));
+
+    let normalized = UnsatExplanation {
+        roots: vec![RootRequirement {
+            name: "editor".to_string(),
+            requirement: VersionReq::parse("^1.2").expect("req"),
+            features: Vec::new(),
+        }],
+        pins: BTreeMap::from([(
+            "libgit".to_string(),
+            VersionReq::parse("~2.0").expect("req"),
+        )]),
+        reason: "no compatible libgit".to_string(),
+    };
+    assert_eq!(
+        normalized.to_string(),
+        "editor >=1.2.0, <2.0.0, pin libgit >=2.0.0, <2.1.0 cannot be satisfied together: no compatible libgit"
+    );
+}
+
+#[test]
+fn bounded_versions_cache_reloads_evicted_packages_with_identical_result() {
+    let mut available: BTreeMap<String, Vec<PackageManifest>> = BTreeMap::new();
+    for (name, version, dependency) in [
+        ("alpha", "2.0.0", None),
+        ("alpha", "1.0.0", None),
+        ("beta", "1.0.0", None),
+        ("gamma", "1.0.0", Some("alpha = \"^1\"")),
+    ] {
+        let dependencies = dependency
+            .map(|dependency| format!("[dependencies]\n{dependency}\n"))
+            .unwrap_or_default();
+        available
+            .entry(name.to_string())
+            .or_default()
+            .push(manifest(&format!(
+                "name = \"{name}\"\nversion = \"{version}\"\n{dependencies}"
+            )));
+    }
+    let roots = ["alpha", "beta", "gamma"]
+        .iter()
+        .map(|name| RootRequirement {
+            name: name.to_string(),
+            requirement: VersionReq::STAR,
+            features: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut unbounded_loads = 0;
+    let unbounded = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| {
+        unbounded_loads += 1;
+        Ok(available.get(name).cloned().unwrap_or_default())
+    })
+    .expect("unbounded resolution must succeed");
+
+    let mut bounded_loads = 0;
+    let bounded = resolve_dependency_graph_with_cache_limit(&roots, &BTreeMap::new(), 1, |name| {
+        bounded_loads += 1;
+        Ok(available.get(name).cloned().unwrap_or_default())
+    })
+    .expect("bounded resolution must succeed");
+
+    assert_eq!(unbounded_loads, 3);
+    assert!(
+        bounded_loads > unbounded_loads,
+        "cache bound must force reloads ({bounded_loads} loads)"
+    );
+    let versions = |graph: &ResolvedGraph| {
+        graph
+            .manifests
+            .iter()
+            .map(|(name, manifest)| (name.clone(), manifest.version.to_string()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(versions(&bounded), versions(&unbounded));
+    assert_eq!(bounded.install_order, unbounded.install_order);
+    assert_eq!(bounded.manifests["alpha"].version.to_string(), "1.0.0");
+}
+
 #[test]
 fn resolves_multi_root_global_graph() {
     let mut available = BTreeMap::new();
@@ -346,10 +803,12 @@ sha256 = "shared11"
         RootRequirement {
             name: "tool-a".to_string(),
             requirement: VersionReq::STAR,
+            features: Vec::new(),
         },
         RootRequirement {
             name: "tool-b".to_string(),
             requirement: VersionReq::STAR,
+            features: Vec::new(),
         },
     ];
 
@@ -368,6 +827,151 @@ sha256 = "shared11"
         "1.3.0"
     );
     assert_eq!(graph.install_order, vec!["shared", "tool-a", "tool-b"]);
+    graph
+        .assert_unified()
+        .expect("shared dependency must resolve to one version");
+}
+
+#[test]
+fn install_levels_group_independent_packages_after_their_dependencies() {
+    let available: BTreeMap<String, Vec<PackageManifest>> = [
+        (
+            "app-a",
+            "name = \"app-a\"\nversion = \"1.0.0\"\n[dependencies]\nlib = \"*\"\n",
+        ),
+        ("app-b", "name = \"app-b\"\nversion = \"1.0.0\"\n"),
+        ("lib", "name = \"lib\"\nversion = \"1.0.0\"\n"),
+    ]
+    .into_iter()
+    .map(|(name, raw)| (name.to_string(), vec![manifest(raw)]))
+    .collect();
+    let roots = ["app-a", "app-b"]
+        .into_iter()
+        .map(|name| RootRequirement {
+            name: name.to_string(),
+            requirement: VersionReq::STAR,
+            features: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+
+    let graph = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| {
+        Ok(available.get(name).cloned().unwrap_or_default())
+    })
+    .expect("must resolve graph");
+
+    assert_eq!(graph.install_order, vec!["app-b", "lib", "app-a"]);
+    assert_eq!(
+        graph.install_levels(),
+        vec![
+            vec!["app-b".to_string(), "lib".to_string()],
+            vec!["app-a".to_string()],
+        ]
+    );
+}
+
+#[test]
+fn install_order_is_identical_for_every_insertion_order() {
+    let entries = [
+        ("app-a", vec!["lib-c", "lib-a"]),
+        ("app-b", vec!["lib-b"]),
+        ("lib-a", vec!["zlib"]),
+        ("lib-b", vec![]),
+        ("lib-c", vec![]),
+        ("tool-1", vec![]),
+        ("tool-2", vec![]),
+        ("tool-3", vec![]),
+        ("tool-4", vec![]),
+        ("tool-5", vec![]),
+        ("zlib", vec![]),
+    ];
+    let manifests = entries
+        .iter()
+        .map(|(name, deps)| {
+            let mut raw = format!("name = \"{name}\"\nversion = \"1.0.0\"\n[dependencies]\n");
+            for dep in deps {
+                raw.push_str(&format!("{dep} = \"*\"\n"));
+            }
+            (name.to_string(), manifest(&raw))
+        })
+        .collect::<Vec<_>>();
+    let expected = vec![
+        "lib-b", "app-b", "lib-c", "tool-1", "tool-2", "tool-3", "tool-4", "tool-5", "zlib",
+        "lib-a", "app-a",
+    ];
+
+    let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+    for _ in 0..64 {
+        let mut shuffled = manifests.clone();
+        for index in (1..shuffled.len()).rev() {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            shuffled.swap(index, (seed % (index as u64 + 1)) as usize);
+        }
+
+        let mut selected = BTreeMap::new();
+        for (name, manifest) in &shuffled {
+            selected.insert(name.clone(), manifest.clone());
+        }
+        let order = crate::order::topo_order(&selected, &BTreeMap::new())
+            .expect("acyclic graph must order");
+        assert_eq!(order, expected);
+
+        let roots = shuffled
+            .iter()
+            .map(|(name, _)| RootRequirement {
+                name: name.clone(),
+                requirement: VersionReq::STAR,
+                features: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+        let graph = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| {
+            Ok(shuffled
+                .iter()
+                .filter(|(candidate, _)| candidate == name)
+                .map(|(_, manifest)| manifest.clone())
+                .collect())
+        })
+        .expect("must resolve shuffled roots");
+        assert_eq!(graph.install_order, expected);
+    }
+}
+
+#[test]
+fn assert_unified_reports_unsatisfied_and_misordered_dependency_edges() {
+    let app = |name: &str| {
+        manifest(&format!(
+            "name = \"{name}\"\nversion = \"1.0.0\"\n[dependencies]\nshared = \"^1.2\"\n"
+        ))
+    };
+    let graph = ResolvedGraph {
+        manifests: BTreeMap::from([
+            ("tool-a".to_string(), app("tool-a")),
+            ("tool-b".to_string(), app("tool-b")),
+            (
+                "shared".to_string(),
+                manifest("name = \"shared\"\nversion = \"1.1.0\"\n"),
+            ),
+        ]),
+        install_order: vec![
+            "tool-a".to_string(),
+            "shared".to_string(),
+            "tool-b".to_string(),
+        ],
+        features: BTreeMap::new(),
+        advisories: Vec::new(),
+    };
+
+    let err = graph
+        .assert_unified()
+        .expect_err("unsatisfied edges must be reported");
+    assert_eq!(
+        err.to_string(),
+        "resolved graph has conflicting package selections: \
+         tool-a requires shared ^1.2 (selected 1.1.0); \
+         tool-a requires shared (not installed before it); \
+         tool-b requires shared ^1.2 (selected 1.1.0)"
+    );
 }
 
 #[test]
@@ -418,6 +1022,7 @@ sha256 = "compiler"
     let roots = vec![RootRequirement {
         name: "app".to_string(),
         requirement: VersionReq::STAR,
+        features: Vec::new(),
     }];
 
     let graph = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| {
@@ -484,6 +1089,7 @@ sha256 = "gcc"
     let roots = vec![RootRequirement {
         name: "app".to_string(),
         requirement: VersionReq::STAR,
+        features: Vec::new(),
     }];
 
     let graph = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| {
@@ -552,6 +1158,7 @@ sha256 = "bar"
     let roots = vec![RootRequirement {
         name: "app".to_string(),
         requirement: VersionReq::STAR,
+        features: Vec::new(),
     }];
 
     let err = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| {
@@ -566,6 +1173,59 @@ sha256 = "bar"
     );
 }
 
+#[test]
+fn fails_when_selected_package_provides_conflicted_capability() {
+    let mut available = BTreeMap::new();
+    available.insert(
+        "postfix".to_string(),
+        vec![manifest(
+            r#"
+name = "postfix"
+version = "3.8.0"
+provides = ["mta"]
+[conflicts]
+mta = "*"
+[[artifacts]]
+target = "x86_64-unknown-linux-gnu"
+url = "https://example.test/postfix-3.8.0.tar.zst"
+sha256 = "postfix"
+"#,
+        )],
+    );
+    available.insert(
+        "exim".to_string(),
+        vec![manifest(
+            r#"
+name = "exim"
+version = "4.97.0"
+provides = ["mta"]
+[[artifacts]]
+target = "x86_64-unknown-linux-gnu"
+url = "https://example.test/exim-4.97.0.tar.zst"
+sha256 = "exim"
+"#,
+        )],
+    );
+    let root = |name: &str| RootRequirement {
+        name: name.to_string(),
+        requirement: VersionReq::STAR,
+        features: Vec::new(),
+    };
+    let load = |name: &str| Ok(available.get(name).cloned().unwrap_or_default());
+
+    let graph = resolve_dependency_graph(&[root("postfix")], &BTreeMap::new(), load)
+        .expect("a provider must not conflict with its own capability");
+    assert_eq!(graph.install_order, vec!["postfix"]);
+
+    let err = resolve_dependency_graph(&[root("postfix"), root("exim")], &BTreeMap::new(), load)
+        .expect_err("two mta providers must be rejected");
+    assert!(
+        err.to_string()
+            .contains("no compatible dependency graph found"),
+        "unexpected error: {err}"
+    );
+}
+
 #[test]
 fn fails_when_selected_package_conflicts_with_installed_state() {
     let mut available = BTreeMap::new();
@@ -618,6 +1278,7 @@ sha256 = "bar"
     let roots = vec![RootRequirement {
         name: "app".to_string(),
         requirement: VersionReq::STAR,
+        features: Vec::new(),
     }];
 
     let err =
@@ -633,6 +1294,246 @@ sha256 = "bar"
     );
 }
 
+fn feature_fixture() -> BTreeMap<String, Vec<PackageManifest>> {
+    let mut available = BTreeMap::new();
+    available.insert(
+        "app".to_string(),
+        vec![manifest(
+            r#"
+name = "app"
+version = "1.0.0"
+[dependencies]
+net = { version = "^1", features = ["tls"] }
+[features]
+cli = []
+[[artifacts]]
+target = "x86_64-unknown-linux-gnu"
+url = "https://example.test/app-1.0.0.tar.zst"
+sha256 = "app"
+"#,
+        )],
+    );
+    available.insert(
+        "net".to_string(),
+        vec![manifest(
+            r#"
+name = "net"
+version = "1.4.0"
+[dependencies]
+openssl = { version = "^3", optional = true }
+[features]
+tls = ["openssl"]
+[[artifacts]]
+target = "x86_64-unknown-linux-gnu"
+url = "https://example.test/net-1.4.0.tar.zst"
+sha256 = "net"
+"#,
+        )],
+    );
+    available.insert(
+        "openssl".to_string(),
+        vec![manifest(
+            r#"
+name = "openssl"
+version = "3.2.0"
+[[artifacts]]
+target = "x86_64-unknown-linux-gnu"
+url = "https://example.test/openssl-3.2.0.tar.zst"
+sha256 = "openssl"
+"#,
+        )],
+    );
+    available
+}
+
+#[test]
+fn root_feature_enables_optional_dependency() {
+    let available = feature_fixture();
+    let roots = vec![RootRequirement {
+        name: "net".to_string(),
+        requirement: VersionReq::STAR,
+        features: vec!["tls".to_string()],
+    }];
+
+    let graph = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| {
+        Ok(available.get(name).cloned().unwrap_or_default())
+    })
+    .expect("feature graph must resolve");
+
+    assert_eq!(graph.install_order, vec!["openssl", "net"]);
+    assert!(graph.manifests["net"].dependencies.contains_key("openssl"));
+    assert!(graph.features["net"].contains("tls"));
+}
+
+#[test]
+fn optional_dependency_is_absent_without_feature() {
+    let available = feature_fixture();
+    let roots = vec![RootRequirement {
+        name: "net".to_string(),
+        requirement: VersionReq::STAR,
+        features: Vec::new(),
+    }];
+
+    let graph = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| {
+        Ok(available.get(name).cloned().unwrap_or_default())
+    })
+    .expect("graph must resolve");
+
+    assert_eq!(graph.install_order, vec!["net"]);
+    assert!(!graph.manifests.contains_key("openssl"));
+    assert!(graph.features.is_empty());
+}
+
+#[test]
+fn dependency_features_propagate_transitively() {
+    let available = feature_fixture();
+    let roots = vec![RootRequirement {
+        name: "app".to_string(),
+        requirement: VersionReq::STAR,
+        features: vec!["cli".to_string()],
+    }];
+
+    let graph = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| {
+        Ok(available.get(name).cloned().unwrap_or_default())
+    })
+    .expect("transitive feature graph must resolve");
+
+    assert_eq!(graph.install_order, vec!["openssl", "net", "app"]);
+    assert!(graph.features["net"].contains("tls"));
+    assert!(graph.features["app"].contains("cli"));
+}
+
+#[test]
+fn feature_propagation_converges_when_features_change_the_selection() {
+    // Without `lib/x`, `mid@2` is chosen and requests `lib/x`; with it, `blocker`
+    // pins `core@1` and so forces `mid@1`, which no longer requests it.
+    let mut available = BTreeMap::new();
+    let mut add = |raw: &str| {
+        let manifest = manifest(raw);
+        available
+            .entry(manifest.name.clone())
+            .or_insert_with(Vec::new)
+            .push(manifest);
+    };
+    add(r#"
+name = "app"
+version = "1.0.0"
+[dependencies]
+mid = "*"
+lib = "*"
+"#);
+    add(r#"
+name = "mid"
+version = "2.0.0"
+[dependencies]
+core = "^2"
+lib = { version = "*", features = ["x"] }
+"#);
+    add(r#"
+name = "mid"
+version = "1.0.0"
+[dependencies]
+core = "^1"
+lib = "*"
+"#);
+    add(r#"
+name = "lib"
+version = "1.0.0"
+[dependencies]
+blocker = { version = "*", optional = true }
+[features]
+x = ["blocker"]
+"#);
+    add(r#"
+name = "blocker"
+version = "1.0.0"
+[dependencies]
+core = "^1"
+"#);
+    add(r#"
+name = "core"
+version = "2.0.0"
+"#);
+    add(r#"
+name = "core"
+version = "1.0.0"
+"#);
+    let roots = vec![RootRequirement {
+        name: "app".to_string(),
+        requirement: VersionReq::STAR,
+        features: Vec::new(),
+    }];
+
+    let graph = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| {
+        Ok(available.get(name).cloned().unwrap_or_default())
+    })
+    .expect("feature propagation must converge");
+
+    assert_eq!(graph.manifests["mid"].version.to_string(), "1.0.0");
+    assert!(graph.manifests.contains_key("blocker"));
+    assert!(graph.features["lib"].contains("x"));
+}
+
+#[test]
+fn fails_when_root_requests_undeclared_feature() {
+    let available = feature_fixture();
+    let roots = vec![RootRequirement {
+        name: "net".to_string(),
+        requirement: VersionReq::STAR,
+        features: vec!["quic".to_string()],
+    }];
+
+    let err = resolve_dependency_graph(&roots, &BTreeMap::new(), |name| {
+        Ok(available.get(name).cloned().unwrap_or_default())
+    })
+    .expect_err("unknown feature must be rejected");
+
+    assert!(
+        err.to_string()
+            .contains("package 'net' does not declare feature 'quic'"),
+        "unexpected error: {err}"
+    );
+}
+
+#[test]
+fn graph_diff_categorizes_added_removed_and_changed_packages() {
+    let graph = |entries: &[(&str, &str)]| ResolvedGraph {
+        manifests: entries
+            .iter()
+            .map(|(name, version)| {
+                (
+                    name.to_string(),
+                    manifest(&format!("name = \"{name}\"\nversion = \"{version}\"\n")),
+                )
+            })
+            .collect(),
+        install_order: entries.iter().map(|(name, _)| name.to_string()).collect(),
+        features: BTreeMap::new(),
+        advisories: Vec::new(),
+    };
+    let before = graph(&[("app", "1.0.0"), ("lib", "1.2.0"), ("legacy", "0.9.0")]);
+    let after = graph(&[("app", "1.0.0"), ("lib", "1.3.0"), ("zlib", "2.1.0")]);
+
+    let diff = before.diff(&after);
+    assert_eq!(
+        diff.added.keys().map(String::as_str).collect::<Vec<_>>(),
+        vec!["zlib"]
+    );
+    assert_eq!(diff.added["zlib"].to_string(), "2.1.0");
+    assert_eq!(
+        diff.removed.keys().map(String::as_str).collect::<Vec<_>>(),
+        vec!["legacy"]
+    );
+    assert_eq!(
+        diff.changed
+            .get("lib")
+            .map(|change| (change.from.to_string(), change.to.to_string())),
+        Some(("1.2.0".to_string(), "1.3.0".to_string()))
+    );
+    assert_eq!(diff.changed.len(), 1);
+    assert!(before.diff(&before).is_empty());
+}
+
 fn manifest(raw: &str) -> PackageManifest {
     PackageManifest::from_toml_str(raw).expect("manifest must parse")
 }
@@ -1,16 +1,187 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 
+use anyhow::Result;
 use crosspack_core::PackageManifest;
-use semver::VersionReq;
+use semver::{Version, VersionReq};
 
+use crate::constraints::normalize_version_req;
+use crate::features::active_dependencies;
+use crate::order::install_levels;
+
+/// A root may be listed more than once; every listed requirement must hold for
+/// the selected version, and the requested features are unioned.
 #[derive(Debug, Clone)]
 pub struct RootRequirement {
     pub name: String,
     pub requirement: VersionReq,
+    pub features: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ResolvedGraph {
     pub manifests: BTreeMap<String, PackageManifest>,
+    /// Dependencies before dependents; packages that become ready together are
+    /// ordered by name, so the sequence is stable regardless of root order.
     pub install_order: Vec<String>,
+    pub features: BTreeMap<String, BTreeSet<String>>,
+    /// Non-fatal notices about selected packages, ordered by package name.
+    pub advisories: Vec<Advisory>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Advisory {
+    pub package: String,
+    pub version: Version,
+    pub kind: AdvisoryKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdvisoryKind {
+    Deprecated { message: String },
+}
+
+impl fmt::Display for Advisory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            AdvisoryKind::Deprecated { message } => write!(
+                f,
+                "{} {} is deprecated: {message}",
+                self.package, self.version
+            ),
+        }
+    }
+}
+
+/// A minimal set of roots and pins that cannot be resolved together: dropping any
+/// one of them makes the rest resolvable.
+#[derive(Debug, Clone)]
+pub struct UnsatExplanation {
+    pub roots: Vec<RootRequirement>,
+    pub pins: BTreeMap<String, VersionReq>,
+    /// Resolver error for the minimal set.
+    pub reason: String,
+}
+
+impl fmt::Display for UnsatExplanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut requirements = self
+            .roots
+            .iter()
+            .map(|root| format!("{} {}", root.name, normalize_version_req(&root.requirement)))
+            .collect::<Vec<_>>();
+        requirements.extend(self.pins.iter().map(|(name, requirement)| {
+            format!("pin {name} {}", normalize_version_req(requirement))
+        }));
+        write!(
+            f,
+            "{} cannot be satisfied together: {}",
+            requirements.join(", "),
+            self.reason
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GraphDiff {
+    pub added: BTreeMap<String, Version>,
+    pub removed: BTreeMap<String, Version>,
+    pub changed: BTreeMap<String, VersionChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionChange {
+    pub from: Version,
+    pub to: Version,
+}
+
+impl ResolvedGraph {
+    /// Groups `install_order` into dependency levels: every package's active
+    /// dependencies are in earlier levels, so packages within one level have no
+    /// dependency relationship and may be installed concurrently.
+    pub fn install_levels(&self) -> Vec<Vec<String>> {
+        install_levels(&self.manifests, &self.features, &self.install_order)
+    }
+
+    pub fn diff(&self, other: &ResolvedGraph) -> GraphDiff {
+        let mut diff = GraphDiff::default();
+        for (name, manifest) in &self.manifests {
+            match other.manifests.get(name) {
+                None => {
+                    diff.removed.insert(name.clone(), manifest.version.clone());
+                }
+                Some(next) if next.version != manifest.version => {
+                    diff.changed.insert(
+                        name.clone(),
+                        VersionChange {
+                            from: manifest.version.clone(),
+                            to: next.version.clone(),
+                        },
+                    );
+                }
+                Some(_) => {}
+            }
+        }
+        for (name, manifest) in &other.manifests {
+            if !self.manifests.contains_key(name) {
+                diff.added.insert(name.clone(), manifest.version.clone());
+            }
+        }
+        diff
+    }
+
+    /// Checks that every active dependency edge is satisfied by the one version
+    /// selected for that dependency and that dependencies precede dependents in
+    /// `install_order`.
+    pub fn assert_unified(&self) -> Result<()> {
+        let mut conflicts = Vec::new();
+        let mut positions = BTreeMap::new();
+        for (index, name) in self.install_order.iter().enumerate() {
+            if positions.insert(name.as_str(), index).is_some() {
+                conflicts.push(format!("{name} (listed twice in install order)"));
+            }
+        }
+
+        for (name, manifest) in &self.manifests {
+            let Some(&position) = positions.get(name.as_str()) else {
+                conflicts.push(format!("{name} (missing from install order)"));
+                continue;
+            };
+            for (dependency, requirement) in active_dependencies(manifest, self.features.get(name))
+            {
+                let Some(selected) = self.manifests.get(&dependency) else {
+                    conflicts.push(format!(
+                        "{name} requires {dependency} {requirement} (not selected)"
+                    ));
+                    continue;
+                };
+                if !requirement.matches(&selected.version) {
+                    conflicts.push(format!(
+                        "{name} requires {dependency} {requirement} (selected {})",
+                        selected.version
+                    ));
+                }
+                match positions.get(dependency.as_str()) {
+                    Some(&dependency_position) if dependency_position < position => {}
+                    _ => conflicts.push(format!(
+                        "{name} requires {dependency} (not installed before it)"
+                    )),
+                }
+            }
+        }
+
+        if !conflicts.is_empty() {
+            anyhow::bail!(
+                "resolved graph has conflicting package selections: {}",
+                conflicts.join("; ")
+            );
+        }
+        Ok(())
+    }
+}
+
+impl GraphDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
 }
@@ -14,7 +14,7 @@ pub fn verify_sha256(bytes: &[u8], expected_hex: &str) -> bool {
     actual.eq_ignore_ascii_case(expected_hex)
 }
 
-pub fn verify_sha256_reader<R: Read>(reader: &mut R, expected_hex: &str) -> Result<bool> {
+pub fn hash_sha256_reader<R: Read>(reader: &mut R) -> Result<String> {
     let mut hasher = Sha256::new();
     let mut buf = [0u8; 16 * 1024];
     loop {
@@ -27,10 +27,40 @@ pub fn verify_sha256_reader<R: Read>(reader: &mut R, expected_hex: &str) -> Resu
         hasher.update(&buf[..read]);
     }
 
-    let actual = hex::encode(hasher.finalize());
+    Ok(hex::encode(hasher.finalize()))
+}
+
+pub fn verify_sha256_reader<R: Read>(reader: &mut R, expected_hex: &str) -> Result<bool> {
+    let actual = hash_sha256_reader(reader)?;
     Ok(actual.eq_ignore_ascii_case(expected_hex))
 }
 
+pub struct Sha256Reader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> Sha256Reader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    pub fn finalize_hex(self) -> String {
+        hex::encode(self.hasher.finalize())
+    }
+}
+
+impl<R: Read> Read for Sha256Reader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
 pub fn verify_sha256_file(path: &Path, expected_hex: &str) -> Result<bool> {
     let mut file = std::fs::File::open(path)
         .with_context(|| format!("failed to read file for checksum: {}", path.display()))?;
@@ -61,4 +91,40 @@ mod tests {
         .expect("must read");
         assert!(ok);
     }
+
+    #[test]
+    fn hash_reader_returns_known_digest() {
+        let mut reader = Cursor::new(b"crosspack");
+        let digest = hash_sha256_reader(&mut reader).expect("must read");
+        assert_eq!(
+            digest,
+            "650c2cb6e617c91277fa43239c46df0d39c198cd2b936b83dd9136da7cfe60ab"
+        );
+    }
+
+    #[test]
+    fn verify_reader_matches_case_insensitively_and_rejects_mismatch() {
+        let mut upper = Cursor::new(b"crosspack");
+        assert!(verify_sha256_reader(
+            &mut upper,
+            "650C2CB6E617C91277FA43239C46DF0D39C198CD2B936B83DD9136DA7CFE60AB",
+        )
+        .expect("must read"));
+
+        let mut mismatched = Cursor::new(b"crosspack!");
+        assert!(!verify_sha256_reader(
+            &mut mismatched,
+            "650c2cb6e617c91277fa43239c46df0d39c198cd2b936b83dd9136da7cfe60ab",
+        )
+        .expect("must read"));
+    }
+
+    #[test]
+    fn sha256_reader_hashes_bytes_as_they_pass_through() {
+        let mut reader = Sha256Reader::new(Cursor::new(b"crosspack"));
+        let mut copied = Vec::new();
+        reader.read_to_end(&mut copied).expect("must read");
+        assert_eq!(copied, b"crosspack");
+        assert_eq!(reader.finalize_hex(), sha256_hex(b"crosspack"));
+    }
 }
@@ -0,0 +1,37 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub fn hmac_sha256_hex(key: &[u8], payload: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(key).expect("hmac accepts keys of any length");
+    mac.update(payload);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+pub fn verify_hmac_sha256_hex(key: &[u8], payload: &[u8], expected_hex: &str) -> bool {
+    let Ok(expected) = hex::decode(expected_hex.trim()) else {
+        return false;
+    };
+    let mut mac = HmacSha256::new_from_slice(key).expect("hmac accepts keys of any length");
+    mac.update(payload);
+    mac.verify_slice(&expected).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hmac_matches_rfc4231_vector_and_verifies() {
+        let key = [0x0bu8; 20];
+        let digest = hmac_sha256_hex(&key, b"Hi There");
+        assert_eq!(
+            digest,
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        );
+        assert!(verify_hmac_sha256_hex(&key, b"Hi There", &digest));
+        assert!(!verify_hmac_sha256_hex(&key, b"Hi there", &digest));
+        assert!(!verify_hmac_sha256_hex(&key, b"Hi There", "not-hex"));
+    }
+}
@@ -1,5 +1,10 @@
 mod checksum;
 mod ed25519;
+mod keyed_digest;
 
-pub use checksum::{sha256_hex, verify_sha256, verify_sha256_file, verify_sha256_reader};
+pub use checksum::{
+    hash_sha256_reader, sha256_hex, verify_sha256, verify_sha256_file, verify_sha256_reader,
+    Sha256Reader,
+};
 pub use ed25519::verify_ed25519_signature_hex;
+pub use keyed_digest::{hmac_sha256_hex, verify_hmac_sha256_hex};